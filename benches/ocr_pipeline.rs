@@ -0,0 +1,93 @@
+//! OCR 流程的基准测试
+//!
+//! 使用 `cargo bench` 运行，用于衡量批量 OCR、缓存等性能优化的效果。
+//! 测试图片是 [`FIXTURE_DIR`] 中的抽卡记录界面截图（任意 16:9 分辨率的 PNG）。
+//! 仓库中的截图是按 1920x1080 的界面布局合成的英文客户端页面（DejaVu Sans 字体），
+//! 不是游戏中的真实截图，OCR 的耗时与真实截图有差别；比较优化效果时可以换成自己的截图。
+//! 配置和 OCR 缓存写入临时的数据目录，不改动真实的数据。
+
+use std::path::PathBuf;
+use std::sync::Once;
+
+use criterion::{black_box, criterion_group, criterion_main, Criterion};
+
+use snowbreak_gacha_export::config::config;
+use snowbreak_gacha_export::data_dir::DATA_DIR_ENV;
+use snowbreak_gacha_export::ocr::{ocr_batch, CharsetHint};
+use snowbreak_gacha_export::record_image::{ParseOptions, RecordImage};
+
+/// 基准测试使用的截图所在的目录
+static FIXTURE_DIR: &str = concat!(env!("CARGO_MANIFEST_DIR"), "/benches/fixtures");
+
+/// 使用临时的数据目录，必须在第一次读取配置之前调用
+fn use_temp_data_dir() {
+    static INIT: Once = Once::new();
+    INIT.call_once(|| {
+        let dir = std::env::temp_dir().join("snowbreak_gacha_export_bench");
+        let _ = std::fs::remove_dir_all(&dir);
+        std::env::set_var(DATA_DIR_ENV, dir);
+    });
+}
+
+/// 按文件名排列的抽卡记录界面截图
+fn record_pages() -> Vec<RecordImage> {
+    let mut paths = std::fs::read_dir(FIXTURE_DIR)
+        .map(|entries| {
+            entries
+                .filter_map(|entry| entry.ok().map(|entry| entry.path()))
+                .filter(|path| path.extension().is_some_and(|ext| ext == "png"))
+                .collect::<Vec<PathBuf>>()
+        })
+        .unwrap_or_default();
+    paths.sort();
+    assert!(!paths.is_empty(), "no screenshots in {FIXTURE_DIR}");
+    paths
+        .iter()
+        .map(|path| RecordImage::new(image::open(path).unwrap()))
+        .collect()
+}
+
+fn bench_record_image(c: &mut Criterion) {
+    use_temp_data_dir();
+    let record_pages = record_pages();
+    let record_image = &record_pages[0];
+    let (x0, x1) = record_image.layout.item_name_x;
+    let (y0, y1) = record_image.layout.record_ys[0];
+    c.bench_function("stars", |b| b.iter(|| black_box(record_image).stars()));
+    c.bench_function("get_ocr_image", |b| {
+        b.iter(|| black_box(record_image).get_ocr_image(x0, y0, x1, y1))
+    });
+    // 第一次之后 OCR 结果都来自缓存，测量的是裁剪、缓存查找和解析的耗时
+    let options = ParseOptions::from_config();
+    c.bench_function("parse_cached", |b| {
+        b.iter(|| {
+            for record_image in &record_pages {
+                black_box(record_image).parse(&options, &ocr_batch).unwrap();
+            }
+        })
+    });
+}
+
+fn bench_ocr(c: &mut Criterion) {
+    use_temp_data_dir();
+    let record_pages = record_pages();
+    let record_image = &record_pages[0];
+    let (x0, x1) = record_image.layout.item_name_x;
+    let (y0, y1) = record_image.layout.record_ys[0];
+    let image = record_image.get_ocr_image(x0, y0, x1, y1);
+    let backend = config().ocr_backend;
+    c.bench_function("recognize", |b| {
+        b.iter(|| {
+            backend
+                .recognize(black_box(image.clone()), CharsetHint::Name)
+                .unwrap()
+        })
+    });
+}
+
+criterion_group! {
+    name = benches;
+    config = Criterion::default().sample_size(20);
+    targets = bench_record_image, bench_ocr
+}
+criterion_main!(benches);
@@ -2,28 +2,132 @@ use enigo::Button::Left;
 use enigo::Coordinate::Abs;
 use enigo::Direction::Click;
 use enigo::{Enigo, Mouse, Settings};
+use serde::{Deserialize, Serialize};
 use window_inspector::position_size::get_client_xywh;
 
+use crate::config::config;
+
 static PAGE_BUTTON_X: u32 = 1664;
 static PREVIOUS_PAGE_BUTTON_Y: u32 = 435;
 static NEXT_PAGE_BUTTON_Y: u32 = 616;
+/// 重新点击时的坐标偏移，点击被吞掉时换一个位置更容易生效
+static CLICK_JITTERS: [(i32, i32); 4] = [(0, 0), (6, 0), (-6, 4), (0, -6)];
 
-pub fn next_page(hwnd: isize) {
-    let (client_x, client_y, client_width, client_height) = get_client_xywh(hwnd).unwrap();
-    let screen_x = client_x + (client_width as f32 * PAGE_BUTTON_X as f32 / 1920.0).round() as i32;
-    let screen_y =
-        client_y + (client_height as f32 * NEXT_PAGE_BUTTON_Y as f32 / 1080.0).round() as i32;
-    let mut enigo = Enigo::new(&Settings::default()).unwrap();
-    enigo.move_mouse(screen_x, screen_y, Abs).unwrap();
-    enigo.button(Left, Click).unwrap();
+/// 第 `attempt` 次点击（从 0 开始）的坐标
+fn jittered(x: u32, y: u32, attempt: u32) -> (u32, u32) {
+    let (dx, dy) = CLICK_JITTERS[attempt as usize % CLICK_JITTERS.len()];
+    (x.saturating_add_signed(dx), y.saturating_add_signed(dy))
+}
+
+/// 点击方式
+#[derive(Debug, Copy, Clone, PartialEq, Eq, Default, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum ClickMethod {
+    /// 用 Enigo 移动鼠标并点击
+    #[default]
+    Mouse,
+    /// 直接向游戏窗口发送鼠标消息，不移动鼠标，
+    /// 用于游戏或者其他程序拦截了模拟的全局鼠标事件时
+    WindowMessage,
+}
+
+impl ClickMethod {
+    /// 点击没有生效时改用的另一种点击方式
+    pub fn fallback(&self) -> Self {
+        match self {
+            ClickMethod::Mouse => ClickMethod::WindowMessage,
+            ClickMethod::WindowMessage => ClickMethod::Mouse,
+        }
+    }
 }
 
-pub fn previous_page(hwnd: isize) {
+/// 以 1920x1080 为基准的坐标转换为客户区中的坐标
+fn to_client(x: u32, y: u32, client_width: i32, client_height: i32) -> (i32, i32) {
+    (
+        (client_width as f32 * x as f32 / 1920.0).round() as i32,
+        (client_height as f32 * y as f32 / 1080.0).round() as i32,
+    )
+}
+
+/// 点击窗口客户区中的位置，坐标以 1920x1080 为基准，使用配置中的点击方式
+pub fn click(hwnd: isize, x: u32, y: u32) {
+    click_with(hwnd, x, y, config().click_method);
+}
+
+/// 用指定的点击方式点击窗口客户区中的位置，坐标以 1920x1080 为基准
+pub fn click_with(hwnd: isize, x: u32, y: u32, method: ClickMethod) {
     let (client_x, client_y, client_width, client_height) = get_client_xywh(hwnd).unwrap();
-    let screen_x = client_x + (client_width as f32 * PAGE_BUTTON_X as f32 / 1920.0).round() as i32;
-    let screen_y =
-        client_y + (client_height as f32 * PREVIOUS_PAGE_BUTTON_Y as f32 / 1080.0).round() as i32;
-    let mut enigo = Enigo::new(&Settings::default()).unwrap();
-    enigo.move_mouse(screen_x, screen_y, Abs).unwrap();
-    enigo.button(Left, Click).unwrap();
+    let (x, y) = to_client(x, y, client_width, client_height);
+    match method {
+        ClickMethod::Mouse => {
+            let mut enigo = Enigo::new(&Settings::default()).unwrap();
+            enigo.move_mouse(client_x + x, client_y + y, Abs).unwrap();
+            enigo.button(Left, Click).unwrap();
+        }
+        ClickMethod::WindowMessage => send_click_message(hwnd, x, y),
+    }
+}
+
+/// 向窗口发送鼠标按下、抬起的消息，坐标是客户区中的坐标
+#[cfg(windows)]
+fn send_click_message(hwnd: isize, x: i32, y: i32) {
+    use windows::Win32::Foundation::{HWND, LPARAM, WPARAM};
+    use windows::Win32::UI::WindowsAndMessaging::{
+        SendMessageW, WM_LBUTTONDOWN, WM_LBUTTONUP, WM_MOUSEMOVE,
+    };
+
+    /// 鼠标左键按下（`MK_LBUTTON`）
+    const MK_LBUTTON: usize = 0x0001;
+
+    let hwnd = HWND(hwnd);
+    // 低 16 位是 x，高 16 位是 y
+    let position = LPARAM((((y as u16 as u32) << 16) | x as u16 as u32) as isize);
+    unsafe {
+        SendMessageW(hwnd, WM_MOUSEMOVE, WPARAM(0), position);
+        SendMessageW(hwnd, WM_LBUTTONDOWN, WPARAM(MK_LBUTTON), position);
+        SendMessageW(hwnd, WM_LBUTTONUP, WPARAM(0), position);
+    }
+}
+
+#[cfg(not(windows))]
+fn send_click_message(_hwnd: isize, _x: i32, _y: i32) {
+    log::warn!("clicking by window messages is only supported on Windows");
+}
+
+pub fn next_page(hwnd: isize) {
+    next_page_attempt(hwnd, 0, config().click_method);
+}
+
+/// 点击下一页，重新点击时稍微偏移坐标
+/// # 参数
+/// - `attempt`: 这一页第几次点击，从 0 开始
+/// - `method`: 点击方式，点击没有生效时导入过程会改用另一种方式
+pub fn next_page_attempt(hwnd: isize, attempt: u32, method: ClickMethod) {
+    let (x, y) = jittered(PAGE_BUTTON_X, NEXT_PAGE_BUTTON_Y, attempt);
+    click_with(hwnd, x, y, method);
+}
+
+/// 用指定的点击方式点击上一页，与翻到下一页使用同一种点击方式
+pub fn previous_page(hwnd: isize, method: ClickMethod) {
+    click_with(hwnd, PAGE_BUTTON_X, PREVIOUS_PAGE_BUTTON_Y, method);
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    #[test]
+    fn test_jittered() {
+        assert_eq!(jittered(100, 100, 0), (100, 100));
+        assert_eq!(jittered(100, 100, 2), (94, 104));
+        assert_eq!(jittered(100, 100, 4), (100, 100));
+        assert_eq!(jittered(0, 0, 2), (0, 4));
+    }
+
+    #[test]
+    fn test_to_client() {
+        assert_eq!(to_client(1664, 616, 1920, 1080), (1664, 616));
+        assert_eq!(to_client(1664, 616, 1280, 720), (1109, 411));
+        assert_eq!(ClickMethod::Mouse.fallback().fallback(), ClickMethod::Mouse);
+    }
 }
@@ -0,0 +1,256 @@
+use anyhow::{anyhow, Result};
+use chrono::{Local, NaiveDateTime, TimeZone};
+use lazy_static::lazy_static;
+use serde::Deserialize;
+
+use crate::record::{BannerType, ItemType};
+
+/// 卡池排期数据，随游戏版本更新手动维护
+static BANNER_SCHEDULE_JSON: &str = include_str!("../data/banner_schedule.json");
+
+/// 数据文件中的一期限定卡池
+#[derive(Debug, Clone, Deserialize)]
+struct RawBannerPhase {
+    item_type: ItemType,
+    start: String,
+    end: String,
+    featured_5_star: Vec<String>,
+    featured_4_star: Vec<String>,
+}
+
+#[derive(Debug, Clone, Deserialize)]
+struct RawBannerSchedule {
+    covered_until: Option<String>,
+    phases: Vec<RawBannerPhase>,
+}
+
+/// 一期限定卡池的UP物品
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct FeaturedItems {
+    /// UP的5星物品名称（包含各语言的名称）
+    pub five_star: Vec<String>,
+    /// UP的4星物品名称（包含各语言的名称）
+    pub four_star: Vec<String>,
+}
+
+impl FeaturedItems {
+    /// 物品是否是本期UP物品
+    pub fn is_featured(&self, item_name: &str) -> bool {
+        self.five_star
+            .iter()
+            .chain(self.four_star.iter())
+            .any(|name| name == item_name)
+    }
+}
+
+/// 一期限定卡池
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct BannerPhase {
+    /// 卡池物品类型（角色池/武器池）
+    pub item_type: ItemType,
+    /// 开始时间戳（包含）
+    pub start: u64,
+    /// 结束时间戳（包含）
+    pub end: u64,
+    pub featured: FeaturedItems,
+}
+
+impl BannerPhase {
+    pub fn contains(&self, timestamp: u64) -> bool {
+        self.start <= timestamp && timestamp <= self.end
+    }
+}
+
+fn parse_time(time_str: &str) -> Result<u64> {
+    let time = NaiveDateTime::parse_from_str(time_str, "%Y-%m-%d %H:%M")
+        .map_err(|e| anyhow!("Failed to parse date time: {time_str}, {:?}", e))?;
+    let local_date_time = Local
+        .from_local_datetime(&time)
+        .single()
+        .ok_or(anyhow!("Invalid local date time: {}", time_str))?;
+    Ok(local_date_time.timestamp() as u64)
+}
+
+/// 限定卡池排期表
+/// 只有 `covered_until` 之前的时间使用排期表，之前的每一期限定卡池和UP的5星、4星都已经录入；
+/// 没有设置 `covered_until` 时排期表还不完整，UP标记、大保底状态、开放时间检查和歪的比例都不启用
+#[derive(Debug, Clone, Default)]
+pub struct BannerSchedule {
+    phases: Vec<BannerPhase>,
+    /// 排期表完整录入到的时间戳（包含）
+    covered_until: Option<u64>,
+}
+
+impl BannerSchedule {
+    /// 从 banner_schedule.json 格式的数据中读取排期表
+    pub fn from_json(json: &str) -> Result<Self> {
+        let raw = serde_json::from_str::<RawBannerSchedule>(json)?;
+        let phases = raw
+            .phases
+            .into_iter()
+            .map(|phase| {
+                Ok(BannerPhase {
+                    item_type: phase.item_type,
+                    start: parse_time(&phase.start)?,
+                    end: parse_time(&phase.end)?,
+                    featured: FeaturedItems {
+                        five_star: phase.featured_5_star,
+                        four_star: phase.featured_4_star,
+                    },
+                })
+            })
+            .collect::<Result<Vec<_>>>()?;
+        let covered_until = raw.covered_until.as_deref().map(parse_time).transpose()?;
+        Ok(Self {
+            phases,
+            covered_until,
+        })
+    }
+
+    /// 排期表是否已经录入，没有录入时不导出UP列
+    pub fn is_maintained(&self) -> bool {
+        self.covered_until.is_some()
+    }
+
+    /// 时间是否在排期表完整录入的范围内
+    fn covers(&self, timestamp: u64) -> bool {
+        let first_start = self.phases.iter().map(|phase| phase.start).min();
+        match (first_start, self.covered_until) {
+            (Some(first_start), Some(covered_until)) => {
+                first_start <= timestamp && timestamp <= covered_until
+            }
+            _ => false,
+        }
+    }
+
+    /// 查询某个时间点某个限定卡池的UP物品
+    /// # 返回
+    /// 非限定卡池、时间不在排期表完整录入的范围内或者排期表中没有对应的卡池时返回 None
+    pub fn featured_items(
+        &self,
+        timestamp: u64,
+        banner_type: BannerType,
+    ) -> Option<&FeaturedItems> {
+        if !banner_type.is_limited() || !self.covers(timestamp) {
+            return None;
+        }
+        let item_type = banner_type.item_type()?;
+        self.phases
+            .iter()
+            .find(|phase| phase.item_type == item_type && phase.contains(timestamp))
+            .map(|phase| &phase.featured)
+    }
+
+    /// 限定卡池在某个时间是否开放
+    /// 排期表中相邻两期之间没有这类卡池，时间落在其中说明选错了卡池或者日期识别有误
+    /// # 返回
+    /// 非限定卡池，或者时间不在排期表完整录入的范围内时返回 None
+    pub fn is_available(&self, timestamp: u64, banner_type: BannerType) -> Option<bool> {
+        if !banner_type.is_limited() || !self.covers(timestamp) {
+            return None;
+        }
+        let item_type = banner_type.item_type()?;
+        Some(
+            self.phases
+                .iter()
+                .any(|phase| phase.item_type == item_type && phase.contains(timestamp)),
+        )
+    }
+}
+
+lazy_static! {
+    static ref BANNER_SCHEDULE: BannerSchedule = BannerSchedule::from_json(BANNER_SCHEDULE_JSON)
+        .unwrap_or_else(|e| {
+            log::error!("Failed to parse banner schedule: {:?}", e);
+            BannerSchedule::default()
+        });
+}
+
+/// data/banner_schedule.json 中的排期表
+pub fn banner_schedule() -> &'static BannerSchedule {
+    &BANNER_SCHEDULE
+}
+
+/// 按排期表查询UP物品，见 [`BannerSchedule::featured_items`]
+pub fn featured_items(timestamp: u64, banner_type: BannerType) -> Option<&'static FeaturedItems> {
+    BANNER_SCHEDULE.featured_items(timestamp, banner_type)
+}
+
+/// 按排期表查询限定卡池是否开放，见 [`BannerSchedule::is_available`]
+pub fn is_available(timestamp: u64, banner_type: BannerType) -> Option<bool> {
+    BANNER_SCHEDULE.is_available(timestamp, banner_type)
+}
+
+#[cfg(test)]
+pub(crate) mod test {
+    use super::*;
+
+    /// 完整录入到第一期结束的测试排期表，与真实的排期表分开
+    static TEST_SCHEDULE_JSON: &str = r#"{
+        "covered_until": "2023-08-31 03:59",
+        "phases": [
+            {
+                "item_type": "Character",
+                "start": "2023-07-20 10:00",
+                "end": "2023-08-10 03:59",
+                "featured_5_star": ["里芙·无限之视", "Lyfe - Wild Hunt"],
+                "featured_4_star": ["测试角色"]
+            }
+        ]
+    }"#;
+
+    pub(crate) fn test_schedule() -> BannerSchedule {
+        BannerSchedule::from_json(TEST_SCHEDULE_JSON).unwrap()
+    }
+
+    #[test]
+    fn test_parse_bundled_schedule() {
+        let schedule = BannerSchedule::from_json(BANNER_SCHEDULE_JSON).unwrap();
+        for phase in schedule.phases {
+            assert!(phase.start < phase.end);
+            assert!(!phase.featured.five_star.is_empty());
+        }
+    }
+
+    #[test]
+    fn test_featured_items() {
+        let schedule = test_schedule();
+        let phase = schedule.phases.first().unwrap();
+        let banner_type = BannerType::LimitedCharacter50Percent;
+        let featured = schedule.featured_items(phase.start, banner_type).unwrap();
+        assert!(featured.is_featured("Lyfe - Wild Hunt"));
+        assert!(featured.is_featured("测试角色"));
+        assert!(schedule
+            .featured_items(phase.start, BannerType::PermanentCharacter)
+            .is_none());
+        assert!(schedule.featured_items(0, banner_type).is_none());
+        // 没有录入完整的排期表不使用
+        assert!(BannerSchedule::default()
+            .featured_items(phase.start, banner_type)
+            .is_none());
+    }
+
+    #[test]
+    fn test_is_available() {
+        let schedule = test_schedule();
+        let phase = schedule.phases.first().unwrap();
+        let banner_type = BannerType::LimitedCharacter50Percent;
+        assert_eq!(schedule.is_available(phase.start, banner_type), Some(true));
+        assert_eq!(schedule.is_available(phase.end, banner_type), Some(true));
+        // 两期之间
+        assert_eq!(
+            schedule.is_available(phase.end + 60, banner_type),
+            Some(false)
+        );
+        // 排期表还没有录入
+        assert_eq!(
+            schedule.is_available(schedule.covered_until.unwrap() + 60, banner_type),
+            None
+        );
+        assert_eq!(schedule.is_available(0, banner_type), None);
+        assert_eq!(
+            schedule.is_available(phase.start, BannerType::PermanentWeapon),
+            None
+        );
+    }
+}
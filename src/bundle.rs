@@ -0,0 +1,214 @@
+//! 数据包
+//!
+//! 把抽卡记录、配置和备份打包成一个 zip 文件，在另一台电脑上导入时与本地的抽卡记录合并，
+//! 换电脑时不会丢失保底进度。
+//! 只打包可以共享的配置，本机的字段（包括 WebDAV 同步密码）不会打包。
+
+use std::fs::File;
+use std::io::{Read, Write};
+use std::path::{Path, PathBuf};
+
+use anyhow::{anyhow, Result};
+use zip::write::SimpleFileOptions;
+use zip::{CompressionMethod, ZipArchive, ZipWriter};
+
+use crate::config::portable_config;
+use crate::data_dir::data_dir;
+use crate::record::TotalRecords;
+
+static RECORDS_FILE: &str = "records/records.json";
+static CONFIG_FILE: &str = "config.json";
+/// 备份目录，其中的文件都会被打包
+static BACKUP_DIR: &str = "records/backups";
+
+/// 导入数据包的结果
+#[derive(Debug)]
+pub struct BundleImport {
+    /// 合并后新增的抽卡记录数量
+    pub added: u32,
+    /// 复制到本地的备份文件数量
+    pub backups: usize,
+    /// 是否用数据包中的配置覆盖了本地配置
+    pub config_replaced: bool,
+}
+
+/// 把数据目录中的文件打包到 `path`
+/// # 返回
+/// 打包的文件数量
+pub fn export_bundle(path: &Path) -> Result<usize> {
+    export_bundle_from(data_dir(), path)
+}
+
+/// 导入数据包，抽卡记录与本地的合并
+/// # 参数
+/// - `with_config`: 是否用数据包中的配置覆盖本地配置
+pub fn import_bundle(path: &Path, with_config: bool) -> Result<BundleImport> {
+    import_bundle_to(data_dir(), path, with_config)
+}
+
+/// 需要打包的文件，相对于数据目录的路径
+fn bundle_files(data_dir: &Path) -> Result<Vec<PathBuf>> {
+    let mut files = [RECORDS_FILE, CONFIG_FILE]
+        .into_iter()
+        .map(PathBuf::from)
+        .filter(|file| data_dir.join(file).is_file())
+        .collect::<Vec<_>>();
+    let backup_dir = data_dir.join(BACKUP_DIR);
+    if backup_dir.is_dir() {
+        for entry in backup_dir.read_dir()? {
+            let path = entry?.path();
+            if path.is_file() {
+                files.push(Path::new(BACKUP_DIR).join(path.file_name().unwrap()));
+            }
+        }
+    }
+    Ok(files)
+}
+
+/// zip 中统一使用 `/` 作为路径分隔符
+fn zip_name(file: &Path) -> String {
+    file.components()
+        .map(|component| component.as_os_str().to_string_lossy())
+        .collect::<Vec<_>>()
+        .join("/")
+}
+
+fn export_bundle_from(data_dir: &Path, path: &Path) -> Result<usize> {
+    let files = bundle_files(data_dir)?;
+    if !files.iter().any(|file| file == Path::new(RECORDS_FILE)) {
+        return Err(anyhow!("no records to export"));
+    }
+    if let Some(parent) = path.parent() {
+        std::fs::create_dir_all(parent)?;
+    }
+    let mut zip = ZipWriter::new(File::create(path)?);
+    let options = SimpleFileOptions::default().compression_method(CompressionMethod::Deflated);
+    for file in files.iter() {
+        let mut contents = std::fs::read(data_dir.join(file))?;
+        // 旧版本的 config.json 中可能还有本机的字段
+        if file == Path::new(CONFIG_FILE) {
+            let config = serde_json::from_slice(&contents)?;
+            contents = serde_json::to_vec_pretty(&portable_config(config))?;
+        }
+        zip.start_file(zip_name(file), options)?;
+        zip.write_all(&contents)?;
+    }
+    zip.finish()?;
+    Ok(files.len())
+}
+
+fn import_bundle_to(data_dir: &Path, path: &Path, with_config: bool) -> Result<BundleImport> {
+    let mut zip = ZipArchive::new(File::open(path)?)?;
+
+    // 抽卡记录逐个卡池合并
+    let mut records_json = String::new();
+    zip.by_name(RECORDS_FILE)
+        .map_err(|_| anyhow!("{} not found in bundle", RECORDS_FILE))?
+        .read_to_string(&mut records_json)?;
+    let bundle_records: TotalRecords = serde_json::from_str(&records_json)?;
+    bundle_records.warn_checksum_mismatches(&path.display().to_string());
+    let records_path = data_dir.join(RECORDS_FILE);
+    let mut total_records = if records_path.exists() {
+        TotalRecords::read_from(&records_path)?
+    } else {
+        TotalRecords::default()
+    };
+    let added = total_records.merge(bundle_records)?;
+    total_records.save_to(&records_path)?;
+
+    // 本地已有的备份不覆盖
+    let mut backups = 0;
+    for i in 0..zip.len() {
+        let mut file = zip.by_index(i)?;
+        let Some(name) = file.enclosed_name() else {
+            continue;
+        };
+        if !name.starts_with(BACKUP_DIR) {
+            continue;
+        }
+        let target = data_dir.join(name);
+        if target.exists() {
+            continue;
+        }
+        if let Some(parent) = target.parent() {
+            std::fs::create_dir_all(parent)?;
+        }
+        std::io::copy(&mut file, &mut File::create(target)?)?;
+        backups += 1;
+    }
+
+    let config_replaced = if with_config {
+        match zip.by_name(CONFIG_FILE) {
+            Ok(mut file) => {
+                std::io::copy(&mut file, &mut File::create(data_dir.join(CONFIG_FILE))?)?;
+                true
+            }
+            Err(_) => false,
+        }
+    } else {
+        false
+    };
+
+    Ok(BundleImport {
+        added,
+        backups,
+        config_replaced,
+    })
+}
+
+#[cfg(test)]
+mod test {
+    use std::collections::HashMap;
+
+    use super::*;
+    use crate::record::{BannerType, ImportBatch, ItemType, OneRecord, RecordSource};
+
+    #[test]
+    fn test_export_and_import_bundle() {
+        let dir = std::env::temp_dir().join("snowbreak_gacha_export_test_bundle");
+        let _ = std::fs::remove_dir_all(&dir);
+        let (from_dir, to_dir) = (dir.join("from"), dir.join("to"));
+        let record = |timestamp| OneRecord::new(3, "a".to_string(), ItemType::Weapon, timestamp);
+        let save = |data_dir: &Path, records: Vec<OneRecord>| {
+            let mut total_records = TotalRecords::new(HashMap::new());
+            let batch = ImportBatch::new(BannerType::PermanentWeapon, RecordSource::Ocr);
+            total_records
+                .add_record("id".to_string(), batch, records)
+                .unwrap();
+            total_records.save_to(data_dir.join(RECORDS_FILE)).unwrap();
+        };
+        save(&from_dir, vec![record(3), record(2), record(1)]);
+        std::fs::create_dir_all(from_dir.join(BACKUP_DIR)).unwrap();
+        std::fs::write(from_dir.join(BACKUP_DIR).join("backup.json"), "{}").unwrap();
+        save(&to_dir, vec![record(2), record(1)]);
+        std::fs::write(
+            from_dir.join(CONFIG_FILE),
+            r#"{"language": "English", "sync": {"enabled": true, "endpoint": "", "username": "u", "password": "p"}}"#,
+        )
+        .unwrap();
+
+        let bundle_path = dir.join("bundle.zip");
+        assert_eq!(export_bundle_from(&from_dir, &bundle_path).unwrap(), 3);
+        // 同步密码不会打包
+        let mut config = String::new();
+        ZipArchive::new(File::open(&bundle_path).unwrap())
+            .unwrap()
+            .by_name(CONFIG_FILE)
+            .unwrap()
+            .read_to_string(&mut config)
+            .unwrap();
+        let config = serde_json::from_str::<serde_json::Value>(&config).unwrap();
+        assert_eq!(config["language"], "English");
+        assert!(config.get("sync").is_none());
+        let bundle_import = import_bundle_to(&to_dir, &bundle_path, false).unwrap();
+        assert_eq!(bundle_import.added, 1);
+        assert_eq!(bundle_import.backups, 1);
+        assert!(!bundle_import.config_replaced);
+        let total_records = TotalRecords::read_from(to_dir.join(RECORDS_FILE)).unwrap();
+        assert_eq!(
+            total_records.records["id"].records[&BannerType::PermanentWeapon],
+            vec![record(3), record(2), record(1)]
+        );
+        std::fs::remove_dir_all(&dir).unwrap();
+    }
+}
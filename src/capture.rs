@@ -4,9 +4,19 @@ use std::time::{Duration, Instant};
 
 use anyhow::{anyhow, Result};
 use client_capture::ClientCapture;
+use image::imageops::FilterType;
 use image::{DynamicImage, GenericImageView};
 use lazy_static::lazy_static;
 
+/// 比较连续两帧时缩小到的尺寸
+static COMPARE_SIZE: (u32, u32) = (160, 90);
+/// 缩小后的一行像素平均亮度差超过这个值时认为两帧不一致
+static MAX_ROW_DIFF: f32 = 8.0;
+/// 连续两帧不一致时最多再截取的次数
+static FRAME_RETRIES: u32 = 3;
+/// 连续两次截取之间的间隔
+static FRAME_INTERVAL: Duration = Duration::from_millis(20);
+
 lazy_static! {
     static ref CLIENT_CAPTURE: Arc<Mutex<Option<ClientCapture>>> = Arc::new(Mutex::new(None));
 }
@@ -15,7 +25,7 @@ fn is_capture_initialized() -> bool {
     CLIENT_CAPTURE.lock().unwrap().is_some()
 }
 
-pub fn init_capture(window_title: String) {
+fn init_capture(window_title: String) {
     if !is_capture_initialized() {
         let mut client_capture = ClientCapture::new(
             "UnrealWindow".to_string(),
@@ -37,27 +47,110 @@ pub fn init_capture(window_title: String) {
     }
 }
 
-pub fn release_capture() {
+fn release_capture() {
     if is_capture_initialized() {
         let client_capture = CLIENT_CAPTURE.lock().unwrap().take().unwrap();
         client_capture.stop();
     }
 }
 
-pub fn capture_image() -> Result<DynamicImage> {
+/// 截图会话，创建时开始截图，离开作用域时停止截图
+/// 出错提前返回时也会释放截图工具
+pub struct CaptureSession(());
+
+impl CaptureSession {
+    pub fn start(window_title: String) -> Self {
+        init_capture(window_title);
+        Self(())
+    }
+}
+
+impl Drop for CaptureSession {
+    fn drop(&mut self) {
+        release_capture();
+    }
+}
+
+fn grab_image() -> Result<DynamicImage> {
     let mut client_capture = CLIENT_CAPTURE.lock().unwrap().take().unwrap();
     let image_result = client_capture.get_img();
     CLIENT_CAPTURE.lock().unwrap().replace(client_capture);
-    let image = image_result?;
+    image_result
+}
+
+/// 两帧画面是否一致
+/// 画面撕裂时一部分行来自上一帧，按行比较缩小后的亮度，任意一行差异过大都视为不一致
+fn frames_match(a: &DynamicImage, b: &DynamicImage) -> bool {
+    if a.dimensions() != b.dimensions() {
+        return false;
+    }
+    let (width, height) = COMPARE_SIZE;
+    let a = a
+        .resize_exact(width, height, FilterType::Triangle)
+        .to_luma8();
+    let b = b
+        .resize_exact(width, height, FilterType::Triangle)
+        .to_luma8();
+    a.rows().zip(b.rows()).all(|(row_a, row_b)| {
+        let diff = row_a
+            .zip(row_b)
+            .map(|(pa, pb)| pa.0[0].abs_diff(pb.0[0]) as u32)
+            .sum::<u32>();
+        diff as f32 / width as f32 <= MAX_ROW_DIFF
+    })
+}
+
+/// 截取游戏画面
+/// 截图偶尔会取到正在刷新的帧，画面撕裂导致某一行识别错误。
+/// 连续截取两帧，一致时才使用，不一致时丢弃较早的一帧重新截取，超过次数后使用最后一帧
+pub fn capture_image() -> Result<DynamicImage> {
+    let mut image = grab_image()?;
+    let mut stable = false;
+    for attempt in 0..=FRAME_RETRIES {
+        sleep(FRAME_INTERVAL);
+        let next = grab_image()?;
+        stable = frames_match(&image, &next);
+        image = next;
+        if stable {
+            break;
+        }
+        log::debug!("frames differ, capture again (attempt {})", attempt + 1);
+    }
+    if !stable {
+        log::warn!("frames are still changing after {FRAME_RETRIES} retries, using the last one");
+    }
     let image_size = image.dimensions();
     let ratio = num_rational::Ratio::new(image_size.0 as i64, image_size.1 as i64);
     if ratio != num_rational::Ratio::new(16, 9) {
         return Err(anyhow!("Invalid image ratio: {:?}", ratio));
     }
-    let image = if image_size.0 == 1920 {
-        image
-    } else {
-        image.resize_exact(1920, 1080, image::imageops::FilterType::Lanczos3)
-    };
+    // 保持原分辨率，坐标由 RecordImage 的 Layout 缩放
     Ok(image)
 }
+
+#[cfg(test)]
+mod test {
+    use image::{GrayImage, Luma};
+
+    use super::*;
+
+    #[test]
+    fn test_frames_match() {
+        let frame = |torn_rows: u32| {
+            DynamicImage::ImageLuma8(GrayImage::from_fn(320, 180, |x, y| {
+                if y < torn_rows {
+                    Luma([255 - (x % 256) as u8])
+                } else {
+                    Luma([(x % 256) as u8])
+                }
+            }))
+        };
+        assert!(frames_match(&frame(0), &frame(0)));
+        // 上面一部分来自另一帧
+        assert!(!frames_match(&frame(0), &frame(20)));
+        assert!(!frames_match(
+            &frame(0),
+            &DynamicImage::ImageLuma8(GrayImage::new(160, 90))
+        ));
+    }
+}
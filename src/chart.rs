@@ -0,0 +1,95 @@
+//! 终端图表
+//!
+//! 用 Unicode 方块字符在控制台画横向条形图，不打开 Excel 也能直观地看到统计结果。
+
+use crate::i18n::tr;
+use crate::record::OneAccountRecords;
+use crate::stats::{period_pulls, pity_histogram, Period};
+
+/// 条形的最大宽度（字符数）
+static BAR_WIDTH: usize = 40;
+/// 1/8 到 7/8 宽度的方块
+static PARTIAL_BLOCKS: [char; 7] = ['▏', '▎', '▍', '▌', '▋', '▊', '▉'];
+/// 抽数分布每组包含的抽数
+static HISTOGRAM_BUCKET: u32 = 10;
+
+/// 按最大值缩放到 `width` 个字符的条形，精度为 1/8 字符
+fn bar(value: u32, max: u32, width: usize) -> String {
+    if max == 0 {
+        return String::new();
+    }
+    let eighths = (value as u64 * width as u64 * 8).div_ceil(max as u64) as usize;
+    let mut bar = "█".repeat(eighths / 8);
+    let partial = eighths % 8;
+    if partial > 0 {
+        bar.push(PARTIAL_BLOCKS[partial - 1]);
+    }
+    bar
+}
+
+/// 横向条形图，每行为 标签、条形、数值
+pub fn bar_chart(rows: &[(String, u32)]) -> Vec<String> {
+    let label_width = rows
+        .iter()
+        .map(|(label, _)| label.chars().count())
+        .max()
+        .unwrap_or(0);
+    let max = rows.iter().map(|(_, value)| *value).max().unwrap_or(0);
+    rows.iter()
+        .map(|(label, value)| {
+            format!(
+                "{label:>label_width$} │{} {value}",
+                bar(*value, max, BAR_WIDTH)
+            )
+        })
+        .collect()
+}
+
+/// 打印每月抽卡次数和出5星抽数分布的图表
+pub fn print_charts(account_records: &OneAccountRecords) {
+    let mut monthly_pulls: Vec<(String, u32)> = vec![];
+    for period_pulls in period_pulls(account_records, Period::Month) {
+        match monthly_pulls.last_mut() {
+            Some((period, pulls)) if *period == period_pulls.period => *pulls += period_pulls.pulls,
+            _ => monthly_pulls.push((period_pulls.period, period_pulls.pulls)),
+        }
+    }
+    if !monthly_pulls.is_empty() {
+        println!("{}", tr!("chart.monthly_pulls"));
+        for line in bar_chart(&monthly_pulls) {
+            println!("  {line}");
+        }
+    }
+
+    let histogram = pity_histogram(account_records, HISTOGRAM_BUCKET)
+        .into_iter()
+        .map(|(range, count)| (format!("{}-{}", range.start(), range.end()), count))
+        .collect::<Vec<_>>();
+    if !histogram.is_empty() {
+        println!("{}", tr!("chart.pity_histogram"));
+        for line in bar_chart(&histogram) {
+            println!("  {line}");
+        }
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    #[test]
+    fn test_bar_chart() {
+        assert_eq!(bar(0, 0, 4), "");
+        assert_eq!(bar(4, 4, 4), "████");
+        assert_eq!(bar(1, 4, 4), "█");
+        assert_eq!(bar(1, 8, 1), "▏");
+        assert_eq!(bar(3, 4, 1), "▊");
+        let rows = [("2024-05".to_string(), 10), ("2024-06".to_string(), 5)];
+        let lines = bar_chart(&rows);
+        assert_eq!(lines[0], format!("2024-05 │{} 10", "█".repeat(BAR_WIDTH)));
+        assert_eq!(
+            lines[1],
+            format!("2024-06 │{} 5", "█".repeat(BAR_WIDTH / 2))
+        );
+    }
+}
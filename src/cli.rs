@@ -0,0 +1,326 @@
+use std::path::PathBuf;
+
+use clap::{Parser, Subcommand};
+
+use crate::export::ExportFormat;
+use crate::import::ImportMode;
+use crate::importer::ImportFormat;
+use crate::language::Language;
+use crate::logging::LogLevel;
+use crate::record::BannerType;
+
+/// 尘白禁区抽卡记录导出工具
+#[derive(Debug, Parser)]
+#[command(version, about)]
+pub struct Cli {
+    /// 非交互模式：不等待输入，结束时写入 result.json 并以退出码表示结果
+    #[arg(long)]
+    pub non_interactive: bool,
+
+    /// 账号ID，非交互模式下必须提供
+    #[arg(long)]
+    pub account: Option<String>,
+
+    /// 卡池类型，非交互模式下必须提供
+    #[arg(long, value_enum)]
+    pub banner: Option<BannerType>,
+
+    /// 显示所有卡池的导入计划后依次导入，只默认选中配置文件中 `account_banners` 设置的这个账号使用的卡池，不能用于非交互模式
+    #[arg(long, conflicts_with_all = ["banner", "non_interactive"])]
+    pub banner_all: bool,
+
+    /// 导入模式，默认使用配置文件中的设置
+    #[arg(long, value_enum)]
+    pub import_mode: Option<ImportMode>,
+
+    /// 导入后导出的格式，可以用逗号分隔多个，默认使用配置文件中的设置
+    #[arg(long, value_enum, value_delimiter = ',')]
+    pub format: Vec<ExportFormat>,
+
+    /// Excel 每个卡池导出为一个文件，默认使用配置文件中的设置
+    #[arg(long)]
+    pub split_per_banner: bool,
+
+    /// 导出文件使用的语言，默认使用配置文件中的设置，没有设置时与界面语言相同
+    #[arg(long, value_enum)]
+    pub export_language: Option<Language>,
+
+    /// 导出时按物品字典把物品名称翻译为导出语言，默认使用配置文件中的设置
+    #[arg(long)]
+    pub translate_item_names: bool,
+
+    /// 日志级别，默认使用配置文件中的设置
+    #[arg(long, value_enum)]
+    pub log_level: Option<LogLevel>,
+
+    /// 安静模式：控制台只显示翻页进度和导入结果，日志只写入日志文件
+    #[arg(long)]
+    pub quiet: bool,
+
+    /// 把每一页截图画上计算出的行和单元格的位置，保存到数据目录的 debug_boxes 目录中
+    #[arg(long)]
+    pub debug_boxes: bool,
+
+    #[command(subcommand)]
+    pub command: Option<Command>,
+}
+
+#[derive(Debug, Subcommand)]
+pub enum Command {
+    /// 撤销一个卡池最近一次导入新增的抽卡记录
+    Undo {
+        /// 账号ID
+        #[arg(long)]
+        account: String,
+        /// 卡池类型
+        #[arg(long, value_enum)]
+        banner: BannerType,
+    },
+    /// 输入游戏内的保底计数，早期抽卡记录已经过期时用来补全保底计算
+    PityAnchor {
+        /// 账号ID
+        #[arg(long)]
+        account: String,
+        /// 卡池类型
+        #[arg(long, value_enum)]
+        banner: BannerType,
+        /// 距离上一个5星已经抽了多少次
+        #[arg(long)]
+        pulls_since_5_star: u32,
+        /// 距离上一个4星已经抽了多少次
+        #[arg(long, default_value_t = 0)]
+        pulls_since_4_star: u32,
+    },
+    /// 不截图，只根据已保存的抽卡记录输出每个卡池当前的保底计数
+    Pity {
+        /// 账号ID，默认使用配置文件中的默认账号，没有默认账号时输出所有账号
+        #[arg(long)]
+        account: Option<String>,
+    },
+    /// 从当前的保底计数开始模拟接下来的抽卡，估计获得5星和当期限定物品的概率
+    Simulate {
+        /// 卡池类型
+        #[arg(long, value_enum)]
+        banner: BannerType,
+        /// 抽卡次数
+        #[arg(long)]
+        pulls: u32,
+        /// 账号ID，用这个账号的抽卡记录计算当前的保底计数
+        #[arg(long)]
+        account: Option<String>,
+        /// 距离上一个5星已经抽了多少次，提供时不使用抽卡记录计算
+        #[arg(long)]
+        pity: Option<u32>,
+        /// 上一个5星不是当期限定物品，下一个5星必定是当期限定物品
+        #[arg(long)]
+        guaranteed: bool,
+        /// 模拟次数
+        #[arg(long, default_value_t = 10000)]
+        trials: u32,
+    },
+    /// 比较两个 records.json，列出每个卡池新增和删除的记录
+    Diff {
+        /// 旧的 records.json，例如备份
+        old: PathBuf,
+        /// 新的 records.json，默认为当前的抽卡记录
+        new: Option<PathBuf>,
+    },
+    /// 按账号、卡池合并两个 records.json，例如两台电脑上分别导入的抽卡记录
+    Merge {
+        /// 第一个 records.json
+        first: PathBuf,
+        /// 第二个 records.json
+        second: PathBuf,
+        /// 合并结果的保存路径
+        #[arg(short, long)]
+        output: PathBuf,
+    },
+    /// 按保留策略把较早的抽卡记录移到归档文件 records/archive.json
+    Prune {
+        /// 只保留最近几年的抽卡记录，默认使用配置文件中的 retention_years
+        #[arg(long)]
+        years: Option<u32>,
+    },
+    /// 导入其他抽卡记录工具导出的 JSON/CSV 文件，与保存的抽卡记录合并
+    ImportFile {
+        /// 文件路径
+        path: PathBuf,
+        /// 文件格式，默认根据文件内容判断
+        #[arg(long)]
+        format: Option<ImportFormat>,
+        /// 文件中没有账号ID时使用的账号ID，默认使用配置文件中的 default_account_id
+        #[arg(long)]
+        account: Option<String>,
+    },
+    /// 在本机启动统计页面，内容与 HTML 导出相同，每次打开时读取最新的抽卡记录
+    Serve {
+        /// 监听的端口
+        #[arg(long, default_value_t = crate::serve::DEFAULT_PORT)]
+        port: u16,
+    },
+    /// 给一条抽卡记录添加备注，例如“为活动队伍抽的”，导出时附加备注列
+    Annotate {
+        /// 账号ID
+        #[arg(long)]
+        account: String,
+        /// 卡池类型
+        #[arg(long, value_enum)]
+        banner: BannerType,
+        /// 抽卡时间，格式与导出中的相同，例如 `2024-06-01 10:00`
+        #[arg(long)]
+        time: String,
+        /// 物品名称，同一时间有多种物品（十连）时必须提供
+        #[arg(long)]
+        name: Option<String>,
+        /// 同一时间（和名称）的记录中按游戏中从上到下的顺序是第几条，从 1 开始，
+        /// 十连中有多条相同的物品时必须提供
+        #[arg(long)]
+        index: Option<usize>,
+        /// 备注，不提供时删除备注
+        note: Option<String>,
+    },
+    /// 按物品名称查找所有账号、卡池中的抽卡记录和当时的保底计数
+    Find {
+        /// 物品名称，没有完全相同的名称时查找包含它的名称
+        name: String,
+    },
+    /// 账号维护，例如把以前用默认账号ID导入的记录迁移到真实的UID
+    Account {
+        #[command(subcommand)]
+        action: AccountAction,
+    },
+    /// 数据包，用于在多台电脑之间同步抽卡记录
+    Bundle {
+        #[command(subcommand)]
+        action: BundleAction,
+    },
+}
+
+#[derive(Debug, Subcommand)]
+pub enum AccountAction {
+    /// 修改账号ID
+    Rename {
+        /// 原来的账号ID
+        old: String,
+        /// 新的账号ID，不能已经存在
+        new: String,
+    },
+    /// 把一个账号的抽卡记录按卡池合并到另一个账号，然后删除这个账号
+    Merge {
+        /// 被合并的账号ID
+        from: String,
+        /// 合并到的账号ID
+        into: String,
+    },
+}
+
+#[derive(Debug, Subcommand)]
+pub enum BundleAction {
+    /// 把抽卡记录、配置和备份打包成 zip 文件
+    Export {
+        /// 数据包路径
+        path: PathBuf,
+    },
+    /// 导入数据包，抽卡记录与本地的合并
+    Import {
+        /// 数据包路径
+        path: PathBuf,
+        /// 用数据包中的配置覆盖本地配置
+        #[arg(long)]
+        with_config: bool,
+    },
+}
+
+#[cfg(test)]
+mod test {
+    use clap::CommandFactory;
+
+    use super::*;
+
+    #[test]
+    fn test_cli() {
+        Cli::command().debug_assert();
+        let cli = Cli::parse_from([
+            "snowbreak_gacha_export",
+            "undo",
+            "--account",
+            "123",
+            "--banner",
+            "permanent-weapon",
+        ]);
+        assert!(matches!(
+            cli.command,
+            Some(Command::Undo {
+                banner: BannerType::PermanentWeapon,
+                ..
+            })
+        ));
+        let cli = Cli::parse_from([
+            "snowbreak_gacha_export",
+            "merge",
+            "a.json",
+            "b.json",
+            "-o",
+            "out.json",
+        ]);
+        assert!(matches!(cli.command, Some(Command::Merge { .. })));
+        let cli = Cli::parse_from(["snowbreak_gacha_export", "serve"]);
+        assert!(matches!(
+            cli.command,
+            Some(Command::Serve { port }) if port == crate::serve::DEFAULT_PORT
+        ));
+        let cli = Cli::parse_from([
+            "snowbreak_gacha_export",
+            "annotate",
+            "--account",
+            "123",
+            "--banner",
+            "limited-character-50",
+            "--time",
+            "2024-06-01 10:00",
+            "event team",
+        ]);
+        assert!(matches!(
+            cli.command,
+            Some(Command::Annotate {
+                name: None,
+                index: None,
+                note: Some(_),
+                ..
+            })
+        ));
+        let cli = Cli::parse_from([
+            "snowbreak_gacha_export",
+            "annotate",
+            "--account",
+            "123",
+            "--banner",
+            "permanent-weapon",
+            "--time",
+            "2024-06-01 10:00",
+            "--name",
+            "a",
+            "--index",
+            "2",
+        ]);
+        assert!(matches!(
+            cli.command,
+            Some(Command::Annotate {
+                index: Some(2),
+                note: None,
+                ..
+            })
+        ));
+        let cli = Cli::parse_from(["snowbreak_gacha_export", "--export-language", "english"]);
+        assert_eq!(cli.export_language, Some(Language::English));
+        let cli = Cli::parse_from(["snowbreak_gacha_export", "--banner-all", "--account", "123"]);
+        assert!(cli.banner_all);
+        assert!(Cli::try_parse_from([
+            "snowbreak_gacha_export",
+            "--banner-all",
+            "--banner",
+            "beginner"
+        ])
+        .is_err());
+    }
+}
@@ -0,0 +1,301 @@
+//! 从剪贴板中的截图导入
+//!
+//! 监视剪贴板，用户在游戏中自己翻页并截图（Win+PrtSc、ShareX 等），
+//! 识别剪贴板中出现的每一张抽卡记录截图，按回车后合并保存为一次导入。
+//! 截图需要是 16:9 的游戏窗口客户区或全屏截图。
+
+use std::collections::BTreeMap;
+use std::time::Duration;
+
+use anyhow::{anyhow, Result};
+use image::{DynamicImage, GenericImageView, ImageFormat};
+use tokio::time::sleep;
+
+use crate::export::{ExportFormat, ExportOptions};
+use crate::i18n::{self, tr};
+use crate::import::{
+    check_banner_type, collect_rows, detect_client_language, finish_import, ImportMode,
+    ImportSummary,
+};
+use crate::ocr::{ocr_batch, save_ocr_cache};
+use crate::progress::{emit, ProgressEvent};
+use crate::record::{BannerType, ImportBatch, OneRecord, RecordSource};
+use crate::record_image::{parse_record_page, set_client_language, InvalidRow, ParseOptions};
+use crate::report::SessionReport;
+use crate::run_result::{Failure, FailureKind};
+use crate::user_interaction::try_read_line;
+
+/// 检查剪贴板的间隔
+static POLL_INTERVAL: Duration = Duration::from_millis(500);
+
+/// 截图宽高比与 16:9 的最大相对误差，1366x768 等分辨率不是严格的 16:9
+static ASPECT_RATIO_TOLERANCE: f64 = 0.01;
+
+/// BITMAPINFOHEADER 中的 BI_BITFIELDS 压缩方式，颜色掩码紧跟在信息头后面
+static BI_BITFIELDS: u32 = 3;
+
+/// 剪贴板内容的序号，每次剪贴板变化时增加
+#[cfg(windows)]
+fn clipboard_sequence_number() -> u32 {
+    unsafe { windows::Win32::System::DataExchange::GetClipboardSequenceNumber() }
+}
+
+#[cfg(not(windows))]
+fn clipboard_sequence_number() -> u32 {
+    0
+}
+
+/// 剪贴板中的 DIB 位图数据，剪贴板中没有图片时返回 `None`
+#[cfg(windows)]
+fn clipboard_dib() -> Result<Option<Vec<u8>>> {
+    use windows::Win32::Foundation::{HGLOBAL, HWND};
+    use windows::Win32::System::DataExchange::{
+        CloseClipboard, GetClipboardData, IsClipboardFormatAvailable, OpenClipboard,
+    };
+    use windows::Win32::System::Memory::{GlobalLock, GlobalSize, GlobalUnlock};
+
+    /// 标准剪贴板格式 CF_DIB
+    const CF_DIB: u32 = 8;
+
+    unsafe {
+        if IsClipboardFormatAvailable(CF_DIB).is_err() {
+            return Ok(None);
+        }
+        OpenClipboard(HWND::default())?;
+        let result = (|| {
+            let handle = GetClipboardData(CF_DIB)?;
+            let hglobal = HGLOBAL(handle.0 as _);
+            let pointer = GlobalLock(hglobal) as *const u8;
+            if pointer.is_null() {
+                return Err(anyhow!("failed to lock clipboard data"));
+            }
+            let dib = std::slice::from_raw_parts(pointer, GlobalSize(hglobal)).to_vec();
+            let _ = GlobalUnlock(hglobal);
+            Ok(Some(dib))
+        })();
+        let _ = CloseClipboard();
+        result
+    }
+}
+
+#[cfg(not(windows))]
+fn clipboard_dib() -> Result<Option<Vec<u8>>> {
+    Err(anyhow!("clipboard import is only supported on Windows"))
+}
+
+/// 在 DIB 数据前加上 BMP 文件头，得到完整的 BMP 文件
+fn dib_to_bmp(dib: &[u8]) -> Result<Vec<u8>> {
+    if dib.len() < 40 {
+        return Err(anyhow!("invalid DIB, length: {}", dib.len()));
+    }
+    let u32_at = |offset: usize| u32::from_le_bytes(dib[offset..offset + 4].try_into().unwrap());
+    let header_size = u32_at(0);
+    let bit_count = u16::from_le_bytes([dib[14], dib[15]]) as u32;
+    let compression = u32_at(16);
+    let colors_used = u32_at(32);
+    let masks_size = if header_size == 40 && compression == BI_BITFIELDS {
+        12
+    } else {
+        0
+    };
+    let palette_size = match colors_used {
+        0 if bit_count <= 8 => (1 << bit_count) * 4,
+        0 => 0,
+        colors_used => colors_used * 4,
+    };
+    let pixel_offset = 14 + header_size + masks_size + palette_size;
+    let file_size = 14 + dib.len() as u32;
+    let mut bmp = Vec::with_capacity(file_size as usize);
+    bmp.extend_from_slice(b"BM");
+    bmp.extend_from_slice(&file_size.to_le_bytes());
+    bmp.extend_from_slice(&[0; 4]);
+    bmp.extend_from_slice(&pixel_offset.to_le_bytes());
+    bmp.extend_from_slice(dib);
+    Ok(bmp)
+}
+
+/// 剪贴板中的图片
+fn clipboard_image() -> Result<Option<DynamicImage>> {
+    let Some(dib) = clipboard_dib()? else {
+        return Ok(None);
+    };
+    let image = image::load_from_memory_with_format(&dib_to_bmp(&dib)?, ImageFormat::Bmp)?;
+    Ok(Some(image))
+}
+
+/// 是否是 16:9 的截图
+fn is_16_9(image: &DynamicImage) -> bool {
+    let (width, height) = image.dimensions();
+    height > 0
+        && ((width as f64 / height as f64) / (16.0 / 9.0) - 1.0).abs() <= ASPECT_RATIO_TOLERANCE
+}
+
+/// 监视剪贴板，识别其中的抽卡记录截图，用户按回车后合并保存
+pub async fn import_from_clipboard(
+    account_id: &str,
+    banner_type: BannerType,
+    import_mode: ImportMode,
+    formats: &[ExportFormat],
+    export_options: &ExportOptions,
+) -> Result<ImportSummary, Failure> {
+    println!("{}", tr!("clipboard.start"));
+    set_client_language(None);
+    // 页码 -> 这一页的识别结果，同一页截图多次时使用最后一次
+    let mut pages: BTreeMap<u32, Vec<Result<OneRecord, Box<InvalidRow>>>> = BTreeMap::new();
+    let mut last_sequence_number = clipboard_sequence_number();
+    // 按回车结束，提前返回时不会留下读取输入的任务
+    loop {
+        sleep(POLL_INTERVAL).await;
+        if try_read_line().is_some() {
+            break;
+        }
+        let sequence_number = clipboard_sequence_number();
+        if sequence_number == last_sequence_number {
+            continue;
+        }
+        last_sequence_number = sequence_number;
+        let image = match clipboard_image() {
+            Ok(Some(image)) => image,
+            Ok(None) => continue,
+            Err(e) => {
+                log::warn!("failed to read clipboard image: {:?}", e);
+                continue;
+            }
+        };
+        if !is_16_9(&image) {
+            println!("{}", tr!("clipboard.not_record_page"));
+            continue;
+        }
+        let options = ParseOptions::from_config();
+        // OCR 在阻塞线程池中进行，避免阻塞 tokio 运行时
+        let parsed_page =
+            tokio::task::spawn_blocking(move || parse_record_page(image, &options, &ocr_batch))
+                .await
+                .map_err(|e| {
+                    Failure::new(
+                        FailureKind::Ocr,
+                        anyhow!("Failed to join ocr task: {:?}", e),
+                    )
+                })?
+                .map_err(|e| Failure::new(FailureKind::Ocr, e))?;
+        let (Some(page), true) = (parsed_page.index, parsed_page.is_record_page()) else {
+            log::debug!("not a record page: {:?}", parsed_page.diagnostics);
+            println!("{}", tr!("clipboard.not_record_page"));
+            continue;
+        };
+        let rows = parsed_page.rows;
+        emit(ProgressEvent::PageCaptured { page });
+        emit(ProgressEvent::PageParsed {
+            page,
+            records: rows.iter().filter(|row| row.is_ok()).count(),
+            invalid_rows: rows.iter().filter(|row| row.is_err()).count(),
+        });
+        println!(
+            "{}",
+            tr!("clipboard.page_added", page = page, rows = rows.len())
+        );
+        pages.insert(page, rows);
+    }
+    if let Err(e) = save_ocr_cache() {
+        log::warn!("failed to save ocr cache: {:?}", e);
+    }
+    if pages.is_empty() {
+        return Err(Failure::new(
+            FailureKind::Capture,
+            anyhow!("{}", tr!("clipboard.no_pages")),
+        ));
+    }
+
+    let batch = ImportBatch::new(banner_type, RecordSource::Clipboard);
+    let mut report = SessionReport::new(account_id, banner_type, i18n::language());
+    let missing_pages = missing_pages(&pages.keys().copied().collect::<Vec<_>>());
+    if !missing_pages.is_empty() {
+        let warning = format!("missing pages: {:?}", missing_pages);
+        log::warn!("{warning}");
+        println!(
+            "{}",
+            tr!(
+                "clipboard.missing_pages",
+                pages = format!("{:?}", missing_pages)
+            )
+        );
+        report.add_warning(warning);
+    }
+    let mut records = vec![];
+    let mut invalid_rows = vec![];
+    for (page, rows) in pages {
+        report.add_page(page, &rows);
+        collect_rows(
+            page,
+            rows,
+            batch.provenance(Some(page)),
+            import_mode,
+            &mut records,
+            &mut invalid_rows,
+        )
+        .map_err(|e| Failure::new(FailureKind::Ocr, e))?;
+    }
+    detect_client_language(&records);
+    check_banner_type(banner_type, &records).map_err(|e| Failure::new(FailureKind::Capture, e))?;
+    finish_import(
+        account_id,
+        batch,
+        records,
+        invalid_rows,
+        report,
+        formats,
+        export_options,
+    )
+    .await
+}
+
+/// 第一页到最后一页之间缺少的页码
+fn missing_pages(pages: &[u32]) -> Vec<u32> {
+    let last_page = pages.iter().max().copied().unwrap_or(0);
+    (1..last_page)
+        .filter(|page| !pages.contains(page))
+        .collect()
+}
+
+#[cfg(test)]
+mod test {
+    use std::io::Cursor;
+
+    use image::{Rgb, RgbImage};
+
+    use super::*;
+
+    #[test]
+    fn test_dib_to_bmp() {
+        let image = RgbImage::from_fn(4, 2, |x, y| Rgb([x as u8 * 60, y as u8 * 100, 7]));
+        let mut bmp = Cursor::new(vec![]);
+        DynamicImage::ImageRgb8(image.clone())
+            .write_to(&mut bmp, ImageFormat::Bmp)
+            .unwrap();
+        // 去掉 BMP 文件头就是剪贴板中的 DIB 数据
+        let dib = &bmp.get_ref()[14..];
+        let decoded =
+            image::load_from_memory_with_format(&dib_to_bmp(dib).unwrap(), ImageFormat::Bmp)
+                .unwrap()
+                .to_rgb8();
+        assert_eq!(decoded, image);
+        assert!(dib_to_bmp(&dib[..20]).is_err());
+    }
+
+    #[test]
+    fn test_is_16_9() {
+        let image = |width, height| DynamicImage::new_rgb8(width, height);
+        assert!(is_16_9(&image(1920, 1080)));
+        assert!(is_16_9(&image(1366, 768)));
+        assert!(is_16_9(&image(1600, 900)));
+        assert!(!is_16_9(&image(1920, 1200)));
+        assert!(!is_16_9(&image(1024, 768)));
+    }
+
+    #[test]
+    fn test_missing_pages() {
+        assert_eq!(missing_pages(&[1, 2, 3]), Vec::<u32>::new());
+        assert_eq!(missing_pages(&[2, 5]), vec![1, 3, 4]);
+        assert_eq!(missing_pages(&[]), Vec::<u32>::new());
+    }
+}
@@ -0,0 +1,537 @@
+//! 命令行子命令
+
+use std::path::Path;
+use std::process::ExitCode;
+
+use anyhow::{anyhow, Context, Result};
+use chrono::{Local, Months};
+use enum_iterator::all;
+
+use crate::bundle::{export_bundle, import_bundle};
+use crate::cli::{AccountAction, BundleAction, Command};
+use crate::config::config;
+use crate::data_dir::data_path;
+use crate::diff::diff_records;
+use crate::error::find_export_error;
+use crate::i18n::{self, tr};
+use crate::importer::{import_file, ImportFormat};
+use crate::record::{BannerType, OneAccountRecords, PityAnchor, TotalRecords};
+use crate::record_image::parse_timestamp;
+use crate::review::PendingReview;
+use crate::search::RecordIndex;
+use crate::serve::serve;
+use crate::simulate::{simulate, SimulationStart};
+use crate::stats::{current_4_star_pity, current_pity};
+use crate::table::Table;
+use crate::user_interaction::print_remediation_hint;
+
+/// 按保留策略移出的抽卡记录的归档文件
+static ARCHIVE_FILE: &str = "records/archive.json";
+
+/// 运行子命令
+pub fn run_command(command: &Command) -> ExitCode {
+    let result = match command {
+        Command::Undo { account, banner } => undo(account, *banner),
+        Command::PityAnchor {
+            account,
+            banner,
+            pulls_since_5_star,
+            pulls_since_4_star,
+        } => pity_anchor(account, *banner, *pulls_since_5_star, *pulls_since_4_star),
+        Command::Pity { account } => pity(account.as_deref()),
+        Command::Simulate {
+            banner,
+            pulls,
+            account,
+            pity,
+            guaranteed,
+            trials,
+        } => simulate_pulls(
+            *banner,
+            *pulls,
+            account.as_deref(),
+            *pity,
+            *guaranteed,
+            *trials,
+        ),
+        Command::Diff { old, new } => diff(old, new.as_deref()),
+        Command::Merge {
+            first,
+            second,
+            output,
+        } => merge(first, second, output),
+        Command::Prune { years } => prune(*years),
+        Command::ImportFile {
+            path,
+            format,
+            account,
+        } => import_from_file(path, *format, account.as_deref()),
+        Command::Serve { port } => serve(*port),
+        Command::Annotate {
+            account,
+            banner,
+            time,
+            name,
+            index,
+            note,
+        } => annotate(
+            account,
+            *banner,
+            time,
+            name.as_deref(),
+            *index,
+            note.clone(),
+        ),
+        Command::Find { name } => find(name),
+        Command::Account { action } => account(action),
+        Command::Bundle { action } => bundle(action),
+    };
+    match result {
+        Ok(()) => ExitCode::SUCCESS,
+        Err(e) => {
+            log::error!("{:?}", e);
+            println!("{e:#}");
+            print_remediation_hint(&e);
+            match find_export_error(&e) {
+                Some(export_error) => ExitCode::from(export_error.exit_code()),
+                None => ExitCode::FAILURE,
+            }
+        }
+    }
+}
+
+fn undo(account_id: &str, banner_type: BannerType) -> Result<()> {
+    let mut total_records = TotalRecords::read_or_default();
+    let batch = total_records
+        .undo_last_batch(account_id, banner_type)
+        .ok_or_else(|| anyhow!("{}", tr!("no_batch_to_undo")))?;
+    total_records.save()?;
+    println!(
+        "{}",
+        tr!(
+            "undo_finished",
+            time = batch.readable_imported_at(),
+            num = batch.added
+        )
+    );
+    Ok(())
+}
+
+fn pity_anchor(
+    account_id: &str,
+    banner_type: BannerType,
+    pulls_since_5_star: u32,
+    pulls_since_4_star: u32,
+) -> Result<()> {
+    if pulls_since_5_star >= banner_type.pity_count() {
+        return Err(anyhow!(
+            "{}",
+            tr!(
+                "pity_anchor_out_of_range",
+                max = banner_type.pity_count() - 1
+            )
+        ));
+    }
+    let anchor = PityAnchor {
+        banner_type,
+        timestamp: Local::now().timestamp() as u64,
+        pulls_since_5_star,
+        pulls_since_4_star,
+    };
+    let mut total_records = TotalRecords::read_or_default();
+    total_records.set_pity_anchor(account_id.to_string(), anchor);
+    total_records.save()?;
+    println!("{}", tr!("pity_anchor_saved"));
+    Ok(())
+}
+
+fn annotate(
+    account_id: &str,
+    banner_type: BannerType,
+    time: &str,
+    item_name: Option<&str>,
+    index: Option<usize>,
+    note: Option<String>,
+) -> Result<()> {
+    let timestamp = parse_timestamp(time)?;
+    let mut total_records = TotalRecords::read_or_default();
+    let account_records = total_records
+        .records
+        .get_mut(account_id)
+        .ok_or_else(|| anyhow!("{}", tr!("account_not_found", account = account_id)))?;
+    let removed = note.as_deref().is_none_or(|note| note.trim().is_empty());
+    let num = account_records.annotate(banner_type, timestamp, item_name, index, note)?;
+    total_records.save()?;
+    if removed {
+        println!("{}", tr!("annotate.removed", num = num));
+    } else {
+        println!("{}", tr!("annotate.saved", num = num));
+    }
+    Ok(())
+}
+
+fn pity(account_id: Option<&str>) -> Result<()> {
+    let total_records = TotalRecords::read_or_default();
+    let account_records = match account_id
+        .map(str::to_string)
+        .or(config().default_account_id)
+    {
+        Some(account_id) => vec![total_records
+            .records
+            .get(&account_id)
+            .ok_or_else(|| anyhow!("{}", tr!("account_not_found", account = account_id)))?],
+        None => total_records.records.values().collect(),
+    };
+    for account_records in account_records {
+        if total_records.records.len() > 1 {
+            println!("{}", account_records.id);
+        }
+        print_pity(account_records);
+    }
+    Ok(())
+}
+
+/// 每个有抽卡记录的卡池输出一行：距离上一个5星的抽数和还差多少抽保底，距离上一个4星的抽数，大保底状态
+fn print_pity(account_records: &OneAccountRecords) {
+    let language = i18n::language();
+    let mut table = Table::new([
+        tr!("export.banner"),
+        tr!("excel.after_5_star"),
+        tr!("pity.remaining"),
+        tr!("excel.after_4_star"),
+        tr!("pity.guarantee_state"),
+    ])
+    .align_right(&[1, 2, 3]);
+    for banner_type in all::<BannerType>() {
+        let records = account_records
+            .records
+            .get(&banner_type)
+            .map(Vec::as_slice)
+            .unwrap_or_default();
+        let anchor = account_records.pity_anchor(banner_type);
+        if records.is_empty() && anchor.is_none() {
+            continue;
+        }
+        let pity_5 = current_pity(records, anchor);
+        table.add_row(vec![
+            banner_type.display_name_for_user(language).to_string(),
+            format!("{pity_5}/{}", banner_type.pity_count()),
+            banner_type.pity_count().saturating_sub(pity_5).to_string(),
+            current_4_star_pity(records, anchor).to_string(),
+            account_records
+                .guarantee_state(banner_type)
+                .map(|state| state.describe(language))
+                .unwrap_or_default(),
+        ]);
+    }
+    table.print();
+}
+
+fn simulate_pulls(
+    banner_type: BannerType,
+    pulls: u32,
+    account_id: Option<&str>,
+    pity: Option<u32>,
+    guaranteed: bool,
+    trials: u32,
+) -> Result<()> {
+    if trials == 0 {
+        return Err(anyhow!("trials must be greater than 0"));
+    }
+    let pity = match (pity, account_id) {
+        (Some(pity), _) => pity,
+        (None, Some(account_id)) => {
+            let total_records = TotalRecords::read_or_default();
+            let account_records = total_records
+                .records
+                .get(account_id)
+                .ok_or_else(|| anyhow!("{}", tr!("account_not_found", account = account_id)))?;
+            let records = account_records
+                .records
+                .get(&banner_type)
+                .map(Vec::as_slice)
+                .unwrap_or_default();
+            current_pity(records, account_records.pity_anchor(banner_type))
+        }
+        (None, None) => 0,
+    };
+    if pity >= banner_type.pity_count() {
+        return Err(anyhow!(
+            "{}",
+            tr!(
+                "pity_anchor_out_of_range",
+                max = banner_type.pity_count() - 1
+            )
+        ));
+    }
+    let start = SimulationStart {
+        banner_type,
+        pity,
+        guaranteed,
+    };
+    let result = simulate(start, pulls, trials, &mut fastrand::Rng::new());
+    let percent = |probability: f64| format!("{:.1}%", probability * 100.0);
+    println!(
+        "{}",
+        tr!(
+            "simulate.start",
+            pity = pity,
+            pulls = pulls,
+            trials = trials
+        )
+    );
+    println!(
+        "{}",
+        tr!(
+            "simulate.expected_5_star",
+            value = format!("{:.2}", result.expected_five_star())
+        )
+    );
+    for count in 0..result.five_star_distribution.len() {
+        println!(
+            "  {}",
+            tr!(
+                "simulate.5_star_count",
+                count = count,
+                probability = percent(result.probability_of(count))
+            )
+        );
+    }
+    if let Some(probability) = result.probability_featured() {
+        println!(
+            "{}",
+            tr!("simulate.featured", probability = percent(probability))
+        );
+    }
+    Ok(())
+}
+
+fn diff(old_path: &Path, new_path: Option<&Path>) -> Result<()> {
+    let old = TotalRecords::read_from(old_path)
+        .with_context(|| format!("failed to read {}", old_path.display()))?;
+    let new = match new_path {
+        Some(new_path) => TotalRecords::read_from(new_path)
+            .with_context(|| format!("failed to read {}", new_path.display()))?,
+        None => TotalRecords::read_or_default(),
+    };
+    let diffs = diff_records(&old, &new);
+    if diffs.is_empty() {
+        println!("{}", tr!("diff.no_difference"));
+        return Ok(());
+    }
+    let language = i18n::language();
+    for banner_diff in diffs {
+        println!(
+            "{}",
+            tr!(
+                "diff.banner",
+                account = banner_diff.account_id,
+                banner = banner_diff.banner_type.display_name_for_user(language),
+                added = banner_diff.added.len(),
+                removed = banner_diff.removed.len()
+            )
+        );
+        for (sign, records) in [("+", &banner_diff.added), ("-", &banner_diff.removed)] {
+            for record in records {
+                println!(
+                    "  {sign} {}★ {} ({}) {}",
+                    record.star,
+                    record.item_name,
+                    record.item_type.display_name_for_user(language),
+                    record.readable_date_time_str()
+                );
+            }
+        }
+    }
+    Ok(())
+}
+
+fn merge(first_path: &Path, second_path: &Path, output_path: &Path) -> Result<()> {
+    let read = |path: &Path| {
+        TotalRecords::read_from(path).with_context(|| format!("failed to read {}", path.display()))
+    };
+    let mut merged = read(first_path)?;
+    let add_num = merged.merge(read(second_path)?)?;
+    merged
+        .save_to(output_path)
+        .with_context(|| format!("failed to save {}", output_path.display()))?;
+    println!(
+        "{}",
+        tr!(
+            "merge.finished",
+            accounts = merged.records.len(),
+            num = add_num,
+            path = output_path.display()
+        )
+    );
+    Ok(())
+}
+
+fn prune(years: Option<u32>) -> Result<()> {
+    let years = years
+        .or(config().retention_years)
+        .ok_or_else(|| anyhow!("{}", tr!("prune.no_policy")))?;
+    let cutoff = Local::now()
+        .checked_sub_months(Months::new(years * 12))
+        .ok_or_else(|| anyhow!("invalid retention years: {years}"))?;
+    let mut total_records = TotalRecords::read_or_default();
+    let archived = total_records.prune_before(cutoff.timestamp() as u64);
+    let archived_num = archived
+        .records
+        .values()
+        .flat_map(|account_records| account_records.records.values())
+        .map(Vec::len)
+        .sum::<usize>();
+    if archived_num == 0 {
+        println!("{}", tr!("prune.nothing", years = years));
+        return Ok(());
+    }
+    // 先保存归档，归档失败时不修改抽卡记录
+    let archive_path = data_path(ARCHIVE_FILE);
+    let mut archive = if archive_path.exists() {
+        TotalRecords::read_from(&archive_path)
+            .with_context(|| format!("failed to read {}", archive_path.display()))?
+    } else {
+        TotalRecords::default()
+    };
+    archive.merge(archived)?;
+    archive.save_to(&archive_path)?;
+    total_records.save()?;
+    log::info!("archived {archived_num} records older than {years} years");
+    println!(
+        "{}",
+        tr!(
+            "prune.finished",
+            num = archived_num,
+            years = years,
+            path = archive_path.display()
+        )
+    );
+    Ok(())
+}
+
+fn import_from_file(
+    path: &Path,
+    format: Option<ImportFormat>,
+    account_id: Option<&str>,
+) -> Result<()> {
+    let default_account_id = config().default_account_id;
+    let account_id = account_id.or(default_account_id.as_deref());
+    let summary = import_file(path, format, account_id)?;
+    println!(
+        "{}",
+        tr!("import_file.format", format = summary.format.display_name())
+    );
+    let language = i18n::language();
+    for (account_id, banner_type, add_num) in summary.added {
+        println!(
+            "{}",
+            tr!(
+                "import_file.added",
+                account = account_id,
+                banner = banner_type.display_name_for_user(language),
+                num = add_num
+            )
+        );
+    }
+    Ok(())
+}
+
+fn find(name: &str) -> Result<()> {
+    let total_records = TotalRecords::read_or_default();
+    let index = RecordIndex::new(&total_records);
+    let hits = index.find(name);
+    if hits.is_empty() {
+        println!("{}", tr!("find.not_found", name = name));
+        return Ok(());
+    }
+    let language = i18n::language();
+    let mut table = Table::new([
+        tr!("export.account"),
+        tr!("export.banner"),
+        tr!("excel.time"),
+        tr!("excel.star"),
+        tr!("excel.name"),
+        tr!("excel.after_5_star"),
+        tr!("excel.after_4_star"),
+    ])
+    .align_right(&[5, 6]);
+    for hit in hits {
+        table.add_row(vec![
+            hit.account_id.to_string(),
+            hit.banner_type.display_name_for_user(language).to_string(),
+            hit.record.readable_date_time_str(),
+            format!("{}★", hit.record.star),
+            hit.record.item_name.clone(),
+            hit.pulls_since_5_star.to_string(),
+            hit.pulls_since_4_star.to_string(),
+        ]);
+    }
+    table.print();
+    Ok(())
+}
+
+fn account(action: &AccountAction) -> Result<()> {
+    let mut total_records = TotalRecords::read_or_default();
+    let mut pending_review = PendingReview::read_or_default();
+    let (old_id, new_id) = match action {
+        AccountAction::Rename { old, new } => {
+            if total_records.records.contains_key(new) {
+                return Err(anyhow!("{}", tr!("account.already_exists", account = new)));
+            }
+            if !total_records.records.contains_key(old) {
+                return Err(anyhow!("{}", tr!("account_not_found", account = old)));
+            }
+            total_records.rename_account(old, new)?;
+            println!("{}", tr!("account.renamed", old = old, new = new));
+            (old, new)
+        }
+        AccountAction::Merge { from, into } => {
+            if !total_records.records.contains_key(from) {
+                return Err(anyhow!("{}", tr!("account_not_found", account = from)));
+            }
+            let add_num = total_records.merge_accounts(from, into)?;
+            println!(
+                "{}",
+                tr!("account.merged", from = from, into = into, num = add_num)
+            );
+            (from, into)
+        }
+    };
+    total_records.save()?;
+    // 待审核的记录也移到新的账号
+    for pending_record in pending_review.records.iter_mut() {
+        if &pending_record.account_id == old_id {
+            pending_record.account_id = new_id.clone();
+        }
+    }
+    pending_review.save()?;
+    Ok(())
+}
+
+fn bundle(action: &BundleAction) -> Result<()> {
+    match action {
+        BundleAction::Export { path } => {
+            let file_num = export_bundle(path)?;
+            println!(
+                "{}",
+                tr!("bundle_exported", num = file_num, path = path.display())
+            );
+        }
+        BundleAction::Import { path, with_config } => {
+            let bundle_import = import_bundle(path, *with_config)?;
+            println!(
+                "{}",
+                tr!(
+                    "bundle_imported",
+                    add_num = bundle_import.added,
+                    backup_num = bundle_import.backups
+                )
+            );
+            if bundle_import.config_replaced {
+                println!("{}", tr!("bundle_config_replaced"));
+            }
+        }
+    }
+    Ok(())
+}
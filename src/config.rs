@@ -1,13 +1,176 @@
+use std::collections::HashMap;
+use std::io::{stdin, IsTerminal};
+use std::path::{Path, PathBuf};
+use std::sync::RwLock;
+
 use anyhow::Result;
 use lazy_static::lazy_static;
 use serde::{Deserialize, Serialize};
+use serde_json::{Map, Value};
 
+use crate::action::ClickMethod;
+use crate::data_dir::{data_dir, data_path, write_atomic};
+use crate::error::save_error;
+use crate::export::ExportFormat;
+use crate::import::ImportMode;
 use crate::language::Language;
+use crate::logging::LogLevel;
+use crate::navigation::Waypoint;
+use crate::ocr::OcrBackendKind;
+use crate::preprocess::Preprocessing;
+use crate::rarity_detector::StarColors;
+use crate::record::BannerType;
 use crate::user_interaction::language;
 
-#[derive(Debug, Copy, Clone, Serialize, Deserialize)]
+/// 可以在多台电脑之间同步的配置：语言、账号、导出设置等
+static CONFIG_FILE: &str = "config.json";
+/// 只属于这台电脑的配置，同步设置时不会带上错误的坐标和路径
+static LOCAL_CONFIG_FILE: &str = "config.local.json";
+
+/// 保存在 config.local.json 中的字段：校准数据、OCR 路径、截图和点击设置、同步账号和日志级别等
+static LOCAL_KEYS: &[&str] = &[
+    "output_dir",
+    "capture_delay_ms",
+    "ocr_backend",
+    "never_elevate",
+    "post_export_hook",
+    "star_colors",
+    "navigation",
+    "navigation_delay_ms",
+    "wait_for_game_secs",
+    "click_retries",
+    "click_method",
+    "ocr_preprocessing",
+    "ocr_threads",
+    "paddle_ocr_path",
+    "sync",
+    "log_level",
+];
+
+/// 去掉本机的字段（包括 WebDAV 同步密码），只保留可以在电脑之间共享的配置，用于打包数据
+pub fn portable_config(config: Value) -> Value {
+    split_local(config).0
+}
+
+/// 把完整的配置分成（便携的配置, 本机的配置）
+fn split_local(config: Value) -> (Value, Value) {
+    let Value::Object(mut portable) = config else {
+        return (config, Value::Object(Map::new()));
+    };
+    let local = LOCAL_KEYS
+        .iter()
+        .filter_map(|key| portable.remove_entry(*key))
+        .collect::<Map<_, _>>();
+    (Value::Object(portable), Value::Object(local))
+}
+
+/// 合并便携的配置和本机的配置，本机的配置优先
+/// 旧版本的 config.json 中包含所有字段，没有 config.local.json 时仍然使用其中的值
+fn merge_local(portable: Value, local: Value) -> Value {
+    match (portable, local) {
+        (Value::Object(mut portable), Value::Object(local)) => {
+            portable.extend(local);
+            Value::Object(portable)
+        }
+        (portable, _) => portable,
+    }
+}
+
+/// 配置分别保存在 config.json 和 config.local.json 中，读取时合并
+/// 本机的字段见 [`LOCAL_KEYS`]
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(default)]
 pub struct Config {
     pub language: Language,
+    /// 导出完成或失败时显示系统通知
+    pub notification: bool,
+    /// 导出文件所在的目录，相对路径以数据目录为基准
+    pub output_dir: String,
+    /// 翻页后等待界面刷新的时间（毫秒）
+    pub capture_delay_ms: u64,
+    /// OCR 引擎
+    pub ocr_backend: OcrBackendKind,
+    /// 启动时检查更新
+    pub check_update: bool,
+    /// 从不以管理员权限重新运行，即使游戏以管理员权限运行
+    pub never_elevate: bool,
+    /// 一次导入最长的翻页时间（秒），超过后中止
+    pub session_timeout_secs: u64,
+    /// 一次导入最多翻页数，超过后中止
+    pub max_pages: u32,
+    /// 导入模式
+    pub import_mode: ImportMode,
+    /// WebDAV 远程同步，需要启用 `sync` 功能编译
+    pub sync: Option<SyncConfig>,
+    /// 导入后导出的格式
+    pub export_formats: Vec<ExportFormat>,
+    /// 导出成功后运行的命令，第一项为程序，其余为参数，为空时不运行
+    pub post_export_hook: Vec<String>,
+    /// 星级的参考颜色
+    pub star_colors: StarColors,
+    /// 不在抽卡记录界面时，从游戏主界面自动打开
+    pub auto_open_record: bool,
+    /// 自动打开抽卡记录界面的点击步骤
+    pub navigation: Vec<Waypoint>,
+    /// 自动打开抽卡记录界面时，每步点击后等待界面切换的时间（毫秒）
+    pub navigation_delay_ms: u64,
+    /// 游戏没有运行时最多等待游戏启动并打开抽卡记录界面的时间（秒）
+    pub wait_for_game_secs: u64,
+    /// 翻页点击没有生效时最多重新点击的次数，超过后视为已经是最后一页
+    pub click_retries: u32,
+    /// 翻页等点击的方式，第一页就翻不动时导入过程中会自动改用另一种方式
+    pub click_method: ClickMethod,
+    /// OCR 前的图片预处理
+    pub ocr_preprocessing: Preprocessing,
+    /// 每次从游戏导入后在 reports 目录中写入导入报告
+    pub session_report: bool,
+    /// 不在抽卡记录界面时识别当前所在的界面，给出具体的操作提示（实验性）
+    pub screen_guidance: bool,
+    /// Excel 每个卡池导出为一个文件
+    pub export_split_per_banner: bool,
+    /// 从游戏导入后让用户输入游戏内的总抽数，与保存的抽卡记录数量核对
+    pub reconcile_total_pulls: bool,
+    /// 默认账号ID，还没有抽卡记录时直接使用，非交互模式下没有指定 `--account` 时使用
+    pub default_account_id: Option<String>,
+    /// 账号ID -> 这个账号使用的卡池，导入计划中只默认选中这些卡池，没有设置的账号选中所有卡池
+    pub account_banners: HashMap<String, Vec<BannerType>>,
+    /// OCR 工作线程数量，为 0 时使用 CPU 核心数
+    pub ocr_threads: usize,
+    /// PaddleOCR-json.exe 的路径，相对路径以数据目录为基准，为空时自动查找
+    pub paddle_ocr_path: Option<String>,
+    /// 导出后直接打开 Excel/HTML 报告，关闭时询问是否打开
+    pub auto_open_report: bool,
+    /// Excel 中附加星级分布、每个5星用的抽数的图表，文件会变大
+    pub export_excel_charts: bool,
+    /// 日志级别
+    pub log_level: LogLevel,
+    /// 安静模式：控制台只显示翻页进度和导入结果，日志只写入日志文件
+    pub quiet: bool,
+    /// 保留策略：`prune` 子命令只保留最近几年的抽卡记录，更早的记录移到归档文件
+    pub retention_years: Option<u32>,
+    /// 导出文件使用的语言，为 `None` 时与界面语言相同
+    pub export_language: Option<Language>,
+    /// 导出时按物品字典把物品名称翻译为导出语言，保存的抽卡记录不变
+    pub export_translate_item_names: bool,
+    /// 导出时在每个账号的目录中写入 pity_state.json，供抽卡规划工具读取
+    pub export_pity_state: bool,
+    /// 增量导入：识别出一页的记录都已经保存过时停止翻页，更早的记录不用再截图识别
+    pub incremental_import: bool,
+    /// 增量导入时，识别出记录都已保存的页之后再多翻几页才停止，防止同一分钟内相同的记录导致误判
+    pub incremental_margin_pages: u32,
+    /// 严格模式下一页识别失败时先跳过，翻完所有页后回到这些页重新识别，仍然失败才中止导入
+    pub retry_failed_pages: bool,
+}
+
+/// WebDAV 远程同步配置
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+#[serde(default)]
+pub struct SyncConfig {
+    pub enabled: bool,
+    /// 远程目录地址，抽卡记录保存为其中的 records.json
+    pub endpoint: String,
+    pub username: String,
+    pub password: String,
 }
 
 impl Config {
@@ -15,40 +178,89 @@ impl Config {
         self.language = language;
     }
 
-    pub fn load_config() -> Result<Self> {
-        let path = "config.json";
+    /// 读取一个配置文件
+    /// # 返回
+    /// 文件不存在时为 `None`
+    /// # 错误
+    /// 文件无法读取，或者不是有效的配置
+    fn read_config_file(path: &Path) -> Result<Option<Value>> {
+        if !path.exists() {
+            return Ok(None);
+        }
         let file = std::fs::File::open(path)?;
         let reader = std::io::BufReader::new(file);
-        serde_json::from_reader(reader).map_err(|e| e.into())
+        let value: Value = serde_json::from_reader(reader)?;
+        // 字段的类型不对时整个配置都无法读取
+        serde_json::from_value::<Self>(value.clone())?;
+        Ok(Some(value))
+    }
+
+    /// 读取目录中的两个配置文件，读取失败的文件使用默认值
+    /// # 返回
+    /// （配置, config.json 是否需要重新写入, config.local.json 是否需要重新写入）
+    fn load_from(dir: &Path) -> (Self, bool, bool) {
+        let read = |file_name| match Self::read_config_file(&dir.join(file_name)) {
+            Ok(value) => (value, false),
+            Err(e) => {
+                log::error!("Failed to load {}: {}", file_name, e);
+                (None, true)
+            }
+        };
+        let (portable, _) = read(CONFIG_FILE);
+        let (local, is_local_invalid) = read(LOCAL_CONFIG_FILE);
+        // 旧版本的 config.json 中有本机的字段（包括同步密码），移到 config.local.json 中
+        let has_local_keys = portable
+            .as_ref()
+            .is_some_and(|portable| LOCAL_KEYS.iter().any(|key| portable.get(key).is_some()));
+        let save_local =
+            is_local_invalid || (local.is_none() && portable.is_none()) || has_local_keys;
+        let save_portable = portable.is_none() || has_local_keys;
+        let empty = || Value::Object(Map::new());
+        let config = serde_json::from_value(merge_local(
+            portable.unwrap_or_else(empty),
+            local.unwrap_or_else(empty),
+        ))
+        .unwrap_or_default();
+        (config, save_portable, save_local)
     }
 
     pub fn is_config_file_exists() -> bool {
-        let path = "config.json";
-        std::path::Path::new(path).exists()
+        let path = data_path(CONFIG_FILE);
+        path.exists()
     }
 
     pub fn save_config(&self) -> Result<()> {
-        let path = "config.json";
-        let file = std::fs::File::create(path)?;
-        let writer = std::io::BufWriter::new(file);
-        serde_json::to_writer_pretty(writer, self).map_err(|e| e.into())
+        self.save_files(true, true)
     }
 
-    pub fn load_or_init_config() -> Self {
-        let is_config_file_exists = Self::is_config_file_exists();
-        let config_res = Self::load_config();
-        if is_config_file_exists {
-            match config_res {
-                Ok(config) => return config,
-                Err(e) => {
-                    log::error!("Failed to load config: {}", e);
-                }
+    /// 保存其中一个或两个配置文件
+    fn save_files(&self, save_portable: bool, save_local: bool) -> Result<()> {
+        let (portable, local) = split_local(serde_json::to_value(self)?);
+        for (file_name, value, save) in [
+            (CONFIG_FILE, portable, save_portable),
+            (LOCAL_CONFIG_FILE, local, save_local),
+        ] {
+            if !save {
+                continue;
             }
+            let path = data_path(file_name);
+            let contents = serde_json::to_vec_pretty(&value)?;
+            write_atomic(&path, &contents).map_err(|e| save_error(e, &path))?;
+        }
+        Ok(())
+    }
+
+    /// 读取配置，首次运行或者文件损坏时使用默认值并写入
+    /// 只重新写入读取失败的文件，例如 config.json 损坏时不会覆盖 config.local.json 中的校准数据
+    pub fn load_or_init_config() -> Self {
+        let (mut config, save_portable, save_local) = Self::load_from(data_dir());
+        // 标准输入不是终端时（例如非交互模式、基准测试）无法选择语言，使用默认语言
+        if save_portable && stdin().is_terminal() {
+            config.set_language(language());
+        }
+        if save_portable || save_local {
+            config.save_files(save_portable, save_local).unwrap();
         }
-        let mut config = Self::default();
-        let language = language();
-        config.set_language(language);
-        config.save_config().unwrap();
         config
     }
 }
@@ -57,10 +269,133 @@ impl Default for Config {
     fn default() -> Self {
         Self {
             language: Language::ChineseSimplified,
+            notification: false,
+            output_dir: ".".to_string(),
+            capture_delay_ms: 200,
+            ocr_backend: OcrBackendKind::CnOcr,
+            check_update: true,
+            never_elevate: false,
+            session_timeout_secs: 600,
+            max_pages: 1000,
+            import_mode: ImportMode::Strict,
+            sync: None,
+            export_formats: vec![ExportFormat::Excel],
+            post_export_hook: vec![],
+            star_colors: StarColors::default(),
+            auto_open_record: false,
+            // 点击位置还没有用真实截图验证过，由用户自己配置
+            navigation: vec![],
+            navigation_delay_ms: 1500,
+            wait_for_game_secs: 300,
+            click_retries: 2,
+            click_method: ClickMethod::default(),
+            ocr_preprocessing: Preprocessing::default(),
+            session_report: true,
+            // 识别界面的坐标还没有用真实截图验证过
+            screen_guidance: false,
+            export_split_per_banner: false,
+            reconcile_total_pulls: false,
+            default_account_id: None,
+            account_banners: HashMap::new(),
+            ocr_threads: 0,
+            paddle_ocr_path: None,
+            auto_open_report: false,
+            export_excel_charts: false,
+            log_level: LogLevel::Info,
+            quiet: false,
+            retention_years: None,
+            export_language: None,
+            export_translate_item_names: false,
+            export_pity_state: true,
+            incremental_import: true,
+            incremental_margin_pages: 1,
+            retry_failed_pages: true,
         }
     }
 }
 
+impl Config {
+    /// 导出目录中的文件路径，相对路径的导出目录以数据目录为基准
+    pub fn output_path(&self, file_name: &str) -> PathBuf {
+        data_dir().join(&self.output_dir).join(file_name)
+    }
+}
+
 lazy_static! {
-    pub static ref CONFIG: Config = Config::load_or_init_config();
+    static ref CONFIG: RwLock<Config> = RwLock::new(Config::load_or_init_config());
+}
+
+/// 当前配置
+pub fn config() -> Config {
+    CONFIG.read().unwrap().clone()
+}
+
+/// 修改配置并保存到数据目录中的 config.json 和 config.local.json
+pub fn update_config(f: impl FnOnce(&mut Config)) -> Result<()> {
+    let mut config = CONFIG.write().unwrap();
+    f(&mut config);
+    config.save_config()
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    #[test]
+    fn test_split_local() {
+        let config = Config::default();
+        let (portable, local) = split_local(serde_json::to_value(&config).unwrap());
+        assert!(portable.get("language").is_some());
+        assert!(portable.get("star_colors").is_none());
+        assert!(local.get("star_colors").is_some());
+        assert!(local.get("language").is_none());
+        assert_eq!(local.as_object().unwrap().len(), LOCAL_KEYS.len());
+
+        // 本机的配置优先，旧版本的 config.json 中的本机字段在没有 config.local.json 时仍然生效
+        let mut old = serde_json::to_value(&config).unwrap();
+        old["capture_delay_ms"] = 500.into();
+        let merged: Config = serde_json::from_value(merge_local(
+            old.clone(),
+            serde_json::json!({"capture_delay_ms": 300}),
+        ))
+        .unwrap();
+        assert_eq!(merged.capture_delay_ms, 300);
+        let merged: Config =
+            serde_json::from_value(merge_local(old, Value::Object(Map::new()))).unwrap();
+        assert_eq!(merged.capture_delay_ms, 500);
+    }
+
+    #[test]
+    fn test_load_from() {
+        let dir = std::env::temp_dir().join("snowbreak_gacha_export_test_config");
+        let _ = std::fs::remove_dir_all(&dir);
+        std::fs::create_dir_all(&dir).unwrap();
+        // 首次运行
+        let (_, save_portable, save_local) = Config::load_from(&dir);
+        assert!(save_portable && save_local);
+
+        // config.json 损坏时保留 config.local.json 中的校准数据
+        std::fs::write(dir.join(CONFIG_FILE), "{").unwrap();
+        std::fs::write(dir.join(LOCAL_CONFIG_FILE), r#"{"capture_delay_ms": 123}"#).unwrap();
+        let (config, save_portable, save_local) = Config::load_from(&dir);
+        assert_eq!(config.capture_delay_ms, 123);
+        assert!(save_portable && !save_local);
+
+        // 字段类型不对的 config.local.json 也视为损坏
+        std::fs::write(dir.join(CONFIG_FILE), r#"{"notification": false}"#).unwrap();
+        std::fs::write(dir.join(LOCAL_CONFIG_FILE), r#"{"capture_delay_ms": "x"}"#).unwrap();
+        let (config, save_portable, save_local) = Config::load_from(&dir);
+        assert!(!config.notification);
+        assert!(!save_portable && save_local);
+
+        // 旧版本的 config.json 中的本机字段移到 config.local.json
+        std::fs::remove_file(dir.join(LOCAL_CONFIG_FILE)).unwrap();
+        let (_, save_portable, save_local) = Config::load_from(&dir);
+        assert!(!save_portable && !save_local);
+        std::fs::write(dir.join(CONFIG_FILE), r#"{"capture_delay_ms": 456}"#).unwrap();
+        let (config, save_portable, save_local) = Config::load_from(&dir);
+        assert_eq!(config.capture_delay_ms, 456);
+        assert!(save_portable && save_local);
+        std::fs::remove_dir_all(&dir).unwrap();
+    }
 }
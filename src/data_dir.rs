@@ -0,0 +1,189 @@
+//! 数据目录
+//!
+//! 配置、抽卡记录、缓存等文件都保存在数据目录中：
+//! - 程序所在目录可写时使用程序所在目录（便携模式）
+//! - 否则使用 `%APPDATA%/snowbreak_gacha_export`，例如程序放在 Program Files 中时
+//!
+//! 旧版本把文件保存在工作目录，首次使用新的数据目录时会把这些文件复制过来。
+//!
+//! 设置了环境变量 [`DATA_DIR_ENV`] 时使用其中的目录，不迁移旧文件，基准测试用它避免改动真实的数据。
+
+use std::io::Write;
+use std::path::{Path, PathBuf};
+
+use anyhow::Result;
+use lazy_static::lazy_static;
+
+/// 指定数据目录的环境变量
+pub static DATA_DIR_ENV: &str = "SNOWBREAK_DATA_DIR";
+
+/// 数据目录中需要迁移的文件
+const DATA_FILES: [&str; 4] = [
+    "config.json",
+    "config.local.json",
+    "records/records.json",
+    "cache/ocr_cache.json",
+];
+
+lazy_static! {
+    static ref DATA_DIR: PathBuf = resolve_data_dir();
+}
+
+/// 数据目录
+pub fn data_dir() -> &'static Path {
+    &DATA_DIR
+}
+
+/// 数据目录中的文件路径
+pub fn data_path(relative_path: impl AsRef<Path>) -> PathBuf {
+    data_dir().join(relative_path)
+}
+
+fn exe_dir() -> Option<PathBuf> {
+    std::env::current_exe()
+        .ok()
+        .and_then(|path| path.parent().map(Path::to_path_buf))
+}
+
+/// 每个用户自己的数据目录，Windows 上为 `%APPDATA%/snowbreak_gacha_export`
+fn user_data_dir() -> Option<PathBuf> {
+    dirs::data_dir().map(|dir| dir.join("snowbreak_gacha_export"))
+}
+
+/// 先写入同目录中的临时文件再重命名，写入过程中中断不会留下不完整的文件
+///
+/// 重命名前把临时文件写入磁盘，避免系统崩溃后重命名已经生效而内容还没有写入。
+pub fn write_atomic(path: &Path, contents: &[u8]) -> std::io::Result<()> {
+    let mut temp_file_name = path.file_name().unwrap_or_default().to_os_string();
+    temp_file_name.push(".tmp");
+    let temp_path = path.with_file_name(temp_file_name);
+    let write = || {
+        let mut file = std::fs::File::create(&temp_path)?;
+        file.write_all(contents)?;
+        file.sync_all()
+    };
+    if let Err(e) = write() {
+        let _ = std::fs::remove_file(&temp_path);
+        return Err(e);
+    }
+    std::fs::rename(&temp_path, path).inspect_err(|_| {
+        let _ = std::fs::remove_file(&temp_path);
+    })
+}
+
+/// 尝试在目录中创建文件来判断目录是否可写
+fn is_dir_writable(dir: &Path) -> bool {
+    let test_file = dir.join(".write_test");
+    match std::fs::File::create(&test_file) {
+        Ok(_) => {
+            let _ = std::fs::remove_file(&test_file);
+            true
+        }
+        Err(_) => false,
+    }
+}
+
+fn resolve_data_dir() -> PathBuf {
+    if let Some(dir) = std::env::var_os(DATA_DIR_ENV) {
+        let dir = PathBuf::from(dir);
+        log::info!("data dir from {}: {:?}", DATA_DIR_ENV, dir);
+        if let Err(e) = std::fs::create_dir_all(&dir) {
+            log::error!("failed to create data dir {:?}: {:?}", dir, e);
+        }
+        return dir;
+    }
+    let data_dir = match exe_dir() {
+        Some(dir) if is_dir_writable(&dir) => {
+            log::info!("portable mode, data dir: {:?}", dir);
+            dir
+        }
+        exe_dir => match user_data_dir() {
+            Some(dir) => {
+                log::info!("data dir: {:?}", dir);
+                dir
+            }
+            None => exe_dir.unwrap_or_else(|| PathBuf::from(".")),
+        },
+    };
+    if let Err(e) = std::fs::create_dir_all(&data_dir) {
+        log::error!("failed to create data dir {:?}: {:?}", data_dir, e);
+    }
+
+    // 旧版本的文件在工作目录中，工作目录一般就是程序所在目录
+    let old_dirs = std::env::current_dir().ok().into_iter().chain(exe_dir());
+    for old_dir in old_dirs {
+        if old_dir == data_dir {
+            continue;
+        }
+        match migrate_data_files(&old_dir, &data_dir) {
+            Ok(0) => {}
+            Ok(num) => log::info!("migrated {} files from {:?}", num, old_dir),
+            Err(e) => log::error!("failed to migrate files from {:?}: {:?}", old_dir, e),
+        }
+    }
+    data_dir
+}
+
+/// 把旧目录中的数据文件复制到数据目录，数据目录中已有的文件不会被覆盖
+/// # 返回
+/// 复制的文件数量
+fn migrate_data_files(old_dir: &Path, data_dir: &Path) -> Result<usize> {
+    let mut migrated_num = 0;
+    for file in DATA_FILES {
+        let from = old_dir.join(file);
+        let to = data_dir.join(file);
+        if !from.is_file() || to.exists() {
+            continue;
+        }
+        if let Some(parent) = to.parent() {
+            std::fs::create_dir_all(parent)?;
+        }
+        std::fs::copy(&from, &to)?;
+        migrated_num += 1;
+    }
+    Ok(migrated_num)
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    #[test]
+    fn test_migrate_data_files() {
+        let dir = std::env::temp_dir().join("snowbreak_gacha_export_test_migrate");
+        let _ = std::fs::remove_dir_all(&dir);
+        let old_dir = dir.join("old");
+        let data_dir = dir.join("data");
+        std::fs::create_dir_all(old_dir.join("records")).unwrap();
+        std::fs::create_dir_all(&data_dir).unwrap();
+        std::fs::write(old_dir.join("config.json"), "old").unwrap();
+        std::fs::write(old_dir.join("records/records.json"), "old").unwrap();
+        std::fs::write(data_dir.join("config.json"), "new").unwrap();
+
+        assert_eq!(migrate_data_files(&old_dir, &data_dir).unwrap(), 1);
+        assert_eq!(
+            std::fs::read_to_string(data_dir.join("config.json")).unwrap(),
+            "new"
+        );
+        assert_eq!(
+            std::fs::read_to_string(data_dir.join("records/records.json")).unwrap(),
+            "old"
+        );
+        assert_eq!(migrate_data_files(&old_dir, &data_dir).unwrap(), 0);
+        std::fs::remove_dir_all(&dir).unwrap();
+    }
+
+    #[test]
+    fn test_write_atomic() {
+        let dir = std::env::temp_dir().join("snowbreak_gacha_export_test_write_atomic");
+        let _ = std::fs::remove_dir_all(&dir);
+        std::fs::create_dir_all(&dir).unwrap();
+        let path = dir.join("records.json");
+        std::fs::write(&path, "old").unwrap();
+
+        write_atomic(&path, b"new").unwrap();
+        assert_eq!(std::fs::read_to_string(&path).unwrap(), "new");
+        assert!(!dir.join("records.json.tmp").exists());
+        std::fs::remove_dir_all(&dir).unwrap();
+    }
+}
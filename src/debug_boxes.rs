@@ -0,0 +1,55 @@
+//! 坐标调试图片
+//!
+//! 以 `--debug-boxes` 运行时，导入的每一页截图画上计算出的行、单元格、页码区域和星级采样点，
+//! 保存到数据目录的 `debug_boxes/<导入开始时间>/page_<页码>.png`。
+//! 界面坐标与用户的设置不符时，用户可以把这些图片附在问题反馈中。
+
+use std::path::PathBuf;
+use std::sync::atomic::{AtomicBool, Ordering};
+
+use anyhow::Result;
+use chrono::Local;
+
+use crate::data_dir::data_path;
+use crate::record_image::RecordImage;
+
+static ENABLED: AtomicBool = AtomicBool::new(false);
+
+/// 开启保存调试图片
+pub fn enable() {
+    ENABLED.store(true, Ordering::Relaxed);
+}
+
+/// 一次导入的调试图片目录
+pub struct DebugBoxes {
+    dir: PathBuf,
+}
+
+impl DebugBoxes {
+    /// 没有开启时返回 `None`
+    pub fn new() -> Option<Self> {
+        if !ENABLED.load(Ordering::Relaxed) {
+            return None;
+        }
+        let dir = data_path(format!(
+            "debug_boxes/{}",
+            Local::now().format("%Y%m%d_%H%M%S")
+        ));
+        Some(Self { dir })
+    }
+
+    fn save_page(&self, page: u32, record_image: &RecordImage) -> Result<PathBuf> {
+        std::fs::create_dir_all(&self.dir)?;
+        let path = self.dir.join(format!("page_{page}.png"));
+        record_image.debug_boxes_image().save(&path)?;
+        Ok(path)
+    }
+
+    /// 保存一页的调试图片，失败时只记录日志
+    pub fn save(&self, page: u32, record_image: &RecordImage) {
+        match self.save_page(page, record_image) {
+            Ok(path) => log::debug!("debug boxes saved to {:?}", path),
+            Err(e) => log::warn!("failed to save debug boxes of page {page}: {:?}", e),
+        }
+    }
+}
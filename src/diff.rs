@@ -0,0 +1,117 @@
+//! 比较两份抽卡记录
+//!
+//! 用于确认同步结果，或者在误覆盖后从备份中找回丢失的记录。
+//! 同一时间可能有多条相同的记录（十连中的重复物品），按数量比较。
+
+use std::cmp::Reverse;
+use std::collections::HashMap;
+
+use enum_iterator::all;
+
+use crate::record::{BannerType, OneRecord, TotalRecords};
+
+/// 一个账号一个卡池的差异
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct BannerDiff {
+    pub account_id: String,
+    pub banner_type: BannerType,
+    /// 只在新记录中的记录，按时间倒序
+    pub added: Vec<OneRecord>,
+    /// 只在旧记录中的记录，按时间倒序
+    pub removed: Vec<OneRecord>,
+}
+
+/// `records` 中比 `other` 多出的记录
+fn difference(records: &[OneRecord], other: &[OneRecord]) -> Vec<OneRecord> {
+    let mut other_counts: HashMap<&OneRecord, usize> = HashMap::new();
+    for record in other {
+        *other_counts.entry(record).or_default() += 1;
+    }
+    let mut difference = records
+        .iter()
+        .filter(|record| match other_counts.get_mut(record) {
+            Some(count) if *count > 0 => {
+                *count -= 1;
+                false
+            }
+            _ => true,
+        })
+        .cloned()
+        .collect::<Vec<_>>();
+    difference.sort_by_key(|record| Reverse(record.timestamp));
+    difference
+}
+
+/// 比较两份抽卡记录，只返回有差异的卡池，按账号ID、卡池排序
+pub fn diff_records(old: &TotalRecords, new: &TotalRecords) -> Vec<BannerDiff> {
+    let mut account_ids = old
+        .records
+        .keys()
+        .chain(new.records.keys())
+        .collect::<Vec<_>>();
+    account_ids.sort();
+    account_ids.dedup();
+    let banner_records = |total_records: &TotalRecords, account_id: &str, banner_type| {
+        total_records
+            .records
+            .get(account_id)
+            .and_then(|account_records| account_records.records.get(&banner_type))
+            .cloned()
+            .unwrap_or_default()
+    };
+    let mut diffs = vec![];
+    for account_id in account_ids {
+        for banner_type in all::<BannerType>() {
+            let old_records = banner_records(old, account_id, banner_type);
+            let new_records = banner_records(new, account_id, banner_type);
+            let added = difference(&new_records, &old_records);
+            let removed = difference(&old_records, &new_records);
+            if !added.is_empty() || !removed.is_empty() {
+                diffs.push(BannerDiff {
+                    account_id: account_id.clone(),
+                    banner_type,
+                    added,
+                    removed,
+                });
+            }
+        }
+    }
+    diffs
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use crate::record::{ImportBatch, ItemType, RecordSource};
+
+    #[test]
+    fn test_diff_records() {
+        let record = |name: &str, timestamp| {
+            OneRecord::new(3, name.to_string(), ItemType::Weapon, timestamp)
+        };
+        let total_records = |records: Vec<OneRecord>| {
+            let mut total_records = TotalRecords::default();
+            total_records
+                .add_record(
+                    "id".to_string(),
+                    ImportBatch::new(BannerType::PermanentWeapon, RecordSource::Ocr),
+                    records,
+                )
+                .unwrap();
+            total_records
+        };
+        let old = total_records(vec![record("a", 2), record("a", 2), record("b", 1)]);
+        let new = total_records(vec![record("c", 3), record("a", 2), record("b", 1)]);
+        let diffs = diff_records(&old, &new);
+        assert_eq!(diffs.len(), 1);
+        assert_eq!(diffs[0].banner_type, BannerType::PermanentWeapon);
+        assert_eq!(diffs[0].added, [record("c", 3)]);
+        // 重复的记录按数量比较
+        assert_eq!(diffs[0].removed, [record("a", 2)]);
+        assert!(diff_records(&new, &new).is_empty());
+        assert_eq!(
+            diff_records(&TotalRecords::default(), &new)[0].added.len(),
+            3
+        );
+    }
+}
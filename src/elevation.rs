@@ -0,0 +1,86 @@
+//! 管理员权限检测
+//!
+//! 普通权限的进程无法向以管理员权限运行的窗口发送模拟输入（UIPI），
+//! 只有游戏以管理员权限运行时才需要提升本程序的权限。
+
+use admin_runner::is_admin;
+use anyhow::{anyhow, Result};
+
+use crate::config::config;
+
+/// 游戏进程是否以管理员权限运行
+/// 无法查询游戏进程时视为以管理员权限运行
+#[cfg(windows)]
+pub fn is_window_process_elevated(hwnd: isize) -> bool {
+    use std::ffi::c_void;
+
+    use windows::Win32::Foundation::{CloseHandle, HANDLE, HWND};
+    use windows::Win32::Security::{
+        GetTokenInformation, TokenElevation, TOKEN_ELEVATION, TOKEN_QUERY,
+    };
+    use windows::Win32::System::Threading::{
+        OpenProcess, OpenProcessToken, PROCESS_QUERY_LIMITED_INFORMATION,
+    };
+    use windows::Win32::UI::WindowsAndMessaging::GetWindowThreadProcessId;
+
+    unsafe {
+        let mut process_id = 0u32;
+        GetWindowThreadProcessId(HWND(hwnd), Some(&mut process_id));
+        let process = match OpenProcess(PROCESS_QUERY_LIMITED_INFORMATION, false, process_id) {
+            Ok(process) => process,
+            Err(e) => {
+                log::debug!("failed to open game process: {:?}", e);
+                return true;
+            }
+        };
+        let mut token = HANDLE::default();
+        let elevated = if OpenProcessToken(process, TOKEN_QUERY, &mut token).is_ok() {
+            let mut elevation = TOKEN_ELEVATION::default();
+            let mut size = 0u32;
+            let result = GetTokenInformation(
+                token,
+                TokenElevation,
+                Some(&mut elevation as *mut TOKEN_ELEVATION as *mut c_void),
+                std::mem::size_of::<TOKEN_ELEVATION>() as u32,
+                &mut size,
+            );
+            let _ = CloseHandle(token);
+            match result {
+                Ok(()) => elevation.TokenIsElevated != 0,
+                Err(e) => {
+                    log::debug!("failed to query game process token: {:?}", e);
+                    true
+                }
+            }
+        } else {
+            true
+        };
+        let _ = CloseHandle(process);
+        elevated
+    }
+}
+
+#[cfg(not(windows))]
+pub fn is_window_process_elevated(_hwnd: isize) -> bool {
+    false
+}
+
+/// 是否需要以管理员权限重新运行
+/// # 参数
+/// - `hwnd`: 游戏窗口，没有找到游戏窗口时为 `None`，此时不提升权限
+pub fn needs_elevation(hwnd: Option<isize>) -> bool {
+    if is_admin() || config().never_elevate {
+        return false;
+    }
+    hwnd.is_some_and(is_window_process_elevated)
+}
+
+/// 检查能否向游戏窗口发送模拟输入
+pub fn check_input_permission(hwnd: isize) -> Result<()> {
+    if !is_admin() && is_window_process_elevated(hwnd) {
+        return Err(anyhow!(
+            "the game is running as admin, run this program as admin too"
+        ));
+    }
+    Ok(())
+}
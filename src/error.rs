@@ -0,0 +1,192 @@
+//! 用户可以自行处理的错误
+//!
+//! 每种错误对应一条本地化的处理建议和进程退出码，其他错误仍然使用 anyhow。
+//! 错误可以作为 anyhow 的 context 附加在原始错误上，用 [`find_export_error`] 从错误链中取出。
+
+use std::fmt::{Display, Formatter};
+use std::path::{Path, PathBuf};
+
+use crate::i18n::{self, tr};
+use crate::record::BannerType;
+use crate::run_result::FailureKind;
+use crate::screen::Screen;
+
+/// Windows 的 ERROR_SHARING_VIOLATION 和 ERROR_LOCK_VIOLATION，文件被其他程序打开时出现
+static LOCKED_OS_ERRORS: [i32; 2] = [32, 33];
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum ExportError {
+    /// 没有找到游戏窗口
+    WindowNotFound,
+    /// 游戏不在抽卡记录界面
+    NotOnRecordScreen {
+        /// 实际所在的界面
+        screen: Screen,
+    },
+    /// 一行抽卡记录识别失败
+    OcrFailed {
+        page: u32,
+        /// 从 1 开始的行号
+        row: usize,
+    },
+    /// 新旧抽卡记录的时间顺序冲突，无法合并
+    MergeConflict { banner_type: BannerType },
+    /// 文件被其他程序占用或没有写入权限
+    SaveLocked { path: PathBuf },
+    /// 已经有其他实例在运行
+    AlreadyRunning,
+    /// 识别出的物品不属于选择的卡池，游戏中打开的可能是其他卡池
+    BannerMismatch {
+        banner_type: BannerType,
+        item_name: String,
+    },
+}
+
+impl Display for ExportError {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        match self {
+            ExportError::WindowNotFound => write!(f, "game window not found"),
+            ExportError::NotOnRecordScreen { screen } => {
+                write!(
+                    f,
+                    "not in the record interface, current screen: {:?}",
+                    screen
+                )
+            }
+            ExportError::OcrFailed { page, row } => {
+                write!(f, "failed to recognize page {page} row {row}")
+            }
+            ExportError::MergeConflict { banner_type } => {
+                write!(f, "{:?} records conflict with saved records", banner_type)
+            }
+            ExportError::SaveLocked { path } => {
+                write!(f, "{} is locked by another program", path.display())
+            }
+            ExportError::AlreadyRunning => write!(f, "another instance is already running"),
+            ExportError::BannerMismatch {
+                banner_type,
+                item_name,
+            } => write!(f, "{item_name} does not belong to {:?}", banner_type),
+        }
+    }
+}
+
+impl std::error::Error for ExportError {}
+
+impl ExportError {
+    /// 给用户的处理建议
+    pub fn hint(&self) -> String {
+        match self {
+            ExportError::WindowNotFound => tr!("error.window_not_found").to_string(),
+            ExportError::NotOnRecordScreen { screen } => screen.guidance().to_string(),
+            ExportError::OcrFailed { page, row } => {
+                tr!("error.ocr_failed", page = page, row = row)
+            }
+            ExportError::MergeConflict { banner_type } => tr!(
+                "error.merge_conflict",
+                banner = banner_type.display_name_for_user(i18n::language())
+            ),
+            ExportError::SaveLocked { path } => {
+                tr!("error.save_locked", path = path.display())
+            }
+            ExportError::AlreadyRunning => tr!("error.already_running").to_string(),
+            ExportError::BannerMismatch {
+                banner_type,
+                item_name,
+            } => tr!(
+                "error.banner_mismatch",
+                banner = banner_type.display_name_for_user(i18n::language()),
+                item = item_name
+            ),
+        }
+    }
+
+    /// 失败的类型
+    pub fn failure_kind(&self) -> FailureKind {
+        match self {
+            ExportError::WindowNotFound
+            | ExportError::NotOnRecordScreen { .. }
+            | ExportError::BannerMismatch { .. } => FailureKind::Capture,
+            ExportError::OcrFailed { .. } => FailureKind::Ocr,
+            ExportError::MergeConflict { .. } => FailureKind::MergeConflict,
+            ExportError::SaveLocked { .. } | ExportError::AlreadyRunning => FailureKind::Other,
+        }
+    }
+
+    /// 进程退出码，比 [`FailureKind::exit_code`] 更具体
+    pub fn exit_code(&self) -> u8 {
+        match self {
+            ExportError::WindowNotFound => 5,
+            ExportError::NotOnRecordScreen { .. } => 6,
+            ExportError::SaveLocked { .. } => 7,
+            ExportError::AlreadyRunning => 8,
+            ExportError::BannerMismatch { .. } => 9,
+            _ => self.failure_kind().exit_code(),
+        }
+    }
+}
+
+/// 错误链中的 [`ExportError`]
+pub fn find_export_error(error: &anyhow::Error) -> Option<&ExportError> {
+    error.downcast_ref::<ExportError>().or_else(|| {
+        error
+            .chain()
+            .find_map(|cause| cause.downcast_ref::<ExportError>())
+    })
+}
+
+/// 错误链中 [`ExportError`] 的处理建议
+pub fn remediation_hint(error: &anyhow::Error) -> Option<String> {
+    find_export_error(error).map(ExportError::hint)
+}
+
+/// 写入文件失败的错误，文件被占用或没有权限时附加 [`ExportError::SaveLocked`]
+pub fn save_error(error: std::io::Error, path: &Path) -> anyhow::Error {
+    let is_locked = error.kind() == std::io::ErrorKind::PermissionDenied
+        || (cfg!(windows)
+            && error
+                .raw_os_error()
+                .is_some_and(|code| LOCKED_OS_ERRORS.contains(&code)));
+    if is_locked {
+        anyhow::Error::new(error).context(ExportError::SaveLocked {
+            path: path.to_path_buf(),
+        })
+    } else {
+        anyhow::Error::new(error).context(format!("failed to write {}", path.display()))
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use anyhow::Context;
+
+    use super::*;
+
+    #[test]
+    fn test_find_export_error() {
+        let error = anyhow::anyhow!("invalid time")
+            .context(ExportError::OcrFailed { page: 2, row: 3 })
+            .context("import failed");
+        let export_error = find_export_error(&error).unwrap();
+        assert_eq!(export_error, &ExportError::OcrFailed { page: 2, row: 3 });
+        assert_eq!(export_error.exit_code(), 3);
+        assert!(find_export_error(&anyhow::anyhow!("other")).is_none());
+
+        let path = Path::new("records.xlsx");
+        let error = save_error(std::io::ErrorKind::PermissionDenied.into(), path);
+        assert_eq!(
+            find_export_error(&error),
+            Some(&ExportError::SaveLocked {
+                path: path.to_path_buf()
+            })
+        );
+        let error = save_error(std::io::ErrorKind::NotFound.into(), path);
+        assert!(find_export_error(&error).is_none());
+        let error: anyhow::Result<()> =
+            Err(ExportError::WindowNotFound).context("failed to get window");
+        assert_eq!(
+            find_export_error(&error.unwrap_err()).map(ExportError::exit_code),
+            Some(5)
+        );
+    }
+}
@@ -0,0 +1,339 @@
+//! 导出
+//!
+//! 每种导出格式实现 [`Exporter`]，注册在 [`EXPORTERS`] 中，按格式选择一种或多种导出。
+
+use std::borrow::Cow;
+use std::path::PathBuf;
+
+use anyhow::Result;
+use clap::ValueEnum;
+use enum_iterator::{all, Sequence};
+use lazy_static::lazy_static;
+use serde::{Deserialize, Serialize};
+
+use crate::banner_schedule::banner_schedule;
+use crate::config::config;
+use crate::error::save_error;
+use crate::i18n;
+use crate::items::translate_item_name;
+use crate::language::Language;
+use crate::progress::{emit, ProgressEvent};
+use crate::record::{BannerType, OneAccountRecords, TotalRecords};
+use pity_state::write_pity_states;
+use template::{Column, ExportTemplate};
+
+mod csv;
+mod excel;
+mod html;
+mod json;
+mod markdown;
+pub mod meta;
+pub mod pity_state;
+pub mod template;
+
+pub use html::render_html;
+
+/// 导出格式
+#[derive(Debug, Copy, Clone, PartialEq, Eq, Hash, Serialize, Deserialize, Sequence, ValueEnum)]
+pub enum ExportFormat {
+    /// records.xlsx
+    Excel,
+    /// records.csv
+    Csv,
+    /// records_export.json
+    Json,
+    /// records.html
+    Html,
+    /// records.md
+    Markdown,
+}
+
+impl ExportFormat {
+    /// 导出文件名
+    pub fn file_name(&self) -> &'static str {
+        match self {
+            ExportFormat::Excel => "records.xlsx",
+            ExportFormat::Csv => "records.csv",
+            ExportFormat::Json => "records_export.json",
+            ExportFormat::Html => "records.html",
+            ExportFormat::Markdown => "records.md",
+        }
+    }
+
+    pub fn display_name(&self) -> &'static str {
+        match self {
+            ExportFormat::Excel => "Excel (records.xlsx)",
+            ExportFormat::Csv => "CSV (records.csv)",
+            ExportFormat::Json => "JSON (records_export.json)",
+            ExportFormat::Html => "HTML (records.html)",
+            ExportFormat::Markdown => "Markdown (records.md)",
+        }
+    }
+}
+
+/// 导出选项
+#[derive(Debug, Clone)]
+pub struct ExportOptions {
+    /// 表头、卡池名称等使用的语言
+    pub language: Language,
+    /// 导出目录
+    pub output_dir: PathBuf,
+    /// Excel 每个卡池导出为一个文件
+    pub split_per_banner: bool,
+    /// Excel 附加汇总图表表
+    pub charts: bool,
+    /// 导出模板中的列，为 `None` 时使用每种格式的默认列
+    pub columns: Option<Vec<Column>>,
+    /// 按物品字典把物品名称翻译为导出语言
+    pub translate_item_names: bool,
+    /// 在每个账号的目录中写入 pity_state.json
+    pub pity_state: bool,
+}
+
+impl ExportOptions {
+    /// 配置中的导出语言（没有设置时为当前界面语言）和导出目录
+    pub fn from_config() -> Self {
+        Self {
+            language: config().export_language.unwrap_or(i18n::language()),
+            output_dir: config().output_path(""),
+            split_per_banner: config().export_split_per_banner,
+            charts: config().export_excel_charts,
+            columns: load_template_columns(),
+            translate_item_names: config().export_translate_item_names,
+            pity_state: config().export_pity_state,
+        }
+    }
+
+    /// 导出使用的抽卡记录，翻译物品名称时复制一份，保存的抽卡记录不变
+    pub fn localized_records<'a>(&self, records: &'a TotalRecords) -> Cow<'a, TotalRecords> {
+        if !self.translate_item_names {
+            return Cow::Borrowed(records);
+        }
+        let mut records = records.clone();
+        for one_record in records
+            .records
+            .values_mut()
+            .flat_map(|account_records| account_records.records.values_mut())
+            .flatten()
+        {
+            one_record.item_name =
+                translate_item_name(&one_record.item_name, self.language).to_string();
+        }
+        Cow::Owned(records)
+    }
+
+    /// 导出的列
+    /// # 参数
+    /// - `default`: 这种格式的默认列，有备注时最后加上备注列，排期表没有录入时去掉UP列
+    /// - `records`: 导出的抽卡记录
+    pub fn columns(&self, default: &[Column], records: &TotalRecords) -> Vec<Column> {
+        if let Some(columns) = &self.columns {
+            return columns.clone();
+        }
+        let mut columns = default.to_vec();
+        if !banner_schedule().is_maintained() {
+            columns.retain(|column| *column != Column::Featured);
+        }
+        if records.has_notes() {
+            columns.push(Column::Note);
+        }
+        columns
+    }
+
+    /// 导出文件路径
+    pub fn output_path(&self, format: ExportFormat) -> PathBuf {
+        self.output_dir.join(format.file_name())
+    }
+}
+
+/// 读取导出模板，模板有误时记录日志并使用默认列
+fn load_template_columns() -> Option<Vec<Column>> {
+    match ExportTemplate::load() {
+        Ok(template) => template.map(|template| template.columns),
+        Err(e) => {
+            log::warn!("invalid export template: {:?}", e);
+            None
+        }
+    }
+}
+
+pub trait Exporter: Send + Sync {
+    fn format(&self) -> ExportFormat;
+
+    /// 导出抽卡记录
+    /// # 返回
+    /// 导出文件的路径，导出为多个文件时为所在的目录
+    fn export(&self, records: &TotalRecords, opts: &ExportOptions) -> Result<PathBuf>;
+}
+
+lazy_static! {
+    /// 所有导出格式的实现
+    static ref EXPORTERS: Vec<Box<dyn Exporter>> = vec![
+        Box::new(excel::ExcelExporter),
+        Box::new(csv::CsvExporter),
+        Box::new(json::JsonExporter),
+        Box::new(html::HtmlExporter),
+        Box::new(markdown::MarkdownExporter),
+    ];
+}
+
+/// 导出格式对应的实现
+pub fn exporter(format: ExportFormat) -> &'static dyn Exporter {
+    EXPORTERS
+        .iter()
+        .find(|exporter| exporter.format() == format)
+        .map(Box::as_ref)
+        .unwrap()
+}
+
+/// 按顺序导出多种格式，重复的格式只导出一次，开启时每个账号另外写入 pity_state.json
+/// # 返回
+/// 导出文件的路径
+pub fn export_all(
+    records: &TotalRecords,
+    formats: &[ExportFormat],
+    opts: &ExportOptions,
+) -> Result<Vec<PathBuf>> {
+    let mut paths = vec![];
+    for (i, format) in formats.iter().enumerate() {
+        if formats[..i].contains(format) {
+            continue;
+        }
+        let path = exporter(*format).export(records, opts)?;
+        log::info!("exported {:?} to {:?}", format, path);
+        emit(ProgressEvent::ExportWritten {
+            format: *format,
+            path: path.clone(),
+        });
+        paths.push(path);
+    }
+    if opts.pity_state {
+        for path in write_pity_states(records, &opts.output_dir) {
+            log::info!("exported pity state to {:?}", path);
+        }
+    }
+    Ok(paths)
+}
+
+/// 按账号ID、卡池顺序排列的抽卡记录，用于逐表输出的文本格式
+fn banner_records(records: &TotalRecords) -> Vec<(&str, BannerType, &OneAccountRecords)> {
+    let mut account_ids = records.records.keys().collect::<Vec<_>>();
+    account_ids.sort();
+    account_ids
+        .into_iter()
+        .flat_map(|account_id| {
+            let account_records = &records.records[account_id];
+            all::<BannerType>()
+                .filter(|banner_type| account_records.records.contains_key(banner_type))
+                .map(move |banner_type| (account_id.as_str(), banner_type, account_records))
+        })
+        .collect()
+}
+
+/// 把文本写入导出文件
+fn write_output(opts: &ExportOptions, format: ExportFormat, content: String) -> Result<PathBuf> {
+    std::fs::create_dir_all(&opts.output_dir)?;
+    let path = opts.output_path(format);
+    std::fs::write(&path, content).map_err(|e| save_error(e, &path))?;
+    Ok(path)
+}
+
+#[cfg(test)]
+mod test {
+    use std::collections::HashMap;
+
+    use super::*;
+    use crate::record::{ImportBatch, ItemType, OneRecord, RecordSource};
+
+    #[test]
+    fn test_export_all() {
+        let dir = std::env::temp_dir().join("snowbreak_gacha_export_test_export");
+        let _ = std::fs::remove_dir_all(&dir);
+        let mut total_records = TotalRecords::new(HashMap::new());
+        total_records
+            .add_record(
+                "id".to_string(),
+                ImportBatch::new(BannerType::PermanentWeapon, RecordSource::Ocr),
+                vec![
+                    OneRecord::new(5, "a|<b>".to_string(), ItemType::Weapon, 2),
+                    OneRecord::new(3, "c,\"d\"".to_string(), ItemType::Weapon, 1),
+                ],
+            )
+            .unwrap();
+        let opts = ExportOptions {
+            language: Language::English,
+            output_dir: dir.clone(),
+            split_per_banner: false,
+            charts: true,
+            columns: None,
+            translate_item_names: false,
+            pity_state: true,
+        };
+
+        let formats = all::<ExportFormat>().collect::<Vec<_>>();
+        let paths = export_all(&total_records, &formats, &opts).unwrap();
+        assert_eq!(paths.len(), formats.len());
+        for (format, path) in formats.iter().zip(paths.iter()) {
+            assert_eq!(exporter(*format).format(), *format);
+            assert_eq!(path, &opts.output_path(*format));
+            assert!(path.is_file());
+        }
+        let csv = std::fs::read_to_string(opts.output_path(ExportFormat::Csv)).unwrap();
+        assert!(csv.contains("\"c,\"\"d\"\"\""));
+        let html = std::fs::read_to_string(opts.output_path(ExportFormat::Html)).unwrap();
+        assert!(html.contains("a|&lt;b&gt;"));
+        let markdown = std::fs::read_to_string(opts.output_path(ExportFormat::Markdown)).unwrap();
+        assert!(markdown.contains("a\\|<b>"));
+        assert!(html.contains(&total_records.checksum()));
+        let json = TotalRecords::read_from(opts.output_path(ExportFormat::Json)).unwrap();
+        assert_eq!(
+            json.records["id"].records,
+            total_records.records["id"].records
+        );
+        let json = std::fs::read_to_string(opts.output_path(ExportFormat::Json)).unwrap();
+        let json = serde_json::from_str::<serde_json::Value>(&json).unwrap();
+        assert_eq!(json["export_meta"]["accounts"][0], "id");
+        let pity_state = std::fs::read_to_string(dir.join("id").join("pity_state.json")).unwrap();
+        let pity_state = serde_json::from_str::<serde_json::Value>(&pity_state).unwrap();
+        assert_eq!(pity_state["banners"][0]["pity_5_star"], 0);
+
+        // 重复的格式只导出一次
+        let paths = export_all(
+            &total_records,
+            &[ExportFormat::Csv, ExportFormat::Csv],
+            &opts,
+        )
+        .unwrap();
+        assert_eq!(paths.len(), 1);
+
+        // 翻译物品名称时校验和仍然与保存的抽卡记录一致
+        total_records
+            .add_record(
+                "id".to_string(),
+                ImportBatch::new(BannerType::PermanentCharacter, RecordSource::Ocr),
+                vec![OneRecord::new(
+                    5,
+                    "里芙·无限之视".to_string(),
+                    ItemType::Character,
+                    3,
+                )],
+            )
+            .unwrap();
+        let opts = ExportOptions {
+            translate_item_names: true,
+            ..opts
+        };
+        export_all(
+            &total_records,
+            &[ExportFormat::Html, ExportFormat::Markdown],
+            &opts,
+        )
+        .unwrap();
+        for format in [ExportFormat::Html, ExportFormat::Markdown] {
+            let content = std::fs::read_to_string(opts.output_path(format)).unwrap();
+            assert!(content.contains("Lyfe - Wild Hunt"));
+            assert!(content.contains(&total_records.checksum()));
+        }
+        std::fs::remove_dir_all(&dir).unwrap();
+    }
+}
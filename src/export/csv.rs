@@ -0,0 +1,59 @@
+use std::path::PathBuf;
+
+use anyhow::Result;
+
+use super::template::{rows, Column};
+use super::{banner_records, write_output, ExportFormat, ExportOptions, Exporter};
+use crate::record::TotalRecords;
+
+/// 所有账号、卡池的抽卡记录在同一个表中，每行一条
+pub struct CsvExporter;
+
+/// 默认列
+static DEFAULT_COLUMNS: [Column; 6] = [
+    Column::Account,
+    Column::Banner,
+    Column::Star,
+    Column::Name,
+    Column::Type,
+    Column::Time,
+];
+
+/// 含有逗号、引号或换行的字段用引号包围，引号写两次
+fn escape(field: &str) -> String {
+    if field.contains([',', '"', '\n', '\r']) {
+        format!("\"{}\"", field.replace('"', "\"\""))
+    } else {
+        field.to_string()
+    }
+}
+
+impl Exporter for CsvExporter {
+    fn format(&self) -> ExportFormat {
+        ExportFormat::Csv
+    }
+
+    fn export(&self, records: &TotalRecords, opts: &ExportOptions) -> Result<PathBuf> {
+        let records = &*opts.localized_records(records);
+        let language = opts.language;
+        let columns = opts.columns(&DEFAULT_COLUMNS, records);
+        let headers = columns
+            .iter()
+            .map(|column| escape(column.header(language)))
+            .collect::<Vec<_>>();
+        let mut content = headers.join(",") + "\n";
+        for (account_id, banner_type, account_records) in banner_records(records) {
+            for row in rows(&columns, account_id, account_records, banner_type, language) {
+                let fields = row
+                    .cells
+                    .iter()
+                    .map(|cell| escape(&cell.to_string()))
+                    .collect::<Vec<_>>();
+                content += &fields.join(",");
+                content += "\n";
+            }
+        }
+        // 带 BOM，Excel 打开时能正确识别 UTF-8 编码
+        write_output(opts, self.format(), format!("\u{feff}{content}"))
+    }
+}
@@ -0,0 +1,48 @@
+use std::path::PathBuf;
+
+use anyhow::Result;
+
+use super::meta::ExportMeta;
+use super::template::Column;
+use super::{ExportFormat, ExportOptions, Exporter};
+use crate::record::TotalRecords;
+use crate::save::{save_excel, save_excel_per_banner};
+
+/// 每个账号每个卡池一个工作表，另有物品数量统计表
+/// 按卡池分文件时每个账号每个卡池一个文件，保存在导出目录的 records_per_banner 中
+pub struct ExcelExporter;
+
+/// 默认列
+static DEFAULT_COLUMNS: [Column; 9] = [
+    Column::Star,
+    Column::Name,
+    Column::Type,
+    Column::Time,
+    Column::PullsSince5Star,
+    Column::PullsToPity,
+    Column::PullsSince4Star,
+    Column::Featured,
+    Column::TenPull,
+];
+
+impl Exporter for ExcelExporter {
+    fn format(&self) -> ExportFormat {
+        ExportFormat::Excel
+    }
+
+    fn export(&self, records: &TotalRecords, opts: &ExportOptions) -> Result<PathBuf> {
+        // 校验和按保存的抽卡记录计算，与 records.json 一致，不受物品名称翻译影响
+        let meta = ExportMeta::new(records);
+        let records = &*opts.localized_records(records);
+        let columns = opts.columns(&DEFAULT_COLUMNS, records);
+        if opts.split_per_banner {
+            let dir = opts.output_dir.join("records_per_banner");
+            let paths = save_excel_per_banner(records, opts.language, &columns, &meta, &dir)?;
+            log::info!("exported {} files to {:?}", paths.len(), dir);
+            return Ok(dir);
+        }
+        let path = opts.output_path(self.format());
+        save_excel(records, opts.language, &columns, &meta, opts.charts, &path)?;
+        Ok(path)
+    }
+}
@@ -0,0 +1,91 @@
+use std::path::PathBuf;
+
+use anyhow::Result;
+
+use super::meta::ExportMeta;
+use super::template::{rows, Column};
+use super::{banner_records, write_output, ExportFormat, ExportOptions, Exporter};
+use crate::i18n::translate;
+use crate::record::TotalRecords;
+use crate::stats::TEN_PULL;
+
+/// 单个 HTML 文件，每个账号每个卡池一个表格，5星/4星与 Excel 使用相同的背景色
+pub struct HtmlExporter;
+
+/// 默认列
+static DEFAULT_COLUMNS: [Column; 5] = [
+    Column::Star,
+    Column::Name,
+    Column::Type,
+    Column::Time,
+    Column::TenPull,
+];
+
+static STYLE: &str = "body { font-family: sans-serif; }
+table { border-collapse: collapse; margin-bottom: 1em; }
+th, td { border: 1px solid #ccc; padding: 2px 8px; }
+.star-5 { background-color: #e99b37; }
+.star-4 { background-color: #c069d6; }
+.ten-pull-start { border-top: 3px solid #333; }
+.ten-pull-end { border-bottom: 3px solid #333; }";
+
+fn escape(text: &str) -> String {
+    text.replace('&', "&amp;")
+        .replace('<', "&lt;")
+        .replace('>', "&gt;")
+        .replace('"', "&quot;")
+}
+
+impl Exporter for HtmlExporter {
+    fn format(&self) -> ExportFormat {
+        ExportFormat::Html
+    }
+
+    fn export(&self, records: &TotalRecords, opts: &ExportOptions) -> Result<PathBuf> {
+        write_output(opts, self.format(), render_html(records, opts))
+    }
+}
+
+/// 生成 HTML 页面，导出和本地统计页面（`serve` 子命令）共用
+pub fn render_html(records: &TotalRecords, opts: &ExportOptions) -> String {
+    // 校验和按保存的抽卡记录计算，与 records.json 一致，不受物品名称翻译影响
+    let meta = ExportMeta::new(records);
+    let records = &*opts.localized_records(records);
+    let language = opts.language;
+    let title = translate(language, "export.title");
+    let columns = opts.columns(&DEFAULT_COLUMNS, records);
+    let header_row = columns
+        .iter()
+        .map(|column| format!("<th>{}</th>", column.header(language)))
+        .collect::<String>();
+    let mut body = format!("<h1>{title}</h1>\n");
+    for (account_id, banner_type, account_records) in banner_records(records) {
+        body += &format!(
+            "<h2>{} - {}</h2>\n<table>\n<tr>{header_row}</tr>\n",
+            escape(account_id),
+            banner_type.display_name_for_user(language)
+        );
+        for row in rows(&columns, account_id, account_records, banner_type, language) {
+            // 十连的第一条和最后一条记录加上分隔线
+            let separator = match row.ten_pull_number {
+                Some(number) if number as usize == TEN_PULL => " ten-pull-start",
+                Some(1) => " ten-pull-end",
+                _ => "",
+            };
+            let cells = row
+                .cells
+                .iter()
+                .map(|cell| format!("<td>{}</td>", escape(&cell.to_string())))
+                .collect::<String>();
+            body += &format!("<tr class=\"star-{}{separator}\">{cells}</tr>\n", row.star);
+        }
+        body += "</table>\n";
+    }
+    body += &format!(
+        "<footer><p>{}</p></footer>\n",
+        escape(&meta.summary(language))
+    );
+    format!(
+            "<!DOCTYPE html>\n<html>\n<head>\n<meta charset=\"utf-8\">\n<title>{title}</title>\n<style>\n{STYLE}\n</style>\n</head>\n<body>\n{body}</body>\n</html>\n"
+        )
+}
@@ -0,0 +1,22 @@
+use std::path::PathBuf;
+
+use anyhow::Result;
+
+use super::meta::ExportMeta;
+use super::{write_output, ExportFormat, ExportOptions, Exporter};
+use crate::record::TotalRecords;
+
+/// 与 records.json 相同的格式，可以再次导入，另外附加导出信息 `export_meta`
+pub struct JsonExporter;
+
+impl Exporter for JsonExporter {
+    fn format(&self) -> ExportFormat {
+        ExportFormat::Json
+    }
+
+    fn export(&self, records: &TotalRecords, opts: &ExportOptions) -> Result<PathBuf> {
+        let mut content = serde_json::to_value(records.with_checksums())?;
+        content["export_meta"] = serde_json::to_value(ExportMeta::new(records))?;
+        write_output(opts, self.format(), serde_json::to_string_pretty(&content)?)
+    }
+}
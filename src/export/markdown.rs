@@ -0,0 +1,58 @@
+use std::path::PathBuf;
+
+use anyhow::Result;
+
+use super::meta::ExportMeta;
+use super::template::{rows, Column};
+use super::{banner_records, write_output, ExportFormat, ExportOptions, Exporter};
+use crate::i18n::translate;
+use crate::record::TotalRecords;
+
+/// 每个账号每个卡池一个表格
+pub struct MarkdownExporter;
+
+/// 默认列
+static DEFAULT_COLUMNS: [Column; 4] = [Column::Star, Column::Name, Column::Type, Column::Time];
+
+/// 表格中的 `|` 需要转义
+fn escape(text: &str) -> String {
+    text.replace('|', "\\|")
+}
+
+impl Exporter for MarkdownExporter {
+    fn format(&self) -> ExportFormat {
+        ExportFormat::Markdown
+    }
+
+    fn export(&self, records: &TotalRecords, opts: &ExportOptions) -> Result<PathBuf> {
+        // 校验和按保存的抽卡记录计算，与 records.json 一致，不受物品名称翻译影响
+        let meta = ExportMeta::new(records);
+        let records = &*opts.localized_records(records);
+        let language = opts.language;
+        let columns = opts.columns(&DEFAULT_COLUMNS, records);
+        let headers = columns
+            .iter()
+            .map(|column| column.header(language))
+            .collect::<Vec<_>>();
+        let mut content = format!("# {}\n", translate(language, "export.title"));
+        for (account_id, banner_type, account_records) in banner_records(records) {
+            content += &format!(
+                "\n## {} - {}\n\n",
+                escape(account_id),
+                banner_type.display_name_for_user(language)
+            );
+            content += &format!("| {} |\n", headers.join(" | "));
+            content += &format!("|{}\n", " --- |".repeat(headers.len()));
+            for row in rows(&columns, account_id, account_records, banner_type, language) {
+                let fields = row
+                    .cells
+                    .iter()
+                    .map(|cell| escape(&cell.to_string()))
+                    .collect::<Vec<_>>();
+                content += &format!("| {} |\n", fields.join(" | "));
+            }
+        }
+        content += &format!("\n---\n\n{}\n", escape(&meta.summary(language)));
+        write_output(opts, self.format(), content)
+    }
+}
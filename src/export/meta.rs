@@ -0,0 +1,58 @@
+//! 导出文件的元数据
+//!
+//! 每个导出文件都记录导出工具的版本、导出时间、账号和抽卡记录的校验和
+//! （Excel 的文档属性、JSON 的 `export_meta`、HTML 和 Markdown 的页脚），
+//! 分享出去的文件可以看出是哪个版本、哪份抽卡记录导出的。
+
+use chrono::Local;
+use serde::{Deserialize, Serialize};
+
+use crate::i18n::{format_translation, translate};
+use crate::language::Language;
+use crate::record::TotalRecords;
+
+/// 导出工具的名称
+pub static TOOL_NAME: &str = "snowbreak_gacha_export";
+
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct ExportMeta {
+    /// 导出工具的版本
+    pub tool_version: String,
+    /// 导出时间
+    pub exported_at: String,
+    /// 导出的账号ID，按ID排序
+    pub accounts: Vec<String>,
+    /// 抽卡记录的校验和，见 [`TotalRecords::checksum`]
+    pub records_checksum: String,
+}
+
+impl ExportMeta {
+    pub fn new(records: &TotalRecords) -> Self {
+        let mut accounts = records.records.keys().cloned().collect::<Vec<_>>();
+        accounts.sort();
+        Self {
+            tool_version: env!("CARGO_PKG_VERSION").to_string(),
+            exported_at: Local::now().format("%Y-%m-%d %H:%M:%S").to_string(),
+            accounts,
+            records_checksum: records.checksum(),
+        }
+    }
+
+    /// 工具名称和版本，例如 `snowbreak_gacha_export 1.2.3`
+    pub fn tool(&self) -> String {
+        format!("{TOOL_NAME} {}", self.tool_version)
+    }
+
+    /// 一行说明，用于页脚
+    pub fn summary(&self, language: Language) -> String {
+        format_translation(
+            translate(language, "export.meta"),
+            &[
+                ("tool", self.tool()),
+                ("time", self.exported_at.clone()),
+                ("accounts", self.accounts.join(", ")),
+                ("checksum", self.records_checksum.clone()),
+            ],
+        )
+    }
+}
@@ -0,0 +1,207 @@
+//! 每个账号的保底状态
+//!
+//! 导出时在导出目录的 `<账号ID>/pity_state.json` 中写入每个卡池当前的保底计数和大保底状态，
+//! 抽卡规划网站、工具可以直接读取，不需要解析完整的抽卡记录。
+//! 账号ID中不能用在文件名中的字符替换为 `_`，见 [`account_dir_name`]。
+
+use std::path::{Path, PathBuf};
+
+use anyhow::Result;
+use chrono::Local;
+use clap::ValueEnum;
+use enum_iterator::all;
+use serde::{Deserialize, Serialize};
+
+use crate::data_dir::write_atomic;
+use crate::error::save_error;
+use crate::record::{BannerType, OneAccountRecords, TotalRecords};
+use crate::stats::{current_4_star_pity, current_pity};
+
+/// 保底状态文件名
+pub static PITY_STATE_FILE: &str = "pity_state.json";
+
+/// Windows 文件名中不能出现的字符
+const ILLEGAL_FILE_NAME_CHARS: [char; 9] = ['<', '>', ':', '"', '/', '\\', '|', '?', '*'];
+
+/// Windows 保留的设备名，不能用作文件名
+const RESERVED_FILE_NAMES: [&str; 4] = ["CON", "PRN", "AUX", "NUL"];
+
+/// 一个卡池的保底状态
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct BannerPityState {
+    /// 卡池类型，与命令行中的相同，例如 `limited-character-50`
+    pub banner: String,
+    /// 距离上一个5星已经抽了多少次
+    pub pity_5_star: u32,
+    /// 5星保底的抽数
+    pub hard_pity_5_star: u32,
+    /// 距离上一个4星已经抽了多少次
+    pub pity_4_star: u32,
+    /// 下一个5星必定是UP物品，只有50%限定卡池有
+    pub guaranteed: Option<bool>,
+    /// 从上一次赢得小保底起连续歪的次数，只有50%限定卡池有
+    pub lost_streak: Option<u32>,
+}
+
+/// 一个账号的保底状态
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct PityState {
+    /// 导出工具的版本
+    pub tool_version: String,
+    /// 导出时间
+    pub exported_at: String,
+    pub account_id: String,
+    /// 有抽卡记录、保底计数锚点或大保底状态的卡池
+    pub banners: Vec<BannerPityState>,
+}
+
+impl PityState {
+    pub fn new(account_id: &str, account_records: &OneAccountRecords) -> Self {
+        let banners = all::<BannerType>()
+            .filter_map(|banner_type| {
+                let records = account_records
+                    .records
+                    .get(&banner_type)
+                    .map(Vec::as_slice)
+                    .unwrap_or_default();
+                let anchor = account_records.pity_anchor(banner_type);
+                let guarantee_state = account_records.guarantee_state(banner_type);
+                if records.is_empty() && anchor.is_none() && guarantee_state.is_none() {
+                    return None;
+                }
+                Some(BannerPityState {
+                    banner: banner_type
+                        .to_possible_value()
+                        .map(|value| value.get_name().to_string())
+                        .unwrap_or_default(),
+                    pity_5_star: current_pity(records, anchor),
+                    hard_pity_5_star: banner_type.pity_count(),
+                    pity_4_star: current_4_star_pity(records, anchor),
+                    guaranteed: guarantee_state.map(|state| state.guaranteed),
+                    lost_streak: guarantee_state.map(|state| state.lost_streak),
+                })
+            })
+            .collect();
+        Self {
+            tool_version: env!("CARGO_PKG_VERSION").to_string(),
+            exported_at: Local::now().format("%Y-%m-%d %H:%M:%S").to_string(),
+            account_id: account_id.to_string(),
+            banners,
+        }
+    }
+}
+
+/// 账号ID转换为目录名，账号ID是用户随意输入的
+/// 不能出现的字符和控制字符替换为 `_`，去掉结尾的点和空格，
+/// 只有点的名称（例如 `..`）和设备名前面加上 `_`，目录不会跑到导出目录外面
+pub fn account_dir_name(account_id: &str) -> String {
+    let name = account_id
+        .chars()
+        .map(|c| {
+            if c.is_control() || ILLEGAL_FILE_NAME_CHARS.contains(&c) {
+                '_'
+            } else {
+                c
+            }
+        })
+        .collect::<String>();
+    let name = name.trim_end_matches(['.', ' ']);
+    let stem = name
+        .split('.')
+        .next()
+        .unwrap_or_default()
+        .to_ascii_uppercase();
+    let is_reserved = RESERVED_FILE_NAMES.contains(&stem.as_str())
+        || (stem.len() == 4
+            && (stem.starts_with("COM") || stem.starts_with("LPT"))
+            && stem.ends_with(|c: char| c.is_ascii_digit()));
+    if name.is_empty() || is_reserved {
+        format!("_{name}")
+    } else {
+        name.to_string()
+    }
+}
+
+/// 写入一个账号的保底状态
+fn write_pity_state(
+    account_id: &str,
+    account_records: &OneAccountRecords,
+    output_dir: &Path,
+) -> Result<PathBuf> {
+    let pity_state = PityState::new(account_id, account_records);
+    let dir = output_dir.join(account_dir_name(account_id));
+    std::fs::create_dir_all(&dir).map_err(|e| save_error(e, &dir))?;
+    let path = dir.join(PITY_STATE_FILE);
+    write_atomic(&path, serde_json::to_string_pretty(&pity_state)?.as_bytes())
+        .map_err(|e| save_error(e, &path))?;
+    Ok(path)
+}
+
+/// 在导出目录中写入每个账号的保底状态，一个账号写入失败时只记录日志，不影响其他账号和导出
+/// # 返回
+/// 写入的文件路径
+pub fn write_pity_states(records: &TotalRecords, output_dir: &Path) -> Vec<PathBuf> {
+    // 大保底状态只在保存时更新，按保存后的抽卡记录导出
+    let records = records.with_checksums();
+    let mut account_ids = records.records.keys().collect::<Vec<_>>();
+    account_ids.sort();
+    let mut paths = vec![];
+    for account_id in account_ids {
+        match write_pity_state(account_id, &records.records[account_id], output_dir) {
+            Ok(path) => paths.push(path),
+            Err(e) => log::warn!("failed to write pity state of {account_id}: {:?}", e),
+        }
+    }
+    paths
+}
+
+#[cfg(test)]
+mod test {
+    use std::collections::HashMap;
+
+    use super::*;
+    use crate::record::{ItemType, OneRecord};
+
+    #[test]
+    fn test_pity_state() {
+        let mut account_records = OneAccountRecords::new("id".to_string(), HashMap::new());
+        // 按时间倒序：最新的 2 抽之前是一个4星，再之前是一个5星
+        let star = [3, 3, 4, 3, 5, 3];
+        account_records.records.insert(
+            BannerType::PermanentWeapon,
+            star.iter()
+                .enumerate()
+                .map(|(i, star)| {
+                    OneRecord::new(*star, "a".to_string(), ItemType::Weapon, 100 - i as u64)
+                })
+                .collect(),
+        );
+        let pity_state = PityState::new("id", &account_records);
+        assert_eq!(
+            pity_state.banners,
+            [BannerPityState {
+                banner: "permanent-weapon".to_string(),
+                pity_5_star: 4,
+                hard_pity_5_star: 60,
+                pity_4_star: 2,
+                guaranteed: None,
+                lost_streak: None,
+            }]
+        );
+        let json = serde_json::to_value(&pity_state).unwrap();
+        assert_eq!(json["account_id"], "id");
+        assert_eq!(json["banners"][0]["guaranteed"], serde_json::Value::Null);
+    }
+
+    #[test]
+    fn test_account_dir_name() {
+        assert_eq!(account_dir_name("玩家1"), "玩家1");
+        assert_eq!(account_dir_name("a:b?c*"), "a_b_c_");
+        assert_eq!(account_dir_name("../x"), ".._x");
+        assert_eq!(account_dir_name(".."), "_");
+        assert_eq!(account_dir_name("a. "), "a");
+        assert_eq!(account_dir_name("nul"), "_nul");
+        assert_eq!(account_dir_name("COM1.txt"), "_COM1.txt");
+        assert_eq!(account_dir_name("COMMON"), "COMMON");
+    }
+}
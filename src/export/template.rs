@@ -0,0 +1,303 @@
+//! 导出模板
+//!
+//! 数据目录中的 `export_template.toml` 列出导出的列和顺序，对 Excel、CSV、HTML、Markdown 都生效，例如：
+//!
+//! ```toml
+//! columns = ["name", "time", "pulls_since_5_star"]
+//! ```
+//!
+//! 没有模板时每种格式使用自己的默认列。
+
+use std::fmt::{Display, Formatter};
+
+use anyhow::{anyhow, Result};
+use enum_iterator::Sequence;
+use serde::{Deserialize, Serialize};
+
+use crate::banner_schedule::featured_items;
+use crate::data_dir::data_path;
+use crate::i18n::translate;
+use crate::items::{item_category, ItemCategory};
+use crate::language::Language;
+use crate::record::{BannerType, OneAccountRecords, OneRecord};
+use crate::stats::{pity_counts, ten_pull_numbers, TEN_PULL};
+
+/// 模板文件名
+static TEMPLATE_FILE: &str = "export_template.toml";
+
+/// 导出的一列
+#[derive(Debug, Copy, Clone, PartialEq, Eq, Serialize, Deserialize, Sequence)]
+#[serde(rename_all = "snake_case")]
+pub enum Column {
+    /// 账号ID
+    Account,
+    /// 卡池
+    Banner,
+    /// 星级
+    Star,
+    /// 物品名称
+    Name,
+    /// 物品类型
+    Type,
+    /// 角色属性，见物品字典
+    Element,
+    /// 武器类型，角色为使用的武器类型，见物品字典
+    WeaponType,
+    /// 抽卡时间
+    Time,
+    /// 距离上一个5星的抽数
+    #[serde(rename = "pulls_since_5_star")]
+    PullsSince5Star,
+    /// 距离5星保底的抽数
+    PullsToPity,
+    /// 距离上一个4星的抽数
+    #[serde(rename = "pulls_since_4_star")]
+    PullsSince4Star,
+    /// 是否是UP物品
+    Featured,
+    /// 在十连中的序号
+    TenPull,
+    /// 备注
+    Note,
+}
+
+impl Column {
+    /// 表头的翻译键
+    fn header_key(&self) -> &'static str {
+        match self {
+            Column::Account => "export.account",
+            Column::Banner => "export.banner",
+            Column::Star => "excel.star",
+            Column::Name => "excel.name",
+            Column::Type => "excel.type",
+            Column::Element => "export.element",
+            Column::WeaponType => "export.weapon_type",
+            Column::Time => "excel.time",
+            Column::PullsSince5Star => "excel.after_5_star",
+            Column::PullsToPity => "excel.5_star_pity",
+            Column::PullsSince4Star => "excel.after_4_star",
+            Column::Featured => "excel.featured",
+            Column::TenPull => "excel.ten_pull",
+            Column::Note => "export.note",
+        }
+    }
+
+    pub fn header(&self, language: Language) -> &'static str {
+        translate(language, self.header_key())
+    }
+
+    /// Excel 中的列宽
+    pub fn width(&self) -> f64 {
+        match self {
+            Column::Star | Column::Type => 5.0,
+            Column::Name | Column::Time | Column::Account | Column::Note => 20.0,
+            Column::Banner => 16.0,
+            Column::TenPull => 6.0,
+            Column::PullsSince5Star | Column::PullsToPity | Column::PullsSince4Star => 8.0,
+            Column::Featured | Column::Element | Column::WeaponType => 10.0,
+        }
+    }
+}
+
+/// 导出模板
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct ExportTemplate {
+    pub columns: Vec<Column>,
+}
+
+impl ExportTemplate {
+    fn parse(content: &str) -> Result<Self> {
+        let template = toml::from_str::<Self>(content)?;
+        if template.columns.is_empty() {
+            return Err(anyhow!("no columns in {TEMPLATE_FILE}"));
+        }
+        Ok(template)
+    }
+
+    /// 读取数据目录中的模板，没有模板文件时返回 None
+    pub fn load() -> Result<Option<Self>> {
+        let path = data_path(TEMPLATE_FILE);
+        if !path.exists() {
+            return Ok(None);
+        }
+        Self::parse(&std::fs::read_to_string(path)?).map(Some)
+    }
+}
+
+/// 单元格，数字在 Excel 中保存为数字
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum Cell {
+    Number(u32),
+    Text(String),
+}
+
+impl Display for Cell {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        match self {
+            Cell::Number(number) => write!(f, "{number}"),
+            Cell::Text(text) => write!(f, "{text}"),
+        }
+    }
+}
+
+/// 一条抽卡记录导出的一行
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Row {
+    pub star: u8,
+    /// 在十连中的序号，见 [`ten_pull_numbers`]
+    pub ten_pull_number: Option<u32>,
+    pub cells: Vec<Cell>,
+}
+
+/// 限定卡池中的5星/4星是否是UP物品
+/// 排期表中没有对应卡池时返回空字符串
+fn featured_mark(one_record: &OneRecord, banner_type: BannerType, language: Language) -> &str {
+    let Some(featured) = featured_items(one_record.timestamp, banner_type) else {
+        return "";
+    };
+    let featured_names = match one_record.star {
+        5 => &featured.five_star,
+        4 => &featured.four_star,
+        _ => return "",
+    };
+    if featured_names.is_empty() {
+        return "";
+    }
+    if featured.is_featured(&one_record.item_name) {
+        translate(language, "excel.is_featured")
+    } else {
+        translate(language, "excel.off_banner")
+    }
+}
+
+/// 一个卡池的抽卡记录按列生成的行，与记录顺序相同
+pub fn rows(
+    columns: &[Column],
+    account_id: &str,
+    account_records: &OneAccountRecords,
+    banner_type: BannerType,
+    language: Language,
+) -> Vec<Row> {
+    let one_records = account_records
+        .records
+        .get(&banner_type)
+        .map(Vec::as_slice)
+        .unwrap_or_default();
+    let pity_counts = pity_counts(one_records, account_records.pity_anchor(banner_type));
+    one_records
+        .iter()
+        .zip(pity_counts)
+        .zip(ten_pull_numbers(one_records))
+        .map(
+            |((one_record, (count_after_5_star, count_after_4_star)), ten_pull_number)| {
+                let cells = columns
+                    .iter()
+                    .map(|column| match column {
+                        Column::Account => Cell::Text(account_id.to_string()),
+                        Column::Banner => {
+                            Cell::Text(banner_type.display_name_for_user(language).to_string())
+                        }
+                        Column::Star => Cell::Number(one_record.star as u32),
+                        Column::Name => Cell::Text(one_record.item_name.clone()),
+                        Column::Type => Cell::Text(
+                            one_record
+                                .item_type
+                                .display_name_for_user(language)
+                                .to_string(),
+                        ),
+                        Column::Element => Cell::Text(
+                            item_category(&one_record.item_name, ItemCategory::Element, language)
+                                .unwrap_or_default()
+                                .to_string(),
+                        ),
+                        Column::WeaponType => Cell::Text(
+                            item_category(
+                                &one_record.item_name,
+                                ItemCategory::WeaponType,
+                                language,
+                            )
+                            .unwrap_or_default()
+                            .to_string(),
+                        ),
+                        Column::Time => Cell::Text(one_record.readable_date_time_str()),
+                        Column::PullsSince5Star => Cell::Number(count_after_5_star),
+                        Column::PullsToPity => Cell::Number(
+                            banner_type.pity_count().saturating_sub(count_after_5_star),
+                        ),
+                        Column::PullsSince4Star => Cell::Number(count_after_4_star),
+                        Column::Featured => {
+                            Cell::Text(featured_mark(one_record, banner_type, language).to_string())
+                        }
+                        Column::TenPull => Cell::Text(
+                            ten_pull_number
+                                .map(|number| format!("{number}/{TEN_PULL}"))
+                                .unwrap_or_default(),
+                        ),
+                        Column::Note => Cell::Text(one_record.note.clone().unwrap_or_default()),
+                    })
+                    .collect();
+                Row {
+                    star: one_record.star,
+                    ten_pull_number,
+                    cells,
+                }
+            },
+        )
+        .collect()
+}
+
+#[cfg(test)]
+mod test {
+    use std::collections::HashMap;
+
+    use super::*;
+    use crate::record::ItemType;
+
+    #[test]
+    fn test_template() {
+        let template =
+            ExportTemplate::parse("columns = [\"name\", \"time\", \"pulls_since_5_star\"]")
+                .unwrap();
+        assert_eq!(
+            template.columns,
+            [Column::Name, Column::Time, Column::PullsSince5Star]
+        );
+        assert!(ExportTemplate::parse("columns = []").is_err());
+        assert!(ExportTemplate::parse("columns = [\"unknown\"]").is_err());
+
+        let account_records = OneAccountRecords::new(
+            "id".to_string(),
+            HashMap::from([(
+                BannerType::PermanentWeapon,
+                vec![
+                    OneRecord::new(5, "a".to_string(), ItemType::Weapon, 2),
+                    OneRecord::new(3, "b".to_string(), ItemType::Weapon, 1),
+                ],
+            )]),
+        );
+        let rows = rows(
+            &[
+                Column::Name,
+                Column::PullsSince5Star,
+                Column::Account,
+                Column::WeaponType,
+            ],
+            "id",
+            &account_records,
+            BannerType::PermanentWeapon,
+            Language::English,
+        );
+        assert_eq!(rows.len(), 2);
+        assert_eq!(rows[0].star, 5);
+        assert_eq!(
+            rows[0].cells,
+            [
+                Cell::Text("a".to_string()),
+                Cell::Number(2),
+                Cell::Text("id".to_string()),
+                // 物品字典中没有的物品
+                Cell::Text(String::new())
+            ]
+        );
+    }
+}
@@ -1,15 +1,153 @@
-use anyhow::anyhow;
-use window_inspector::find::get_hwnd_ref_cache;
+//! 查找游戏窗口
+//!
+//! 同时打开多个游戏客户端（例如国服和国际服）时列出所有窗口让用户选择，
+//! 选择在本次运行中保持不变。截图按窗口标题查找，两个窗口标题相同时截图可能来自另一个窗口。
 
-pub fn get_game_window_info() -> anyhow::Result<(isize, String)> {
-    let window_class = "UnrealWindow";
-    let possible_window_titles = ["尘白禁区", "Snowbreak: Containment Zone"];
+use std::io::{stdin, IsTerminal};
+use std::sync::Mutex;
+
+use lazy_static::lazy_static;
+
+use crate::error::ExportError;
+use crate::i18n::tr;
+use crate::user_interaction::select_index;
+
+static WINDOW_CLASS: &str = "UnrealWindow";
+static POSSIBLE_WINDOW_TITLES: [&str; 2] = ["尘白禁区", "Snowbreak: Containment Zone"];
+
+lazy_static! {
+    /// 本次运行中选择的游戏窗口
+    static ref SELECTED_HWND: Mutex<Option<isize>> = Mutex::new(None);
+}
+
+/// 游戏窗口
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct GameWindow {
+    pub hwnd: isize,
+    pub title: String,
+    /// 游戏进程ID
+    pub pid: u32,
+}
+
+/// 所有游戏窗口
+#[cfg(windows)]
+pub fn find_game_windows() -> Vec<GameWindow> {
+    use windows::Win32::Foundation::{BOOL, HWND, LPARAM};
+    use windows::Win32::UI::WindowsAndMessaging::{
+        EnumWindows, GetClassNameW, GetWindowTextW, GetWindowThreadProcessId,
+    };
 
-    for title in possible_window_titles.iter() {
-        if let Ok(hwnd) = get_hwnd_ref_cache(window_class, title) {
-            return Ok((hwnd, title.to_string()));
+    unsafe extern "system" fn callback(hwnd: HWND, lparam: LPARAM) -> BOOL {
+        let game_windows = &mut *(lparam.0 as *mut Vec<GameWindow>);
+        let mut buffer = [0u16; 256];
+        let len = GetClassNameW(hwnd, &mut buffer);
+        if String::from_utf16_lossy(&buffer[..len as usize]) != WINDOW_CLASS {
+            return true.into();
         }
+        let len = GetWindowTextW(hwnd, &mut buffer);
+        let title = String::from_utf16_lossy(&buffer[..len as usize]);
+        if POSSIBLE_WINDOW_TITLES.contains(&title.as_str()) {
+            let mut pid = 0u32;
+            GetWindowThreadProcessId(hwnd, Some(&mut pid));
+            game_windows.push(GameWindow {
+                hwnd: hwnd.0,
+                title,
+                pid,
+            });
+        }
+        true.into()
+    }
+
+    let mut game_windows: Vec<GameWindow> = vec![];
+    unsafe {
+        if let Err(e) = EnumWindows(
+            Some(callback),
+            LPARAM(&mut game_windows as *mut Vec<GameWindow> as isize),
+        ) {
+            log::warn!("failed to enumerate windows: {:?}", e);
+        }
+    }
+    game_windows
+}
+
+/// 所有游戏窗口，每个标题最多找到一个
+#[cfg(not(windows))]
+pub fn find_game_windows() -> Vec<GameWindow> {
+    use window_inspector::find::get_hwnd_ref_cache;
+
+    POSSIBLE_WINDOW_TITLES
+        .iter()
+        .filter_map(|title| {
+            get_hwnd_ref_cache(WINDOW_CLASS, title)
+                .ok()
+                .map(|hwnd| GameWindow {
+                    hwnd,
+                    title: title.to_string(),
+                    pid: 0,
+                })
+        })
+        .collect()
+}
+
+/// 不需要询问用户时选择的窗口
+/// 只有一个窗口，或者本次运行中已经选择过的窗口仍然存在
+fn preselected_window(game_windows: &[GameWindow], selected: Option<isize>) -> Option<usize> {
+    if game_windows.len() == 1 {
+        return Some(0);
+    }
+    selected.and_then(|hwnd| game_windows.iter().position(|window| window.hwnd == hwnd))
+}
+
+/// 获取游戏窗口
+/// 有多个游戏窗口时让用户选择，非交互运行时使用第一个
+/// # 返回
+/// （窗口句柄, 窗口标题）
+pub fn get_game_window_info() -> anyhow::Result<(isize, String)> {
+    let game_windows = find_game_windows();
+    if game_windows.is_empty() {
+        return Err(ExportError::WindowNotFound.into());
     }
+    let mut selected_hwnd = SELECTED_HWND.lock().unwrap();
+    let index = match preselected_window(&game_windows, *selected_hwnd) {
+        Some(index) => index,
+        None if stdin().is_terminal() => {
+            let options = game_windows
+                .iter()
+                .map(|window| tr!("game_window", title = window.title, pid = window.pid))
+                .collect::<Vec<_>>();
+            select_index(tr!("select_game_window"), &options)
+        }
+        None => {
+            log::warn!(
+                "{} game windows found, use the first one",
+                game_windows.len()
+            );
+            0
+        }
+    };
+    let game_window = &game_windows[index];
+    log::info!("game window: {:?}", game_window);
+    selected_hwnd.replace(game_window.hwnd);
+    Ok((game_window.hwnd, game_window.title.clone()))
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
 
-    Err(anyhow!("Failed to get game window info"))
+    #[test]
+    fn test_preselected_window() {
+        let window = |hwnd| GameWindow {
+            hwnd,
+            title: POSSIBLE_WINDOW_TITLES[0].to_string(),
+            pid: hwnd as u32,
+        };
+        assert_eq!(preselected_window(&[window(1)], None), Some(0));
+        assert_eq!(preselected_window(&[window(1)], Some(2)), Some(0));
+        let game_windows = [window(1), window(2)];
+        assert_eq!(preselected_window(&game_windows, None), None);
+        assert_eq!(preselected_window(&game_windows, Some(2)), Some(1));
+        // 选择过的窗口已经关闭
+        assert_eq!(preselected_window(&game_windows, Some(3)), None);
+    }
 }
@@ -0,0 +1,89 @@
+//! 导出后运行的用户命令
+//!
+//! 在 config.json 的 `post_export_hook` 中配置，例如 `["python", "upload.py"]`。
+//! 导出文件的路径依次追加为命令的参数，导出摘要以 JSON 写入环境变量
+//! `SNOWBREAK_EXPORT_SUMMARY`，可以用来把表格自动上传到自己的服务器等。
+
+use std::path::PathBuf;
+use std::process::Command;
+
+use anyhow::{anyhow, Result};
+use serde::Serialize;
+
+use crate::config::config;
+use crate::record::BannerType;
+
+/// 导出摘要的环境变量名
+static SUMMARY_ENV: &str = "SNOWBREAK_EXPORT_SUMMARY";
+
+/// 导出摘要
+#[derive(Debug, Clone, Serialize)]
+pub struct ExportSummary {
+    /// 导入后导出时的账号ID，在菜单中单独导出时为空
+    pub account_id: Option<String>,
+    /// 导入后导出时的卡池类型，在菜单中单独导出时为空
+    pub banner_type: Option<BannerType>,
+    /// 这次导入新增的抽卡记录数量
+    pub added: u32,
+    /// 导出文件的路径
+    pub paths: Vec<PathBuf>,
+}
+
+/// 根据配置构造命令，没有配置时返回 None
+fn hook_command(hook: &[String], summary: &ExportSummary) -> Result<Option<Command>> {
+    let Some((program, args)) = hook.split_first() else {
+        return Ok(None);
+    };
+    let mut command = Command::new(program);
+    command
+        .args(args)
+        .args(&summary.paths)
+        .env(SUMMARY_ENV, serde_json::to_string(summary)?);
+    Ok(Some(command))
+}
+
+/// 运行导出后命令，等待命令结束
+/// 没有配置时什么都不做
+pub fn run_post_export_hook(summary: &ExportSummary) -> Result<()> {
+    let Some(mut command) = hook_command(&config().post_export_hook, summary)? else {
+        return Ok(());
+    };
+    log::info!("run post export hook: {:?}", command);
+    let status = command.status()?;
+    if !status.success() {
+        return Err(anyhow!("post export hook exited with {}", status));
+    }
+    Ok(())
+}
+
+#[cfg(test)]
+mod test {
+    use std::ffi::OsStr;
+
+    use super::*;
+
+    #[test]
+    fn test_hook_command() {
+        let summary = ExportSummary {
+            account_id: Some("id".to_string()),
+            banner_type: Some(BannerType::Beginner),
+            added: 3,
+            paths: vec![PathBuf::from("records.xlsx"), PathBuf::from("records.csv")],
+        };
+        assert!(hook_command(&[], &summary).unwrap().is_none());
+
+        let hook = ["python".to_string(), "upload.py".to_string()];
+        let command = hook_command(&hook, &summary).unwrap().unwrap();
+        assert_eq!(command.get_program(), "python");
+        assert_eq!(
+            command.get_args().collect::<Vec<_>>(),
+            ["upload.py", "records.xlsx", "records.csv"]
+        );
+        let (key, value) = command.get_envs().next().unwrap();
+        assert_eq!(key, SUMMARY_ENV);
+        let value: serde_json::Value =
+            serde_json::from_str(value.and_then(OsStr::to_str).unwrap()).unwrap();
+        assert_eq!(value["added"], 3);
+        assert_eq!(value["banner_type"], "Beginner");
+    }
+}
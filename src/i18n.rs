@@ -0,0 +1,722 @@
+//! 界面文本翻译
+//!
+//! 所有面向用户的文本都在这里按语言维护，通过 `tr!` 宏获取当前界面语言的文本。
+//! 新增语言时只需要在 [`Language`] 中增加一项，并在本文件中增加对应的翻译表。
+
+use std::collections::HashMap;
+use std::sync::RwLock;
+
+use enum_iterator::all;
+use lazy_static::lazy_static;
+
+use crate::language::Language;
+
+/// 简体中文
+static ZH_CN: &[(&str, &str)] = &[
+    // 通用
+    ("input_tip", "输入："),
+    ("invalid_input", "无效输入：{input}，请重新输入"),
+    ("press_enter_to_exit", "按下回车键退出"),
+    // 启动
+    (
+        "new_version_available",
+        "有新版本，请前往 https://github.com/enximi/snowbreak_gacha_export/releases 更新",
+    ),
+    (
+        "usage_tip",
+        "仅支持 16:9 窗口化/无边框\n先打开抽卡记录界面，后运行本程序",
+    ),
+    ("select_game_window", "找到多个游戏窗口，输入数字选择"),
+    ("game_window", "{title}（进程ID {pid}）"),
+    // 卡池与账号选择
+    ("select_banner", "输入数字选择卡池"),
+    ("input_account_id", "输入账号ID："),
+    ("account_id_empty", "账号ID不能为空"),
+    ("select_account", "输入数字选择账号"),
+    ("no_account_id", "没有账号ID"),
+    ("existing_account_ids", "已有账号ID："),
+    (
+        "select_or_new_account",
+        "输入1以选择已有账号，输入2以输入新账号",
+    ),
+    // 主菜单
+    ("menu.title", "输入数字选择功能"),
+    ("menu.import", "导入抽卡记录"),
+    ("menu.view_stats", "查看统计"),
+    ("menu.export", "导出"),
+    ("menu.manage_accounts", "管理账号"),
+    ("menu.settings", "设置"),
+    ("menu.repair_data", "修复数据"),
+    ("menu.exit", "退出"),
+    ("menu.back", "返回"),
+    ("import_finished", "导入完成，新增 {add_num} 条记录"),
+    ("report_saved", "导入报告：{path}"),
+    ("import_failed", "导入失败：{error}"),
+    ("save_failed", "保存失败：{error}"),
+    ("no_records", "没有抽卡记录"),
+    ("select_export_format", "输入数字选择导出格式"),
+    ("export_written", "已导出到 {path}"),
+    ("account_record_count", "{account_id}（{count} 条记录）"),
+    ("select_account_to_delete", "输入数字选择要删除的账号"),
+    (
+        "confirm_delete_account",
+        "确认删除账号 {account_id} 的所有抽卡记录？",
+    ),
+    ("account_deleted", "已删除账号 {account_id}"),
+    ("settings_saved", "设置已保存"),
+    (
+        "repair_finished",
+        "修复完成，重新排序了 {num} 个卡池的抽卡记录",
+    ),
+    ("menu.undo_import", "撤销最近一次导入"),
+    ("no_batch_to_undo", "没有可以撤销的导入"),
+    ("confirm_undo", "撤销 {time} 的导入，删除 {num} 条记录？"),
+    ("undo_finished", "已撤销 {time} 的导入，删除了 {num} 条记录"),
+    ("rows_quarantined", "{num} 行识别失败，已保存到待审核列表"),
+    ("quarantined_row", "第 {page} 页第 {row} 行：{error}"),
+    (
+        "game_not_running_wait",
+        "没有找到游戏窗口，是否等待游戏启动？",
+    ),
+    (
+        "waiting_for_game_window",
+        "等待游戏启动…剩余 {seconds} 秒  ",
+    ),
+    (
+        "waiting_for_record_screen",
+        "等待打开抽卡记录界面…剩余 {seconds} 秒  ",
+    ),
+    ("wait_for_game_timeout", "等待超时"),
+    ("menu.import_plan", "按计划导入多个卡池"),
+    ("menu.import_clipboard", "从剪贴板中的截图导入"),
+    ("plan.title", "导入计划（游戏中不显示总页数，页数按已保存的记录估计，不含上次导入后的新记录）："),
+    ("plan.banner", "{banner}：估计约 {pages} 页"),
+    ("plan.banner_unknown", "{banner}：没有已保存的记录，页数未知（按 1 页估计）"),
+    ("plan.summary", "估计共约 {pages} 页，预计 {seconds} 秒，实际页数以游戏中为准"),
+    ("plan.input", "输入数字选择或取消卡池，直接按回车键开始导入："),
+    ("plan.switch_banner", "在游戏中切换到{banner}的抽卡记录，然后按回车键："),
+    ("plan.account_banners", "已按 config.json 中的 account_banners 只选中这个账号使用的卡池"),
+    // 设置
+    ("settings.title", "输入数字选择要修改的设置"),
+    ("settings.language", "语言：{value}"),
+    ("settings.output_dir", "导出目录：{value}"),
+    ("settings.capture_delay", "翻页等待时间：{value} 毫秒"),
+    ("settings.ocr_backend", "OCR 引擎：{value}"),
+    ("settings.check_update", "启动时检查更新：{value}"),
+    ("settings.notification", "系统通知：{value}"),
+    ("on", "开"),
+    ("off", "关"),
+    ("input_output_dir", "输入导出目录（留空不修改）："),
+    ("input_capture_delay", "输入翻页等待时间（毫秒）："),
+    ("select_ocr_backend", "输入数字选择 OCR 引擎"),
+    ("settings.never_elevate", "从不以管理员权限运行：{value}"),
+    ("settings.import_mode", "导入模式：{value}"),
+    ("select_import_mode", "输入数字选择导入模式"),
+    ("import_mode.strict", "严格（识别失败时中止）"),
+    ("import_mode.lenient", "宽松（识别失败的行留待审核）"),
+    (
+        "settings.calibrate_star_colors",
+        "校准星级颜色（开启 HDR 或护眼模式时使用）",
+    ),
+    (
+        "calibrate_star_colors_hint",
+        "在游戏中打开一页包含多种星级的抽卡记录，然后按回车键：",
+    ),
+    ("star_colors_calibrated", "已校准 {stars} 星的颜色"),
+    ("calibrate_star_colors_failed", "校准失败：{error}"),
+    ("settings.auto_open_record", "自动打开抽卡记录界面（实验性）：{value}"),
+    ("navigation.not_configured", "没有配置自动打开抽卡记录界面的点击步骤，请在 config.local.json 的 navigation 中按游戏界面填写，或在游戏中手动打开抽卡记录界面"),
+    ("settings.reconcile_total_pulls", "导入后与游戏内总抽数核对：{value}"),
+    ("settings.auto_open_report", "导出后自动打开报告：{value}"),
+    ("open_report.confirm", "是否打开 {path}？"),
+    ("open_report.failed", "打开报告失败：{error}"),
+    ("settings.export_language", "导出语言：{value}"),
+    ("settings.export_language_same_as_ui", "与界面语言相同"),
+    ("select_export_language", "选择导出文件使用的语言"),
+    ("settings.export_translate_item_names", "导出时翻译物品名称：{value}"),
+    // 统计
+    ("item_counts", "物品数量："),
+    ("period_pulls.month", "每月抽卡次数："),
+    ("period_pulls.week", "每周抽卡次数："),
+    ("luck.title", "出5星的平均抽数（实际 / 期望）："),
+    ("luck.win_rate", "当期限定物品 {won}/{contested}（{rate}，95% 置信区间 {low}~{high}，期望 {expected}），最长连续歪 {streak} 次，大保底的5星不计入"),
+    ("luck.win_rate.consistent", "与期望相符，差异在样本量的正常波动范围内"),
+    ("luck.win_rate.above", "明显高于期望"),
+    ("luck.win_rate.below", "明显低于期望"),
+    ("chart.monthly_pulls", "每月抽卡次数："),
+    ("chart.pity_histogram", "出5星用的抽数分布："),
+    ("luck.average", "平均抽数"),
+    ("luck.expected", "期望抽数"),
+    ("stats.records", "记录数"),
+    // 通知
+    ("export_finished", "导出完成"),
+    ("export_finished_text", "新增 {add_num} 条记录"),
+    (
+        "export_finished_text_with_warnings",
+        "新增 {add_num} 条记录，{warning_num} 个警告",
+    ),
+    ("export_failed", "导出失败"),
+    // 卡池
+    ("banner.limited_character_100", "100%限定角色池"),
+    ("banner.limited_weapon_100", "100%限定武器池"),
+    ("banner.limited_character_50", "50%限定角色池"),
+    ("banner.limited_weapon_50", "50%限定武器池"),
+    ("banner.permanent_character", "常驻角色池"),
+    ("banner.permanent_weapon", "常驻武器池"),
+    ("banner.beginner", "新手池"),
+    // 物品类型
+    ("item_type.character", "角色"),
+    ("item_type.weapon", "武器"),
+    // Excel
+    ("excel.star", "品质"),
+    ("excel.name", "名称"),
+    ("excel.type", "类型"),
+    ("excel.time", "时间"),
+    ("excel.after_5_star", "5星后"),
+    ("excel.5_star_pity", "5星保底"),
+    ("excel.after_4_star", "4星后"),
+    ("excel.featured", "UP"),
+    ("excel.copies", "数量"),
+    ("excel.item_count_sheet", "{account_id}-物品数量"),
+    ("excel.is_featured", "UP"),
+    ("excel.off_banner", "歪"),
+    ("excel.period", "周期"),
+    ("excel.pulls", "抽数"),
+    ("excel.5_star_count", "5星数量"),
+    ("excel.period_pulls_sheet", "{account_id}-抽卡次数"),
+    ("excel.ten_pull", "十连"),
+    ("excel.banner_summary", "共 {pulls} 抽，5星 {five_star} 个，4星 {four_star} 个"),
+    // 数据包
+    ("bundle_exported", "已打包 {num} 个文件到 {path}"),
+    (
+        "bundle_imported",
+        "导入完成，新增 {add_num} 条记录，{backup_num} 个备份",
+    ),
+    ("bundle_config_replaced", "已使用数据包中的配置"),
+    // 待审核
+    ("menu.review_pending", "审核识别失败的记录"),
+    ("no_pending_records", "没有待审核的记录"),
+    ("review.title", "输入数字选择操作"),
+    ("review.fix", "修正并合并"),
+    ("review.discard", "丢弃"),
+    ("review.skip", "跳过"),
+    (
+        "review.location",
+        "{account_id} {banner} 第 {page} 页第 {row} 行",
+    ),
+    ("review.image", "截图：{path}"),
+    (
+        "review.ocr_guess",
+        "识别结果：{star}星 {name} {item_type} {time}",
+    ),
+    ("review.error", "错误：{error}"),
+    ("review.input_name", "输入名称（留空保留识别结果）："),
+    ("review.select_type", "输入数字选择类型"),
+    (
+        "review.input_time",
+        "输入时间，格式 2024-01-01 12:00（留空保留识别结果）：",
+    ),
+    ("review.duplicate", "这条记录已经保存过，没有重复添加"),
+    // 导出
+    ("export.title", "尘白禁区抽卡记录"),
+    ("export.account", "账号"),
+    ("export.banner", "卡池"),
+    ("export.meta", "由 {tool} 于 {time} 导出，账号：{accounts}，抽卡记录校验和：{checksum}"),
+    ("excel.legend_sheet", "工作表名称"),
+    ("excel.sheet_name", "工作表"),
+    ("excel.full_sheet_name", "完整名称"),
+    // 保底计数
+    (
+        "pity_anchor_saved",
+        "已保存保底计数，之后导出的保底计算会包含缺失的抽卡记录",
+    ),
+    (
+        "pity_anchor_out_of_range",
+        "距离上一个5星的抽数不能超过 {max}",
+    ),
+    // 模拟
+    ("account_not_found", "没有找到账号 {account} 的抽卡记录"),
+    (
+        "simulate.start",
+        "从距离上一个5星 {pity} 抽开始，模拟 {pulls} 抽 {trials} 次：",
+    ),
+    ("simulate.expected_5_star", "5星数量期望：{value}"),
+    ("simulate.5_star_count", "{count} 个5星：{probability}"),
+    ("simulate.featured", "获得当期限定物品的概率：{probability}"),
+    // 备注
+    ("export.note", "备注"),
+    ("export.element", "属性"),
+    ("export.weapon_type", "武器类型"),
+    // 比较
+    ("diff.no_difference", "两份抽卡记录没有差异"),
+    (
+        "diff.banner",
+        "{account} - {banner}：新增 {added} 条，删除 {removed} 条",
+    ),
+    ("merge.finished", "已合并 {accounts} 个账号的抽卡记录，第二个文件新增 {num} 条，保存到 {path}"),
+    ("prune.no_policy", "没有设置保留策略，请用 --years 指定保留的年数，或在 config.json 中设置 retention_years"),
+    ("prune.nothing", "没有早于 {years} 年前的抽卡记录"),
+    ("prune.finished", "已把 {num} 条早于 {years} 年前的抽卡记录移到 {path}"),
+    ("serve.listening", "统计页面：{url}\n按 Ctrl+C 停止"),
+    // 查找
+    ("find.not_found", "没有找到 {name} 的抽卡记录"),
+    // 错误
+    ("error.hint", "提示：{hint}"),
+    ("error.window_not_found", "没有找到游戏窗口，请先启动游戏，并且不要最小化窗口"),
+    ("error.not_on_record_screen", "请在游戏中打开抽卡记录界面后重试，或者在设置中开启自动打开抽卡记录界面"),
+    ("error.ocr_failed", "第 {page} 页第 {row} 行识别失败，可以在设置中切换为宽松模式，把识别失败的行留待审核"),
+    ("error.merge_conflict", "{banner}的新记录与已保存的记录时间顺序冲突，可以用 undo 子命令撤销最近一次导入后重试"),
+    ("error.save_locked", "无法写入 {path}，请关闭打开了这个文件的程序（例如 Excel）后重试"),
+    ("screen.main_menu", "当前在游戏主界面，请点击右侧的共鸣，再点击右下角的记录"),
+    ("screen.banner_screen", "当前在共鸣界面，请点击右下角的记录"),
+    ("screen.details_popup", "当前打开了卡池详情，请关闭弹窗后点击右下角的记录"),
+    ("error.already_running", "已经有一个抽卡记录导出工具在运行，请关闭后重试，或切换到已经打开的窗口"),
+    ("error.banner_mismatch", "选择的是{banner}，但识别出的 {item} 不属于这个卡池，请在游戏中打开{banner}的抽卡记录后重试"),
+    ("panic.message", "程序出现了意外错误：{error}\n日志已保存到 {log_path}，反馈问题时请附上日志文件"),
+    // 剪贴板
+    ("clipboard.start", "正在监视剪贴板，请在游戏中自己翻页，每页截图一次（Win+PrtSc、ShareX 等），全部截完后按回车"),
+    ("clipboard.page_added", "已识别第 {page} 页，{rows} 行"),
+    ("clipboard.not_record_page", "剪贴板中的截图不是抽卡记录界面，请截取整个游戏窗口或全屏"),
+    ("clipboard.no_pages", "没有识别到抽卡记录截图"),
+    ("clipboard.missing_pages", "缺少第 {pages} 页的截图，这些页中的记录不会导入"),
+    // 账号
+    ("account.already_exists", "账号 {account} 已经存在，请使用 account merge 合并"),
+    ("account.renamed", "已把账号 {old} 改为 {new}"),
+    ("account.merged", "已把账号 {from} 的抽卡记录合并到 {into}，新增 {num} 条记录"),
+    ("use_default_account", "使用默认账号ID：{account}"),
+    // 核对
+    ("input_in_game_total", "输入游戏内这个卡池的总抽数进行核对（直接回车跳过）："),
+    ("reconcile.match", "抽卡记录数量与游戏内一致，共 {num} 抽"),
+    ("reconcile.missing", "保存了 {stored} 抽，游戏内为 {in_game} 抽，少了 {num} 抽，可能漏掉了页面，建议重新导入这个卡池"),
+    ("reconcile.extra", "保存了 {stored} 抽，游戏内为 {in_game} 抽，多了 {num} 抽，可能有重复的记录或其他卡池的记录"),
+    // 向导
+    ("wizard.welcome", "欢迎使用！接下来进行首次设置，之后可以在设置菜单或 config.json 中修改"),
+    ("wizard.input_account_id", "输入默认账号ID，例如游戏中的UID，还没有抽卡记录时直接使用（留空跳过）："),
+    ("wizard.input_output_dir", "输入导出目录，相对路径以数据目录为基准（当前：{dir}，留空不修改）："),
+    ("wizard.window_modes", "支持窗口化和无边框窗口模式，游戏画面需要是 16:9（例如 1920x1080、2560x1440），独占全屏可能无法截图，请切换为无边框窗口；导出时不要最小化或遮挡游戏窗口"),
+    ("wizard.capture_test", "是否打开游戏后测试截图"),
+    ("wizard.capture_ok", "截图成功，游戏画面分辨率为 {width}x{height}"),
+    ("wizard.on_record_screen", "当前在抽卡记录界面，可以开始导入"),
+    ("wizard.capture_failed", "截图失败：{error}"),
+    ("wizard.finished", "首次设置完成"),
+    // OCR 引擎
+    ("paddle_ocr.manual", "没有找到 PaddleOCR-json，可以从 {url} 下载后解压到数据目录，或在 config.json 的 paddle_ocr_path 中设置 PaddleOCR-json.exe 的路径"),
+    ("paddle_ocr.not_found", "没有找到 PaddleOCR-json，请在设置中重新选择 OCR 引擎，或从 {url} 下载后解压到数据目录"),
+    // 导入
+    ("page_summary", "第 {page} 页：+{added} 条，共 {total} 条，其中5星 {five_star} 条"),
+    ("page_summary.invalid", "，{num} 行识别失败"),
+    ("page_summary.empty", "这一页没有识别出抽卡记录，请检查游戏窗口是否被遮挡"),
+    ("records.checksum_mismatch", "警告：{source} 中账号 {account_id} 的{banner}抽卡记录与保存时的校验和不一致，文件可能被手动修改或被截断。请在合并前检查该文件，必要时从备份恢复。"),
+    ("client_language_detected", "检测到游戏客户端语言为 {language}，将优先按这种语言识别物品类型"),
+    ("outside_banner_window", "警告：{num} 条抽卡记录的时间不在所选卡池的开放时间内，可能选错了卡池或者日期识别有误："),
+    ("excel.summary_sheet", "{account_id}-图表"),
+    ("excel.rarity_chart", "星级分布"),
+    ("excel.pity_chart", "每个5星用的抽数"),
+    ("excel.pulls_for_5_star", "用的抽数"),
+    ("import_file.format", "文件格式：{format}"),
+    ("import_file.added", "{account} - {banner}：新增 {num} 条抽卡记录"),
+    ("import_file.no_account", "文件中没有账号ID，请用 --account 指定，或在 config.json 中设置 default_account_id"),
+    ("import.known_page", "第 {page} 页的抽卡记录都已保存，停止翻页，跳过了约 {skipped} 页"),
+    ("import.stopped_early", "第 {page} 页之后多次点击都没有翻页，但已保存的记录约有 {pages} 页，提前停止翻页"),
+    ("import.incomplete", "翻页在第 {page} 页提前停止，这次导入可能不完整，请确认游戏窗口可以点击后重新导入"),
+    ("import.page_skipped", "第 {page} 页识别失败，先跳过，翻完所有页后重新识别"),
+    ("import.page_retry", "回到第 {page} 页重新识别"),
+    ("import.page_recovered", "第 {page} 页重新识别成功"),
+    // 保底
+    ("guarantee.title", "大保底状态："),
+    ("guarantee.guaranteed", "大保底，下一个5星必定是UP物品"),
+    ("guarantee.not_guaranteed", "小保底"),
+    ("guarantee.lost_streak", "，已连续歪 {num} 次"),
+    ("pity.remaining", "距离保底"),
+    ("pity.guarantee_state", "大保底状态"),
+    // 备注
+    ("annotate.not_found", "这个时间没有抽卡记录"),
+    ("annotate.ambiguous", "这个时间有多种物品：{names}，请用 --name 或 --index 指定"),
+    ("annotate.duplicate", "这个时间有 {num} 条 {name}，请用 --index 指定第几条（按游戏中从上到下的顺序，从 1 开始）"),
+    ("annotate.index_out_of_range", "没有第 {index} 条，这个时间只有 {num} 条匹配的抽卡记录"),
+    ("annotate.saved", "已给 {num} 条抽卡记录添加备注"),
+    ("annotate.removed", "已删除 {num} 条抽卡记录的备注"),
+];
+
+/// 英文
+static EN: &[(&str, &str)] = &[
+    // 通用
+    ("input_tip", "input: "),
+    ("invalid_input", "Invalid input: {input}, please input again"),
+    ("press_enter_to_exit", "Press enter to exit"),
+    // 启动
+    (
+        "new_version_available",
+        "New version available, please update in https://github.com/enximi/snowbreak_gacha_export/releases",
+    ),
+    (
+        "usage_tip",
+        "Only support 16:9 windowed/borderless\nOpen the gacha record interface first, then run this program",
+    ),
+    ("select_game_window", "Multiple game windows found, input a number to select"),
+    ("game_window", "{title} (PID {pid})"),
+    // 卡池与账号选择
+    ("select_banner", "Input a number to select banner"),
+    ("input_account_id", "Input account ID: "),
+    ("account_id_empty", "Account ID cannot be empty"),
+    ("select_account", "Input a number to select account"),
+    ("no_account_id", "No account ID"),
+    ("existing_account_ids", "Existing account IDs:"),
+    (
+        "select_or_new_account",
+        "Input 1 to select an existing account, input 2 to input a new account",
+    ),
+    // 主菜单
+    ("menu.title", "Input a number to select a function"),
+    ("menu.import", "Import gacha records"),
+    ("menu.view_stats", "View statistics"),
+    ("menu.export", "Export"),
+    ("menu.manage_accounts", "Manage accounts"),
+    ("menu.settings", "Settings"),
+    ("menu.repair_data", "Repair data"),
+    ("menu.exit", "Exit"),
+    ("menu.back", "Back"),
+    ("import_finished", "Import finished, {add_num} new records"),
+    ("report_saved", "Import report: {path}"),
+    ("import_failed", "Import failed: {error}"),
+    ("save_failed", "Failed to save: {error}"),
+    ("no_records", "No gacha records"),
+    ("select_export_format", "Input a number to select export format"),
+    ("export_written", "Exported to {path}"),
+    ("account_record_count", "{account_id} ({count} records)"),
+    ("select_account_to_delete", "Input a number to select the account to delete"),
+    (
+        "confirm_delete_account",
+        "Delete all gacha records of account {account_id}?",
+    ),
+    ("account_deleted", "Account {account_id} deleted"),
+    ("settings_saved", "Settings saved"),
+    ("repair_finished", "Repair finished, re-sorted records of {num} banners"),
+    ("menu.undo_import", "Undo last import"),
+    ("no_batch_to_undo", "No import to undo"),
+    ("confirm_undo", "Undo the import at {time} and delete {num} records?"),
+    ("undo_finished", "Undid the import at {time}, deleted {num} records"),
+    ("rows_quarantined", "{num} rows failed to be recognized and were saved for review"),
+    ("quarantined_row", "Page {page} row {row}: {error}"),
+    ("game_not_running_wait", "Game window not found, wait for the game to start?"),
+    ("waiting_for_game_window", "Waiting for the game to start... {seconds}s left  "),
+    ("waiting_for_record_screen", "Waiting for the record screen... {seconds}s left  "),
+    ("wait_for_game_timeout", "Timed out waiting for the game"),
+    ("menu.import_plan", "Import multiple banners with a plan"),
+    ("menu.import_clipboard", "Import from clipboard screenshots"),
+    ("plan.title", "Import plan (the game does not show the total page count, pages are estimated from saved records and exclude pulls since the last import):"),
+    ("plan.banner", "{banner}: about {pages} pages (estimated)"),
+    ("plan.banner_unknown", "{banner}: no saved records, page count unknown (counted as 1 page)"),
+    ("plan.summary", "About {pages} pages in total (estimated), about {seconds} s; the actual page count may differ"),
+    ("plan.input", "Input a number to select or deselect a banner, press Enter to start: "),
+    ("plan.switch_banner", "Switch to the records of {banner} in the game, then press Enter: "),
+    ("plan.account_banners", "Only the banners this account uses are selected, per account_banners in config.json"),
+    // 设置
+    ("settings.title", "Input a number to select the setting to change"),
+    ("settings.language", "Language: {value}"),
+    ("settings.output_dir", "Output directory: {value}"),
+    ("settings.capture_delay", "Page turn delay: {value} ms"),
+    ("settings.ocr_backend", "OCR engine: {value}"),
+    ("settings.check_update", "Check update on startup: {value}"),
+    ("settings.notification", "System notification: {value}"),
+    ("on", "on"),
+    ("off", "off"),
+    ("input_output_dir", "Input output directory (empty to keep): "),
+    ("input_capture_delay", "Input page turn delay (ms): "),
+    ("select_ocr_backend", "Input a number to select OCR engine"),
+    ("settings.never_elevate", "Never run as admin: {value}"),
+    ("settings.import_mode", "Import mode: {value}"),
+    ("select_import_mode", "Input a number to select import mode"),
+    ("import_mode.strict", "Strict (abort on recognition failure)"),
+    ("import_mode.lenient", "Lenient (keep failed rows for review)"),
+    ("settings.calibrate_star_colors", "Calibrate star colors (for HDR or night light)"),
+    ("calibrate_star_colors_hint", "Open a record page with several star levels in the game, then press Enter: "),
+    ("star_colors_calibrated", "Calibrated colors of {stars} star"),
+    ("calibrate_star_colors_failed", "Calibration failed: {error}"),
+    ("settings.auto_open_record", "Auto open record screen (experimental): {value}"),
+    ("navigation.not_configured", "No clicks are configured for opening the record screen. Fill in navigation in config.local.json for your game UI, or open the record screen in the game yourself"),
+    ("settings.reconcile_total_pulls", "Reconcile with in-game total after import: {value}"),
+    ("settings.auto_open_report", "Open report after export: {value}"),
+    ("open_report.confirm", "Open {path}?"),
+    ("open_report.failed", "Failed to open the report: {error}"),
+    ("settings.export_language", "Export language: {value}"),
+    ("settings.export_language_same_as_ui", "Same as interface"),
+    ("select_export_language", "Select the language of exported files"),
+    ("settings.export_translate_item_names", "Translate item names in exports: {value}"),
+    // 统计
+    ("item_counts", "Copies:"),
+    ("period_pulls.month", "Pulls per month:"),
+    ("period_pulls.week", "Pulls per week:"),
+    ("luck.title", "Average pulls per 5* (actual / expected):"),
+    ("luck.win_rate", "Featured {won}/{contested} ({rate}, 95% CI {low}-{high}, expected {expected}), longest losing streak {streak}; guaranteed 5* are not counted"),
+    ("luck.win_rate.consistent", "Consistent with the expectation given the sample size"),
+    ("luck.win_rate.above", "Significantly above the expectation"),
+    ("luck.win_rate.below", "Significantly below the expectation"),
+    ("chart.monthly_pulls", "Pulls per month:"),
+    ("chart.pity_histogram", "Pulls per 5* distribution:"),
+    ("luck.average", "Average"),
+    ("luck.expected", "Expected"),
+    ("stats.records", "Records"),
+    // 通知
+    ("export_finished", "Export finished"),
+    ("export_finished_text", "{add_num} new records"),
+    (
+        "export_finished_text_with_warnings",
+        "{add_num} new records, {warning_num} warnings",
+    ),
+    ("export_failed", "Export failed"),
+    // 卡池
+    ("banner.limited_character_100", "100% Limited Character Banner"),
+    ("banner.limited_weapon_100", "100% Limited Weapon Banner"),
+    ("banner.limited_character_50", "50% Limited Character Banner"),
+    ("banner.limited_weapon_50", "50% Limited Weapon Banner"),
+    ("banner.permanent_character", "Permanent Character Banner"),
+    ("banner.permanent_weapon", "Permanent Weapon Banner"),
+    ("banner.beginner", "Beginner Banner"),
+    // 物品类型
+    ("item_type.character", "Operative"),
+    ("item_type.weapon", "Weapon"),
+    // Excel
+    ("excel.star", "Star"),
+    ("excel.name", "Name"),
+    ("excel.type", "Type"),
+    ("excel.time", "Time"),
+    ("excel.after_5_star", "After 5*"),
+    ("excel.5_star_pity", "5* Pity"),
+    ("excel.after_4_star", "After 4*"),
+    ("excel.featured", "Featured"),
+    ("excel.copies", "Copies"),
+    ("excel.item_count_sheet", "{account_id}-Copies"),
+    ("excel.is_featured", "Featured"),
+    ("excel.off_banner", "Off-banner"),
+    ("excel.period", "Period"),
+    ("excel.pulls", "Pulls"),
+    ("excel.5_star_count", "5* Count"),
+    ("excel.period_pulls_sheet", "{account_id}-Pulls"),
+    ("excel.ten_pull", "10-pull"),
+    ("excel.banner_summary", "{pulls} pulls, {five_star} 5*, {four_star} 4*"),
+    // 数据包
+    ("bundle_exported", "Packed {num} files into {path}"),
+    ("bundle_imported", "Import finished, {add_num} new records, {backup_num} backups"),
+    ("bundle_config_replaced", "Replaced config with the one in the bundle"),
+    // 待审核
+    ("menu.review_pending", "Review unrecognized records"),
+    ("no_pending_records", "No records to review"),
+    ("review.title", "Input a number to select an action"),
+    ("review.fix", "Fix and merge"),
+    ("review.discard", "Discard"),
+    ("review.skip", "Skip"),
+    ("review.location", "{account_id} {banner} page {page} row {row}"),
+    ("review.image", "Screenshot: {path}"),
+    ("review.ocr_guess", "OCR guess: {star}* {name} {item_type} {time}"),
+    ("review.error", "Error: {error}"),
+    ("review.input_name", "Input name (empty to keep the OCR guess): "),
+    ("review.select_type", "Input a number to select type"),
+    ("review.input_time", "Input time like 2024-01-01 12:00 (empty to keep the OCR guess): "),
+    ("review.duplicate", "This record is already saved and was not added again"),
+    // 导出
+    ("export.title", "Snowbreak Gacha Records"),
+    ("export.account", "Account"),
+    ("export.banner", "Banner"),
+    ("export.meta", "Exported by {tool} at {time}, accounts: {accounts}, records checksum: {checksum}"),
+    ("excel.legend_sheet", "Sheet names"),
+    ("excel.sheet_name", "Sheet"),
+    ("excel.full_sheet_name", "Full name"),
+    // 保底计数
+    ("pity_anchor_saved", "Pity counters saved, later exports will account for the missing records"),
+    ("pity_anchor_out_of_range", "Pulls since the last 5* cannot exceed {max}"),
+    // 模拟
+    ("account_not_found", "No records of account {account}"),
+    ("simulate.start", "Simulating {pulls} pulls {trials} times, starting {pity} pulls after the last 5*:"),
+    ("simulate.expected_5_star", "Expected 5* count: {value}"),
+    ("simulate.5_star_count", "{count} 5*: {probability}"),
+    ("simulate.featured", "Probability of getting the featured item: {probability}"),
+    // 备注
+    ("export.note", "Note"),
+    ("export.element", "Element"),
+    ("export.weapon_type", "Weapon type"),
+    // 比较
+    ("diff.no_difference", "No difference between the two records"),
+    ("diff.banner", "{account} - {banner}: {added} added, {removed} removed"),
+    ("merge.finished", "Merged the records of {accounts} accounts, {num} records added from the second file, saved to {path}"),
+    ("prune.no_policy", "No retention policy, specify the number of years to keep with --years or set retention_years in config.json"),
+    ("prune.nothing", "No records older than {years} years"),
+    ("prune.finished", "Moved {num} records older than {years} years to {path}"),
+    ("serve.listening", "Stats page: {url}\nPress Ctrl+C to stop"),
+    // 查找
+    ("find.not_found", "No records of {name}"),
+    // 错误
+    ("error.hint", "Hint: {hint}"),
+    ("error.window_not_found", "Game window not found. Start the game first and keep the window from being minimized"),
+    ("error.not_on_record_screen", "Open the gacha record screen in the game and retry, or enable auto opening the record screen in settings"),
+    ("error.ocr_failed", "Failed to recognize page {page} row {row}. Switch to lenient mode in settings to keep unrecognized rows for review"),
+    ("error.merge_conflict", "New {banner} records conflict with the saved records. Undo the last import with the undo subcommand and retry"),
+    ("error.save_locked", "Cannot write {path}. Close the program that has it open (for example Excel) and retry"),
+    ("screen.main_menu", "You are on the main screen. Click Resonance on the right, then Record in the lower right"),
+    ("screen.banner_screen", "You are on the banner screen. Click Record in the lower right"),
+    ("screen.details_popup", "The banner details popup is open. Close it, then click Record in the lower right"),
+    ("error.already_running", "Another instance of the exporter is already running. Close it and retry, or switch to the window that is already open"),
+    ("error.banner_mismatch", "You selected {banner}, but the recognized {item} does not belong to it. Open the records of {banner} in the game and retry"),
+    ("panic.message", "Something went wrong: {error}\nThe log has been saved to {log_path}, please attach it when reporting the issue"),
+    // 剪贴板
+    ("clipboard.start", "Watching the clipboard. Page through the records in the game and take a screenshot of each page (Win+PrtSc, ShareX, etc.), then press Enter"),
+    ("clipboard.page_added", "Page {page} recognized, {rows} rows"),
+    ("clipboard.not_record_page", "The screenshot in the clipboard is not the record screen. Capture the whole game window or full screen"),
+    ("clipboard.no_pages", "No record screenshots recognized"),
+    ("clipboard.missing_pages", "Screenshots of pages {pages} are missing, records on these pages are not imported"),
+    // 账号
+    ("account.already_exists", "Account {account} already exists, use account merge instead"),
+    ("account.renamed", "Renamed account {old} to {new}"),
+    ("account.merged", "Merged account {from} into {into}, {num} new records"),
+    ("use_default_account", "Using the default account ID: {account}"),
+    // 核对
+    ("input_in_game_total", "Input the in-game total pulls of this banner to reconcile (press Enter to skip): "),
+    ("reconcile.match", "Record count matches the game, {num} pulls in total"),
+    ("reconcile.missing", "{stored} pulls saved but the game shows {in_game}, {num} missing; some pages may have been skipped, consider importing this banner again"),
+    ("reconcile.extra", "{stored} pulls saved but the game shows {in_game}, {num} extra; there may be duplicate records or records of another banner"),
+    // 向导
+    ("wizard.welcome", "Welcome! Let us go through the first-time setup; everything can be changed later in the settings menu or config.json"),
+    ("wizard.input_account_id", "Input the default account ID, e.g. your in-game UID, used directly before any records exist (empty to skip): "),
+    ("wizard.input_output_dir", "Input the output directory, relative to the data directory (current: {dir}, empty to keep): "),
+    ("wizard.window_modes", "Windowed and borderless window modes are supported and the game must be 16:9 (e.g. 1920x1080, 2560x1440). Exclusive fullscreen may not be capturable, switch to borderless window instead. Do not minimize or cover the game window while exporting"),
+    ("wizard.capture_test", "Open the game and test capturing"),
+    ("wizard.capture_ok", "Captured successfully, the game resolution is {width}x{height}"),
+    ("wizard.on_record_screen", "The game is on the record screen, ready to import"),
+    ("wizard.capture_failed", "Capture failed: {error}"),
+    ("wizard.finished", "First-time setup finished"),
+    // OCR 引擎
+    ("paddle_ocr.manual", "PaddleOCR-json not found. Download it from {url} and extract it into the data directory, or set the path of PaddleOCR-json.exe as paddle_ocr_path in config.json"),
+    ("paddle_ocr.not_found", "PaddleOCR-json not found. Select the OCR engine again in the settings, or download it from {url} and extract it into the data directory"),
+    // 导入
+    ("page_summary", "Page {page}: +{added} records, {total} in total, {five_star}×5★ so far"),
+    ("page_summary.invalid", "; {num} rows failed"),
+    ("page_summary.empty", "No records recognized on this page, check whether the game window is covered"),
+    ("records.checksum_mismatch", "WARNING: the {banner} records of account {account_id} in {source} do not match the checksum saved with them. The file may have been edited by hand or truncated. Check it before merging and restore a backup if needed."),
+    ("client_language_detected", "Detected game client language: {language}. Item types will be matched in this language first"),
+    ("outside_banner_window", "WARNING: {num} records fall outside the availability window of the selected banner. You may have picked the wrong banner, or the dates were misread:"),
+    ("excel.summary_sheet", "{account_id}-Charts"),
+    ("excel.rarity_chart", "Rarity Distribution"),
+    ("excel.pity_chart", "Pulls per 5*"),
+    ("excel.pulls_for_5_star", "Pulls Used"),
+    ("import_file.format", "File format: {format}"),
+    ("import_file.added", "{account} - {banner}: {num} new records"),
+    ("import_file.no_account", "The file has no account id, specify one with --account or set default_account_id in config.json"),
+    ("import.known_page", "Records on page {page} are already saved, stopped paging and skipped about {skipped} pages"),
+    ("import.stopped_early", "The page did not advance after page {page} despite repeated clicks, but about {pages} pages are saved. Stopped paging early"),
+    ("import.incomplete", "Paging stopped early at page {page}, so this import may be incomplete. Make sure the game window accepts clicks and import again"),
+    ("import.page_skipped", "Failed to recognize page {page}, skipped it and will retry after the last page"),
+    ("import.page_retry", "Going back to page {page} to recognize it again"),
+    ("import.page_recovered", "Page {page} recognized on retry"),
+    // 保底
+    ("guarantee.title", "Guarantee state:"),
+    ("guarantee.guaranteed", "Guaranteed, the next 5* is the featured item"),
+    ("guarantee.not_guaranteed", "50/50"),
+    ("guarantee.lost_streak", "; lost {num} 50/50 in a row"),
+    ("pity.remaining", "To pity"),
+    ("pity.guarantee_state", "Guarantee"),
+    // 备注
+    ("annotate.not_found", "No record at this time"),
+    ("annotate.ambiguous", "Several items at this time: {names}, specify one with --name or --index"),
+    ("annotate.duplicate", "{num} records of {name} at this time, pick one with --index (counting from 1, top to bottom as in the game)"),
+    ("annotate.index_out_of_range", "No record number {index}, only {num} matching records at this time"),
+    ("annotate.saved", "Added the note to {num} records"),
+    ("annotate.removed", "Removed the note from {num} records"),
+];
+
+fn translation_table(language: Language) -> &'static [(&'static str, &'static str)] {
+    match language {
+        Language::ChineseSimplified => ZH_CN,
+        Language::English => EN,
+    }
+}
+
+lazy_static! {
+    static ref TRANSLATIONS: HashMap<Language, HashMap<&'static str, &'static str>> =
+        all::<Language>()
+            .map(|language| {
+                (
+                    language,
+                    translation_table(language).iter().copied().collect(),
+                )
+            })
+            .collect();
+    static ref CURRENT_LANGUAGE: RwLock<Language> = RwLock::new(Language::ChineseSimplified);
+}
+
+/// 设置界面语言
+pub fn set_language(language: Language) {
+    *CURRENT_LANGUAGE.write().unwrap() = language;
+}
+
+/// 当前界面语言
+pub fn language() -> Language {
+    *CURRENT_LANGUAGE.read().unwrap()
+}
+
+/// 获取指定语言的文本，没有翻译时返回 key
+pub fn translate(language: Language, key: &'static str) -> &'static str {
+    match TRANSLATIONS[&language].get(key) {
+        Some(text) => text,
+        None => {
+            log::warn!("missing translation: {key} ({language:?})");
+            key
+        }
+    }
+}
+
+/// 替换文本中的 `{name}` 占位符
+pub fn format_translation(text: &str, args: &[(&str, String)]) -> String {
+    args.iter().fold(text.to_string(), |text, (name, value)| {
+        text.replace(&format!("{{{name}}}"), value)
+    })
+}
+
+/// 获取当前界面语言的文本
+/// # 用法
+/// - `tr!("key")`
+/// - `tr!("key", name = value)`，替换文本中的 `{name}`
+#[macro_export]
+macro_rules! tr {
+    ($key:expr) => {
+        $crate::i18n::translate($crate::i18n::language(), $key)
+    };
+    ($key:expr, $($name:ident = $value:expr),+ $(,)?) => {
+        $crate::i18n::format_translation(
+            $crate::i18n::translate($crate::i18n::language(), $key),
+            &[$((stringify!($name), $value.to_string())),+],
+        )
+    };
+}
+
+pub use tr;
+
+#[cfg(test)]
+mod test {
+    use std::collections::HashSet;
+
+    use super::*;
+
+    #[test]
+    fn test_all_languages_have_same_keys() {
+        let keys = |language| {
+            translation_table(language)
+                .iter()
+                .map(|(key, _)| *key)
+                .collect::<HashSet<_>>()
+        };
+        let expected = keys(Language::ChineseSimplified);
+        assert_eq!(expected.len(), ZH_CN.len(), "duplicate keys");
+        for language in all::<Language>() {
+            assert_eq!(keys(language), expected, "{language:?}");
+        }
+    }
+
+    #[test]
+    fn test_format_translation() {
+        let text = format_translation(
+            "{add_num} new records, {warning_num} warnings",
+            &[("add_num", 3.to_string()), ("warning_num", 1.to_string())],
+        );
+        assert_eq!(text, "3 new records, 1 warnings");
+    }
+}
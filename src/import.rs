@@ -0,0 +1,1086 @@
+use std::io::{stdout, Write};
+use std::path::{Path, PathBuf};
+use std::sync::Arc;
+use std::time::Duration;
+use std::time::Instant;
+
+use anyhow::anyhow;
+use clap::ValueEnum;
+use enum_iterator::Sequence;
+use image::{DynamicImage, GrayImage};
+use serde::{Deserialize, Serialize};
+use tokio::sync::{mpsc, watch};
+use tokio::time::sleep;
+use window_inspector::top_most::cancel_window_top_most;
+use window_inspector::top_most::set_window_top_most;
+
+use crate::action::{next_page_attempt, previous_page, ClickMethod};
+use crate::banner_schedule::is_available;
+use crate::capture::{capture_image, CaptureSession};
+use crate::config::{config, update_config};
+use crate::data_dir::data_path;
+use crate::debug_boxes::DebugBoxes;
+use crate::elevation::check_input_permission;
+use crate::error::{find_export_error, ExportError};
+use crate::export::{export_all, ExportFormat, ExportOptions};
+use crate::game_info::get_game_window_info;
+use crate::hook::{run_post_export_hook, ExportSummary};
+use crate::i18n::{self, tr};
+use crate::items::learn_item_types;
+use crate::language::{detect_language, Language};
+use crate::logging::is_quiet;
+use crate::navigation::open_record_screen;
+use crate::ocr::{ocr_batch, ocr_batch_uncached, save_ocr_cache, CharsetHint, OcrBackendKind};
+use crate::ocr_pool;
+use crate::paddle_ocr::{find_engine, RELEASES_URL};
+use crate::plan::estimated_pages;
+use crate::preprocess::Preprocessing;
+use crate::progress::{emit, ProgressEvent};
+use crate::record::{BannerType, ImportBatch, OneRecord, Provenance, RecordSource, TotalRecords};
+use crate::record_image::{
+    parse_record_page, set_client_language, InvalidRow, ParseOptions, ParsedPage, RecordImage,
+};
+use crate::report::SessionReport;
+use crate::review::{quarantine, PendingRecord};
+use crate::run_result::{Failure, FailureKind};
+use crate::screen::classify_screen;
+use crate::stats::item_counts;
+use crate::user_interaction::print_item_counts;
+use crate::watchdog::SessionWatchdog;
+
+/// 识别任务的进度，翻页时用来判断是否可以提前停止
+#[derive(Debug, Copy, Clone, Default)]
+struct OcrProgress {
+    /// 已经识别完的页数
+    parsed_pages: u32,
+    /// 第一个记录都已经保存过的页
+    known_page: Option<u32>,
+}
+
+/// 一页识别出的记录是否都已经保存过，并且在保存的记录中是连续的
+/// 这一页之后的记录都比它早，也都已经保存过，不用再翻页
+fn is_known_page(page_records: &[OneRecord], stored_records: &[OneRecord]) -> bool {
+    !page_records.is_empty()
+        && stored_records
+            .windows(page_records.len())
+            .any(|window| window == page_records)
+}
+
+/// 增量导入提前停止时大约跳过的页数
+/// 新增的记录把已保存的记录往后推了大约 `known_page - 1` 页
+/// # 参数
+/// - `estimated_pages`: 已保存的记录的页数
+/// - `known_page`: 第一个记录都已经保存过的页
+/// - `last_page`: 停止翻页时所在的页
+fn skipped_pages(estimated_pages: u32, known_page: u32, last_page: u32) -> u32 {
+    (estimated_pages + known_page - 1).saturating_sub(last_page)
+}
+
+/// 重新识别跳过的页时，每页最多截图识别的次数
+/// 每次都不使用 OCR 缓存，最后一次对整页使用最强的预处理
+static FAILED_PAGE_RETRIES: u32 = 2;
+/// 翻回跳过的页时每相差一页允许的时间，另外加上 [`GO_TO_PAGE_EXTRA_TIME`]
+static GO_TO_PAGE_TIME_PER_PAGE: Duration = Duration::from_secs(1);
+static GO_TO_PAGE_EXTRA_TIME: Duration = Duration::from_secs(15);
+
+/// 把重新识别成功的一页记录按页码插回，记录按页码顺序排列
+fn insert_page_records(records: &mut Vec<OneRecord>, page: u32, page_records: Vec<OneRecord>) {
+    let index = records
+        .iter()
+        .position(|record| record.provenance.and_then(|provenance| provenance.page) > Some(page))
+        .unwrap_or(records.len());
+    records.splice(index..index, page_records);
+}
+
+/// 导入模式
+#[derive(Debug, Copy, Clone, PartialEq, Eq, Serialize, Deserialize, Sequence, ValueEnum)]
+pub enum ImportMode {
+    /// 严格模式：任意一行识别失败都中止导入
+    Strict,
+    /// 宽松模式：识别失败的行保存到待审核列表，继续导入
+    Lenient,
+}
+
+impl ImportMode {
+    pub fn display_name(&self) -> &'static str {
+        match self {
+            ImportMode::Strict => tr!("import_mode.strict"),
+            ImportMode::Lenient => tr!("import_mode.lenient"),
+        }
+    }
+}
+
+/// 一次导入的结果
+#[derive(Debug)]
+pub struct ImportSummary {
+    /// 新增抽卡记录数量
+    pub added: u32,
+    /// 宽松模式下被放入待审核列表的行
+    pub quarantined: Vec<PendingRecord>,
+    /// 导入报告的路径
+    pub report_path: Option<PathBuf>,
+    /// 导出文件的路径
+    pub paths: Vec<PathBuf>,
+    /// 时间不在卡池开放时间内的抽卡记录，见 [`outside_banner_window`]
+    pub outside_window: Vec<OneRecord>,
+    /// 翻页一直没有生效、在最后一页之前就停止时所在的页码，这次导入可能不完整
+    pub stopped_early: Option<u32>,
+}
+
+impl ImportSummary {
+    pub fn warnings(&self) -> Vec<String> {
+        self.quarantined
+            .iter()
+            .map(|pending_record| {
+                format!(
+                    "page {} row {} quarantined: {}",
+                    pending_record.page,
+                    pending_record.row + 1,
+                    pending_record.error
+                )
+            })
+            .chain(self.outside_window.iter().map(outside_window_warning))
+            .chain(self.stopped_early.map(stopped_early_warning))
+            .collect()
+    }
+}
+
+fn stopped_early_warning(page: u32) -> String {
+    format!("paging stopped early at page {page}, the import may be incomplete")
+}
+
+/// 看门狗中止导入或跳过的页重新识别仍然失败时已识别的抽卡记录
+#[derive(Serialize)]
+struct PartialImport<'a> {
+    account_id: &'a str,
+    banner_type: BannerType,
+    /// 中止时所在的页码
+    last_page: u32,
+    records: Vec<OneRecord>,
+}
+
+impl PartialImport<'_> {
+    fn save_to(&self, path: &Path) -> anyhow::Result<()> {
+        if let Some(parent) = path.parent() {
+            std::fs::create_dir_all(parent)?;
+        }
+        let file = std::fs::File::create(path)?;
+        let writer = std::io::BufWriter::new(file);
+        serde_json::to_writer_pretty(writer, self).map_err(|e| e.into())
+    }
+
+    /// 保存到 records/partial.json，返回的错误附带保存的位置
+    fn save_with_error(&self, kind: FailureKind, e: anyhow::Error) -> Failure {
+        let path = data_path("records/partial.json");
+        match self.save_to(&path) {
+            Ok(()) => Failure::new(
+                kind,
+                e.context(format!("partial results saved to {}", path.display())),
+            ),
+            Err(save_error) => {
+                log::error!("failed to save partial results: {:?}", save_error);
+                Failure::new(kind, e)
+            }
+        }
+    }
+}
+
+/// 等待游戏启动并打开抽卡记录界面，每秒检查一次并显示剩余时间
+/// 开启自动打开抽卡记录界面时，找到游戏窗口即可
+pub async fn wait_for_game(timeout: Duration) -> anyhow::Result<()> {
+    let start = Instant::now();
+    let mut capture_session = None;
+    loop {
+        let elapsed = start.elapsed();
+        if elapsed > timeout {
+            println!();
+            return Err(anyhow!("game not ready in {:?}", timeout));
+        }
+        let remaining = (timeout - elapsed).as_secs();
+        match &capture_session {
+            None => {
+                print!("\r{}", tr!("waiting_for_game_window", seconds = remaining));
+                if let Ok((_, title)) = get_game_window_info() {
+                    log::info!("game window found: {title}");
+                    if config().auto_open_record {
+                        println!();
+                        return Ok(());
+                    }
+                    capture_session = Some(CaptureSession::start(title));
+                }
+            }
+            Some(_) => {
+                print!(
+                    "\r{}",
+                    tr!("waiting_for_record_screen", seconds = remaining)
+                );
+                let is_record_image = capture_image()
+                    .map(|image| RecordImage::new(image).is_record_image())
+                    .unwrap_or(false);
+                if is_record_image {
+                    println!();
+                    return Ok(());
+                }
+            }
+        }
+        stdout().flush()?;
+        sleep(Duration::from_secs(1)).await;
+    }
+}
+
+/// 截取游戏中当前的抽卡记录界面，校准星级颜色并保存到配置
+/// # 返回
+/// 校准了的星级
+pub fn calibrate_star_colors() -> anyhow::Result<Vec<u8>> {
+    let (hwnd, window_title) = get_game_window_info()?;
+    set_window_top_most(hwnd).unwrap();
+    cancel_window_top_most(hwnd).unwrap();
+    let image = {
+        let _capture_session = CaptureSession::start(window_title);
+        capture_image()
+    };
+    let record_image = RecordImage::new(image?);
+    let (star_colors, stars) = config()
+        .star_colors
+        .calibrate(&record_image.star_pixels())?;
+    log::info!("calibrated star colors: {:?}", star_colors);
+    update_config(|config| config.star_colors = star_colors)?;
+    Ok(stars)
+}
+
+/// 从游戏中导入一个卡池的抽卡记录，合并保存后按 `formats` 和 `export_options` 导出
+pub async fn import_records(
+    account_id: &str,
+    user_selected_banner_type: BannerType,
+    import_mode: ImportMode,
+    formats: &[ExportFormat],
+    export_options: &ExportOptions,
+) -> Result<ImportSummary, Failure> {
+    // 获取游戏窗口
+    let (hwnd, window_title) =
+        get_game_window_info().map_err(|e| Failure::new(FailureKind::Capture, e))?;
+    log::info!("window title: {window_title}");
+    check_input_permission(hwnd).map_err(|e| Failure::new(FailureKind::Other, e))?;
+
+    let config = config();
+
+    if config.ocr_backend == OcrBackendKind::PaddleOcr && find_engine().is_none() {
+        return Err(Failure::new(
+            FailureKind::Other,
+            anyhow!("{}", tr!("paddle_ocr.not_found", url = RELEASES_URL)),
+        ));
+    }
+
+    // 翻到第一页的同时加载 OCR 模型
+    ocr_pool::warm_up();
+    set_client_language(None);
+
+    // 导入前先拉取其他电脑上的抽卡记录
+    #[cfg(feature = "sync")]
+    if let Err(e) = crate::sync::pull().await {
+        log::warn!("failed to pull records from remote: {:?}", e);
+    }
+
+    // 类型文本识别失败时根据已有记录中的物品名称推断类型
+    let total_records = TotalRecords::read_or_default();
+    learn_item_types(&total_records);
+    // 识别每页时不再读取配置和全局状态，检测到客户端语言后识别任务更新自己的一份
+    let parse_options = Arc::new(ParseOptions::from_config());
+    // 已有记录的页数，只用于显示进度，新增的记录可能使实际页数更多
+    let estimated_pages = estimated_pages(
+        total_records.records.get(account_id),
+        user_selected_banner_type,
+    );
+    // 增量导入时与这些记录比较，判断是否可以停止翻页
+    // 保存的记录中有缺失时导入所有页面，提前停止翻页就补不上缺失的记录
+    let stored_records = match total_records.records.get(account_id) {
+        Some(account_records) if config.incremental_import => {
+            let gaps = account_records.gaps(user_selected_banner_type);
+            if gaps > 0 {
+                log::info!("{gaps} stored records are missing, import all pages to fill them");
+                vec![]
+            } else {
+                account_records
+                    .records
+                    .get(&user_selected_banner_type)
+                    .cloned()
+                    .unwrap_or_default()
+            }
+        }
+        _ => vec![],
+    };
+    let incremental = !stored_records.is_empty();
+
+    let capture_delay = Duration::from_millis(config.capture_delay_ms);
+
+    // 游戏窗口置顶
+    set_window_top_most(hwnd).unwrap();
+    cancel_window_top_most(hwnd).unwrap();
+
+    // 创建截图工具，提前返回时自动释放
+    let capture_session = CaptureSession::start(window_title.clone());
+
+    // 截图后立即交给识别任务，识别完的截图随即释放
+    // 通道容量限制了同时在内存中的截图数量，与总页数无关
+    // 识别任务出错时提前结束，已识别的部分仍然写入导入报告
+    let batch = ImportBatch::new(user_selected_banner_type, RecordSource::Ocr);
+    let mut report = SessionReport::new(account_id, user_selected_banner_type, i18n::language());
+    let (page_sender, mut page_receiver) = mpsc::channel::<(u32, RecordImage)>(2);
+    let (progress_sender, mut progress_receiver) = watch::channel(OcrProgress::default());
+    let skip_failed_pages = config.retry_failed_pages && import_mode == ImportMode::Strict;
+    let ocr_task = tokio::spawn({
+        let batch = batch.clone();
+        let debug_boxes = DebugBoxes::new();
+        let mut options = parse_options.clone();
+        async move {
+            let mut records = vec![];
+            let mut invalid_rows = vec![];
+            let mut error = None;
+            // 严格模式下识别失败、等翻完所有页后重新识别的页
+            let mut failed_pages = vec![];
+            let mut banner_checked = false;
+            while let Some((page, record_image)) = page_receiver.recv().await {
+                let provenance = batch.provenance(Some(page));
+                if let Some(debug_boxes) = &debug_boxes {
+                    debug_boxes.save(page, &record_image);
+                }
+                let rows =
+                    match parse_page_async(record_image.image, options.clone(), ocr_batch).await {
+                        Ok(parsed_page) => parsed_page.rows,
+                        Err(e) => {
+                            error = Some(e);
+                            break;
+                        }
+                    };
+                report.add_page(page, &rows);
+                let invalid_num = rows.iter().filter(|row| row.is_err()).count();
+                let record_num = records.len();
+                if let Err(e) = collect_rows(
+                    page,
+                    rows,
+                    provenance,
+                    import_mode,
+                    &mut records,
+                    &mut invalid_rows,
+                ) {
+                    if !skip_failed_pages {
+                        error = Some(e);
+                        break;
+                    }
+                    // 丢弃这一页已经收集的记录，整页重新识别
+                    records.truncate(record_num);
+                    log::warn!("page {page} skipped: {:?}", e);
+                    println!("{}", tr!("import.page_skipped", page = page));
+                    report.add_warning(format!("page {page} skipped: {e:#}"));
+                    failed_pages.push((page, e));
+                    progress_sender.send_modify(|progress| progress.parsed_pages = page);
+                    continue;
+                }
+                emit(ProgressEvent::PageParsed {
+                    page,
+                    records: records.len() - record_num,
+                    invalid_rows: invalid_num,
+                });
+                print_page_summary(
+                    page,
+                    estimated_pages,
+                    records.len() - record_num,
+                    invalid_num,
+                    &records,
+                );
+                // 第一页就检查卡池是否一致，不一致时尽早中止，不再继续翻页
+                // 第一页被跳过时用第一个识别成功的页检查
+                if !banner_checked {
+                    banner_checked = true;
+                    if let Some(language) = detect_client_language(&records) {
+                        Arc::make_mut(&mut options).client_language = Some(language);
+                    }
+                    if let Err(e) = check_banner_type(batch.banner_type, &records) {
+                        error = Some(e);
+                        break;
+                    }
+                }
+                let is_known =
+                    invalid_num == 0 && is_known_page(&records[record_num..], &stored_records);
+                progress_sender.send_modify(|progress| {
+                    progress.parsed_pages = page;
+                    if is_known {
+                        progress.known_page.get_or_insert(page);
+                    }
+                });
+            }
+            (records, invalid_rows, report, error, failed_pages, options)
+        }
+    });
+
+    // 获取第一个界面，如果不是第一个界面，回到第一个界面
+    let mut last_page_hash;
+    let capture_failure = |e| Failure::new(FailureKind::Capture, e);
+    let image = capture_image().map_err(capture_failure)?;
+    let mut record_image = RecordImage::new(image);
+    let is_record_image =
+        |record_image: &RecordImage| record_image.is_record_image_with(&parse_options, &ocr_batch);
+    if !is_record_image(&record_image) && config.auto_open_record {
+        log::info!("not in the record interface, opening it");
+        record_image = match open_record_screen(hwnd).await {
+            Ok(record_image) => record_image,
+            Err(e) => return Err(Failure::new(FailureKind::Capture, e)),
+        };
+    }
+    if is_record_image(&record_image) {
+        // 回到第一个界面
+        let start = Instant::now();
+        let mut record_image = record_image.clone();
+        let mut index = record_image
+            .index_with(&parse_options, &ocr_batch)
+            .map_err(|e| Failure::new(FailureKind::Ocr, e))?;
+        log::debug!("index: {}", index);
+        while index != 1 {
+            previous_page(hwnd, config.click_method);
+            sleep(capture_delay).await;
+            let image = capture_image().map_err(capture_failure)?;
+            record_image = RecordImage::new(image);
+            // 翻页动画中或者 OCR 出错时读不出页码，重新截图
+            match record_image.index_with(&parse_options, &ocr_batch) {
+                Ok(new_index) => index = new_index,
+                Err(e) => log::debug!("failed to read page index: {:?}", e),
+            }
+            log::debug!("index: {}", index);
+            if start.elapsed().as_secs_f32() > 15.0 {
+                return Err(Failure::new(
+                    FailureKind::Capture,
+                    anyhow!("Failed to back to the first record image"),
+                ));
+            }
+        }
+        last_page_hash = record_image.page_hash();
+        emit(ProgressEvent::PageCaptured { page: 1 });
+        if page_sender.send((1, record_image)).await.is_err() {
+            log::error!("ocr task stopped unexpectedly");
+        }
+        // 等第一页识别完，日常导入时第一页的记录往往都已经保存过，不用翻页
+        // 识别任务出错结束时不再等待，错误在下面处理
+        if incremental {
+            let _ = progress_receiver
+                .wait_for(|progress| progress.parsed_pages >= 1)
+                .await;
+        }
+    } else {
+        let screen = classify_screen(&record_image);
+        log::info!("not in the record interface, current screen: {:?}", screen);
+        return Err(Failure::new(
+            FailureKind::Capture,
+            ExportError::NotOnRecordScreen { screen },
+        ));
+    }
+
+    log::info!("ocring...");
+    let start = Instant::now();
+    let watchdog = SessionWatchdog::new(
+        Duration::from_secs(config.session_timeout_secs),
+        config.max_pages,
+    );
+    let mut watchdog_error = None;
+    let mut now_index = 1;
+    // 页码识别失败时不再点击，重新截图，避免跳过一页
+    let mut need_click = true;
+    // 连续没有翻页的次数
+    let mut unadvanced_num = 0;
+    let mut click_method = config.click_method;
+    // 是否已经改用另一种点击方式
+    let mut click_method_switched = false;
+    // 增量导入提前停止时跳过的页数
+    let mut skipped = None;
+    // 翻页没有生效、在最后一页之前就停止时所在的页
+    let mut stopped_early = None;
+    loop {
+        // 同一分钟内有相同的记录时，一页的记录都在保存的记录中不一定说明没有新记录，多翻几页再停止
+        let known_page = progress_receiver.borrow().known_page;
+        if let Some(known_page) = known_page {
+            if now_index >= known_page + config.incremental_margin_pages {
+                let skipped_num = skipped_pages(estimated_pages, known_page, now_index);
+                log::info!(
+                    "records on page {known_page} are all saved, stop paging at page {now_index}, skipped about {skipped_num} pages"
+                );
+                println!(
+                    "{}",
+                    tr!(
+                        "import.known_page",
+                        page = known_page,
+                        skipped = skipped_num
+                    )
+                );
+                skipped = Some(skipped_num);
+                break;
+            }
+        }
+        if let Err(e) = watchdog.check(now_index) {
+            log::error!("{:?}", e);
+            watchdog_error = Some(e);
+            break;
+        }
+        if need_click {
+            next_page_attempt(hwnd, unadvanced_num, click_method);
+        }
+        sleep(capture_delay).await;
+        let image = capture_image().map_err(capture_failure)?;
+        let record_image = RecordImage::new(image);
+
+        // 画面和上一页几乎一样，说明点击没有生效或者已经是最后一页，重新点击
+        let page_hash = record_image.page_hash();
+        if page_hash.is_similar(&last_page_hash) {
+            unadvanced_num += 1;
+            if unadvanced_num > config.click_retries {
+                // 第一页就翻不动时点击可能被拦截了，换一种点击方式再试一次
+                if now_index == 1 && !click_method_switched {
+                    log::warn!(
+                        "page not advanced with {:?} clicks, fall back to {:?}",
+                        click_method,
+                        click_method.fallback()
+                    );
+                    click_method = click_method.fallback();
+                    click_method_switched = true;
+                    unadvanced_num = 0;
+                    need_click = true;
+                    continue;
+                }
+                // 已保存的记录比已经翻过的页多，说明还没有到最后一页
+                if now_index < estimated_pages {
+                    log::warn!(
+                        "page {now_index} not advanced after {} clicks, stop paging before the last page (about {estimated_pages} pages saved)",
+                        unadvanced_num
+                    );
+                    println!(
+                        "{}",
+                        tr!(
+                            "import.stopped_early",
+                            page = now_index,
+                            pages = estimated_pages
+                        )
+                    );
+                    stopped_early = Some(now_index);
+                } else {
+                    log::info!(
+                        "page {now_index} not advanced after {} clicks, treat it as the last page",
+                        unadvanced_num
+                    );
+                }
+                break;
+            }
+            log::debug!("page {now_index} not advanced, retry clicking");
+            need_click = true;
+            continue;
+        }
+        unadvanced_num = 0;
+
+        match record_image.index_with(&parse_options, &ocr_batch) {
+            Ok(index) if index == now_index + 1 => {
+                last_page_hash = page_hash;
+                emit(ProgressEvent::PageCaptured { page: index });
+                if page_sender.send((index, record_image)).await.is_err() {
+                    // 识别任务出错提前结束，错误在下面处理
+                    break;
+                }
+                now_index += 1;
+                need_click = true;
+            }
+            Ok(_) => break,
+            Err(e) => {
+                log::warn!("failed to read page index after page {now_index}: {:?}", e);
+                need_click = false;
+            }
+        }
+    }
+    drop(page_sender);
+
+    // 停止截图，释放资源
+    drop(capture_session);
+
+    log::debug!("page num: {}", now_index);
+
+    // 重新识别跳过的页时沿用识别任务检测到的客户端语言
+    let (mut records, invalid_rows, mut report, ocr_error, failed_pages, parse_options) =
+        ocr_task.await.map_err(|e| {
+            Failure::new(
+                FailureKind::Ocr,
+                anyhow!("failed to join ocr task: {:?}", e),
+            )
+        })?;
+    log::info!("ocr spend: {:?}", start.elapsed());
+    if let Some(skipped) = skipped {
+        report.set_skipped_pages(skipped);
+    }
+    if let Some(page) = stopped_early {
+        report.add_warning(stopped_early_warning(page));
+    }
+    if let Err(e) = save_ocr_cache() {
+        log::warn!("failed to save ocr cache: {:?}", e);
+    }
+    if let Some(e) = ocr_error {
+        report.add_warning(format!("{e:#}"));
+        save_report(&report);
+        let kind = find_export_error(&e).map_or(FailureKind::Ocr, ExportError::failure_kind);
+        return Err(Failure::new(kind, e));
+    }
+
+    // 看门狗中止时不合并不完整的记录，单独保存已识别的部分
+    if let Some(e) = watchdog_error {
+        report.add_warning(format!("{e:#}"));
+        save_report(&report);
+        let partial_import = PartialImport {
+            account_id,
+            banner_type: user_selected_banner_type,
+            last_page: now_index,
+            records,
+        };
+        return Err(partial_import.save_with_error(FailureKind::Capture, e));
+    }
+
+    // 回到跳过的页重新截图识别，仍然失败时不合并有缺口的记录，单独保存已识别的部分
+    if !failed_pages.is_empty() {
+        let capture_session = CaptureSession::start(window_title);
+        let result = retry_failed_pages(
+            &batch,
+            &parse_options,
+            failed_pages,
+            now_index,
+            (hwnd, capture_delay, click_method),
+            &mut records,
+            &mut report,
+        )
+        .await;
+        drop(capture_session);
+        if let Err(e) = result {
+            report.add_warning(format!("{e:#}"));
+            save_report(&report);
+            let kind = find_export_error(&e).map_or(FailureKind::Ocr, ExportError::failure_kind);
+            let partial_import = PartialImport {
+                account_id,
+                banner_type: user_selected_banner_type,
+                last_page: now_index,
+                records,
+            };
+            return Err(partial_import.save_with_error(kind, e));
+        }
+    }
+
+    let summary = finish_import(
+        account_id,
+        batch,
+        records,
+        invalid_rows,
+        report,
+        formats,
+        export_options,
+    )
+    .await?;
+    Ok(ImportSummary {
+        stopped_early,
+        ..summary
+    })
+}
+
+/// 可以移到阻塞线程池中的 [`BatchOcr`](crate::record_image::BatchOcr)，[`ocr_batch`] 或 [`ocr_batch_uncached`]
+type OcrFn = fn(Vec<(GrayImage, CharsetHint)>) -> anyhow::Result<Vec<String>>;
+
+/// 在阻塞线程池中解析一页截图，避免 OCR 阻塞 tokio 运行时
+async fn parse_page_async(
+    image: DynamicImage,
+    options: Arc<ParseOptions>,
+    ocr: OcrFn,
+) -> anyhow::Result<ParsedPage> {
+    tokio::task::spawn_blocking(move || parse_record_page(image, &options, &ocr))
+        .await
+        .map_err(|e| anyhow!("Failed to join ocr task: {:?}", e))?
+}
+
+/// 翻到指定的页，返回这一页的截图
+/// # 参数
+/// - `from`: 当前所在的页码，用来估计翻页需要的时间
+async fn go_to_page(
+    from: u32,
+    page: u32,
+    options: &ParseOptions,
+    (hwnd, capture_delay, click_method): (isize, Duration, ClickMethod),
+) -> anyhow::Result<RecordImage> {
+    let timeout = GO_TO_PAGE_TIME_PER_PAGE * from.abs_diff(page) + GO_TO_PAGE_EXTRA_TIME;
+    let start = Instant::now();
+    loop {
+        let record_image = RecordImage::new(capture_image()?);
+        match record_image.index_with(options, &ocr_batch) {
+            Ok(index) if index == page => return Ok(record_image),
+            Ok(index) if index > page => previous_page(hwnd, click_method),
+            Ok(_) => next_page_attempt(hwnd, 0, click_method),
+            Err(e) => log::debug!("failed to read page index: {:?}", e),
+        }
+        if start.elapsed() > timeout {
+            return Err(anyhow!("failed to go back to page {page}"));
+        }
+        sleep(capture_delay).await;
+    }
+}
+
+/// 翻完所有页后回到跳过的页重新截图识别，识别成功的记录按页码插回
+/// # 参数
+/// - `failed_pages`: 跳过的页和识别失败的错误，按页码排列
+/// - `current_page`: 翻页结束时所在的页码
+/// # 错误
+/// 重新识别后仍然失败的页的错误
+async fn retry_failed_pages(
+    batch: &ImportBatch,
+    options: &Arc<ParseOptions>,
+    failed_pages: Vec<(u32, anyhow::Error)>,
+    current_page: u32,
+    paging: (isize, Duration, ClickMethod),
+    records: &mut Vec<OneRecord>,
+    report: &mut SessionReport,
+) -> anyhow::Result<()> {
+    let mut current_page = current_page;
+    // 重新截图的画面往往和第一次完全一样，只换掉缓存得到的还是同样的结果，所以最后一次换预处理
+    let strongest = Arc::new(ParseOptions {
+        preprocessing: Preprocessing::strongest(),
+        ..ParseOptions::clone(options)
+    });
+    for (page, mut error) in failed_pages {
+        log::info!("retrying page {page}");
+        println!("{}", tr!("import.page_retry", page = page));
+        let mut page_records = None;
+        for attempt in 1..=FAILED_PAGE_RETRIES {
+            let record_image = go_to_page(current_page, page, options, paging).await?;
+            current_page = page;
+            let attempt_options = if attempt == FAILED_PAGE_RETRIES {
+                strongest.clone()
+            } else {
+                options.clone()
+            };
+            let rows = parse_page_async(record_image.image, attempt_options, ocr_batch_uncached)
+                .await?
+                .rows;
+            let mut collected = vec![];
+            match collect_rows(
+                page,
+                rows,
+                batch.provenance(Some(page)),
+                ImportMode::Strict,
+                &mut collected,
+                &mut vec![],
+            ) {
+                Ok(()) => {
+                    page_records = Some(collected);
+                    break;
+                }
+                Err(e) => {
+                    log::warn!("page {page} failed on retry {attempt}: {:?}", e);
+                    error = e;
+                }
+            }
+        }
+        let Some(page_records) = page_records else {
+            return Err(error);
+        };
+        log::info!("page {page} recovered on retry");
+        println!("{}", tr!("import.page_recovered", page = page));
+        report.add_warning(format!("page {page} recovered on retry"));
+        insert_page_records(records, page, page_records);
+    }
+    Ok(())
+}
+
+/// 每识别完一页输出一行进度，这一页没有识别出记录时提示
+/// # 参数
+/// - `estimated_pages`: 根据已有记录估计的页数，超过后不再显示
+/// - `records`: 到这一页为止识别出的所有记录
+fn print_page_summary(
+    page: u32,
+    estimated_pages: u32,
+    added: usize,
+    invalid_num: usize,
+    records: &[OneRecord],
+) {
+    let five_star_num = records.iter().filter(|record| record.star == 5).count();
+    let page = if page <= estimated_pages {
+        format!("{page}/{estimated_pages}")
+    } else {
+        page.to_string()
+    };
+    let mut summary = tr!(
+        "page_summary",
+        page = page,
+        added = added,
+        total = records.len(),
+        five_star = five_star_num
+    );
+    if invalid_num > 0 {
+        summary += &tr!("page_summary.invalid", num = invalid_num);
+    }
+    println!("{summary}");
+    if added == 0 {
+        log::warn!("no records recognized on page {page}");
+        println!("{}", tr!("page_summary.empty"));
+    }
+}
+
+/// 一页的识别结果分为抽卡记录和识别失败的行
+/// # 错误
+/// 严格模式下有识别失败的行
+pub fn collect_rows(
+    page: u32,
+    rows: Vec<Result<OneRecord, Box<InvalidRow>>>,
+    provenance: Provenance,
+    import_mode: ImportMode,
+    records: &mut Vec<OneRecord>,
+    invalid_rows: &mut Vec<(u32, Box<InvalidRow>)>,
+) -> anyhow::Result<()> {
+    for row in rows {
+        match row {
+            Ok(record) => records.push(OneRecord {
+                provenance: Some(provenance),
+                ..record
+            }),
+            Err(invalid_row) if import_mode == ImportMode::Lenient => {
+                log::warn!(
+                    "page {page} row {}: {:?}",
+                    invalid_row.row + 1,
+                    invalid_row.error
+                );
+                invalid_rows.push((page, invalid_row));
+            }
+            Err(invalid_row) => {
+                let row = invalid_row.row + 1;
+                return Err(invalid_row
+                    .error
+                    .context(ExportError::OcrFailed { page, row }));
+            }
+        }
+    }
+    Ok(())
+}
+
+/// 检查识别出的物品是否属于选择的卡池
+/// # 错误
+/// 有物品不属于这个卡池，游戏中打开的可能是其他卡池
+pub fn check_banner_type(banner_type: BannerType, records: &[OneRecord]) -> anyhow::Result<()> {
+    match records
+        .iter()
+        .find(|record| !banner_type.accepts(record.item_type, record.star))
+    {
+        Some(record) => Err(ExportError::BannerMismatch {
+            banner_type,
+            item_name: record.item_name.clone(),
+        }
+        .into()),
+        None => Ok(()),
+    }
+}
+
+/// 根据识别出的物品名称检测游戏客户端的语言，之后的页面识别物品类型时优先匹配这种语言
+/// 与界面语言不同时提示用户，返回检测到的语言，无法确定时为 `None`
+pub fn detect_client_language(records: &[OneRecord]) -> Option<Language> {
+    let language = detect_language(records.iter().map(|record| record.item_name.as_str()))?;
+    log::info!("client language: {:?}", language);
+    set_client_language(Some(language));
+    if language != i18n::language() {
+        println!("{}", tr!("client_language_detected", language = language));
+    }
+    Some(language)
+}
+
+/// 时间不在卡池开放时间内的抽卡记录，可能选错了卡池或者日期识别有误
+/// 只检查排期表覆盖的限定卡池
+pub fn outside_banner_window(banner_type: BannerType, records: &[OneRecord]) -> Vec<OneRecord> {
+    records
+        .iter()
+        .filter(|record| is_available(record.timestamp, banner_type) == Some(false))
+        .cloned()
+        .collect()
+}
+
+fn outside_window_warning(record: &OneRecord) -> String {
+    format!(
+        "{} at {} is outside the banner availability window",
+        record.item_name,
+        record.readable_date_time_str()
+    )
+}
+
+/// 识别完成后，把识别失败的行放入待审核列表，合并保存抽卡记录，导出并运行导出后命令
+pub async fn finish_import(
+    account_id: &str,
+    mut batch: ImportBatch,
+    records: Vec<OneRecord>,
+    invalid_rows: Vec<(u32, Box<InvalidRow>)>,
+    mut report: SessionReport,
+    formats: &[ExportFormat],
+    export_options: &ExportOptions,
+) -> Result<ImportSummary, Failure> {
+    let banner_type = batch.banner_type;
+    // 识别失败的行没有保存，合并时记为缺失，之后重新导入或审核修正后补上
+    batch.dropped = invalid_rows.len() as u32;
+    // 识别失败的行留待人工审核
+    let quarantined = if invalid_rows.is_empty() {
+        vec![]
+    } else {
+        quarantine(account_id, banner_type, batch.id, invalid_rows)
+            .map_err(|e| Failure::new(FailureKind::Other, e))?
+    };
+    for pending_record in quarantined.iter() {
+        report.add_warning(format!(
+            "page {} row {} quarantined: {}",
+            pending_record.page,
+            pending_record.row + 1,
+            pending_record.error
+        ));
+    }
+
+    let outside_window = outside_banner_window(banner_type, &records);
+    for record in outside_window.iter() {
+        let warning = outside_window_warning(record);
+        log::warn!("{warning}");
+        report.add_warning(warning);
+    }
+
+    // 读取或初始化记录
+    let mut total_record = TotalRecords::read_or_default();
+    let add_num = match total_record.add_record(account_id.to_string(), batch, records) {
+        Ok(add_num) => add_num,
+        Err(e) => {
+            report.add_warning(format!("{e:#}"));
+            save_report(&report);
+            return Err(Failure::new(FailureKind::MergeConflict, e));
+        }
+    };
+    log::info!("add {} records", add_num);
+    emit(ProgressEvent::MergeDone {
+        account_id: account_id.to_string(),
+        banner_type,
+        added: add_num,
+    });
+    report.set_added(add_num);
+    let report_path = save_report(&report);
+    total_record
+        .save()
+        .map_err(|e| Failure::new(FailureKind::Other, e))?;
+
+    #[cfg(feature = "sync")]
+    if let Err(e) = crate::sync::push().await {
+        log::warn!("failed to push records to remote: {:?}", e);
+    }
+
+    if let Some(account_records) = total_record.records.get(account_id) {
+        if !is_quiet() {
+            print_item_counts(&item_counts(account_records));
+        }
+    }
+
+    let paths = export_all(&total_record, formats, export_options)
+        .map_err(|e| Failure::new(FailureKind::Other, e))?;
+    let summary = ExportSummary {
+        account_id: Some(account_id.to_string()),
+        banner_type: Some(banner_type),
+        added: add_num,
+        paths: paths.clone(),
+    };
+    if let Err(e) = run_post_export_hook(&summary) {
+        log::warn!("post export hook failed: {:?}", e);
+    }
+
+    Ok(ImportSummary {
+        added: add_num,
+        quarantined,
+        report_path,
+        paths,
+        outside_window,
+        stopped_early: None,
+    })
+}
+
+/// 配置开启时保存导入报告
+/// # 返回
+/// 报告文件的路径，没有保存时为 `None`
+fn save_report(report: &SessionReport) -> Option<PathBuf> {
+    if !config().session_report {
+        return None;
+    }
+    match report.save() {
+        Ok(path) => {
+            log::info!("session report saved to {:?}", path);
+            Some(path)
+        }
+        Err(e) => {
+            log::warn!("failed to save session report: {:?}", e);
+            None
+        }
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use crate::record::ItemType;
+
+    #[test]
+    fn test_is_known_page() {
+        let record = |name: &str, timestamp| {
+            OneRecord::new(3, name.to_string(), ItemType::Weapon, timestamp)
+        };
+        let stored = [record("c", 3), record("b", 2), record("a", 1)];
+        assert!(is_known_page(&[record("c", 3), record("b", 2)], &stored));
+        assert!(is_known_page(&[record("b", 2), record("a", 1)], &stored));
+        // 有新的记录
+        assert!(!is_known_page(&[record("d", 4), record("c", 3)], &stored));
+        // 不连续
+        assert!(!is_known_page(&[record("c", 3), record("a", 1)], &stored));
+        assert!(!is_known_page(&[], &stored));
+        assert!(!is_known_page(&[record("c", 3)], &[]));
+    }
+
+    #[test]
+    fn test_check_banner_type() {
+        let weapon = OneRecord::new(5, "w".to_string(), ItemType::Weapon, 2);
+        let character = OneRecord::new(5, "c".to_string(), ItemType::Character, 1);
+        let records = [weapon, character];
+
+        // 物品都属于选择的卡池
+        check_banner_type(BannerType::PermanentWeapon, &records[..1]).unwrap();
+        check_banner_type(BannerType::Beginner, &records).unwrap();
+
+        // 物品不属于选择的卡池
+        let error = check_banner_type(BannerType::LimitedCharacter50Percent, &records).unwrap_err();
+        assert_eq!(
+            find_export_error(&error),
+            Some(&ExportError::BannerMismatch {
+                banner_type: BannerType::LimitedCharacter50Percent,
+                item_name: "w".to_string(),
+            })
+        );
+    }
+
+    #[test]
+    fn test_insert_page_records() {
+        let batch = ImportBatch::new(BannerType::PermanentWeapon, RecordSource::Ocr);
+        let record = |page, timestamp| OneRecord {
+            provenance: Some(batch.provenance(Some(page))),
+            ..OneRecord::new(3, "a".to_string(), ItemType::Weapon, timestamp)
+        };
+        // 第 2 页被跳过
+        let mut records = vec![record(1, 6), record(1, 5), record(3, 2), record(3, 1)];
+        insert_page_records(&mut records, 2, vec![record(2, 4), record(2, 3)]);
+        let timestamps = records
+            .iter()
+            .map(|record| record.timestamp)
+            .collect::<Vec<_>>();
+        assert_eq!(timestamps, [6, 5, 4, 3, 2, 1]);
+        // 最后一页被跳过
+        insert_page_records(&mut records, 4, vec![record(4, 0)]);
+        assert_eq!(records.last().unwrap().timestamp, 0);
+    }
+
+    #[test]
+    fn test_skipped_pages() {
+        // 没有新记录，第一页就停止
+        assert_eq!(skipped_pages(10, 1, 1), 9);
+        // 新记录占了一页多，第三页都已保存，多翻一页
+        assert_eq!(skipped_pages(10, 3, 4), 8);
+        assert_eq!(skipped_pages(1, 1, 2), 0);
+    }
+}
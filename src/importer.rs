@@ -0,0 +1,309 @@
+//! 导入其他抽卡记录工具导出的文件
+//!
+//! 每种文件格式实现 [`Importer`]，根据文件内容自动判断格式，也可以用 `--format` 指定。
+//! 支持的格式：
+//!
+//! - UIGF 风格的 JSON：`{"info": {"uid": ...}, "list": [{"gacha_type", "name", "item_type", "rank_type", "time"}, ...]}`
+//! - 表头包含卡池、星级、名称、时间列的 CSV，例如本工具导出的 `records.csv`
+//!
+//! 卡池、物品类型可以是本工具中任意语言的名称或命令行中的卡池名称，时间可以是
+//! `2024-06-01 10:00:00`、`2024/06/01 10:00` 或 Unix 时间戳。
+
+use std::cmp::Reverse;
+use std::collections::HashMap;
+use std::path::Path;
+
+use anyhow::{anyhow, Result};
+use chrono::{Local, NaiveDateTime, TimeZone};
+use clap::ValueEnum;
+use enum_iterator::{all, Sequence};
+use lazy_static::lazy_static;
+use serde::{Deserialize, Serialize};
+
+use crate::i18n::tr;
+use crate::items::{canonical_name, item_type_by_name};
+use crate::language::Language;
+use crate::record::{BannerType, ImportBatch, ItemType, OneRecord, RecordSource, TotalRecords};
+
+mod csv;
+mod uigf;
+
+/// 导入的文件格式
+#[derive(Debug, Copy, Clone, PartialEq, Eq, Hash, Serialize, Deserialize, Sequence, ValueEnum)]
+pub enum ImportFormat {
+    /// UIGF 风格的 JSON
+    UigfJson,
+    /// 带表头的 CSV
+    Csv,
+}
+
+impl ImportFormat {
+    pub fn display_name(&self) -> &'static str {
+        match self {
+            ImportFormat::UigfJson => "UIGF JSON",
+            ImportFormat::Csv => "CSV",
+        }
+    }
+}
+
+/// 从文件中读取的抽卡记录
+#[derive(Debug, Default)]
+pub struct ImportedRecords {
+    /// 账号ID（文件中没有时为 `None`）-> 卡池 -> 抽卡记录，调用 [`ImportedRecords::finish`] 后最新的在最前面
+    pub accounts: HashMap<Option<String>, HashMap<BannerType, Vec<OneRecord>>>,
+}
+
+impl ImportedRecords {
+    fn add(&mut self, account_id: Option<String>, banner_type: BannerType, record: OneRecord) {
+        self.accounts
+            .entry(account_id)
+            .or_default()
+            .entry(banner_type)
+            .or_default()
+            .push(record);
+    }
+
+    /// 每个卡池的抽卡记录按时间倒序排列
+    ///
+    /// 文件中的记录一般按时间正序排列，此时先整体反转，同一分钟内的十连保持正确的顺序。
+    fn finish(mut self) -> Self {
+        for records in self.accounts.values_mut().flat_map(HashMap::values_mut) {
+            if records.first().map(|record| record.timestamp)
+                < records.last().map(|record| record.timestamp)
+            {
+                records.reverse();
+            }
+            records.sort_by_key(|record| Reverse(record.timestamp));
+        }
+        self
+    }
+}
+
+pub trait Importer: Send + Sync {
+    fn format(&self) -> ImportFormat;
+
+    /// 文件内容是否是这种格式
+    fn detect(&self, content: &str) -> bool;
+
+    /// 解析文件内容
+    fn parse(&self, content: &str) -> Result<ImportedRecords>;
+}
+
+lazy_static! {
+    /// 所有导入格式的实现，自动判断格式时按顺序尝试
+    static ref IMPORTERS: Vec<Box<dyn Importer>> =
+        vec![Box::new(uigf::UigfImporter), Box::new(csv::CsvImporter)];
+}
+
+/// 导入格式对应的实现
+pub fn importer(format: ImportFormat) -> &'static dyn Importer {
+    IMPORTERS
+        .iter()
+        .find(|importer| importer.format() == format)
+        .map(Box::as_ref)
+        .unwrap()
+}
+
+/// 根据文件内容判断格式
+pub fn detect_format(content: &str) -> Option<ImportFormat> {
+    IMPORTERS
+        .iter()
+        .find(|importer| importer.detect(content))
+        .map(|importer| importer.format())
+}
+
+/// 卡池名称：命令行中的名称、本工具中任意语言的名称、按卡池导出的文件名或 `records.json` 中的名称
+fn parse_banner(text: &str) -> Result<BannerType> {
+    let text = text.trim();
+    all::<BannerType>()
+        .find(|banner_type| {
+            let value_name = banner_type
+                .to_possible_value()
+                .map(|value| value.get_name().to_string());
+            value_name.as_deref() == Some(text)
+                || format!("{banner_type:?}") == text
+                || banner_type.file_stem() == text
+                || all::<Language>().any(|language| {
+                    banner_type
+                        .display_name_for_user(language)
+                        .eq_ignore_ascii_case(text)
+                })
+        })
+        .ok_or_else(|| anyhow!("unknown banner: {text}"))
+}
+
+/// 星级：`5`、`5星`、`★★★★★`
+fn parse_star(text: &str) -> Result<u8> {
+    let text = text.trim();
+    let star = if !text.is_empty() && text.chars().all(|c| c == '★') {
+        Some(text.chars().count() as u8)
+    } else {
+        text.trim_end_matches('星').trim().parse().ok()
+    };
+    star.filter(|star| (3..=5).contains(star))
+        .ok_or_else(|| anyhow!("invalid star: {text}"))
+}
+
+/// 物品类型：本工具或游戏中任意语言的名称，为空时根据物品名称判断
+fn parse_item_type(text: &str, item_name: &str, star: u8) -> Result<ItemType> {
+    let text = text.trim();
+    if text.is_empty() {
+        return item_type_by_name(item_name, star)
+            .ok_or_else(|| anyhow!("unknown item type of {item_name}"));
+    }
+    all::<ItemType>()
+        .find(|item_type| {
+            format!("{item_type:?}").eq_ignore_ascii_case(text)
+                || all::<Language>().any(|language| {
+                    item_type
+                        .display_name_for_user(language)
+                        .eq_ignore_ascii_case(text)
+                        || item_type
+                            .display_name_in_record_page_in_game(language)
+                            .eq_ignore_ascii_case(text)
+                })
+        })
+        .ok_or_else(|| anyhow!("unknown item type: {text}"))
+}
+
+/// 时间：`2024-06-01 10:00:00`、`2024/06/01 10:00`、Unix 时间戳（秒或毫秒）
+fn parse_time(text: &str) -> Result<u64> {
+    let text = text.trim();
+    if !text.is_empty() && text.chars().all(|c| c.is_ascii_digit()) {
+        let timestamp = text.parse::<u64>()?;
+        // 毫秒时间戳
+        return Ok(if timestamp > 10_000_000_000 {
+            timestamp / 1000
+        } else {
+            timestamp
+        });
+    }
+    let time = [
+        "%Y-%m-%d %H:%M:%S",
+        "%Y-%m-%d %H:%M",
+        "%Y/%m/%d %H:%M:%S",
+        "%Y/%m/%d %H:%M",
+    ]
+    .into_iter()
+    .find_map(|format| NaiveDateTime::parse_from_str(text, format).ok())
+    .ok_or_else(|| anyhow!("invalid time: {text}"))?;
+    Local
+        .from_local_datetime(&time)
+        .earliest()
+        .map(|time| time.timestamp() as u64)
+        .ok_or_else(|| anyhow!("invalid local time: {text}"))
+}
+
+/// 一条抽卡记录的各个字段转换为抽卡记录
+fn parse_record(star: &str, item_name: &str, item_type: &str, time: &str) -> Result<OneRecord> {
+    let star = parse_star(star)?;
+    let item_name = item_name.trim();
+    if item_name.is_empty() {
+        return Err(anyhow!("empty item name"));
+    }
+    let item_type = parse_item_type(item_type, item_name, star)?;
+    Ok(OneRecord::new(
+        star,
+        canonical_name(item_name),
+        item_type,
+        parse_time(time)?,
+    ))
+}
+
+/// 导入文件的结果
+#[derive(Debug)]
+pub struct FileImportSummary {
+    pub format: ImportFormat,
+    /// （账号ID, 卡池, 新增的抽卡记录数量）
+    pub added: Vec<(String, BannerType, u32)>,
+}
+
+/// 导入其他工具导出的文件，合并到保存的抽卡记录中
+/// # 参数
+/// - `format`: 文件格式，为 `None` 时根据文件内容判断
+/// - `account_id`: 文件中没有账号ID时使用的账号ID
+pub fn import_file(
+    path: &Path,
+    format: Option<ImportFormat>,
+    account_id: Option<&str>,
+) -> Result<FileImportSummary> {
+    let content = std::fs::read_to_string(path)?;
+    let content = content.trim_start_matches('\u{feff}');
+    let format = format
+        .or_else(|| detect_format(content))
+        .ok_or_else(|| anyhow!("unknown file format: {}", path.display()))?;
+    log::info!("importing {:?} as {:?}", path, format);
+    let imported = importer(format).parse(content)?;
+
+    let mut total_records = TotalRecords::read_or_default();
+    let mut added = vec![];
+    for (file_account_id, banners) in imported.accounts {
+        let account_id = file_account_id
+            .or(account_id.map(str::to_string))
+            .ok_or_else(|| anyhow!("{}", tr!("import_file.no_account")))?;
+        let mut banners = banners.into_iter().collect::<Vec<_>>();
+        banners.sort_by_key(|(banner_type, _)| all::<BannerType>().position(|b| b == *banner_type));
+        for (banner_type, records) in banners {
+            let batch = ImportBatch::new(banner_type, RecordSource::OtherTool);
+            let add_num = total_records.add_record(account_id.clone(), batch, records)?;
+            added.push((account_id.clone(), banner_type, add_num));
+        }
+    }
+    total_records.save()?;
+    Ok(FileImportSummary { format, added })
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    #[test]
+    fn test_parse_fields() {
+        assert_eq!(
+            parse_banner("limited-weapon-50").unwrap(),
+            BannerType::LimitedWeapon50Percent
+        );
+        assert_eq!(
+            parse_banner("PermanentCharacter").unwrap(),
+            BannerType::PermanentCharacter
+        );
+        assert_eq!(parse_banner("新手池").unwrap(), BannerType::Beginner);
+        assert!(parse_banner("unknown").is_err());
+
+        assert_eq!(parse_star("5").unwrap(), 5);
+        assert_eq!(parse_star("4星").unwrap(), 4);
+        assert_eq!(parse_star("★★★").unwrap(), 3);
+        assert!(parse_star("6").is_err());
+
+        assert_eq!(
+            parse_item_type("Operative", "a", 4).unwrap(),
+            ItemType::Character
+        );
+        assert_eq!(parse_item_type("武器", "a", 4).unwrap(), ItemType::Weapon);
+        assert!(parse_item_type("", "unknown item", 4).is_err());
+
+        let time = parse_time("2024-06-01 10:00:00").unwrap();
+        assert_eq!(parse_time("2024/06/01 10:00").unwrap(), time);
+        assert_eq!(parse_time(&time.to_string()).unwrap(), time);
+        assert_eq!(parse_time(&(time * 1000).to_string()).unwrap(), time);
+    }
+
+    #[test]
+    fn test_finish() {
+        let mut imported = ImportedRecords::default();
+        // 按时间正序，同一分钟内的十连按抽卡顺序
+        for (name, time) in [("a", 1), ("b", 2), ("c", 2)] {
+            imported.add(
+                None,
+                BannerType::PermanentWeapon,
+                OneRecord::new(3, name.to_string(), ItemType::Weapon, time),
+            );
+        }
+        let imported = imported.finish();
+        let names = imported.accounts[&None][&BannerType::PermanentWeapon]
+            .iter()
+            .map(|record| record.item_name.as_str())
+            .collect::<Vec<_>>();
+        assert_eq!(names, ["c", "b", "a"]);
+    }
+}
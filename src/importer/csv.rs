@@ -0,0 +1,183 @@
+use anyhow::{anyhow, Context, Result};
+use enum_iterator::all;
+
+use super::{parse_banner, parse_record, ImportFormat, ImportedRecords, Importer};
+use crate::export::template::Column;
+use crate::language::Language;
+
+/// 带表头的 CSV，根据表头找到卡池、星级、名称、类型、时间、账号列，
+/// 表头可以是本工具导出时任意语言的表头，也可以是常见的英文名称
+pub struct CsvImporter;
+
+/// 需要的列，类型和账号列可以没有
+#[derive(Debug, Copy, Clone, PartialEq, Eq)]
+enum Field {
+    Banner,
+    Star,
+    Name,
+    Type,
+    Time,
+    Account,
+}
+
+impl Field {
+    /// 表头的其他常见名称，比较时不区分大小写
+    fn aliases(&self) -> &'static [&'static str] {
+        match self {
+            Field::Banner => &["banner", "gacha_type", "卡池类型"],
+            Field::Star => &["rarity", "rank_type", "星级", "稀有度"],
+            Field::Name => &["item_name", "物品名称"],
+            Field::Type => &["item_type", "物品类型"],
+            Field::Time => &["抽卡时间"],
+            Field::Account => &["uid", "account_id", "账号ID"],
+        }
+    }
+
+    /// 本工具导出时这一列的表头
+    fn column(&self) -> Column {
+        match self {
+            Field::Banner => Column::Banner,
+            Field::Star => Column::Star,
+            Field::Name => Column::Name,
+            Field::Type => Column::Type,
+            Field::Time => Column::Time,
+            Field::Account => Column::Account,
+        }
+    }
+
+    fn matches(&self, header: &str) -> bool {
+        let header = header.trim();
+        self.aliases()
+            .iter()
+            .any(|alias| alias.eq_ignore_ascii_case(header))
+            || all::<Language>()
+                .any(|language| self.column().header(language).eq_ignore_ascii_case(header))
+    }
+}
+
+/// 解析 CSV，引号包围的字段中可以有逗号、换行，引号写两次
+fn parse_csv(content: &str) -> Vec<Vec<String>> {
+    let mut rows = vec![];
+    let mut row = vec![];
+    let mut field = String::new();
+    let mut quoted = false;
+    let mut chars = content.chars().peekable();
+    while let Some(c) = chars.next() {
+        match (c, quoted) {
+            ('"', true) if chars.peek() == Some(&'"') => {
+                chars.next();
+                field.push('"');
+            }
+            ('"', _) => quoted = !quoted,
+            (',', false) => row.push(std::mem::take(&mut field)),
+            ('\r', false) => {}
+            ('\n', false) => {
+                row.push(std::mem::take(&mut field));
+                rows.push(std::mem::take(&mut row));
+            }
+            (c, _) => field.push(c),
+        }
+    }
+    if !field.is_empty() || !row.is_empty() {
+        row.push(field);
+        rows.push(row);
+    }
+    rows.retain(|row| row.iter().any(|field| !field.trim().is_empty()));
+    rows
+}
+
+/// 每个字段所在的列，必需的列没有时返回 `None`
+fn field_indexes(headers: &[String]) -> Option<[Option<usize>; 6]> {
+    let index = |field: Field| headers.iter().position(|header| field.matches(header));
+    let indexes = [
+        Field::Banner,
+        Field::Star,
+        Field::Name,
+        Field::Type,
+        Field::Time,
+        Field::Account,
+    ]
+    .map(index);
+    let [banner, star, name, _, time, _] = indexes;
+    (banner.is_some() && star.is_some() && name.is_some() && time.is_some()).then_some(indexes)
+}
+
+/// 一行中第 `index` 列的字段，没有这一列时为空字符串
+fn cell(row: &[String], index: Option<usize>) -> &str {
+    index
+        .and_then(|index| row.get(index))
+        .map(String::as_str)
+        .unwrap_or_default()
+}
+
+impl Importer for CsvImporter {
+    fn format(&self) -> ImportFormat {
+        ImportFormat::Csv
+    }
+
+    fn detect(&self, content: &str) -> bool {
+        let header_line = content.lines().next().unwrap_or_default();
+        parse_csv(header_line)
+            .first()
+            .is_some_and(|headers| field_indexes(headers).is_some())
+    }
+
+    fn parse(&self, content: &str) -> Result<ImportedRecords> {
+        let rows = parse_csv(content);
+        let (headers, rows) = rows
+            .split_first()
+            .ok_or_else(|| anyhow!("empty csv file"))?;
+        let [banner, star, name, item_type, time, account] = field_indexes(headers)
+            .ok_or_else(|| anyhow!("missing banner, star, name or time column"))?;
+        let mut imported = ImportedRecords::default();
+        for (i, row) in rows.iter().enumerate() {
+            let parse = || -> Result<_> {
+                let banner_type = parse_banner(cell(row, banner))?;
+                let record = parse_record(
+                    cell(row, star),
+                    cell(row, name),
+                    cell(row, item_type),
+                    cell(row, time),
+                )?;
+                Ok((banner_type, record))
+            };
+            // 表头是第 1 行
+            let (banner_type, record) = parse().with_context(|| format!("line {}", i + 2))?;
+            let account_id = Some(cell(row, account).trim().to_string())
+                .filter(|account_id| !account_id.is_empty());
+            imported.add(account_id, banner_type, record);
+        }
+        Ok(imported.finish())
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use crate::record::BannerType;
+
+    #[test]
+    fn test_csv() {
+        assert_eq!(
+            parse_csv("a,\"b,\"\"c\"\"\"\r\n\n1,\"x\ny\"\n"),
+            vec![vec!["a", "b,\"c\""], vec!["1", "x\ny"]]
+        );
+
+        // 本工具导出的 CSV
+        let content = "账号,卡池,品质,名称,类型,时间\n\
+                       123,常驻武器池,5,b,武器,2024-06-01 10:01\n\
+                       123,常驻武器池,3,a,武器,2024-06-01 10:00\n";
+        assert!(CsvImporter.detect(content));
+        let imported = CsvImporter.parse(content).unwrap();
+        let weapons = &imported.accounts[&Some("123".to_string())][&BannerType::PermanentWeapon];
+        assert_eq!(weapons.len(), 2);
+        assert_eq!(weapons[0].item_name, "b");
+
+        // 其他工具的英文表头，没有账号列
+        let content = "time,banner,rarity,name\n2024-06-01 10:00:00,beginner,4,Unknown\n";
+        assert!(CsvImporter.detect(content));
+        let error = CsvImporter.parse(content).unwrap_err();
+        assert!(format!("{error:#}").contains("line 2"));
+        assert!(!CsvImporter.detect("name,time\n"));
+    }
+}
@@ -0,0 +1,118 @@
+use anyhow::{Context, Result};
+use serde::Deserialize;
+use serde_json::Value;
+
+use super::{parse_banner, parse_record, ImportFormat, ImportedRecords, Importer};
+
+/// UIGF 风格的 JSON，`info.uid` 是账号ID，`list` 中每项是一条抽卡记录，
+/// 卡池写在 `gacha_type` 或 `banner` 中，星级写在 `rank_type` 中
+pub struct UigfImporter;
+
+#[derive(Debug, Default, Deserialize)]
+#[serde(default)]
+struct Info {
+    uid: Value,
+}
+
+#[derive(Debug, Deserialize)]
+struct Item {
+    #[serde(alias = "banner")]
+    gacha_type: Value,
+    name: String,
+    #[serde(default)]
+    item_type: String,
+    rank_type: Value,
+    time: Value,
+    /// 有的工具在每条记录中写账号ID
+    #[serde(default)]
+    uid: Value,
+}
+
+#[derive(Debug, Deserialize)]
+struct UigfFile {
+    #[serde(default)]
+    info: Info,
+    list: Vec<Item>,
+}
+
+/// 字符串或数字字段的文本，其他类型为空字符串
+fn text(value: &Value) -> String {
+    match value {
+        Value::String(text) => text.clone(),
+        Value::Number(number) => number.to_string(),
+        _ => String::new(),
+    }
+}
+
+/// 账号ID，为空时为 `None`
+fn account_id(value: &Value) -> Option<String> {
+    Some(text(value)).filter(|account_id| !account_id.is_empty())
+}
+
+impl Importer for UigfImporter {
+    fn format(&self) -> ImportFormat {
+        ImportFormat::UigfJson
+    }
+
+    fn detect(&self, content: &str) -> bool {
+        serde_json::from_str::<Value>(content)
+            .is_ok_and(|value| value.get("list").is_some_and(Value::is_array))
+    }
+
+    fn parse(&self, content: &str) -> Result<ImportedRecords> {
+        let file = serde_json::from_str::<UigfFile>(content)?;
+        let file_account_id = account_id(&file.info.uid);
+        let mut imported = ImportedRecords::default();
+        for (i, item) in file.list.iter().enumerate() {
+            let parse = || -> Result<_> {
+                let banner_type = parse_banner(&text(&item.gacha_type))?;
+                let record = parse_record(
+                    &text(&item.rank_type),
+                    &item.name,
+                    &item.item_type,
+                    &text(&item.time),
+                )?;
+                Ok((banner_type, record))
+            };
+            let (banner_type, record) = parse().with_context(|| format!("list[{i}]"))?;
+            let account_id = account_id(&item.uid).or(file_account_id.clone());
+            imported.add(account_id, banner_type, record);
+        }
+        Ok(imported.finish())
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use crate::record::{BannerType, ItemType};
+
+    #[test]
+    fn test_uigf() {
+        let content = r#"{
+            "info": {"uid": 10001, "export_app": "other tracker"},
+            "list": [
+                {"gacha_type": "PermanentWeapon", "name": "a", "item_type": "Weapon", "rank_type": "3", "time": "2024-06-01 10:00:00"},
+                {"gacha_type": "常驻武器池", "name": "b", "item_type": "武器", "rank_type": 5, "time": "2024-06-01 10:01:00"},
+                {"banner": "beginner", "name": "c", "item_type": "Operative", "rank_type": "4", "time": "2024-06-01 10:00:00"}
+            ]
+        }"#;
+        assert!(UigfImporter.detect(content));
+        assert!(!UigfImporter.detect("star,name"));
+        let imported = UigfImporter.parse(content).unwrap();
+        let account = &imported.accounts[&Some("10001".to_string())];
+        let weapons = &account[&BannerType::PermanentWeapon];
+        assert_eq!(weapons.len(), 2);
+        assert_eq!(weapons[0].item_name, "b");
+        assert_eq!(weapons[0].star, 5);
+        assert_eq!(
+            account[&BannerType::Beginner][0].item_type,
+            ItemType::Character
+        );
+
+        let invalid =
+            r#"{"list": [{"gacha_type": "x", "name": "a", "rank_type": "3", "time": "1"}]}"#;
+        let error = UigfImporter.parse(invalid).unwrap_err();
+        assert!(format!("{error:#}").contains("list[0]"));
+    }
+}
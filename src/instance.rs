@@ -0,0 +1,58 @@
+//! 单实例锁
+//!
+//! 同时运行两个实例时（例如以管理员权限重新运行后又打开了一次），两个实例会争抢鼠标，
+//! 同时写入 records.json。启动时创建命名互斥体，已经存在时说明有其他实例在运行。
+
+/// 单实例锁，释放后其他实例才能运行
+pub struct InstanceLock {
+    #[cfg(windows)]
+    handle: Option<windows::Win32::Foundation::HANDLE>,
+}
+
+impl Drop for InstanceLock {
+    fn drop(&mut self) {
+        #[cfg(windows)]
+        if let Some(handle) = self.handle.take() {
+            unsafe {
+                let _ = windows::Win32::Foundation::CloseHandle(handle);
+            }
+        }
+    }
+}
+
+/// 获取单实例锁
+/// # 返回
+/// 已经有其他实例在运行时返回 `None`
+#[cfg(windows)]
+pub fn acquire_instance_lock() -> Option<InstanceLock> {
+    use windows::Win32::Foundation::{
+        CloseHandle, GetLastError, BOOL, ERROR_ACCESS_DENIED, ERROR_ALREADY_EXISTS,
+    };
+    use windows::Win32::System::Threading::CreateMutexW;
+
+    // `Local\` 表示只在当前登录会话中唯一
+    let name = windows::core::w!("Local\\snowbreak_gacha_export");
+
+    unsafe {
+        match CreateMutexW(None, BOOL::from(false), name) {
+            Ok(handle) if GetLastError() == ERROR_ALREADY_EXISTS => {
+                let _ = CloseHandle(handle);
+                None
+            }
+            Ok(handle) => Some(InstanceLock {
+                handle: Some(handle),
+            }),
+            // 以管理员权限运行的实例创建的互斥体，普通权限的实例没有权限打开
+            Err(e) if e.code() == ERROR_ACCESS_DENIED.to_hresult() => None,
+            Err(e) => {
+                log::warn!("failed to create instance mutex: {:?}", e);
+                Some(InstanceLock { handle: None })
+            }
+        }
+    }
+}
+
+#[cfg(not(windows))]
+pub fn acquire_instance_lock() -> Option<InstanceLock> {
+    Some(InstanceLock {})
+}
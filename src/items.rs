@@ -0,0 +1,377 @@
+//! 物品字典
+//!
+//! 物品数据随游戏版本更新手动维护，另外从已有的抽卡记录中学习物品类型，
+//! 用于类型文本识别失败时根据物品名称推断物品类型。
+//! 游戏更新改名或调整译名后，旧名称记在物品的 `aliases` 中，去重和统计时视为同一个物品。
+//! 物品的属性和武器类型（`element`、`weapon_type`）可以导出为单独的列，也可以按分类统计数量，
+//! 字典中没有这些数据的物品不显示分类。
+
+use std::collections::HashMap;
+use std::sync::RwLock;
+
+use enum_iterator::Sequence;
+use lazy_static::lazy_static;
+use serde::Deserialize;
+
+use crate::i18n::translate;
+use crate::language::{detect_language, Language};
+use crate::normalize::normalize_text;
+use crate::record::{ItemType, TotalRecords};
+
+static ITEMS_JSON: &str = include_str!("../data/items.json");
+
+/// 一个物品
+#[derive(Debug, Clone, Deserialize)]
+pub struct ItemInfo {
+    /// 各语言的名称
+    pub names: Vec<String>,
+    pub item_type: ItemType,
+    pub star: u8,
+    /// 以前版本的名称
+    #[serde(default)]
+    pub aliases: Vec<String>,
+    /// 角色属性在各语言的名称
+    #[serde(default)]
+    pub element: Vec<String>,
+    /// 武器类型在各语言的名称，角色为使用的武器类型
+    #[serde(default)]
+    pub weapon_type: Vec<String>,
+}
+
+/// 物品的分类
+#[derive(Debug, Copy, Clone, PartialEq, Eq, Hash, Sequence)]
+pub enum ItemCategory {
+    /// 角色属性
+    Element,
+    /// 武器类型
+    WeaponType,
+}
+
+impl ItemCategory {
+    /// 分类的名称，也是导出时的表头
+    pub fn display_name(&self, language: Language) -> &'static str {
+        let key = match self {
+            ItemCategory::Element => "export.element",
+            ItemCategory::WeaponType => "export.weapon_type",
+        };
+        translate(language, key)
+    }
+}
+
+/// 指定语言的名称
+fn localized_name(names: &[String], language: Language) -> Option<&str> {
+    names
+        .iter()
+        .find(|name| detect_language([name.as_str()]) == Some(language))
+        .map(String::as_str)
+}
+
+impl ItemInfo {
+    /// 物品分类在指定语言中的名称，没有这种语言时使用第一个名称
+    /// # 返回
+    /// 字典中没有这个分类时返回 None
+    pub fn category(&self, category: ItemCategory, language: Language) -> Option<&str> {
+        let names = match category {
+            ItemCategory::Element => &self.element,
+            ItemCategory::WeaponType => &self.weapon_type,
+        };
+        localized_name(names, language).or(names.first().map(String::as_str))
+    }
+
+    /// 与旧名称同一种语言的现在的名称
+    fn current_name<'a>(&'a self, alias: &'a str) -> &'a str {
+        let language = detect_language([alias]);
+        self.names
+            .iter()
+            .find(|name| language.is_some() && detect_language([name.as_str()]) == language)
+            .or(self.names.first())
+            .map_or(alias, String::as_str)
+    }
+}
+
+#[derive(Debug, Clone, Deserialize)]
+struct RawItems {
+    items: Vec<ItemInfo>,
+}
+
+/// 物品表，按名称和旧名称查找物品
+#[derive(Debug, Clone, Default)]
+pub struct Items {
+    items: Vec<ItemInfo>,
+    /// 旧名称 -> 现在的名称
+    aliases: HashMap<String, String>,
+}
+
+impl Items {
+    pub fn new(items: Vec<ItemInfo>) -> Self {
+        let aliases = items
+            .iter()
+            .flat_map(|item| {
+                item.aliases
+                    .iter()
+                    .map(move |alias| (alias.clone(), item.current_name(alias).to_string()))
+            })
+            .collect();
+        Self { items, aliases }
+    }
+
+    /// 从 items.json 格式的数据中读取物品表
+    pub fn from_json(json: &str) -> serde_json::Result<Self> {
+        Ok(Self::new(serde_json::from_str::<RawItems>(json)?.items))
+    }
+
+    /// 按名称查找物品，也查找旧名称
+    pub fn find(&self, name: &str) -> Option<&ItemInfo> {
+        let name = self.canonical_name(name);
+        self.items.iter().find(|item| item.names.contains(&name))
+    }
+
+    /// 物品现在的名称，不是旧名称时返回规范化后的名称
+    /// 旧版本保存的名称没有经过 [`normalize_text`]，读取时也要规范化
+    pub fn canonical_name(&self, name: &str) -> String {
+        let name = normalize_text(name);
+        match self.aliases.get(&name) {
+            Some(current_name) => current_name.clone(),
+            None => name,
+        }
+    }
+
+    /// 物品在指定语言中的名称，也翻译旧名称
+    /// 物品表中没有这个物品或者没有这种语言的名称时原样返回
+    pub fn translate<'a>(&'a self, name: &'a str, language: Language) -> &'a str {
+        let Some(item) = self.find(name) else {
+            return name;
+        };
+        localized_name(&item.names, language).unwrap_or(name)
+    }
+
+    /// 物品分类在指定语言中的名称，也查找旧名称，见 [`ItemInfo::category`]
+    pub fn category(&self, name: &str, category: ItemCategory, language: Language) -> Option<&str> {
+        self.find(name)?.category(category, language)
+    }
+}
+
+lazy_static! {
+    /// data/items.json 中的物品
+    static ref ITEMS: Items = Items::from_json(ITEMS_JSON).expect("invalid items.json");
+    /// 从抽卡记录中学到的（物品名称, 星级） -> 物品类型
+    static ref LEARNED_ITEM_TYPES: RwLock<HashMap<(String, u8), ItemType>> =
+        RwLock::new(HashMap::new());
+}
+
+/// 在物品字典中按名称查找物品，也查找旧名称
+pub fn find_item(name: &str) -> Option<&'static ItemInfo> {
+    ITEMS.find(name)
+}
+
+/// 物品字典中物品现在的名称，见 [`Items::canonical_name`]
+pub fn canonical_name(name: &str) -> String {
+    ITEMS.canonical_name(name)
+}
+
+/// 按物品字典翻译物品名称，见 [`Items::translate`]
+pub fn translate_item_name(name: &str, language: Language) -> &str {
+    ITEMS.translate(name, language)
+}
+
+/// 按物品字典查找物品分类，见 [`Items::category`]
+pub fn item_category(
+    name: &str,
+    category: ItemCategory,
+    language: Language,
+) -> Option<&'static str> {
+    ITEMS.category(name, category, language)
+}
+
+/// 根据物品名称和星级推断物品类型，先查物品字典，再查学到的物品
+/// 星级来自星级颜色，与字典中的星级不一致时说明名称识别有误，不做推断
+pub fn item_type_by_name(name: &str, star: u8) -> Option<ItemType> {
+    match find_item(name) {
+        Some(item) if item.star == star => Some(item.item_type),
+        Some(_) => None,
+        None => LEARNED_ITEM_TYPES
+            .read()
+            .unwrap()
+            .get(&(name.to_string(), star))
+            .copied(),
+    }
+}
+
+/// 物品字典和学到的物品类型的快照，解析截图时按名称推断物品类型，不读取全局状态
+/// 默认为空，不做推断
+#[derive(Debug, Clone, Default)]
+pub struct ItemTypes {
+    /// 字典中物品的名称和旧名称 -> (星级, 物品类型)
+    known: HashMap<String, (u8, ItemType)>,
+    /// 从抽卡记录中学到的（物品名称, 星级） -> 物品类型
+    learned: HashMap<(String, u8), ItemType>,
+}
+
+impl ItemTypes {
+    /// 物品表中的物品和学到的物品类型
+    pub fn new(items: &Items, learned: HashMap<(String, u8), ItemType>) -> Self {
+        let known = items
+            .items
+            .iter()
+            .flat_map(|item| {
+                item.names
+                    .iter()
+                    .chain(&item.aliases)
+                    .map(move |name| (name.clone(), (item.star, item.item_type)))
+            })
+            .collect();
+        Self { known, learned }
+    }
+
+    /// 当前的物品字典和学到的物品类型
+    pub fn snapshot() -> Self {
+        Self::new(&ITEMS, LEARNED_ITEM_TYPES.read().unwrap().clone())
+    }
+
+    /// 根据物品名称和星级推断物品类型，规则同 [`item_type_by_name`]
+    pub fn get(&self, name: &str, star: u8) -> Option<ItemType> {
+        match self.known.get(name) {
+            Some(&(item_star, item_type)) if item_star == star => Some(item_type),
+            Some(_) => None,
+            None => self.learned.get(&(name.to_string(), star)).copied(),
+        }
+    }
+}
+
+/// 从已有的抽卡记录中学习物品类型
+pub fn learn_item_types(total_records: &TotalRecords) {
+    let mut learned_item_types = LEARNED_ITEM_TYPES.write().unwrap();
+    for account_records in total_records.records.values() {
+        for record in account_records.records.values().flatten() {
+            learned_item_types
+                .entry((record.item_name.clone(), record.star))
+                .or_insert(record.item_type);
+        }
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    /// 改过名的测试物品，与真实的物品字典分开
+    static TEST_ITEMS_JSON: &str = r#"{"items": [
+        {"names": ["测试角色·新名", "Test New"], "item_type": "Character", "star": 5, "aliases": ["测试角色·旧名", "Test Old"]},
+        {"names": ["测试武器", "Test Weapon"], "item_type": "Weapon", "star": 4, "weapon_type": ["霰弹枪", "Shotgun"]}
+    ]}"#;
+
+    fn test_items() -> Items {
+        Items::from_json(TEST_ITEMS_JSON).unwrap()
+    }
+
+    #[test]
+    fn test_aliases() {
+        let item: ItemInfo = serde_json::from_str(
+            r#"{"names": ["新名称", "New Name"], "item_type": "Weapon", "star": 5, "aliases": ["旧名称", "Old Name"]}"#,
+        )
+        .unwrap();
+        assert_eq!(item.current_name("旧名称"), "新名称");
+        assert_eq!(item.current_name("Old Name"), "New Name");
+        assert_eq!(canonical_name("not an alias"), "not an alias");
+    }
+
+    #[test]
+    fn test_renamed_items() {
+        let items = test_items();
+        assert_eq!(items.canonical_name("测试角色·旧名"), "测试角色·新名");
+        assert_eq!(items.canonical_name("Test Old"), "Test New");
+        // 旧版本保存的名称没有规范化
+        assert_eq!(items.canonical_name("测试角色・旧名"), "测试角色·新名");
+        assert_eq!(items.canonical_name("测试武器"), "测试武器");
+        let item = items.find("测试角色·旧名").unwrap();
+        assert_eq!(item.names[0], "测试角色·新名");
+        assert!(items.find("not an item").is_none());
+        // 测试物品不在真实的物品字典中
+        assert!(find_item("测试角色·旧名").is_none());
+    }
+
+    #[test]
+    fn test_translate_item_name() {
+        assert_eq!(
+            translate_item_name("里芙·无限之视", Language::English),
+            "Lyfe - Wild Hunt"
+        );
+        assert_eq!(
+            translate_item_name("Lyfe - Wild Hunt", Language::ChineseSimplified),
+            "里芙·无限之视"
+        );
+        assert_eq!(
+            translate_item_name("里芙·无限之视", Language::ChineseSimplified),
+            "里芙·无限之视"
+        );
+        assert_eq!(
+            translate_item_name("not an item", Language::English),
+            "not an item"
+        );
+        // 旧名称翻译为另一种语言现在的名称
+        let items = test_items();
+        assert_eq!(
+            items.translate("测试角色·旧名", Language::English),
+            "Test New"
+        );
+        assert_eq!(
+            items.translate("Test Old", Language::ChineseSimplified),
+            "测试角色·新名"
+        );
+    }
+
+    #[test]
+    fn test_item_category() {
+        let items = test_items();
+        assert_eq!(
+            items.category(
+                "Test Weapon",
+                ItemCategory::WeaponType,
+                Language::ChineseSimplified
+            ),
+            Some("霰弹枪")
+        );
+        assert_eq!(
+            items.category("测试武器", ItemCategory::WeaponType, Language::English),
+            Some("Shotgun")
+        );
+        // 字典中没有分类或者没有这个物品
+        assert_eq!(
+            items.category("测试武器", ItemCategory::Element, Language::English),
+            None
+        );
+        assert_eq!(
+            items.category("not an item", ItemCategory::WeaponType, Language::English),
+            None
+        );
+    }
+
+    #[test]
+    fn test_item_type_by_name() {
+        assert_eq!(
+            item_type_by_name("Lyfe - Wild Hunt", 5),
+            Some(ItemType::Character)
+        );
+        assert_eq!(item_type_by_name("Lyfe - Wild Hunt", 4), None);
+        assert_eq!(item_type_by_name("not an item", 5), None);
+
+        let item_types = ItemTypes::snapshot();
+        assert_eq!(
+            item_types.get("Lyfe - Wild Hunt", 5),
+            Some(ItemType::Character)
+        );
+        assert_eq!(item_types.get("Lyfe - Wild Hunt", 4), None);
+        assert_eq!(ItemTypes::default().get("Lyfe - Wild Hunt", 5), None);
+
+        // 旧名称和学到的物品
+        let learned = HashMap::from([(("learned".to_string(), 3), ItemType::Weapon)]);
+        let item_types = ItemTypes::new(&test_items(), learned);
+        assert_eq!(
+            item_types.get("测试角色·旧名", 5),
+            Some(ItemType::Character)
+        );
+        assert_eq!(item_types.get("测试武器", 4), Some(ItemType::Weapon));
+        assert_eq!(item_types.get("learned", 3), Some(ItemType::Weapon));
+        assert_eq!(item_types.get("learned", 4), None);
+    }
+}
@@ -1,9 +1,11 @@
+use std::cmp::Reverse;
 use std::fmt::Display;
 
-use enum_iterator::Sequence;
+use clap::ValueEnum;
+use enum_iterator::{all, Sequence};
 use serde::{Deserialize, Serialize};
 
-#[derive(Debug, Copy, Clone, PartialEq, Eq, Hash, Serialize, Deserialize, Sequence)]
+#[derive(Debug, Copy, Clone, PartialEq, Eq, Hash, Serialize, Deserialize, Sequence, ValueEnum)]
 pub enum Language {
     /// 简体中文
     ChineseSimplified,
@@ -11,6 +13,37 @@ pub enum Language {
     English,
 }
 
+impl Language {
+    /// 文本是否像这种语言：中文含有汉字，英文只有拉丁字母
+    fn matches(&self, text: &str) -> bool {
+        let has_cjk = text.chars().any(|c| ('\u{4e00}'..='\u{9fff}').contains(&c));
+        match self {
+            Language::ChineseSimplified => has_cjk,
+            Language::English => !has_cjk && text.chars().any(|c| c.is_ascii_alphabetic()),
+        }
+    }
+}
+
+/// 根据识别出的文本判断游戏客户端的语言，取匹配文本最多的语言
+/// # 返回
+/// 没有可以判断的文本或者不同语言的文本一样多时返回 `None`
+pub fn detect_language<'a>(texts: impl IntoIterator<Item = &'a str>) -> Option<Language> {
+    let texts = texts.into_iter().collect::<Vec<_>>();
+    let mut counts = all::<Language>()
+        .map(|language| {
+            let count = texts.iter().filter(|text| language.matches(text)).count();
+            (language, count)
+        })
+        .filter(|(_, count)| *count > 0)
+        .collect::<Vec<_>>();
+    counts.sort_by_key(|(_, count)| Reverse(*count));
+    match counts.as_slice() {
+        [(language, _)] => Some(*language),
+        [(language, count), (_, second), ..] if count > second => Some(*language),
+        _ => None,
+    }
+}
+
 impl Display for Language {
     fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
         match self {
@@ -19,3 +52,22 @@ impl Display for Language {
         }
     }
 }
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    #[test]
+    fn test_detect_language() {
+        assert_eq!(
+            detect_language(["里芙·无限之视", "Weapon", "深海"]),
+            Some(Language::ChineseSimplified)
+        );
+        assert_eq!(
+            detect_language(["Lyfe - Wild Hunt", "Operative"]),
+            Some(Language::English)
+        );
+        assert_eq!(detect_language(["里芙", "Lyfe"]), None);
+        assert_eq!(detect_language(["", "123"]), None);
+    }
+}
@@ -0,0 +1,64 @@
+//! 尘白禁区抽卡记录导出工具
+//!
+//! 命令行程序在 `main.rs` 中，这里的模块也供基准测试使用。
+
+pub mod action;
+pub mod banner_schedule;
+pub mod bundle;
+pub mod capture;
+pub mod chart;
+pub mod cli;
+pub mod clipboard;
+pub mod command;
+pub mod config;
+pub mod data_dir;
+pub mod debug_boxes;
+pub mod diff;
+pub mod elevation;
+pub mod error;
+pub mod export;
+pub mod game_info;
+pub mod hook;
+pub mod i18n;
+pub mod import;
+pub mod importer;
+pub mod instance;
+pub mod items;
+pub mod language;
+pub mod logging;
+pub mod menu;
+pub mod navigation;
+pub mod normalize;
+pub mod notification;
+pub mod ocr;
+pub mod ocr_pool;
+pub mod open_report;
+pub mod paddle_ocr;
+pub mod panic_hook;
+pub mod phash;
+pub mod plan;
+pub mod preprocess;
+pub mod progress;
+pub mod rarity_detector;
+pub mod rates;
+pub mod reconcile;
+pub mod record;
+pub mod record_image;
+pub mod report;
+pub mod review;
+pub mod run_result;
+pub mod save;
+#[cfg(feature = "schema")]
+pub mod schema;
+pub mod screen;
+pub mod search;
+pub mod serve;
+pub mod simulate;
+pub mod stats;
+#[cfg(feature = "sync")]
+pub mod sync;
+pub mod table;
+pub mod update;
+pub mod user_interaction;
+pub mod watchdog;
+pub mod wizard;
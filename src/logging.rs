@@ -0,0 +1,102 @@
+//! 日志
+//!
+//! 日志同时写入控制台和数据目录中的 `logs/snowbreak_gacha_export.log`，级别由配置中的
+//! `log_level` 或 `--log-level` 决定，设置了环境变量 `RUST_LOG` 时以环境变量为准。
+//! 安静模式（`quiet`、`--quiet`）下控制台不显示日志，只显示翻页进度和导入结果，日志只写入文件。
+
+use std::fs::{File, OpenOptions};
+use std::io::{stderr, Write};
+use std::path::PathBuf;
+use std::sync::atomic::{AtomicBool, Ordering};
+
+use clap::ValueEnum;
+use enum_iterator::Sequence;
+use log::LevelFilter;
+use serde::{Deserialize, Serialize};
+
+use crate::data_dir::data_path;
+
+/// 日志级别
+#[derive(Debug, Copy, Clone, PartialEq, Eq, Serialize, Deserialize, Sequence, ValueEnum)]
+pub enum LogLevel {
+    Error,
+    Warn,
+    Info,
+    Debug,
+    Trace,
+}
+
+impl LogLevel {
+    pub fn level_filter(&self) -> LevelFilter {
+        match self {
+            LogLevel::Error => LevelFilter::Error,
+            LogLevel::Warn => LevelFilter::Warn,
+            LogLevel::Info => LevelFilter::Info,
+            LogLevel::Debug => LevelFilter::Debug,
+            LogLevel::Trace => LevelFilter::Trace,
+        }
+    }
+}
+
+/// 是否是安静模式
+static QUIET: AtomicBool = AtomicBool::new(false);
+
+/// 安静模式下不在控制台输出统计等详细信息
+pub fn is_quiet() -> bool {
+    QUIET.load(Ordering::Relaxed)
+}
+
+/// 日志文件路径
+pub fn log_path() -> PathBuf {
+    data_path("logs/snowbreak_gacha_export.log")
+}
+
+/// 把日志写入文件，不是安静模式时同时写入控制台
+struct LogWriter {
+    file: Option<File>,
+    console: bool,
+}
+
+impl Write for LogWriter {
+    fn write(&mut self, buf: &[u8]) -> std::io::Result<usize> {
+        if let Some(file) = &mut self.file {
+            // 日志文件写入失败时不影响控制台输出
+            let _ = file.write_all(buf);
+        }
+        if self.console {
+            stderr().write_all(buf)?;
+        }
+        Ok(buf.len())
+    }
+
+    fn flush(&mut self) -> std::io::Result<()> {
+        if let Some(file) = &mut self.file {
+            let _ = file.flush();
+        }
+        stderr().flush()
+    }
+}
+
+fn open_log_file() -> Option<File> {
+    let path = log_path();
+    if let Some(parent) = path.parent() {
+        std::fs::create_dir_all(parent).ok()?;
+    }
+    OpenOptions::new().create(true).append(true).open(path).ok()
+}
+
+/// 初始化日志
+pub fn init_logger(level: LogLevel, quiet: bool) {
+    QUIET.store(quiet, Ordering::Relaxed);
+    let writer = LogWriter {
+        file: open_log_file(),
+        console: !quiet,
+    };
+    let default_filter = format!(
+        "snowbreak_gacha_export={}",
+        level.level_filter().as_str().to_lowercase()
+    );
+    env_logger::Builder::from_env(env_logger::Env::default().default_filter_or(default_filter))
+        .target(env_logger::Target::Pipe(Box::new(writer)))
+        .init();
+}
@@ -1,195 +1,206 @@
+use std::collections::HashMap;
+use std::io::{stdin, IsTerminal};
+use std::process::ExitCode;
 use std::time::Duration;
-use std::time::Instant;
 
 use admin_runner::is_admin;
 use admin_runner::run_as_admin;
+use anyhow::anyhow;
+use clap::Parser;
 use tokio::time::sleep;
-use window_inspector::top_most::cancel_window_top_most;
-use window_inspector::top_most::set_window_top_most;
 
-use crate::action::{next_page, previous_page};
-use crate::capture::{capture_image, init_capture, release_capture};
-use crate::config::CONFIG;
-use crate::game_info::get_game_window_info;
-use crate::language::Language;
-use crate::record::TotalRecords;
-use crate::record_image::RecordImage;
-use crate::save::save_excel;
-use crate::update::is_up_to_date;
-use crate::user_interaction::{account_id, banner_type, wait_enter};
-
-mod action;
-mod capture;
-mod config;
-mod game_info;
-mod language;
-mod record;
-mod record_image;
-mod save;
-mod update;
-mod user_interaction;
+use snowbreak_gacha_export::cli::{Cli, Command};
+use snowbreak_gacha_export::command::run_command;
+use snowbreak_gacha_export::config::{config, Config};
+use snowbreak_gacha_export::debug_boxes;
+use snowbreak_gacha_export::elevation::needs_elevation;
+use snowbreak_gacha_export::error::ExportError;
+use snowbreak_gacha_export::export::ExportOptions;
+use snowbreak_gacha_export::game_info::get_game_window_info;
+use snowbreak_gacha_export::i18n::{self, tr};
+use snowbreak_gacha_export::import::import_records;
+use snowbreak_gacha_export::instance::acquire_instance_lock;
+use snowbreak_gacha_export::logging::init_logger;
+use snowbreak_gacha_export::menu::{import_plan, main_menu};
+use snowbreak_gacha_export::notification::{notify_export_failed, notify_export_finished};
+use snowbreak_gacha_export::ocr_pool;
+use snowbreak_gacha_export::panic_hook;
+use snowbreak_gacha_export::record::BannerType;
+use snowbreak_gacha_export::run_result::{Failure, FailureKind, RunResult};
+use snowbreak_gacha_export::update::is_up_to_date;
+use snowbreak_gacha_export::user_interaction::wait_enter;
+use snowbreak_gacha_export::wizard::run_first_run_wizard;
 
 #[tokio::main]
-async fn main() {
-    env_logger::Builder::from_env(
-        env_logger::Env::default().default_filter_or("snowbreak_gacha_export=info"),
-    )
-    .init();
-
-    let language = CONFIG.language;
-
-    // 检查更新
-    match is_up_to_date().await {
-        Ok((is_up_to_date, latest_version)) => {
-            if !is_up_to_date {
-                log::warn!("New version available: {}", latest_version);
-                let tip = match language {
-                    Language::ChineseSimplified => "有新版本，请前往 https://github.com/enximi/snowbreak_gacha_export/releases 更新",
-                    Language::English => "New version available, please update in https://github.com/enximi/snowbreak_gacha_export/releases",
-                };
-                println!("{}", tip);
-            } else {
-                log::info!("Already up to date, version: {}", env!("CARGO_PKG_VERSION"));
-            }
-        }
-        Err(_) => {
-            log::error!("Failed to check update");
-        }
+async fn main() -> ExitCode {
+    let cli = Cli::parse();
+    // 读取配置时会创建配置文件，需要先判断是否首次运行
+    let first_run = !Config::is_config_file_exists();
+    let config = config();
+    init_logger(
+        cli.log_level.unwrap_or(config.log_level),
+        cli.quiet || config.quiet,
+    );
+    // 双击运行时崩溃后保持窗口打开，用户可以看到提示
+    panic_hook::install(!cli.non_interactive && cli.command.is_none() && stdin().is_terminal());
+    if cli.debug_boxes {
+        debug_boxes::enable();
     }
 
-    // 用户提示
-    let tip = match language {
-        Language::ChineseSimplified => {
-            "仅支持 16:9 窗口化/无边框\n先打开抽卡记录界面，后运行本程序"
-        }
-        Language::English => {
-            "Only support 16:9 windowed/borderless\nOpen the gacha record interface first, then run this program"
-        }
-    };
-    println!("{}", tip);
+    let exit_code = run(cli, first_run).await;
+    // 等待 OCR 工作线程结束，不留下还在运行的线程
+    ocr_pool::shutdown();
+    exit_code
+}
 
-    // 管理员权限
-    if is_admin() {
-        log::info!("run as admin");
+async fn run(cli: Cli, first_run: bool) -> ExitCode {
+    let config = config();
+    i18n::set_language(config.language);
+
+    // 两个实例会争抢鼠标、同时写入抽卡记录，只允许运行一个实例
+    // 本地统计页面只读取抽卡记录，不获取锁，页面打开时仍然可以导入
+    let instance_lock = if matches!(cli.command, Some(Command::Serve { .. })) {
+        None
     } else {
-        log::warn!("not run as admin, rerun as admin");
-        match run_as_admin() {
-            Ok(_) => {
-                log::info!("rerun as admin successfully");
-                println!("exit in 3s");
-                sleep(Duration::from_secs(3)).await;
-                return;
-            }
-            Err(e) => {
-                log::error!("rerun as admin failed: {:?}", e);
-                wait_enter(language);
-                return;
+        let Some(instance_lock) = acquire_instance_lock() else {
+            let error = ExportError::AlreadyRunning;
+            log::error!("{error}");
+            println!("{}", error.hint());
+            if !cli.non_interactive && cli.command.is_none() {
+                wait_enter();
             }
+            return ExitCode::from(error.exit_code());
         };
-    }
-
-    // 获取游戏窗口
-    let (hwnd, window_title) = match get_game_window_info() {
-        Ok((hwnd, title)) => {
-            log::info!("window title: {title}");
-            (hwnd, title)
-        }
-        Err(e) => {
-            log::error!("failed to get game window info: {:?}", e);
-            wait_enter(language);
-            return;
-        }
+        Some(instance_lock)
     };
 
-    // 读取或初始化记录
-    let mut total_record = TotalRecords::read_or_default();
-
-    let account_ids = total_record.records.keys().cloned().collect::<Vec<_>>();
-    let account_id = account_id(language, account_ids);
-    log::info!("account id: {account_id}");
-
-    // 选择卡池类型
-    let user_selected_banner_type = banner_type(language);
-    log::info!("banner type: {:?}", user_selected_banner_type);
-
-    // 游戏窗口置顶
-    set_window_top_most(hwnd).unwrap();
-    cancel_window_top_most(hwnd).unwrap();
-
-    // 创建截图工具
-    init_capture(window_title);
-
-    let mut record_images = vec![];
-
-    // 获取第一个界面，如果不是第一个界面，回到第一个界面
-    let image = capture_image().unwrap();
-    let record_image = RecordImage::new(image);
-    if record_image.is_record_image() {
-        // 回到第一个界面
-        let start = Instant::now();
-        let mut record_image = record_image.clone();
-        let mut index = record_image.index().unwrap();
-        log::debug!("index: {}", index);
-        while index != 1 {
-            previous_page(hwnd);
-            sleep(Duration::from_millis(200)).await;
-            let image = capture_image().unwrap();
-            record_image = RecordImage::new(image);
-            index = record_image.index().unwrap();
-            log::debug!("index: {}", index);
-            if start.elapsed().as_secs_f32() > 15.0 {
-                log::error!("Failed to back to the first record image");
-                wait_enter(language);
-                return;
+    // 检查更新
+    if config.check_update {
+        match is_up_to_date().await {
+            Ok((is_up_to_date, latest_version)) => {
+                if !is_up_to_date {
+                    log::warn!("New version available: {}", latest_version);
+                    println!("{}", tr!("new_version_available"));
+                } else {
+                    log::info!("Already up to date, version: {}", env!("CARGO_PKG_VERSION"));
+                }
+            }
+            Err(_) => {
+                log::error!("Failed to check update");
             }
         }
-        record_images.push(record_image);
-    } else {
-        log::error!("not in the record interface");
-        wait_enter(language);
-        return;
     }
 
-    let mut now_index = 1;
-    loop {
-        next_page(hwnd);
-        sleep(Duration::from_millis(200)).await;
-        let image = capture_image().unwrap();
-        let record_image = RecordImage::new(image);
-        if record_image.index().unwrap() == now_index + 1 {
-            record_images.push(record_image);
-            now_index += 1;
-        } else {
-            break;
-        }
+    if let Some(command) = &cli.command {
+        return run_command(command);
     }
 
-    // 停止截图，释放资源
-    release_capture();
-
-    log::debug!("record_screens.len(): {}", record_images.len());
-
-    log::info!("ocring...");
-    let start = Instant::now();
-    let records = record_images
-        .into_iter()
-        .flat_map(|record_image| record_image.records())
-        .collect::<Vec<_>>();
-    log::info!("ocr spend: {:?}", start.elapsed());
+    // 用户提示
+    println!("{}", tr!("usage_tip"));
 
-    match total_record.add_record(account_id.to_string(), user_selected_banner_type, records) {
-        Ok(add_num) => {
-            log::info!("add {} records", add_num);
+    if cli.non_interactive {
+        run_non_interactive(&cli).await
+    } else {
+        if first_run && stdin().is_terminal() {
+            run_first_run_wizard();
+        }
+        // 只有游戏以管理员权限运行时才需要管理员权限
+        let hwnd = get_game_window_info().ok().map(|(hwnd, _)| hwnd);
+        if needs_elevation(hwnd) {
+            log::warn!("the game is running as admin, rerun as admin");
+            // 以管理员权限运行的实例需要获取单实例锁
+            drop(instance_lock);
+            match run_as_admin() {
+                Ok(_) => {
+                    log::info!("rerun as admin successfully");
+                    println!("exit in 3s");
+                    sleep(Duration::from_secs(3)).await;
+                }
+                Err(e) => {
+                    log::error!("rerun as admin failed: {:?}", e);
+                    wait_enter();
+                }
+            };
+            return ExitCode::SUCCESS;
         }
-        Err(e) => {
-            log::error!("failed to add records: {:?}", e);
-            wait_enter(language);
-            return;
+        log::info!("run as admin: {}", is_admin());
+        if cli.banner_all {
+            import_plan(cli.account.clone()).await;
+        } else {
+            main_menu().await;
         }
+        ExitCode::SUCCESS
     }
-    total_record.save().unwrap();
+}
 
-    save_excel(total_record, language);
+/// 非交互模式，导入一次抽卡记录后写入 result.json
+async fn run_non_interactive(cli: &Cli) -> ExitCode {
+    let run_result = match import_from_cli(cli).await {
+        Ok((added, warnings)) => {
+            let add_num = added.values().sum();
+            for warning in warnings.iter() {
+                log::warn!("{warning}");
+            }
+            notify_export_finished(add_num, warnings.len() as u32);
+            RunResult::success(added, warnings)
+        }
+        Err(failure) => {
+            log::error!("{:?}: {:?}", failure.kind, failure.error);
+            notify_export_failed(&failure.error.to_string());
+            RunResult::failure(&failure)
+        }
+    };
+    if let Err(e) = run_result.save() {
+        log::error!("failed to save result: {:?}", e);
+    }
+    ExitCode::from(run_result.exit_code)
+}
 
-    wait_enter(language);
+/// # 返回
+/// （卡池 -> 新增抽卡记录数量, 警告）
+async fn import_from_cli(cli: &Cli) -> Result<(HashMap<BannerType, u32>, Vec<String>), Failure> {
+    let config = config();
+    let account_id = cli
+        .account
+        .as_ref()
+        .or(config.default_account_id.as_ref())
+        .ok_or_else(|| {
+            Failure::new(
+                FailureKind::Other,
+                anyhow!("--account is required in non-interactive mode"),
+            )
+        })?;
+    let banner_type = cli.banner.ok_or_else(|| {
+        Failure::new(
+            FailureKind::Other,
+            anyhow!("--banner is required in non-interactive mode"),
+        )
+    })?;
+    log::info!("account id: {account_id}");
+    log::info!("banner type: {:?}", banner_type);
+    let import_mode = cli.import_mode.unwrap_or(config.import_mode);
+    let formats = if cli.format.is_empty() {
+        config.export_formats
+    } else {
+        cli.format.clone()
+    };
+    let export_options = ExportOptions::from_config();
+    let export_options = ExportOptions {
+        language: cli.export_language.unwrap_or(export_options.language),
+        split_per_banner: cli.split_per_banner || config.export_split_per_banner,
+        translate_item_names: cli.translate_item_names || export_options.translate_item_names,
+        ..export_options
+    };
+    let import_summary = import_records(
+        account_id,
+        banner_type,
+        import_mode,
+        &formats,
+        &export_options,
+    )
+    .await?;
+    Ok((
+        HashMap::from([(banner_type, import_summary.added)]),
+        import_summary.warnings(),
+    ))
 }
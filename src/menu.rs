@@ -0,0 +1,789 @@
+use std::time::Duration;
+
+use enum_iterator::{all, Sequence};
+
+use crate::chart::print_charts;
+use crate::clipboard::import_from_clipboard;
+use crate::config::{config, update_config};
+use crate::export::{export_all, ExportFormat, ExportOptions};
+use crate::game_info::get_game_window_info;
+use crate::hook::{run_post_export_hook, ExportSummary};
+use crate::i18n::{self, tr};
+use crate::import::{
+    calibrate_star_colors, import_records, wait_for_game, ImportMode, ImportSummary,
+};
+use crate::items::canonical_name;
+use crate::language::Language;
+use crate::logging::is_quiet;
+use crate::notification::{notify_export_failed, notify_export_finished};
+use crate::ocr::OcrBackendKind;
+use crate::open_report::offer_to_open_report;
+use crate::paddle_ocr::{find_engine, RELEASES_URL};
+use crate::plan::ImportPlan;
+use crate::reconcile::{reconcile, stored_pulls, Reconciliation};
+use crate::record::{BannerType, ItemType, OneRecord, Provenance, TotalRecords};
+use crate::record_image::parse_timestamp;
+use crate::review::{PendingRecord, PendingReview};
+use crate::run_result::Failure;
+use crate::stats::{item_counts, luck, period_pulls, Period};
+use crate::table::{align_labels, Table};
+use crate::user_interaction::{
+    account_id, banner_type, confirm, input_line, language, print_category_counts,
+    print_guarantee_states, print_invalid_input, print_item_counts, print_luck, print_period_pulls,
+    print_remediation_hint, select_index,
+};
+
+/// 主菜单功能
+#[derive(Debug, Copy, Clone, PartialEq, Eq, Sequence)]
+enum MenuItem {
+    /// 导入抽卡记录
+    Import,
+    /// 按计划导入多个卡池
+    ImportPlan,
+    /// 从剪贴板中的截图导入
+    ImportClipboard,
+    /// 撤销最近一次导入
+    UndoImport,
+    /// 审核识别失败的记录
+    ReviewPending,
+    /// 查看统计
+    ViewStats,
+    /// 导出
+    Export,
+    /// 管理账号
+    ManageAccounts,
+    /// 设置
+    Settings,
+    /// 修复数据
+    RepairData,
+    /// 退出
+    Exit,
+}
+
+impl MenuItem {
+    fn display_name(&self) -> &'static str {
+        match self {
+            MenuItem::Import => tr!("menu.import"),
+            MenuItem::ImportPlan => tr!("menu.import_plan"),
+            MenuItem::ImportClipboard => tr!("menu.import_clipboard"),
+            MenuItem::UndoImport => tr!("menu.undo_import"),
+            MenuItem::ReviewPending => tr!("menu.review_pending"),
+            MenuItem::ViewStats => tr!("menu.view_stats"),
+            MenuItem::Export => tr!("menu.export"),
+            MenuItem::ManageAccounts => tr!("menu.manage_accounts"),
+            MenuItem::Settings => tr!("menu.settings"),
+            MenuItem::RepairData => tr!("menu.repair_data"),
+            MenuItem::Exit => tr!("menu.exit"),
+        }
+    }
+}
+
+/// 主菜单，循环直到用户选择退出
+pub async fn main_menu() {
+    loop {
+        println!();
+        let options = all::<MenuItem>()
+            .map(|item| item.display_name().to_string())
+            .collect::<Vec<_>>();
+        let index = select_index(tr!("menu.title"), &options);
+        match all::<MenuItem>().nth(index).unwrap() {
+            MenuItem::Import => import().await,
+            MenuItem::ImportPlan => import_plan(None).await,
+            MenuItem::ImportClipboard => import_clipboard().await,
+            MenuItem::UndoImport => undo_import(),
+            MenuItem::ReviewPending => review_pending(),
+            MenuItem::ViewStats => view_stats(),
+            MenuItem::Export => export(),
+            MenuItem::ManageAccounts => manage_accounts(),
+            MenuItem::Settings => settings(),
+            MenuItem::RepairData => repair_data(),
+            MenuItem::Exit => return,
+        }
+    }
+}
+
+fn save_records(total_records: &TotalRecords) {
+    if let Err(e) = total_records.save() {
+        log::error!("failed to save records: {:?}", e);
+        println!("{}", tr!("save_failed", error = e));
+        print_remediation_hint(&e);
+    }
+}
+
+async fn import() {
+    let total_records = TotalRecords::read_or_default();
+    let account_ids = total_records.records.keys().cloned().collect::<Vec<_>>();
+    let account_id = account_id(account_ids);
+    log::info!("account id: {account_id}");
+    let banner_type = banner_type();
+    log::info!("banner type: {:?}", banner_type);
+    if !wait_for_game_if_needed().await {
+        return;
+    }
+    import_banner(&account_id, banner_type).await;
+}
+
+/// 游戏没有运行时询问是否等待
+/// # 返回
+/// 游戏是否已经就绪
+async fn wait_for_game_if_needed() -> bool {
+    if get_game_window_info().is_ok() {
+        return true;
+    }
+    if !confirm(tr!("game_not_running_wait")) {
+        return false;
+    }
+    match wait_for_game(Duration::from_secs(config().wait_for_game_secs)).await {
+        Ok(()) => true,
+        Err(e) => {
+            log::error!("{:?}", e);
+            println!("{}", tr!("wait_for_game_timeout"));
+            false
+        }
+    }
+}
+
+/// 先显示导入计划，用户确认后依次导入选中的卡池
+/// # 参数
+/// - `account_id`: 命令行 `--banner-all` 指定的账号ID，为 None 时让用户选择
+pub async fn import_plan(account_id: Option<String>) {
+    let total_records = TotalRecords::read_or_default();
+    let account_id = account_id.unwrap_or_else(|| {
+        let account_ids = total_records.records.keys().cloned().collect::<Vec<_>>();
+        self::account_id(account_ids)
+    });
+    log::info!("account id: {account_id}");
+    let config = config();
+    let mut plan = ImportPlan::new(
+        total_records.records.get(&account_id),
+        Duration::from_millis(config.capture_delay_ms),
+    );
+    if let Some(banner_types) = config.account_banners.get(&account_id) {
+        log::info!("banners of account {account_id}: {:?}", banner_types);
+        plan.select_only(banner_types);
+        println!("{}", tr!("plan.account_banners"));
+    }
+    loop {
+        println!();
+        println!("{}", tr!("plan.title"));
+        for (i, banner) in plan.banners.iter().enumerate() {
+            let banner_name = banner.banner_type.display_name_for_user(i18n::language());
+            println!(
+                "{}. [{}] {}",
+                i + 1,
+                if banner.selected { "x" } else { " " },
+                if banner.has_records {
+                    tr!(
+                        "plan.banner",
+                        banner = banner_name,
+                        pages = banner.estimated_pages
+                    )
+                } else {
+                    tr!("plan.banner_unknown", banner = banner_name)
+                }
+            );
+        }
+        println!(
+            "{}",
+            tr!(
+                "plan.summary",
+                pages = plan.total_pages(),
+                seconds = plan.estimated_duration().as_secs()
+            )
+        );
+        let input = input_line(tr!("plan.input"));
+        if input.is_empty() {
+            break;
+        }
+        match input.parse::<usize>() {
+            Ok(index) if (1..=plan.banners.len()).contains(&index) => plan.toggle(index - 1),
+            _ => print_invalid_input(input),
+        }
+    }
+    let banner_types = plan.selected();
+    if banner_types.is_empty() || !wait_for_game_if_needed().await {
+        return;
+    }
+    for banner_type in banner_types {
+        log::info!("banner type: {:?}", banner_type);
+        input_line(&tr!(
+            "plan.switch_banner",
+            banner = banner_type.display_name_for_user(i18n::language())
+        ));
+        import_banner(&account_id, banner_type).await;
+    }
+}
+
+/// 导入一个卡池并显示结果
+async fn import_banner(account_id: &str, banner_type: BannerType) {
+    let config = config();
+    let result = import_records(
+        account_id,
+        banner_type,
+        config.import_mode,
+        &config.export_formats,
+        &ExportOptions::from_config(),
+    )
+    .await;
+    let succeeded = result.is_ok();
+    show_import_result(account_id, result);
+    if succeeded && config.reconcile_total_pulls {
+        reconcile_total_pulls(account_id, banner_type);
+    }
+}
+
+/// 让用户输入游戏内的总抽数，与保存的抽卡记录数量核对，不一致时提示
+fn reconcile_total_pulls(account_id: &str, banner_type: BannerType) {
+    let input = input_line(tr!("input_in_game_total"));
+    if input.is_empty() {
+        return;
+    }
+    let Ok(in_game) = input.parse::<u32>() else {
+        print_invalid_input(input);
+        return;
+    };
+    let total_records = TotalRecords::read_or_default();
+    let stored = stored_pulls(total_records.records.get(account_id), banner_type);
+    log::info!(
+        "reconcile {:?}: stored {stored}, in game {in_game}",
+        banner_type
+    );
+    match reconcile(stored, in_game) {
+        Reconciliation::Match => println!("{}", tr!("reconcile.match", num = stored)),
+        Reconciliation::Missing(num) => {
+            log::warn!("{num} records of {:?} may be missing", banner_type);
+            println!(
+                "{}",
+                tr!(
+                    "reconcile.missing",
+                    stored = stored,
+                    in_game = in_game,
+                    num = num
+                )
+            );
+        }
+        Reconciliation::Extra(num) => {
+            log::warn!("{num} extra records of {:?}", banner_type);
+            println!(
+                "{}",
+                tr!(
+                    "reconcile.extra",
+                    stored = stored,
+                    in_game = in_game,
+                    num = num
+                )
+            );
+        }
+    }
+}
+
+/// 从剪贴板中的截图导入一个卡池
+async fn import_clipboard() {
+    let total_records = TotalRecords::read_or_default();
+    let account_ids = total_records.records.keys().cloned().collect::<Vec<_>>();
+    let account_id = account_id(account_ids);
+    log::info!("account id: {account_id}");
+    let banner_type = banner_type();
+    log::info!("banner type: {:?}", banner_type);
+    let config = config();
+    let result = import_from_clipboard(
+        &account_id,
+        banner_type,
+        config.import_mode,
+        &config.export_formats,
+        &ExportOptions::from_config(),
+    )
+    .await;
+    show_import_result(&account_id, result);
+}
+
+/// 显示导入结果并发送通知
+fn show_import_result(account_id: &str, result: Result<ImportSummary, Failure>) {
+    match result {
+        Ok(import_summary) => {
+            println!("{}", tr!("import_finished", add_num = import_summary.added));
+            if let Some(page) = import_summary.stopped_early {
+                println!("{}", tr!("import.incomplete", page = page));
+            }
+            if let Some(report_path) = &import_summary.report_path {
+                println!("{}", tr!("report_saved", path = report_path.display()));
+            }
+            if let Some(account_records) = TotalRecords::read_or_default().records.get(account_id) {
+                if !is_quiet() {
+                    print_guarantee_states(account_records);
+                    print_charts(account_records);
+                }
+            }
+            if !import_summary.quarantined.is_empty() {
+                println!(
+                    "{}",
+                    tr!("rows_quarantined", num = import_summary.quarantined.len())
+                );
+                for pending_record in import_summary.quarantined.iter() {
+                    println!(
+                        "  {}",
+                        tr!(
+                            "quarantined_row",
+                            page = pending_record.page,
+                            row = pending_record.row + 1,
+                            error = pending_record.error
+                        )
+                    );
+                }
+            }
+            if !import_summary.outside_window.is_empty() {
+                println!(
+                    "{}",
+                    tr!(
+                        "outside_banner_window",
+                        num = import_summary.outside_window.len()
+                    )
+                );
+                for record in import_summary.outside_window.iter() {
+                    println!("  {} {}", record.readable_date_time_str(), record.item_name);
+                }
+            }
+            notify_export_finished(
+                import_summary.added,
+                import_summary.quarantined.len() as u32,
+            );
+            offer_to_open_report(&import_summary.paths);
+        }
+        Err(failure) => {
+            log::error!("{:?}: {:?}", failure.kind, failure.error);
+            println!("{}", tr!("import_failed", error = failure.error));
+            print_remediation_hint(&failure.error);
+            notify_export_failed(&failure.error.to_string());
+        }
+    }
+}
+
+fn undo_import() {
+    let mut total_records = TotalRecords::read_or_default();
+    let account_ids = total_records.records.keys().cloned().collect::<Vec<_>>();
+    if account_ids.is_empty() {
+        println!("{}", tr!("no_records"));
+        return;
+    }
+    let account_id = &account_ids[select_index(tr!("select_account"), &account_ids)];
+    let banner_type = banner_type();
+    let Some(batch) = total_records.records[account_id]
+        .batches
+        .iter()
+        .rfind(|batch| batch.banner_type == banner_type)
+    else {
+        println!("{}", tr!("no_batch_to_undo"));
+        return;
+    };
+    let tip = tr!(
+        "confirm_undo",
+        time = batch.readable_imported_at(),
+        num = batch.added
+    );
+    if !confirm(&tip) {
+        return;
+    }
+    if let Some(batch) = total_records.undo_last_batch(account_id, banner_type) {
+        save_records(&total_records);
+        println!(
+            "{}",
+            tr!(
+                "undo_finished",
+                time = batch.readable_imported_at(),
+                num = batch.added
+            )
+        );
+    }
+}
+
+/// 逐条审核宽松模式下识别失败的行，修正后合并到抽卡记录中
+fn review_pending() {
+    let mut pending_review = PendingReview::read_or_default();
+    if pending_review.records.is_empty() {
+        println!("{}", tr!("no_pending_records"));
+        return;
+    }
+    let mut total_records = TotalRecords::read_or_default();
+    let mut index = 0;
+    while index < pending_review.records.len() {
+        let pending_record = pending_review.records[index].clone();
+        println!();
+        print_pending_record(&pending_record);
+        let options = vec![
+            tr!("review.fix").to_string(),
+            tr!("review.discard").to_string(),
+            tr!("review.skip").to_string(),
+            tr!("menu.back").to_string(),
+        ];
+        match select_index(tr!("review.title"), &options) {
+            0 => {
+                let record = fix_pending_record(&pending_record);
+                if total_records.insert_record(
+                    pending_record.account_id.clone(),
+                    pending_record.banner_type,
+                    record,
+                ) {
+                    save_records(&total_records);
+                } else {
+                    println!("{}", tr!("review.duplicate"));
+                }
+            }
+            1 => {}
+            2 => {
+                index += 1;
+                continue;
+            }
+            _ => break,
+        }
+        // 修正或丢弃后从待审核列表中移除
+        if let Some(image_path) = &pending_record.image_path {
+            let _ = std::fs::remove_file(image_path);
+        }
+        pending_review.records.remove(index);
+        if let Err(e) = pending_review.save() {
+            log::error!("failed to save pending review: {:?}", e);
+            println!("{}", tr!("save_failed", error = e));
+            print_remediation_hint(&e);
+        }
+    }
+}
+
+fn print_pending_record(pending_record: &PendingRecord) {
+    let language = i18n::language();
+    println!(
+        "{}",
+        tr!(
+            "review.location",
+            account_id = pending_record.account_id,
+            banner = pending_record.banner_type.display_name_for_user(language),
+            page = pending_record.page,
+            row = pending_record.row + 1
+        )
+    );
+    if let Some(image_path) = &pending_record.image_path {
+        println!("{}", tr!("review.image", path = image_path.display()));
+    }
+    println!(
+        "{}",
+        tr!(
+            "review.ocr_guess",
+            star = pending_record.star,
+            name = pending_record.item_name,
+            item_type = pending_record.item_type_text,
+            time = pending_record.time_text
+        )
+    );
+    println!("{}", tr!("review.error", error = pending_record.error));
+}
+
+/// 让用户修正一条待审核的记录，直接回车保留识别结果
+fn fix_pending_record(pending_record: &PendingRecord) -> OneRecord {
+    let item_name = match input_line(tr!("review.input_name")) {
+        name if name.is_empty() => pending_record.item_name.clone(),
+        name => name,
+    };
+    let options = all::<ItemType>()
+        .map(|item_type| {
+            item_type
+                .display_name_for_user(i18n::language())
+                .to_string()
+        })
+        .collect::<Vec<_>>();
+    let item_type = all::<ItemType>()
+        .nth(select_index(tr!("review.select_type"), &options))
+        .unwrap();
+    let timestamp = loop {
+        let time = match input_line(tr!("review.input_time")) {
+            time if time.is_empty() => pending_record.time_text.clone(),
+            time => time,
+        };
+        match parse_timestamp(&time) {
+            Ok(timestamp) => break timestamp,
+            Err(_) => print_invalid_input(time),
+        }
+    };
+    OneRecord {
+        provenance: Some(Provenance {
+            batch_id: pending_record.batch_id,
+            page: Some(pending_record.page),
+        }),
+        ..OneRecord::new(
+            pending_record.star,
+            canonical_name(&item_name),
+            item_type,
+            timestamp,
+        )
+    }
+}
+
+fn view_stats() {
+    let total_records = TotalRecords::read_or_default();
+    if total_records.records.is_empty() {
+        println!("{}", tr!("no_records"));
+        return;
+    }
+    for (account_id, account_records) in total_records.records.iter() {
+        println!();
+        println!("{}", account_id);
+        let mut table = Table::new([tr!("export.banner"), tr!("stats.records")]).align_right(&[1]);
+        for banner_type in all::<BannerType>() {
+            if let Some(records) = account_records.records.get(&banner_type) {
+                table.add_row(vec![
+                    banner_type
+                        .display_name_for_user(i18n::language())
+                        .to_string(),
+                    records.len().to_string(),
+                ]);
+            }
+        }
+        table.print();
+        let item_counts = item_counts(account_records);
+        print_item_counts(&item_counts);
+        print_category_counts(&item_counts);
+        print_luck(&luck(account_records));
+        print_guarantee_states(account_records);
+        for period in all::<Period>() {
+            print_period_pulls(period, &period_pulls(account_records, period));
+        }
+    }
+}
+
+fn export() {
+    let total_records = TotalRecords::read_or_default();
+    let options = all::<ExportFormat>()
+        .map(|format| format.display_name().to_string())
+        .collect::<Vec<_>>();
+    let index = select_index(tr!("select_export_format"), &options);
+    let format = all::<ExportFormat>().nth(index).unwrap();
+    match export_all(&total_records, &[format], &ExportOptions::from_config()) {
+        Ok(paths) => {
+            for path in paths.iter() {
+                println!("{}", tr!("export_written", path = path.display()));
+            }
+            let summary = ExportSummary {
+                account_id: None,
+                banner_type: None,
+                added: 0,
+                paths,
+            };
+            if let Err(e) = run_post_export_hook(&summary) {
+                log::warn!("post export hook failed: {:?}", e);
+            }
+            offer_to_open_report(&summary.paths);
+        }
+        Err(e) => {
+            log::error!("failed to export {:?}: {:?}", format, e);
+            println!("{}", tr!("save_failed", error = e));
+            print_remediation_hint(&e);
+        }
+    }
+}
+
+fn manage_accounts() {
+    let mut total_records = TotalRecords::read_or_default();
+    let mut account_ids = total_records.records.keys().cloned().collect::<Vec<_>>();
+    if account_ids.is_empty() {
+        println!("{}", tr!("no_account_id"));
+        return;
+    }
+    account_ids.sort();
+    let options = account_ids
+        .iter()
+        .map(|account_id| {
+            let count = total_records.records[account_id]
+                .records
+                .values()
+                .map(|records| records.len())
+                .sum::<usize>();
+            tr!(
+                "account_record_count",
+                account_id = account_id,
+                count = count
+            )
+        })
+        .chain([tr!("menu.back").to_string()])
+        .collect::<Vec<_>>();
+    let index = select_index(tr!("select_account_to_delete"), &options);
+    let Some(account_id) = account_ids.get(index) else {
+        return;
+    };
+    if confirm(&tr!("confirm_delete_account", account_id = account_id)) {
+        total_records.records.remove(account_id);
+        save_records(&total_records);
+        println!("{}", tr!("account_deleted", account_id = account_id));
+    }
+}
+
+/// 导出语言的名称，没有设置时与界面语言相同
+fn export_language_name(language: Option<Language>) -> String {
+    match language {
+        Some(language) => language.to_string(),
+        None => tr!("settings.export_language_same_as_ui").to_string(),
+    }
+}
+
+fn on_off(value: bool) -> &'static str {
+    if value {
+        tr!("on")
+    } else {
+        tr!("off")
+    }
+}
+
+/// 设置菜单，修改后立即保存到 config.json
+fn settings() {
+    loop {
+        let config = config();
+        let options = vec![
+            tr!("settings.language", value = config.language),
+            tr!("settings.output_dir", value = config.output_dir),
+            tr!("settings.capture_delay", value = config.capture_delay_ms),
+            tr!(
+                "settings.ocr_backend",
+                value = config.ocr_backend.display_name()
+            ),
+            tr!("settings.check_update", value = on_off(config.check_update)),
+            tr!("settings.notification", value = on_off(config.notification)),
+            tr!(
+                "settings.never_elevate",
+                value = on_off(config.never_elevate)
+            ),
+            tr!(
+                "settings.import_mode",
+                value = config.import_mode.display_name()
+            ),
+            tr!(
+                "settings.auto_open_record",
+                value = on_off(config.auto_open_record)
+            ),
+            tr!(
+                "settings.reconcile_total_pulls",
+                value = on_off(config.reconcile_total_pulls)
+            ),
+            tr!(
+                "settings.auto_open_report",
+                value = on_off(config.auto_open_report)
+            ),
+            tr!(
+                "settings.export_language",
+                value = export_language_name(config.export_language)
+            ),
+            tr!(
+                "settings.export_translate_item_names",
+                value = on_off(config.export_translate_item_names)
+            ),
+            tr!("settings.calibrate_star_colors").to_string(),
+            tr!("menu.back").to_string(),
+        ];
+        let options = align_labels(&options);
+        println!();
+        let result = match select_index(tr!("settings.title"), &options) {
+            0 => {
+                let language = language();
+                i18n::set_language(language);
+                update_config(|config| config.set_language(language))
+            }
+            1 => {
+                let output_dir = input_line(tr!("input_output_dir"));
+                if output_dir.is_empty() {
+                    continue;
+                }
+                update_config(|config| config.output_dir = output_dir)
+            }
+            2 => match input_line(tr!("input_capture_delay")).parse::<u64>() {
+                Ok(capture_delay_ms) => {
+                    update_config(|config| config.capture_delay_ms = capture_delay_ms)
+                }
+                Err(_) => continue,
+            },
+            3 => {
+                let options = all::<OcrBackendKind>()
+                    .map(|backend| backend.display_name().to_string())
+                    .collect::<Vec<_>>();
+                let index = select_index(tr!("select_ocr_backend"), &options);
+                let ocr_backend = all::<OcrBackendKind>().nth(index).unwrap();
+                if ocr_backend == OcrBackendKind::PaddleOcr && !prepare_paddle_ocr() {
+                    continue;
+                }
+                update_config(|config| config.ocr_backend = ocr_backend)
+            }
+            4 => update_config(|config| config.check_update = !config.check_update),
+            5 => update_config(|config| config.notification = !config.notification),
+            6 => update_config(|config| config.never_elevate = !config.never_elevate),
+            7 => {
+                let options = all::<ImportMode>()
+                    .map(|import_mode| import_mode.display_name().to_string())
+                    .collect::<Vec<_>>();
+                let index = select_index(tr!("select_import_mode"), &options);
+                let import_mode = all::<ImportMode>().nth(index).unwrap();
+                update_config(|config| config.import_mode = import_mode)
+            }
+            8 => update_config(|config| config.auto_open_record = !config.auto_open_record),
+            9 => {
+                update_config(|config| config.reconcile_total_pulls = !config.reconcile_total_pulls)
+            }
+            10 => update_config(|config| config.auto_open_report = !config.auto_open_report),
+            11 => {
+                let languages = [None].into_iter().chain(all::<Language>().map(Some));
+                let options = languages
+                    .clone()
+                    .map(export_language_name)
+                    .collect::<Vec<_>>();
+                let index = select_index(tr!("select_export_language"), &options);
+                let export_language = languages.clone().nth(index).unwrap();
+                update_config(|config| config.export_language = export_language)
+            }
+            12 => update_config(|config| {
+                config.export_translate_item_names = !config.export_translate_item_names
+            }),
+            13 => {
+                input_line(tr!("calibrate_star_colors_hint"));
+                match calibrate_star_colors() {
+                    Ok(stars) => {
+                        let stars = stars.iter().map(u8::to_string).collect::<Vec<_>>();
+                        println!(
+                            "{}",
+                            tr!("star_colors_calibrated", stars = stars.join(", "))
+                        );
+                        continue;
+                    }
+                    Err(e) => {
+                        log::error!("failed to calibrate star colors: {:?}", e);
+                        println!("{}", tr!("calibrate_star_colors_failed", error = e));
+                        continue;
+                    }
+                }
+            }
+            _ => return,
+        };
+        match result {
+            Ok(()) => println!("{}", tr!("settings_saved")),
+            Err(e) => {
+                log::error!("failed to save config: {:?}", e);
+                println!("{}", tr!("save_failed", error = e));
+                print_remediation_hint(&e);
+            }
+        }
+    }
+}
+
+/// 检查 PaddleOCR-json 是否可用，没有找到时提示下载地址
+/// # 返回
+/// 引擎是否可用
+fn prepare_paddle_ocr() -> bool {
+    if let Some(exe) = find_engine() {
+        log::info!("PaddleOCR-json found: {:?}", exe);
+        return true;
+    }
+    println!("{}", tr!("paddle_ocr.manual", url = RELEASES_URL));
+    false
+}
+
+fn repair_data() {
+    let mut total_records = TotalRecords::read_or_default();
+    let repaired_num = total_records.repair();
+    if repaired_num > 0 {
+        save_records(&total_records);
+    }
+    println!("{}", tr!("repair_finished", num = repaired_num));
+}
@@ -0,0 +1,114 @@
+//! 自动打开抽卡记录界面
+//!
+//! 从游戏主界面开始，按顺序点击 config.json 中 `navigation` 的每一步，
+//! 每步点击后截图并识别指定区域的文字，确认到达了预期的界面再点击下一步。
+//! 坐标都以 1920x1080 的窗口客户区为基准，游戏界面调整时可以在配置中修改。
+//!
+//! 这是实验性的功能：点击位置和校验区域还没有用真实截图验证过，所以默认没有任何步骤，
+//! 需要用户按自己的游戏界面在配置中填写（README 中有示例）。
+
+use std::time::Duration;
+
+use anyhow::{anyhow, Result};
+use serde::{Deserialize, Serialize};
+use tokio::time::sleep;
+
+use crate::action::click;
+use crate::capture::capture_image;
+use crate::config::config;
+use crate::i18n::tr;
+use crate::ocr::{ocr, CharsetHint};
+use crate::record_image::RecordImage;
+
+/// 每一步点击后最多截图校验的次数，界面加载较慢时多等几次
+static MAX_VERIFY_TRIES: u32 = 3;
+
+/// 导航的一步
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct Waypoint {
+    /// 点击的位置
+    pub click: (u32, u32),
+    /// 点击后识别文字的区域 (x0, y0, x1, y1)
+    pub verify_area: (u32, u32, u32, u32),
+    /// 识别出的文字包含其中任意一个时视为到达了预期的界面，为空时不校验
+    pub expect_texts: Vec<String>,
+}
+
+impl Waypoint {
+    /// 识别出的文字是否符合预期，忽略空白
+    fn is_reached_text(&self, text: &str) -> bool {
+        let text = text.split_whitespace().collect::<String>();
+        self.expect_texts.is_empty()
+            || self
+                .expect_texts
+                .iter()
+                .any(|expect_text| text.contains(expect_text.as_str()))
+    }
+}
+
+/// 从游戏主界面自动打开抽卡记录界面
+/// # 返回
+/// 抽卡记录界面的截图
+/// # 错误
+/// 没有配置导航步骤、某一步没有到达预期的界面或者截图、OCR 出错
+pub async fn open_record_screen(hwnd: isize) -> Result<RecordImage> {
+    let config = config();
+    if config.navigation.is_empty() {
+        return Err(anyhow!("{}", tr!("navigation.not_configured")));
+    }
+    let delay = Duration::from_millis(config.navigation_delay_ms);
+    for (step, waypoint) in config.navigation.iter().enumerate() {
+        log::info!("navigation step {}: click {:?}", step + 1, waypoint.click);
+        click(hwnd, waypoint.click.0, waypoint.click.1);
+        let mut text = String::new();
+        for _ in 0..MAX_VERIFY_TRIES {
+            sleep(delay).await;
+            let record_image = RecordImage::new(capture_image()?);
+            let (x0, y0, x1, y1) = record_image.layout.scale_area(waypoint.verify_area);
+            text = ocr(
+                record_image.get_ocr_image(x0, y0, x1, y1),
+                CharsetHint::Name,
+            )?;
+            if waypoint.is_reached_text(&text) {
+                break;
+            }
+        }
+        if !waypoint.is_reached_text(&text) {
+            return Err(anyhow!(
+                "navigation step {} not reached, recognized text: {:?}",
+                step + 1,
+                text
+            ));
+        }
+    }
+    for _ in 0..MAX_VERIFY_TRIES {
+        let record_image = RecordImage::new(capture_image()?);
+        if record_image.is_record_image() {
+            return Ok(record_image);
+        }
+        sleep(delay).await;
+    }
+    Err(anyhow!("record interface not opened after navigation"))
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    #[test]
+    fn test_is_reached_text() {
+        let waypoint = Waypoint {
+            click: (1745, 520),
+            verify_area: (1560, 960, 1700, 1010),
+            expect_texts: vec!["记录".to_string(), "Record".to_string()],
+        };
+        assert!(waypoint.is_reached_text("抽卡 记录"));
+        assert!(waypoint.is_reached_text("Record"));
+        assert!(!waypoint.is_reached_text("商店"));
+        let waypoint = Waypoint {
+            expect_texts: vec![],
+            ..waypoint
+        };
+        assert!(waypoint.is_reached_text(""));
+    }
+}
@@ -0,0 +1,65 @@
+//! OCR 文本规范化
+//!
+//! OCR 有时返回全角/半角的不同写法、多余的空格或者形状相近的字符，
+//! 导致相同的物品名称无法去重、无法在物品字典中找到。
+//! 识别结果在保存前依次做 NFKC 规范化、替换形近字符、去掉首尾空白、合并连续空白。
+
+use unicode_normalization::UnicodeNormalization;
+
+/// 形近字符 -> 物品名称中使用的字符
+static HOMOGLYPHS: &[(char, char)] = &[
+    // 名称中的间隔号
+    ('・', '·'),
+    ('•', '·'),
+    ('∙', '·'),
+    ('⋅', '·'),
+    ('‧', '·'),
+    ('･', '·'),
+    // 英文名称中的连字符
+    ('‐', '-'),
+    ('‑', '-'),
+    ('‒', '-'),
+    ('–', '-'),
+    ('—', '-'),
+    ('―', '-'),
+    ('−', '-'),
+    // 撇号
+    ('’', '\''),
+    ('‘', '\''),
+    ('`', '\''),
+];
+
+fn replace_homoglyph(c: char) -> char {
+    HOMOGLYPHS
+        .iter()
+        .find(|(homoglyph, _)| *homoglyph == c)
+        .map_or(c, |(_, replacement)| *replacement)
+}
+
+/// 规范化一段 OCR 文本
+pub fn normalize_text(text: &str) -> String {
+    let text = text.nfkc().map(replace_homoglyph).collect::<String>();
+    text.split_whitespace().collect::<Vec<_>>().join(" ")
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    #[test]
+    fn test_normalize_text() {
+        assert_eq!(normalize_text("里芙・无限之视"), "里芙·无限之视");
+        assert_eq!(
+            normalize_text(" Ｌｙｆｅ – Wild　 Hunt "),
+            "Lyfe - Wild Hunt"
+        );
+        assert_eq!(
+            normalize_text("２０２４－０６－１１ １２：３０"),
+            "2024-06-11 12:30"
+        );
+        assert_eq!(normalize_text("Lyfe’s"), "Lyfe's");
+        // 规范化后的文本不再变化
+        let normalized = normalize_text("里芙·无限之视");
+        assert_eq!(normalize_text(&normalized), normalized);
+    }
+}
@@ -0,0 +1,49 @@
+use anyhow::Result;
+
+use crate::config::config;
+use crate::i18n::tr;
+
+#[cfg(windows)]
+fn show_toast(title: &str, text: &str) -> Result<()> {
+    use tauri_winrt_notification::Toast;
+
+    Toast::new(Toast::POWERSHELL_APP_ID)
+        .title(title)
+        .text1(text)
+        .show()
+        .map_err(|e| anyhow::anyhow!("Failed to show toast: {:?}", e))
+}
+
+/// 只在 Windows 上有系统通知
+#[cfg(not(windows))]
+fn show_toast(_title: &str, _text: &str) -> Result<()> {
+    Ok(())
+}
+
+fn notify(title: &str, text: &str) {
+    if !config().notification {
+        return;
+    }
+    if let Err(e) = show_toast(title, text) {
+        log::warn!("failed to show notification: {:?}", e);
+    }
+}
+
+/// 导出完成通知
+pub fn notify_export_finished(add_num: u32, warning_num: u32) {
+    let text = if warning_num == 0 {
+        tr!("export_finished_text", add_num = add_num)
+    } else {
+        tr!(
+            "export_finished_text_with_warnings",
+            add_num = add_num,
+            warning_num = warning_num
+        )
+    };
+    notify(tr!("export_finished"), &text);
+}
+
+/// 导出失败通知
+pub fn notify_export_failed(reason: &str) {
+    notify(tr!("export_failed"), reason);
+}
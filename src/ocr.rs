@@ -0,0 +1,385 @@
+use std::collections::HashMap;
+use std::fs::File;
+use std::path::PathBuf;
+use std::sync::Mutex;
+
+use anyhow::Result;
+use enum_iterator::Sequence;
+use image::{DynamicImage, GrayImage};
+use lazy_static::lazy_static;
+use serde::{Deserialize, Serialize};
+use sha2::{Digest, Sha256};
+
+use crate::config::config;
+use crate::data_dir::{data_path, write_atomic};
+use crate::normalize::normalize_text;
+use crate::ocr_pool::recognize_all;
+use crate::paddle_ocr;
+
+/// OCR 引擎
+#[derive(Debug, Copy, Clone, PartialEq, Eq, Serialize, Deserialize, Sequence)]
+pub enum OcrBackendKind {
+    /// 内置的 CnOCR 模型
+    CnOcr,
+    /// 单独下载的 PaddleOCR-json 引擎
+    PaddleOcr,
+}
+
+/// 单元格内容的字符集提示
+/// 和图片一起交给 OCR 引擎，引擎用它挑选识别出的文本块，识别后再把结果限制在字符集内
+#[derive(Debug, Copy, Clone, PartialEq, Eq, Hash)]
+pub enum CharsetHint {
+    /// 不限制
+    Any,
+    /// 只有数字，例如页码
+    Digits,
+    /// 日期时间，数字和 `-`、`:`、空格
+    DateTime,
+    /// 物品名称，各种文字、数字和名称中常见的标点
+    Name,
+    /// 固定词汇之一，例如物品类型，只保留词汇中出现的字符
+    Vocabulary(&'static [&'static str]),
+}
+
+/// 物品名称首尾的这些字符是单元格边框、句号等误识别，名称中间的标点都保留
+static NAME_EDGE_NOISE: &str = "|｜。，、,_;；~～";
+
+/// 容易与数字混淆的字符和全角数字转换为数字
+fn to_digit(c: char) -> char {
+    match c {
+        'O' | 'o' => '0',
+        'l' | 'I' | '|' => '1',
+        '０'..='９' => char::from_u32(c as u32 - '０' as u32 + '0' as u32).unwrap(),
+        '：' => ':',
+        '－' | '—' => '-',
+        _ => c,
+    }
+}
+
+impl CharsetHint {
+    /// 把识别结果限制在字符集内
+    pub fn apply(&self, text: &str) -> String {
+        let text: String = match self {
+            CharsetHint::Any => return text.to_string(),
+            CharsetHint::Digits => text
+                .chars()
+                .map(to_digit)
+                .filter(char::is_ascii_digit)
+                .collect(),
+            CharsetHint::DateTime => text
+                .chars()
+                .map(to_digit)
+                .filter(|c| c.is_ascii_digit() || matches!(c, '-' | ':' | ' '))
+                .collect(),
+            CharsetHint::Name => text
+                .trim_matches(|c: char| c.is_whitespace() || NAME_EDGE_NOISE.contains(c))
+                .to_string(),
+            CharsetHint::Vocabulary(words) => text
+                .chars()
+                .filter(|c| *c == ' ' || words.iter().any(|word| word.contains(*c)))
+                .collect(),
+        };
+        text.split_whitespace().collect::<Vec<_>>().join(" ")
+    }
+
+    /// 一个文本块中是否有字符集中的字符，没有时是图标、边框等的误识别，引擎可以丢弃
+    pub fn is_relevant(&self, text: &str) -> bool {
+        match self {
+            CharsetHint::Any => true,
+            CharsetHint::Digits | CharsetHint::DateTime => {
+                text.chars().map(to_digit).any(|c| c.is_ascii_digit())
+            }
+            CharsetHint::Name => text.chars().any(char::is_alphanumeric),
+            CharsetHint::Vocabulary(words) => text
+                .chars()
+                .any(|c| !c.is_whitespace() && words.iter().any(|word| word.contains(c))),
+        }
+    }
+}
+
+impl OcrBackendKind {
+    pub fn display_name(&self) -> &'static str {
+        match self {
+            OcrBackendKind::CnOcr => "CnOCR",
+            OcrBackendKind::PaddleOcr => "PaddleOCR-json",
+        }
+    }
+
+    /// 不使用缓存，直接识别图片中的文字
+    /// # 错误
+    /// 引擎出错，见 [`paddle_ocr::recognize`]
+    pub fn recognize(&self, image: GrayImage, hint: CharsetHint) -> Result<String> {
+        let text = match self {
+            // CnOCR 只返回一段文本，不能按字符集挑选，识别后再限制字符集
+            OcrBackendKind::CnOcr => simple_ocr::ocr(DynamicImage::ImageLuma8(image)).0,
+            // PaddleOCR-json 按字符集丢弃不相关的文本块
+            OcrBackendKind::PaddleOcr => paddle_ocr::recognize(&image, hint)?,
+        };
+        Ok(hint.apply(&normalize_text(&text)))
+    }
+}
+
+fn ocr_cache_path() -> PathBuf {
+    data_path("cache/ocr_cache.json")
+}
+
+/// 缓存最多保存的识别结果数量，超过时丢弃最久没有用到的结果
+static MAX_CACHE_ENTRIES: usize = 20_000;
+
+/// OCR 结果缓存
+#[derive(Debug, Default)]
+struct OcrCache {
+    /// 图片哈希 -> (识别结果, 最近一次用到时的序号)
+    entries: HashMap<String, (String, u64)>,
+    /// 下一次用到缓存时的序号
+    clock: u64,
+    /// 是否有未保存的新结果或者使用顺序的变化
+    dirty: bool,
+}
+
+impl OcrCache {
+    /// 按使用顺序排列的结果创建缓存，缓存文件中保存的是 (图片哈希, 识别结果)，最后一个是最近用到的
+    fn from_entries(entries: Vec<(String, String)>) -> Self {
+        let clock = entries.len() as u64;
+        let entries = entries
+            .into_iter()
+            .zip(0..)
+            .map(|((key, text), used_at)| (key, (text, used_at)))
+            .collect();
+        Self {
+            entries,
+            clock,
+            dirty: false,
+        }
+    }
+
+    fn read() -> Result<Self> {
+        let file = File::open(ocr_cache_path())?;
+        let reader = std::io::BufReader::new(file);
+        Ok(Self::from_entries(serde_json::from_reader(reader)?))
+    }
+
+    fn read_or_default() -> Self {
+        if !ocr_cache_path().exists() {
+            return Self::default();
+        }
+        Self::read().unwrap_or_else(|e| {
+            log::warn!("Failed to read ocr cache: {:?}", e);
+            Self::default()
+        })
+    }
+
+    fn tick(&mut self) -> u64 {
+        self.clock += 1;
+        self.dirty = true;
+        self.clock
+    }
+
+    /// 缓存的识别结果，有结果时同时记录这次使用
+    fn get(&mut self, key: &str) -> Option<&str> {
+        let (text, last_used_at) = self.entries.get_mut(key)?;
+        self.clock += 1;
+        self.dirty = true;
+        *last_used_at = self.clock;
+        Some(text)
+    }
+
+    fn insert(&mut self, key: String, text: String) {
+        let used_at = self.tick();
+        self.entries.insert(key, (text, used_at));
+    }
+
+    /// 只保留最近用到的 `max_entries` 个结果
+    /// # 返回
+    /// 按使用顺序排列的结果，最后一个是最近用到的
+    fn prune(&mut self, max_entries: usize) -> Vec<(&str, &str)> {
+        if self.entries.len() > max_entries {
+            let mut used_ats = self
+                .entries
+                .values()
+                .map(|(_, used_at)| *used_at)
+                .collect::<Vec<_>>();
+            used_ats.sort_unstable();
+            let oldest_kept = used_ats[used_ats.len() - max_entries];
+            self.entries
+                .retain(|_, (_, used_at)| *used_at >= oldest_kept);
+        }
+        let mut entries = self
+            .entries
+            .iter()
+            .map(|(key, (text, used_at))| (*used_at, key.as_str(), text.as_str()))
+            .collect::<Vec<_>>();
+        entries.sort_unstable();
+        entries
+            .into_iter()
+            .map(|(_, key, text)| (key, text))
+            .collect()
+    }
+
+    fn save(&mut self) -> Result<()> {
+        if !self.dirty {
+            return Ok(());
+        }
+        let path = ocr_cache_path();
+        if let Some(parent) = path.parent() {
+            std::fs::create_dir_all(parent)?;
+        }
+        let contents = serde_json::to_vec(&self.prune(MAX_CACHE_ENTRIES))?;
+        write_atomic(&path, &contents)?;
+        self.dirty = false;
+        Ok(())
+    }
+}
+
+lazy_static! {
+    static ref OCR_CACHE: Mutex<OcrCache> = Mutex::new(OcrCache::read_or_default());
+}
+
+/// 缓存的键，引擎或字符集提示不同时识别结果也不同
+fn cache_key(backend: OcrBackendKind, image: &GrayImage, hint: CharsetHint) -> String {
+    let mut hasher = Sha256::new();
+    hasher.update(backend.display_name());
+    hasher.update(image.width().to_le_bytes());
+    hasher.update(image.height().to_le_bytes());
+    hasher.update(image.as_raw());
+    match hint {
+        CharsetHint::Any => format!("{:x}", hasher.finalize()),
+        hint => format!("{:x}:{:?}", hasher.finalize(), hint),
+    }
+}
+
+/// 识别图片中的文字，相同的图片直接使用缓存的结果
+/// # 错误
+/// OCR 工作线程或引擎出错，见 [`recognize_all`]
+pub fn ocr(image: GrayImage, hint: CharsetHint) -> Result<String> {
+    Ok(ocr_batch(vec![(image, hint)])?.pop().unwrap())
+}
+
+/// 一次识别多张图片中的文字
+/// 缓存中没有的图片去重后交给 OCR 工作线程池识别
+/// # 返回
+/// 与传入图片顺序一致的识别结果
+/// # 错误
+/// OCR 工作线程或引擎出错，见 [`recognize_all`]，识别成功的图片仍然会缓存
+pub fn ocr_batch(images: Vec<(GrayImage, CharsetHint)>) -> Result<Vec<String>> {
+    ocr_batch_with(images, true)
+}
+
+/// 不使用缓存，重新识别所有图片，识别结果更新到缓存中
+/// 用于重新识别失败的页，相同的截图从缓存中只能得到同样错误的结果
+pub fn ocr_batch_uncached(images: Vec<(GrayImage, CharsetHint)>) -> Result<Vec<String>> {
+    ocr_batch_with(images, false)
+}
+
+fn ocr_batch_with(images: Vec<(GrayImage, CharsetHint)>, use_cache: bool) -> Result<Vec<String>> {
+    let backend = config().ocr_backend;
+    let keys = images
+        .iter()
+        .map(|(image, hint)| cache_key(backend, image, *hint))
+        .collect::<Vec<_>>();
+
+    // 需要识别的图片，相同的图片只识别一次
+    let mut pending = HashMap::new();
+    {
+        let cache = OCR_CACHE.lock().unwrap();
+        for (key, image) in keys.iter().zip(images) {
+            if !use_cache || !cache.entries.contains_key(key) {
+                pending.entry(key.clone()).or_insert(image);
+            }
+        }
+    }
+
+    let (pending_keys, pending_images): (Vec<_>, Vec<_>) = pending.into_iter().unzip();
+    let texts = recognize_all(backend, pending_images)?;
+
+    // 识别失败的结果不缓存，否则下次只能从缓存中得到空的结果
+    let mut cache = OCR_CACHE.lock().unwrap();
+    let mut error = None;
+    for (key, text) in pending_keys.into_iter().zip(texts) {
+        match text {
+            Ok(text) => cache.insert(key, text),
+            Err(e) => {
+                error.get_or_insert(e);
+            }
+        }
+    }
+    if let Some(e) = error {
+        return Err(e.context(format!("{} failed", backend.display_name())));
+    }
+    Ok(keys
+        .iter()
+        .map(|key| cache.get(key).unwrap().to_string())
+        .collect())
+}
+
+/// 保存 OCR 结果缓存
+pub fn save_ocr_cache() -> Result<()> {
+    OCR_CACHE.lock().unwrap().save()
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    #[test]
+    fn test_charset_hint() {
+        assert_eq!(CharsetHint::Digits.apply(" 1O "), "10");
+        assert_eq!(
+            CharsetHint::DateTime.apply("2024－O6-1l  12：3O。"),
+            "2024-06-11 12:30"
+        );
+        assert_eq!(CharsetHint::Name.apply("里芙·无限之视。"), "里芙·无限之视");
+        assert_eq!(
+            CharsetHint::Name.apply("Lyfe - Wild Hunt|"),
+            "Lyfe - Wild Hunt"
+        );
+        // 名称中间的标点都保留
+        assert_eq!(
+            CharsetHint::Name.apply("| Mr. Lyfe's (Wild) Hunt! "),
+            "Mr. Lyfe's (Wild) Hunt!"
+        );
+        let vocabulary = &["角色", "武器", "Operative", "Weapon"];
+        assert_eq!(
+            CharsetHint::Vocabulary(vocabulary).apply("【武器】"),
+            "武器"
+        );
+        assert_eq!(CharsetHint::Any.apply(" a|b "), " a|b ");
+    }
+
+    #[test]
+    fn test_is_relevant() {
+        assert!(CharsetHint::Digits.is_relevant("第1O页"));
+        assert!(!CharsetHint::Digits.is_relevant("页"));
+        assert!(CharsetHint::Name.is_relevant("·A"));
+        assert!(!CharsetHint::Name.is_relevant("| ·"));
+        let vocabulary = &["角色", "Weapon"];
+        assert!(CharsetHint::Vocabulary(vocabulary).is_relevant("【角色"));
+        assert!(!CharsetHint::Vocabulary(vocabulary).is_relevant("【】"));
+        assert!(CharsetHint::Any.is_relevant(""));
+    }
+
+    #[test]
+    fn test_ocr_cache_prune() {
+        let entries = ["a", "b", "c"]
+            .map(|key| (key.to_string(), key.to_uppercase()))
+            .to_vec();
+        let mut cache = OcrCache::from_entries(entries);
+        assert_eq!(cache.get("a"), Some("A"));
+        cache.insert("d".to_string(), "D".to_string());
+        // 最久没有用到的 b 被丢弃
+        assert_eq!(cache.prune(3), [("c", "C"), ("a", "A"), ("d", "D")]);
+        assert_eq!(cache.get("b"), None);
+        assert_eq!(cache.prune(10).len(), 3);
+    }
+
+    #[test]
+    fn test_ocr_cache_miss() {
+        let mut cache = OcrCache::from_entries(vec![("a".to_string(), "A".to_string())]);
+        // 没有结果时不记录使用，缓存不需要保存
+        assert_eq!(cache.get("b"), None);
+        assert_eq!(cache.clock, 1);
+        assert!(!cache.dirty);
+        assert_eq!(cache.get("a"), Some("A"));
+        assert!(cache.dirty);
+    }
+}
@@ -0,0 +1,158 @@
+//! OCR 工作线程池
+//!
+//! 识别任务分给固定数量的工作线程，线程在整个会话中复用。
+//! 创建线程池时每个线程先识别一张空白图片，提前加载模型，第一页的识别不用等待引擎启动。
+
+use std::sync::mpsc::{channel, Receiver, Sender};
+use std::sync::{Arc, Mutex};
+use std::thread::{available_parallelism, JoinHandle};
+
+use anyhow::{anyhow, Result};
+use image::GrayImage;
+use lazy_static::lazy_static;
+
+use crate::config::config;
+use crate::ocr::{CharsetHint, OcrBackendKind};
+
+/// 一张图片的识别任务
+struct Job {
+    backend: OcrBackendKind,
+    image: GrayImage,
+    hint: CharsetHint,
+    /// 图片在这一批中的下标
+    index: usize,
+    result_sender: Sender<(usize, Result<String>)>,
+}
+
+struct OcrPool {
+    job_sender: Sender<Job>,
+    workers: Vec<JoinHandle<()>>,
+}
+
+impl OcrPool {
+    fn new(size: usize, backend: OcrBackendKind) -> Self {
+        log::info!("starting {size} ocr workers");
+        let (job_sender, job_receiver) = channel::<Job>();
+        let job_receiver = Arc::new(Mutex::new(job_receiver));
+        let workers = (0..size)
+            .map(|_| {
+                let job_receiver = job_receiver.clone();
+                std::thread::spawn(move || worker(backend, job_receiver))
+            })
+            .collect();
+        Self {
+            job_sender,
+            workers,
+        }
+    }
+}
+
+fn worker(backend: OcrBackendKind, job_receiver: Arc<Mutex<Receiver<Job>>>) {
+    // 预热，加载模型
+    if let Err(e) = backend.recognize(GrayImage::new(32, 32), CharsetHint::Any) {
+        log::warn!("Failed to warm up ocr worker: {:?}", e);
+    }
+    loop {
+        // 取出任务后立即释放锁，其他线程可以同时取任务
+        let job = job_receiver.lock().unwrap().recv();
+        let Ok(job) = job else {
+            // 线程池已经关闭
+            return;
+        };
+        let text = job.backend.recognize(job.image, job.hint);
+        let _ = job.result_sender.send((job.index, text));
+    }
+}
+
+lazy_static! {
+    static ref OCR_POOL: Mutex<Option<OcrPool>> = Mutex::new(None);
+}
+
+/// 没有配置线程数量时 PaddleOCR-json 的工作线程数量，每个线程一个引擎进程，占用内存较多
+static PADDLE_OCR_THREADS: usize = 2;
+
+/// 工作线程数量，配置为 0 时使用 CPU 核心数
+fn pool_size() -> usize {
+    let config = config();
+    let cpu_num = available_parallelism().map(|n| n.get()).unwrap_or(1);
+    match (config.ocr_threads, config.ocr_backend) {
+        (0, OcrBackendKind::PaddleOcr) => cpu_num.min(PADDLE_OCR_THREADS),
+        (0, _) => cpu_num,
+        (threads, _) => threads,
+    }
+}
+
+/// 线程池的任务通道，线程池还没有创建时创建
+fn job_sender() -> Sender<Job> {
+    let mut pool = OCR_POOL.lock().unwrap();
+    pool.get_or_insert_with(|| OcrPool::new(pool_size(), config().ocr_backend))
+        .job_sender
+        .clone()
+}
+
+/// 提前创建线程池并加载模型，不等待加载完成
+pub fn warm_up() {
+    job_sender();
+}
+
+/// 关闭线程池，等待工作线程结束，退出前调用
+///
+/// 关闭任务通道后，工作线程识别完手上的图片就会结束。线程池没有创建时什么也不做，
+/// 之后再识别会重新创建线程池。
+pub fn shutdown() {
+    let Some(pool) = OCR_POOL.lock().unwrap().take() else {
+        return;
+    };
+    drop(pool.job_sender);
+    for worker in pool.workers {
+        if worker.join().is_err() {
+            log::error!("ocr worker panicked");
+        }
+    }
+    log::info!("ocr workers stopped");
+}
+
+/// 在线程池中识别多张图片
+/// # 返回
+/// 与传入图片顺序一致的识别结果，引擎出错的图片是 `Err`
+/// # 错误
+/// 工作线程已经全部退出或者识别时 panic，这时丢弃线程池，下次识别时重新创建
+pub fn recognize_all(
+    backend: OcrBackendKind,
+    images: Vec<(GrayImage, CharsetHint)>,
+) -> Result<Vec<Result<String>>> {
+    let image_num = images.len();
+    let job_sender = job_sender();
+    let (result_sender, result_receiver) = channel();
+    for (index, (image, hint)) in images.into_iter().enumerate() {
+        let job = Job {
+            backend,
+            image,
+            hint,
+            index,
+            result_sender: result_sender.clone(),
+        };
+        if job_sender.send(job).is_err() {
+            discard_pool();
+            return Err(anyhow!("ocr workers stopped"));
+        }
+    }
+    drop(result_sender);
+    let mut results = (0..image_num).map(|_| None).collect::<Vec<_>>();
+    for (index, text) in result_receiver {
+        results[index] = Some(text);
+    }
+    // 工作线程 panic 时没有结果
+    let results = results.into_iter().collect::<Option<Vec<_>>>();
+    results.ok_or_else(|| {
+        discard_pool();
+        anyhow!("ocr worker panicked")
+    })
+}
+
+/// 丢弃出错的线程池，不等待工作线程结束，还在运行的线程取不到新任务后自行退出
+fn discard_pool() {
+    if OCR_POOL.lock().unwrap().take().is_some() {
+        log::error!("ocr workers failed, the pool will be recreated");
+    }
+}
@@ -0,0 +1,88 @@
+//! 导出后打开报告
+//!
+//! 导出的 Excel 或 HTML 用系统默认程序打开（`ShellExecuteW`），
+//! 配置中 `auto_open_report` 开启时直接打开，否则询问是否打开。
+
+use std::path::{Path, PathBuf};
+
+use anyhow::{anyhow, Result};
+
+use crate::config::config;
+use crate::i18n::tr;
+use crate::user_interaction::confirm;
+
+/// 导出文件中可以打开的报告：Excel、HTML，或者按卡池分文件导出的 Excel 所在的目录
+pub fn report_path(paths: &[PathBuf]) -> Option<&Path> {
+    paths
+        .iter()
+        .find(|path| {
+            path.is_dir()
+                || path
+                    .extension()
+                    .is_some_and(|extension| extension == "xlsx" || extension == "html")
+        })
+        .map(PathBuf::as_path)
+}
+
+/// 用系统默认程序打开文件，目录在资源管理器中打开
+#[cfg(windows)]
+pub fn open_path(path: &Path) -> Result<()> {
+    use windows::core::{w, HSTRING, PCWSTR};
+    use windows::Win32::Foundation::HWND;
+    use windows::Win32::UI::Shell::ShellExecuteW;
+    use windows::Win32::UI::WindowsAndMessaging::SW_SHOWNORMAL;
+
+    let file = HSTRING::from(path.as_os_str());
+    let instance = unsafe {
+        ShellExecuteW(
+            HWND::default(),
+            w!("open"),
+            PCWSTR(file.as_ptr()),
+            PCWSTR::null(),
+            PCWSTR::null(),
+            SW_SHOWNORMAL,
+        )
+    };
+    // 返回值不大于 32 时表示出错
+    if instance.0 <= 32 {
+        return Err(anyhow!("ShellExecuteW failed with {}", instance.0));
+    }
+    Ok(())
+}
+
+#[cfg(not(windows))]
+pub fn open_path(_path: &Path) -> Result<()> {
+    Err(anyhow!("opening files is only supported on Windows"))
+}
+
+/// 导出后打开报告，配置中没有开启自动打开时先询问
+pub fn offer_to_open_report(paths: &[PathBuf]) {
+    let Some(path) = report_path(paths) else {
+        return;
+    };
+    if !config().auto_open_report && !confirm(&tr!("open_report.confirm", path = path.display())) {
+        return;
+    }
+    log::info!("opening {:?}", path);
+    if let Err(e) = open_path(path) {
+        log::error!("failed to open {:?}: {:?}", path, e);
+        println!("{}", tr!("open_report.failed", error = e));
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    #[test]
+    fn test_report_path() {
+        let paths = [
+            PathBuf::from("records.csv"),
+            PathBuf::from("records.html"),
+            PathBuf::from("records.xlsx"),
+        ];
+        assert_eq!(report_path(&paths), Some(Path::new("records.html")));
+        assert_eq!(report_path(&paths[..1]), None);
+        assert_eq!(report_path(&[]), None);
+    }
+}
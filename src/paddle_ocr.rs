@@ -0,0 +1,345 @@
+//! PaddleOCR-json 引擎
+//!
+//! 引擎是单独的程序，每个 OCR 工作线程启动一个引擎进程，通过标准输入输出传递 JSON：
+//! 输入 `{"image_base64": "..."}`，输出 `{"code": 100, "data": [{"box": [[x, y], ...], "text": "..."}, ...]}`，
+//! `code` 为 101 表示图片中没有文字。工作线程结束时引擎进程随之结束。
+//!
+//! 引擎把一行文字拆成多个文本块时，按位置排序后拼接，结果与 CnOCR 识别整行的写法一致，
+//! 例如 `里芙`、`·`、`无限之视` 拼接为 `里芙·无限之视`。
+//!
+//! 引擎的位置依次为：`config.json` 中的 `paddle_ocr_path`，
+//! 数据目录和程序所在目录中以 `PaddleOCR-json` 开头的目录（有多个版本时使用版本最高的）。
+//! 都没有时提示用户从 GitHub 下载后解压到数据目录，程序不自动下载没有校验过的可执行文件。
+
+use std::cell::RefCell;
+use std::io::{BufRead, BufReader, Cursor, Write};
+use std::path::{Path, PathBuf};
+use std::process::{Child, ChildStdin, ChildStdout, Command, Stdio};
+
+use anyhow::{anyhow, Result};
+use base64::prelude::{Engine as _, BASE64_STANDARD};
+use image::{GrayImage, ImageFormat};
+
+use crate::config::config;
+use crate::data_dir::data_dir;
+use crate::ocr::CharsetHint;
+
+/// 引擎程序的文件名
+static EXE_NAME: &str = "PaddleOCR-json.exe";
+
+/// 引擎所在目录名称的前缀
+static DIR_PREFIX: &str = "PaddleOCR-json";
+
+/// 引擎初始化完成时输出的内容
+static INIT_COMPLETED: &str = "OCR init completed.";
+
+/// 没有找到引擎时让用户自己下载
+pub static RELEASES_URL: &str = "https://github.com/hiroi-sora/PaddleOCR-json/releases";
+
+/// 目录名称中的版本号，例如 `PaddleOCR-json_v.1.3.1`、`PaddleOCR-json_v1.4.1_windows_x64`
+fn dir_version(dir_name: &str) -> Option<Vec<u32>> {
+    let (_, version) = dir_name.strip_prefix(DIR_PREFIX)?.split_once('v')?;
+    let version = version
+        .trim_start_matches('.')
+        .split(|c: char| !c.is_ascii_digit() && c != '.')
+        .next()?;
+    let version = version
+        .split('.')
+        .filter(|part| !part.is_empty())
+        .map(str::parse)
+        .collect::<Result<Vec<u32>, _>>()
+        .ok()?;
+    (!version.is_empty()).then_some(version)
+}
+
+/// 目录中的引擎程序，也查找下一层目录（解压后多一层目录时）
+fn exe_in(dir: &Path) -> Option<PathBuf> {
+    let exe = dir.join(EXE_NAME);
+    if exe.is_file() {
+        return Some(exe);
+    }
+    std::fs::read_dir(dir)
+        .ok()?
+        .flatten()
+        .map(|entry| entry.path().join(EXE_NAME))
+        .find(|exe| exe.is_file())
+}
+
+/// 在目录中查找版本最高的引擎
+fn find_engine_in(dir: &Path) -> Option<PathBuf> {
+    std::fs::read_dir(dir)
+        .ok()?
+        .flatten()
+        .filter_map(|entry| {
+            let name = entry.file_name().to_string_lossy().to_string();
+            if !name.starts_with(DIR_PREFIX) {
+                return None;
+            }
+            let exe = exe_in(&entry.path())?;
+            Some((dir_version(&name).unwrap_or_default(), exe))
+        })
+        .max_by(|(a, _), (b, _)| a.cmp(b))
+        .map(|(_, exe)| exe)
+}
+
+/// 引擎程序的路径，没有找到时返回 `None`
+pub fn find_engine() -> Option<PathBuf> {
+    if let Some(path) = config().paddle_ocr_path {
+        let path = data_dir().join(path);
+        if path.is_file() {
+            return Some(path);
+        }
+        log::warn!("PaddleOCR-json not found at {:?}", path);
+    }
+    let exe_dir = std::env::current_exe()
+        .ok()
+        .and_then(|path| path.parent().map(Path::to_path_buf));
+    std::iter::once(data_dir().to_path_buf())
+        .chain(exe_dir)
+        .find_map(|dir| find_engine_in(&dir))
+}
+
+/// 运行中的引擎进程
+struct Engine {
+    child: Child,
+    stdin: ChildStdin,
+    stdout: BufReader<ChildStdout>,
+}
+
+impl Engine {
+    fn start(exe: &Path) -> Result<Self> {
+        log::info!("starting {:?}", exe);
+        let mut child = Command::new(exe)
+            .current_dir(exe.parent().unwrap_or(Path::new(".")))
+            .stdin(Stdio::piped())
+            .stdout(Stdio::piped())
+            .stderr(Stdio::null())
+            .spawn()?;
+        let stdin = child.stdin.take().unwrap();
+        let mut stdout = BufReader::new(child.stdout.take().unwrap());
+        // 等待模型加载完成
+        let mut line = String::new();
+        loop {
+            line.clear();
+            if stdout.read_line(&mut line)? == 0 {
+                let _ = child.kill();
+                return Err(anyhow!("PaddleOCR-json exited during initialization"));
+            }
+            if line.contains(INIT_COMPLETED) {
+                break;
+            }
+        }
+        Ok(Self {
+            child,
+            stdin,
+            stdout,
+        })
+    }
+
+    fn recognize(&mut self, image: &GrayImage, hint: CharsetHint) -> Result<String> {
+        let mut png = Cursor::new(vec![]);
+        image.write_to(&mut png, ImageFormat::Png)?;
+        // 图片以 base64 传给引擎，避免临时文件路径中有非 ASCII 字符时引擎无法读取
+        let image_base64 = BASE64_STANDARD.encode(png.get_ref());
+        let request = serde_json::json!({ "image_base64": image_base64 });
+        writeln!(self.stdin, "{request}")?;
+        self.stdin.flush()?;
+        let mut line = String::new();
+        if self.stdout.read_line(&mut line)? == 0 {
+            return Err(anyhow!("PaddleOCR-json exited"));
+        }
+        parse_output(&line, hint)
+    }
+}
+
+impl Drop for Engine {
+    fn drop(&mut self) {
+        let _ = self.child.kill();
+        let _ = self.child.wait();
+    }
+}
+
+/// 文本块的左边界、上边界、下边界，`box` 是文本块四个角的坐标
+fn block_bounds(block: &serde_json::Value) -> Option<(f64, f64, f64)> {
+    let points = block["box"]
+        .as_array()?
+        .iter()
+        .map(|point| Some((point[0].as_f64()?, point[1].as_f64()?)))
+        .collect::<Option<Vec<_>>>()?;
+    let xs = points.iter().map(|(x, _)| *x);
+    let ys = points.iter().map(|(_, y)| *y);
+    let left = xs.reduce(f64::min)?;
+    let top = ys.clone().reduce(f64::min)?;
+    let bottom = ys.reduce(f64::max)?;
+    Some((left, top, bottom))
+}
+
+/// 按阅读顺序排列文本块：从上到下分行，同一行从左到右
+/// 有文本块没有位置时返回 `None`
+fn sort_blocks<'a>(blocks: &[&'a serde_json::Value]) -> Option<Vec<&'a serde_json::Value>> {
+    let mut bounds = blocks
+        .iter()
+        .map(|block| Some((block_bounds(block)?, *block)))
+        .collect::<Option<Vec<_>>>()?;
+    bounds.sort_by(|((_, a, _), _), ((_, b, _), _)| a.total_cmp(b));
+    let mut lines: Vec<(f64, Vec<(f64, &serde_json::Value)>)> = vec![];
+    for ((left, top, bottom), block) in bounds {
+        match lines.last_mut() {
+            // 中线在上一行的下边界以上时属于同一行
+            Some((line_bottom, line)) if (top + bottom) / 2.0 < *line_bottom => {
+                *line_bottom = line_bottom.max(bottom);
+                line.push((left, block));
+            }
+            _ => lines.push((bottom, vec![(left, block)])),
+        }
+    }
+    let blocks = lines
+        .into_iter()
+        .flat_map(|(_, mut line)| {
+            line.sort_by(|(a, _), (b, _)| a.total_cmp(b));
+            line.into_iter().map(|(_, block)| block)
+        })
+        .collect();
+    Some(blocks)
+}
+
+/// 中文、日文和全角字符之间不加空格，间隔号也和两边的文字直接相连
+fn is_cjk(c: char) -> bool {
+    matches!(c,
+        '·'
+        | '\u{3000}'..='\u{30ff}'
+        | '\u{3400}'..='\u{4dbf}'
+        | '\u{4e00}'..='\u{9fff}'
+        | '\u{ff00}'..='\u{ffef}'
+    )
+}
+
+/// 拼接同一单元格中的文本块，两边都不是中文时用空格分隔，例如英文单词之间
+fn join_blocks<'a>(texts: impl IntoIterator<Item = &'a str>) -> String {
+    let mut joined = String::new();
+    for text in texts.into_iter().map(str::trim) {
+        let needs_space = match (joined.chars().last(), text.chars().next()) {
+            (Some(last), Some(first)) => !is_cjk(last) && !is_cjk(first),
+            _ => false,
+        };
+        if needs_space {
+            joined.push(' ');
+        }
+        joined.push_str(text);
+    }
+    joined
+}
+
+/// 解析引擎的一行输出，文本块按位置排序后拼接
+/// 首尾没有字符集中字符的文本块是边框、图标等的误识别，丢弃；
+/// 中间的保留，例如名称中被单独识别的间隔号
+fn parse_output(line: &str, hint: CharsetHint) -> Result<String> {
+    let output = serde_json::from_str::<serde_json::Value>(line)?;
+    match output["code"].as_i64() {
+        Some(100) => {
+            let blocks = output["data"]
+                .as_array()
+                .into_iter()
+                .flatten()
+                .collect::<Vec<_>>();
+            // 没有位置时保持引擎输出的顺序
+            let blocks = sort_blocks(&blocks).unwrap_or(blocks);
+            let texts = blocks
+                .into_iter()
+                .filter_map(|block| block["text"].as_str())
+                .collect::<Vec<_>>();
+            let first = texts.iter().position(|text| hint.is_relevant(text));
+            let last = texts.iter().rposition(|text| hint.is_relevant(text));
+            Ok(match (first, last) {
+                (Some(first), Some(last)) => join_blocks(texts[first..=last].iter().copied()),
+                _ => String::new(),
+            })
+        }
+        Some(101) => Ok(String::new()),
+        _ => Err(anyhow!("PaddleOCR-json error: {}", output["data"])),
+    }
+}
+
+thread_local! {
+    /// 当前工作线程的引擎进程
+    static ENGINE: RefCell<Option<Engine>> = const { RefCell::new(None) };
+}
+
+/// 用当前线程的引擎识别图片，引擎还没有启动时启动
+/// 引擎不支持字符白名单，用 `hint` 丢弃不相关的文本块
+/// 出错时结束引擎进程，下次识别时重新启动
+/// # 错误
+/// 没有找到引擎、引擎启动失败或者识别出错
+pub fn recognize(image: &GrayImage, hint: CharsetHint) -> Result<String> {
+    ENGINE.with_borrow_mut(|engine| {
+        let result = match engine.as_mut() {
+            Some(engine) => engine.recognize(image, hint),
+            None => {
+                let exe = find_engine().ok_or_else(|| anyhow!("{EXE_NAME} not found"));
+                exe.and_then(|exe| Engine::start(&exe))
+                    .and_then(|started| engine.insert(started).recognize(image, hint))
+            }
+        };
+        if result.is_err() {
+            *engine = None;
+        }
+        result
+    })
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    #[test]
+    fn test_dir_version() {
+        assert_eq!(dir_version("PaddleOCR-json_v.1.3.1"), Some(vec![1, 3, 1]));
+        assert_eq!(
+            dir_version("PaddleOCR-json_v1.4.1_windows_x64"),
+            Some(vec![1, 4, 1])
+        );
+        assert_eq!(dir_version("PaddleOCR-json"), None);
+        assert_eq!(dir_version("other"), None);
+    }
+
+    #[test]
+    fn test_parse_output() {
+        // 文本块按位置排序，中文和间隔号之间不加空格，与 CnOCR 的结果一致
+        let line = r#"{"code":100,"data":[
+            {"box":[[60,2],[130,2],[130,22],[60,22]],"score":0.9,"text":"无限之视"},
+            {"box":[[0,0],[40,0],[40,20],[0,20]],"score":0.9,"text":"里芙"},
+            {"box":[[45,5],[55,5],[55,15],[45,15]],"score":0.6,"text":"·"}
+        ]}"#;
+        assert_eq!(
+            parse_output(line, CharsetHint::Name).unwrap(),
+            "里芙·无限之视"
+        );
+        // 英文单词之间用空格分隔
+        let line = r#"{"code":100,"data":[
+            {"box":[[70,0],[150,0],[150,20],[70,20]],"score":0.9,"text":"Wild Hunt"},
+            {"box":[[0,0],[60,0],[60,20],[0,20]],"score":0.9,"text":"Lyfe -"}
+        ]}"#;
+        assert_eq!(
+            parse_output(line, CharsetHint::Name).unwrap(),
+            "Lyfe - Wild Hunt"
+        );
+        // 多行时从上到下
+        let line = r#"{"code":100,"data":[
+            {"box":[[0,30],[80,30],[80,50],[0,50]],"score":0.9,"text":"12:30"},
+            {"box":[[10,0],[90,0],[90,20],[10,20]],"score":0.9,"text":"2024-06-11"}
+        ]}"#;
+        assert_eq!(
+            parse_output(line, CharsetHint::DateTime).unwrap(),
+            "2024-06-11 12:30"
+        );
+        assert_eq!(
+            parse_output(r#"{"code":101,"data":""}"#, CharsetHint::Any).unwrap(),
+            ""
+        );
+        assert!(parse_output(r#"{"code":200,"data":"error"}"#, CharsetHint::Any).is_err());
+        // 页码单元格首尾没有数字的文本块是误识别，没有位置时保持引擎输出的顺序
+        let line = r#"{"code":100,"data":[{"box":[],"score":0.5,"text":"<"},{"box":[],"score":0.9,"text":"12"}]}"#;
+        assert_eq!(parse_output(line, CharsetHint::Digits).unwrap(), "12");
+        assert_eq!(parse_output(line, CharsetHint::Any).unwrap(), "< 12");
+    }
+}
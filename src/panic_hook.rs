@@ -0,0 +1,78 @@
+//! 程序崩溃时的提示
+//!
+//! 截图、保存等处还有不少 `.unwrap()`，出现 panic 时默认只在控制台输出英文的错误信息，
+//! 双击运行的控制台窗口会立即关闭，用户看不到发生了什么。
+//! 这里把 panic 写入日志，输出本地化的提示和日志文件位置，交互运行时等待用户按下回车键再退出。
+
+use std::any::Any;
+use std::backtrace::Backtrace;
+use std::panic::PanicHookInfo;
+use std::sync::atomic::{AtomicBool, Ordering};
+
+use crate::i18n::tr;
+use crate::logging::log_path;
+use crate::user_interaction::read_line;
+
+/// 是否已经等待过用户按下回车键，多个线程 panic 时只等待一次
+static WAITED: AtomicBool = AtomicBool::new(false);
+
+/// panic 的信息，`panic!` 的参数是字符串字面量或格式化后的字符串
+fn panic_message(payload: &(dyn Any + Send)) -> &str {
+    if let Some(message) = payload.downcast_ref::<&str>() {
+        message
+    } else if let Some(message) = payload.downcast_ref::<String>() {
+        message
+    } else {
+        "unknown panic"
+    }
+}
+
+fn hook(info: &PanicHookInfo, interactive: bool) {
+    let message = panic_message(info.payload());
+    let location = info
+        .location()
+        .map(|location| location.to_string())
+        .unwrap_or_default();
+    let thread = std::thread::current();
+    log::error!(
+        "thread '{}' panicked at {location}: {message}\n{}",
+        thread.name().unwrap_or("<unnamed>"),
+        Backtrace::force_capture()
+    );
+    println!();
+    println!(
+        "{}",
+        tr!(
+            "panic.message",
+            error = message,
+            log_path = log_path().display()
+        )
+    );
+    if interactive && !WAITED.swap(true, Ordering::Relaxed) {
+        println!("{}", tr!("press_enter_to_exit"));
+        // 这里不能再 panic
+        read_line();
+    }
+}
+
+/// 替换默认的 panic 处理，在初始化日志之后调用
+/// # 参数
+/// - `interactive`: 是否等待用户按下回车键，非交互模式和子命令不等待
+pub fn install(interactive: bool) {
+    std::panic::set_hook(Box::new(move |info| hook(info, interactive)));
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    #[test]
+    fn test_panic_message() {
+        let payload = std::panic::catch_unwind(|| panic!("static message")).unwrap_err();
+        assert_eq!(panic_message(payload.as_ref()), "static message");
+        let payload = std::panic::catch_unwind(|| panic!("formatted {}", 1)).unwrap_err();
+        assert_eq!(panic_message(payload.as_ref()), "formatted 1");
+        let payload = std::panic::catch_unwind(|| std::panic::panic_any(1)).unwrap_err();
+        assert_eq!(panic_message(payload.as_ref()), "unknown panic");
+    }
+}
@@ -0,0 +1,73 @@
+//! 感知哈希
+//!
+//! 用于判断两次截图的画面是否相同，例如翻页点击没有生效时前后两次截图几乎一样。
+
+use image::imageops::FilterType;
+use image::DynamicImage;
+
+/// 不同的位数不超过总位数的这个比例时视为相同的画面
+static SIMILAR_RATIO: f32 = 0.02;
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct PerceptualHash {
+    bits: Vec<bool>,
+}
+
+impl PerceptualHash {
+    /// 差值哈希，缩小为 (width + 1) x height 的灰度图，每一位表示左边的像素是否比右边亮
+    pub fn dhash(image: &DynamicImage, width: u32, height: u32) -> Self {
+        let image = image
+            .resize_exact(width + 1, height, FilterType::Triangle)
+            .to_luma8();
+        let bits = (0..height)
+            .flat_map(|y| (0..width).map(move |x| (x, y)))
+            .map(|(x, y)| image.get_pixel(x, y)[0] > image.get_pixel(x + 1, y)[0])
+            .collect();
+        Self { bits }
+    }
+
+    /// 汉明距离
+    pub fn distance(&self, other: &Self) -> u32 {
+        self.bits
+            .iter()
+            .zip(&other.bits)
+            .filter(|(a, b)| a != b)
+            .count() as u32
+    }
+
+    /// 两个哈希对应的画面是否近似相同
+    pub fn is_similar(&self, other: &Self) -> bool {
+        self.bits.len() == other.bits.len()
+            && self.distance(other) as f32 <= self.bits.len() as f32 * SIMILAR_RATIO
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use image::{GrayImage, Luma};
+
+    use super::*;
+
+    fn stripes(period: u32) -> DynamicImage {
+        DynamicImage::ImageLuma8(GrayImage::from_fn(640, 320, |x, y| {
+            Luma([if (x / period + y / 40).is_multiple_of(2) {
+                30
+            } else {
+                220
+            }])
+        }))
+    }
+
+    #[test]
+    fn test_is_similar() {
+        let hash = PerceptualHash::dhash(&stripes(40), 64, 32);
+        assert_eq!(hash.distance(&hash.clone()), 0);
+
+        // 亮度整体变化不影响哈希
+        let brighter = stripes(40).brighten(10);
+        assert!(hash.is_similar(&PerceptualHash::dhash(&brighter, 64, 32)));
+
+        let other = PerceptualHash::dhash(&stripes(25), 64, 32);
+        assert!(!hash.is_similar(&other));
+    }
+}
@@ -0,0 +1,157 @@
+//! 多个卡池的导入计划
+//!
+//! 游戏中只显示当前页码，不显示总页数，导入前无法从游戏中读取，
+//! 所以用已保存的抽卡记录的数量估计每个卡池的页数，不包含上次导入后的新记录。
+//! 导入前显示估计的总页数和预计耗时，并注明是估计值，用户可以取消不需要导入的卡池。
+
+use std::time::Duration;
+
+use enum_iterator::all;
+
+use crate::record::{BannerType, OneAccountRecords};
+
+/// 每页的抽卡记录数量
+static RECORDS_PER_PAGE: usize = 10;
+/// 每页除翻页等待以外的耗时（截图、识别页码）的估计值
+static PAGE_OVERHEAD_MS: u64 = 500;
+
+/// 一个卡池的导入计划
+#[derive(Debug, Copy, Clone, PartialEq, Eq)]
+pub struct BannerPlan {
+    pub banner_type: BannerType,
+    /// 根据已有抽卡记录估计的页数，至少为 1
+    pub estimated_pages: u32,
+    /// 是否有已保存的记录，没有时页数未知，按 1 页估计
+    pub has_records: bool,
+    pub selected: bool,
+}
+
+/// 多个卡池的导入计划
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct ImportPlan {
+    pub banners: Vec<BannerPlan>,
+    /// 翻页后等待界面刷新的时间
+    pub capture_delay: Duration,
+}
+
+/// 根据已有抽卡记录的数量估计一个卡池的页数，至少为 1
+pub fn estimated_pages(
+    account_records: Option<&OneAccountRecords>,
+    banner_type: BannerType,
+) -> u32 {
+    let record_num = account_records
+        .and_then(|account_records| account_records.records.get(&banner_type))
+        .map_or(0, Vec::len);
+    record_num.div_ceil(RECORDS_PER_PAGE).max(1) as u32
+}
+
+impl ImportPlan {
+    /// 所有卡池都选中的计划
+    /// # 参数
+    /// - `account_records`: 账号已有的抽卡记录，新账号为 None
+    pub fn new(account_records: Option<&OneAccountRecords>, capture_delay: Duration) -> Self {
+        let banners = all::<BannerType>()
+            .map(|banner_type| BannerPlan {
+                banner_type,
+                estimated_pages: estimated_pages(account_records, banner_type),
+                has_records: account_records
+                    .and_then(|account_records| account_records.records.get(&banner_type))
+                    .is_some_and(|records| !records.is_empty()),
+                selected: true,
+            })
+            .collect();
+        Self {
+            banners,
+            capture_delay,
+        }
+    }
+
+    /// 只选中账号使用的卡池，`banner_types` 为空时选中所有卡池
+    pub fn select_only(&mut self, banner_types: &[BannerType]) {
+        for banner in self.banners.iter_mut() {
+            banner.selected = banner_types.is_empty() || banner_types.contains(&banner.banner_type);
+        }
+    }
+
+    /// 切换一个卡池是否导入
+    pub fn toggle(&mut self, index: usize) {
+        if let Some(banner) = self.banners.get_mut(index) {
+            banner.selected = !banner.selected;
+        }
+    }
+
+    /// 选中的卡池
+    pub fn selected(&self) -> Vec<BannerType> {
+        self.banners
+            .iter()
+            .filter(|banner| banner.selected)
+            .map(|banner| banner.banner_type)
+            .collect()
+    }
+
+    /// 选中的卡池的总页数
+    pub fn total_pages(&self) -> u32 {
+        self.banners
+            .iter()
+            .filter(|banner| banner.selected)
+            .map(|banner| banner.estimated_pages)
+            .sum()
+    }
+
+    /// 预计耗时
+    pub fn estimated_duration(&self) -> Duration {
+        (self.capture_delay + Duration::from_millis(PAGE_OVERHEAD_MS)) * self.total_pages()
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use std::collections::HashMap;
+
+    use super::*;
+    use crate::record::{ItemType, OneRecord};
+
+    #[test]
+    fn test_import_plan() {
+        let mut account_records = OneAccountRecords::new("id".to_string(), HashMap::new());
+        account_records.records.insert(
+            BannerType::PermanentWeapon,
+            (0..25)
+                .map(|i| OneRecord::new(3, "a".to_string(), ItemType::Weapon, i))
+                .collect(),
+        );
+        let mut plan = ImportPlan::new(Some(&account_records), Duration::from_millis(200));
+        let banner_num = all::<BannerType>().count() as u32;
+        // 常驻武器池 3 页，其他卡池没有记录，页数未知，各按 1 页估计
+        assert_eq!(plan.total_pages(), banner_num - 1 + 3);
+        assert_eq!(
+            plan.banners
+                .iter()
+                .filter(|banner| banner.has_records)
+                .map(|banner| banner.banner_type)
+                .collect::<Vec<_>>(),
+            [BannerType::PermanentWeapon]
+        );
+        let index = all::<BannerType>()
+            .position(|banner_type| banner_type == BannerType::PermanentWeapon)
+            .unwrap();
+        plan.toggle(index);
+        assert_eq!(plan.total_pages(), banner_num - 1);
+        assert!(!plan.selected().contains(&BannerType::PermanentWeapon));
+        assert_eq!(
+            plan.estimated_duration(),
+            Duration::from_millis(700) * (banner_num - 1)
+        );
+        plan.toggle(banner_num as usize);
+        assert_eq!(plan.selected().len(), banner_num as usize - 1);
+
+        plan.select_only(&[BannerType::Beginner, BannerType::PermanentWeapon]);
+        assert_eq!(
+            plan.selected(),
+            [BannerType::PermanentWeapon, BannerType::Beginner]
+        );
+        assert_eq!(plan.total_pages(), 1 + 3);
+        plan.select_only(&[]);
+        assert_eq!(plan.selected().len(), banner_num as usize);
+    }
+}
@@ -0,0 +1,117 @@
+//! OCR 前的图片预处理
+//!
+//! 低分辨率窗口的截图放大到 1920x1080 后文字模糊，可以在 config.json 的 `ocr_preprocessing`
+//! 中开启放大、拉伸对比度和自适应阈值二值化。开启 `auto_retry` 时，识别失败的行会用
+//! 全部预处理重新识别一次。
+
+use image::imageops::{resize, FilterType};
+use image::GrayImage;
+use imageproc::contrast::{adaptive_threshold, stretch_contrast};
+use serde::{Deserialize, Serialize};
+
+/// 预处理选项
+#[derive(Debug, Copy, Clone, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(default)]
+pub struct Preprocessing {
+    /// 放大两倍
+    pub upscale: bool,
+    /// 把最暗到最亮的范围拉伸到 0-255
+    pub contrast_stretch: bool,
+    /// 用自适应阈值二值化，光照不均匀时比全局阈值好
+    pub adaptive_threshold: bool,
+    /// 识别失败的行用全部预处理重新识别
+    pub auto_retry: bool,
+}
+
+impl Default for Preprocessing {
+    fn default() -> Self {
+        Self {
+            upscale: false,
+            contrast_stretch: false,
+            adaptive_threshold: false,
+            auto_retry: true,
+        }
+    }
+}
+
+impl Preprocessing {
+    /// 开启全部预处理，用于重新识别
+    pub fn strongest() -> Self {
+        Self {
+            upscale: true,
+            contrast_stretch: true,
+            adaptive_threshold: true,
+            auto_retry: false,
+        }
+    }
+
+    /// 是否有需要重新识别时更强的预处理
+    pub fn can_retry(&self) -> bool {
+        self.auto_retry && !(self.upscale && self.contrast_stretch && self.adaptive_threshold)
+    }
+
+    /// 依次拉伸对比度、放大、二值化
+    pub fn apply(&self, image: GrayImage) -> GrayImage {
+        let mut image = image;
+        if self.contrast_stretch {
+            let min = image.pixels().map(|pixel| pixel[0]).min().unwrap_or(0);
+            let max = image.pixels().map(|pixel| pixel[0]).max().unwrap_or(0);
+            if min < max {
+                image = stretch_contrast(&image, min, max, 0, 255);
+            }
+        }
+        if self.upscale {
+            image = resize(
+                &image,
+                image.width() * 2,
+                image.height() * 2,
+                FilterType::CatmullRom,
+            );
+        }
+        if self.adaptive_threshold {
+            // 窗口约为一个字的大小
+            let block_radius = (image.height() / 2).max(1);
+            image = adaptive_threshold(&image, block_radius);
+        }
+        image
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use image::Luma;
+
+    use super::*;
+
+    #[test]
+    fn test_preprocessing() {
+        let image = GrayImage::from_fn(20, 10, |x, _| {
+            if (8..12).contains(&x) {
+                Luma([100])
+            } else {
+                Luma([180])
+            }
+        });
+        assert_eq!(Preprocessing::default().apply(image.clone()), image);
+
+        let stretched = Preprocessing {
+            contrast_stretch: true,
+            ..Default::default()
+        }
+        .apply(image.clone());
+        assert_eq!(stretched.get_pixel(10, 5)[0], 0);
+        assert_eq!(stretched.get_pixel(0, 5)[0], 255);
+
+        let strongest = Preprocessing::strongest();
+        assert!(!strongest.can_retry());
+        assert!(Preprocessing::default().can_retry());
+        let processed = strongest.apply(image);
+        assert_eq!(processed.dimensions(), (40, 20));
+        assert!(processed
+            .pixels()
+            .all(|pixel| pixel[0] == 0 || pixel[0] == 255));
+        // 文字为黑色，背景为白色
+        assert_eq!(processed.get_pixel(20, 10)[0], 0);
+        assert_eq!(processed.get_pixel(2, 10)[0], 255);
+    }
+}
@@ -0,0 +1,101 @@
+//! 导入进度事件
+//!
+//! 截图、识别、合并、导出的进度以结构化的事件发出，GUI、网页等其他前端可以订阅事件显示进度，
+//! 不用解析日志。事件可以用回调（[`subscribe`]）或者通道（[`subscribe_channel`]）接收，
+//! 没有订阅者时发出事件什么也不做。
+
+use std::path::PathBuf;
+use std::sync::mpsc::{channel, Receiver};
+use std::sync::RwLock;
+
+use lazy_static::lazy_static;
+use serde::Serialize;
+
+use crate::export::ExportFormat;
+use crate::record::BannerType;
+
+/// 进度事件
+#[derive(Debug, Clone, PartialEq, Eq, Serialize)]
+#[serde(tag = "event", rename_all = "snake_case")]
+pub enum ProgressEvent {
+    /// 截取了一页抽卡记录，等待识别
+    PageCaptured { page: u32 },
+    /// 一页识别完成
+    PageParsed {
+        page: u32,
+        /// 这一页识别成功的抽卡记录数量
+        records: usize,
+        /// 这一页识别失败的行数
+        invalid_rows: usize,
+    },
+    /// 与已保存的抽卡记录合并完成
+    MergeDone {
+        account_id: String,
+        banner_type: BannerType,
+        /// 新增的抽卡记录数量
+        added: u32,
+    },
+    /// 写入了一个导出文件
+    ExportWritten { format: ExportFormat, path: PathBuf },
+}
+
+/// 订阅者，返回 `false` 时取消订阅
+type Subscriber = Box<dyn Fn(&ProgressEvent) -> bool + Send + Sync>;
+
+lazy_static! {
+    static ref SUBSCRIBERS: RwLock<Vec<Subscriber>> = RwLock::new(vec![]);
+}
+
+/// 用回调订阅进度事件，回调在发出事件的线程中调用，不能在回调中再订阅
+pub fn subscribe(callback: impl Fn(&ProgressEvent) + Send + Sync + 'static) {
+    SUBSCRIBERS.write().unwrap().push(Box::new(move |event| {
+        callback(event);
+        true
+    }));
+}
+
+/// 用通道订阅进度事件，接收端被丢弃后自动取消订阅
+pub fn subscribe_channel() -> Receiver<ProgressEvent> {
+    let (sender, receiver) = channel();
+    SUBSCRIBERS
+        .write()
+        .unwrap()
+        .push(Box::new(move |event| sender.send(event.clone()).is_ok()));
+    receiver
+}
+
+/// 发出进度事件
+pub fn emit(event: ProgressEvent) {
+    log::trace!("progress: {:?}", event);
+    SUBSCRIBERS
+        .write()
+        .unwrap()
+        .retain(|subscriber| subscriber(&event));
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    #[test]
+    fn test_subscribe_channel() {
+        let receiver = subscribe_channel();
+        // 其他测试也可能发出事件
+        let event = ProgressEvent::PageParsed {
+            page: 12345,
+            records: 9,
+            invalid_rows: 1,
+        };
+        emit(event.clone());
+        assert!(receiver.try_iter().any(|received| received == event));
+        assert_eq!(
+            serde_json::to_string(&event).unwrap(),
+            r#"{"event":"page_parsed","page":12345,"records":9,"invalid_rows":1}"#
+        );
+
+        let subscriber_num = SUBSCRIBERS.read().unwrap().len();
+        drop(receiver);
+        emit(ProgressEvent::PageCaptured { page: 1 });
+        assert!(SUBSCRIBERS.read().unwrap().len() < subscriber_num);
+    }
+}
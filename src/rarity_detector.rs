@@ -0,0 +1,204 @@
+//! 星级识别
+//!
+//! 抽卡记录每行左侧的颜色表示星级。每行在颜色条上采样一小块像素，逐个像素与参考颜色比较后
+//! 多数投票，参考颜色可以用校准后的颜色（见 [`StarColors::calibrate`]）。
+
+use anyhow::{anyhow, Result};
+use serde::{Deserialize, Serialize};
+
+/// 星级的参考颜色
+/// 默认值是游戏中的颜色，开启 HDR 或护眼模式时颜色会偏移，可以在设置中校准
+#[derive(Debug, Copy, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(default)]
+pub struct StarColors {
+    pub star_3: [u8; 3],
+    pub star_4: [u8; 3],
+    pub star_5: [u8; 3],
+    /// 与参考颜色的欧氏距离小于这个值时视为对应的星级
+    pub tolerance: f32,
+}
+
+/// 未校准时的容差
+static DEFAULT_TOLERANCE: f32 = 5.0;
+/// 最大与最小通道的差值小于这个值的颜色视为背景的灰色，不参与校准
+static MIN_STAR_SATURATION: u8 = 60;
+
+impl Default for StarColors {
+    fn default() -> Self {
+        Self {
+            star_3: [55, 98, 242],
+            star_4: [192, 105, 214],
+            star_5: [233, 155, 55],
+            tolerance: DEFAULT_TOLERANCE,
+        }
+    }
+}
+
+impl StarColors {
+    fn references(&self) -> [(u8, [u8; 3]); 3] {
+        [(3, self.star_3), (4, self.star_4), (5, self.star_5)]
+    }
+
+    /// 与颜色最接近的参考颜色
+    /// # 返回
+    /// （星级, 距离的平方）
+    fn nearest(&self, rgb: [u8; 3]) -> (u8, u32) {
+        self.references()
+            .into_iter()
+            .map(|(star, reference)| (star, distance_squared(rgb, reference)))
+            .min_by_key(|(_, distance)| *distance)
+            .unwrap()
+    }
+
+    /// RGB 颜色转换为星级
+    pub fn star(&self, rgb: [u8; 3]) -> Result<u8> {
+        RarityDetector::new(self)
+            .classify(rgb)
+            .ok_or_else(|| anyhow!("Unknown star RGB: {:?}", rgb))
+    }
+
+    /// 根据采样的颜色校准
+    /// 每个颜色归到最接近的参考颜色，参考颜色更新为这些颜色的平均值，
+    /// 容差放宽到能覆盖所有采样的颜色。没有采样到的星级保持原来的参考颜色。
+    /// # 返回
+    /// （校准后的颜色, 校准了的星级）
+    pub fn calibrate(&self, samples: &[[u8; 3]]) -> Result<(Self, Vec<u8>)> {
+        let samples = samples
+            .iter()
+            .filter(|rgb| {
+                rgb.iter().max().unwrap() - rgb.iter().min().unwrap() >= MIN_STAR_SATURATION
+            })
+            .collect::<Vec<_>>();
+        if samples.is_empty() {
+            return Err(anyhow!("no star colors found"));
+        }
+        let mut calibrated = *self;
+        let mut stars = vec![];
+        let mut max_deviation: f32 = 0.0;
+        for (star, _) in self.references() {
+            let group = samples
+                .iter()
+                .filter(|&&&rgb| self.nearest(rgb).0 == star)
+                .collect::<Vec<_>>();
+            if group.is_empty() {
+                continue;
+            }
+            let mut mean = [0u8; 3];
+            for (channel, value) in mean.iter_mut().enumerate() {
+                let sum = group.iter().map(|rgb| rgb[channel] as u32).sum::<u32>();
+                *value = (sum as f32 / group.len() as f32).round() as u8;
+            }
+            for &&&rgb in group.iter() {
+                max_deviation = max_deviation.max((distance_squared(rgb, mean) as f32).sqrt());
+            }
+            match star {
+                3 => calibrated.star_3 = mean,
+                4 => calibrated.star_4 = mean,
+                _ => calibrated.star_5 = mean,
+            }
+            stars.push(star);
+        }
+        calibrated.tolerance = DEFAULT_TOLERANCE + max_deviation;
+        Ok((calibrated, stars))
+    }
+}
+
+/// 两个 RGB 颜色欧氏距离的平方，只用整数运算
+fn distance_squared(rgb1: [u8; 3], rgb2: [u8; 3]) -> u32 {
+    rgb1.iter()
+        .zip(rgb2)
+        .map(|(&a, b)| (a as i32 - b as i32).pow(2) as u32)
+        .sum()
+}
+
+/// 按参考颜色判断星级
+///
+/// 创建时把容差换算为距离的平方，之后每个像素只需要整数运算。
+#[derive(Debug, Copy, Clone, PartialEq, Eq)]
+pub struct RarityDetector {
+    references: [(u8, [u8; 3]); 3],
+    /// 距离的平方小于这个值时视为对应的星级
+    max_distance_squared: u32,
+}
+
+impl RarityDetector {
+    /// 用参考颜色（可以是校准后的颜色）创建
+    pub fn new(star_colors: &StarColors) -> Self {
+        let tolerance = star_colors.tolerance.max(0.0);
+        Self {
+            references: star_colors.references(),
+            // 距离是整数的平方根，小于容差等价于平方小于容差平方向上取整
+            max_distance_squared: (tolerance * tolerance).ceil() as u32,
+        }
+    }
+
+    /// 一个像素的星级，不接近任何参考颜色时返回 None
+    pub fn classify(&self, rgb: [u8; 3]) -> Option<u8> {
+        self.references
+            .iter()
+            .map(|&(star, reference)| (star, distance_squared(rgb, reference)))
+            .min_by_key(|(_, distance)| *distance)
+            .filter(|(_, distance)| *distance < self.max_distance_squared)
+            .map(|(star, _)| star)
+    }
+
+    /// 一小块采样像素的星级，按多数投票
+    /// 超过一半的像素是同一个星级时才返回这个星级，个别像素受到压缩噪点、
+    /// 界面动画影响时不会判断错误
+    pub fn classify_patch(&self, patch: &[[u8; 3]]) -> Option<u8> {
+        let mut votes = [0usize; 3];
+        for &rgb in patch {
+            if let Some(index) = self
+                .classify(rgb)
+                .and_then(|star| self.references.iter().position(|(s, _)| *s == star))
+            {
+                votes[index] += 1;
+            }
+        }
+        let (index, &count) = votes.iter().enumerate().max_by_key(|(_, count)| **count)?;
+        (count * 2 > patch.len()).then_some(self.references[index].0)
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    #[test]
+    fn test_calibrate_star_colors() {
+        let star_colors = StarColors::default();
+        assert_eq!(star_colors.star([55, 98, 242]).unwrap(), 3);
+        // HDR 下整体偏亮的颜色
+        let samples = [
+            [75, 120, 255],
+            [77, 118, 255],
+            [215, 125, 235],
+            [200, 200, 200],
+        ];
+        assert!(star_colors.star(samples[0]).is_err());
+        let (calibrated, stars) = star_colors.calibrate(&samples).unwrap();
+        assert_eq!(stars, [3, 4]);
+        assert_eq!(calibrated.star_3, [76, 119, 255]);
+        assert_eq!(calibrated.star_5, star_colors.star_5);
+        assert!(calibrated.tolerance > star_colors.tolerance);
+        for rgb in samples[..3].iter() {
+            assert!(calibrated.star(*rgb).is_ok());
+        }
+        assert!(calibrated.star([200, 200, 200]).is_err());
+        assert!(star_colors.calibrate(&[[200, 200, 200]]).is_err());
+    }
+
+    #[test]
+    fn test_classify_patch() {
+        let star_colors = StarColors::default();
+        let detector = RarityDetector::new(&star_colors);
+        // 与浮点数的欧氏距离结果一致
+        assert_eq!(detector.classify([58, 98, 242]), Some(3));
+        assert_eq!(detector.classify([60, 98, 242]), None);
+        let five = star_colors.star_5;
+        let noise = [120, 120, 120];
+        assert_eq!(detector.classify_patch(&[five, five, noise]), Some(5));
+        assert_eq!(detector.classify_patch(&[five, noise, noise]), None);
+        assert_eq!(detector.classify_patch(&[]), None);
+    }
+}
@@ -0,0 +1,96 @@
+//! 5星概率模型
+//!
+//! 每个卡池的基础概率、软保底开始的抽数和当期限定物品的概率都在 [`rate_model`] 中，
+//! 游戏调整概率时只需要修改这里的数据。软保底开始后概率逐抽线性增加，
+//! 到硬保底（[`BannerType::pity_count`]）时为 100%。
+
+use crate::record::BannerType;
+
+/// 一个卡池的5星概率模型
+#[derive(Debug, Copy, Clone, PartialEq)]
+pub struct RateModel {
+    /// 5星的基础概率
+    pub base_rate: f64,
+    /// 距离上一个5星第几抽开始概率增加
+    pub soft_pity_start: u32,
+    /// 硬保底的抽数
+    pub hard_pity: u32,
+    /// 5星中当期限定物品的概率，非限定卡池为 None
+    pub featured_rate: Option<f64>,
+}
+
+/// 卡池的5星概率模型
+pub fn rate_model(banner_type: BannerType) -> RateModel {
+    let (base_rate, soft_pity_start, featured_rate) = match banner_type {
+        BannerType::LimitedCharacter100Percent => (0.01, 76, Some(1.0)),
+        BannerType::LimitedWeapon100Percent => (0.015, 61, Some(1.0)),
+        BannerType::LimitedCharacter50Percent => (0.02, 61, Some(0.5)),
+        BannerType::LimitedWeapon50Percent => (0.025, 46, Some(0.5)),
+        BannerType::PermanentCharacter => (0.02, 61, None),
+        BannerType::PermanentWeapon => (0.025, 46, None),
+        BannerType::Beginner => (0.02, 38, None),
+    };
+    RateModel {
+        base_rate,
+        soft_pity_start,
+        hard_pity: banner_type.pity_count(),
+        featured_rate,
+    }
+}
+
+impl RateModel {
+    /// 距离上一个5星第 `pull` 抽（从 1 开始）出5星的概率
+    pub fn five_star_probability(&self, pull: u32) -> f64 {
+        if pull >= self.hard_pity {
+            1.0
+        } else if pull >= self.soft_pity_start {
+            let ramp = (pull - self.soft_pity_start + 1) as f64
+                / (self.hard_pity - self.soft_pity_start + 1) as f64;
+            self.base_rate + (1.0 - self.base_rate) * ramp
+        } else {
+            self.base_rate
+        }
+    }
+
+    /// 在第 `pull` 抽之前（含）出5星的概率
+    pub fn cumulative(&self, pull: u32) -> f64 {
+        let not_yet = (1..=pull.min(self.hard_pity))
+            .map(|pull| 1.0 - self.five_star_probability(pull))
+            .product::<f64>();
+        1.0 - not_yet
+    }
+
+    /// 出一个5星的期望抽数
+    pub fn expected_pulls(&self) -> f64 {
+        (0..self.hard_pity)
+            .map(|pull| 1.0 - self.cumulative(pull))
+            .sum()
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use enum_iterator::all;
+
+    use super::*;
+
+    #[test]
+    fn test_rate_model() {
+        for banner_type in all::<BannerType>() {
+            let model = rate_model(banner_type);
+            assert_eq!(model.cumulative(0), 0.0);
+            assert_eq!(model.cumulative(model.hard_pity), 1.0);
+            assert!(model.soft_pity_start < model.hard_pity);
+            for pull in 1..model.hard_pity {
+                assert!(model.five_star_probability(pull) <= model.five_star_probability(pull + 1));
+            }
+        }
+
+        // 固定累积分布，调整概率时需要同时修改这里
+        let model = rate_model(BannerType::PermanentWeapon);
+        let cumulative = [1, 10, 45, 50, 55, 59]
+            .map(|pull| (model.cumulative(pull) * 10000.0).round() / 10000.0);
+        assert_eq!(cumulative, [0.025, 0.2237, 0.68, 0.9108, 0.9984, 1.0]);
+        assert!((model.expected_pulls() - 28.5726).abs() < 1e-4);
+    }
+}
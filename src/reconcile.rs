@@ -0,0 +1,65 @@
+//! 与游戏内抽卡总数核对
+//!
+//! 翻页时如果有页面被跳过，导入不会报错，只会少记录几抽。
+//! 用户输入游戏内显示的卡池总抽数后，与保存的抽卡记录数量比较，及早发现漏掉的页面。
+
+use crate::record::{BannerType, OneAccountRecords};
+
+/// 一个卡池的核对结果
+#[derive(Debug, Copy, Clone, PartialEq, Eq)]
+pub enum Reconciliation {
+    /// 数量一致
+    Match,
+    /// 保存的记录比游戏内少，可能漏掉了页面
+    Missing(u32),
+    /// 保存的记录比游戏内多，可能有重复的记录或其他卡池的记录
+    Extra(u32),
+}
+
+/// 账号中一个卡池保存的抽卡记录数量
+pub fn stored_pulls(account_records: Option<&OneAccountRecords>, banner_type: BannerType) -> u32 {
+    account_records
+        .and_then(|account_records| account_records.records.get(&banner_type))
+        .map_or(0, Vec::len) as u32
+}
+
+/// 比较保存的抽卡记录数量和游戏内的总抽数
+pub fn reconcile(stored: u32, in_game: u32) -> Reconciliation {
+    if stored < in_game {
+        Reconciliation::Missing(in_game - stored)
+    } else if stored > in_game {
+        Reconciliation::Extra(stored - in_game)
+    } else {
+        Reconciliation::Match
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use std::collections::HashMap;
+
+    use super::*;
+    use crate::record::{ItemType, OneRecord};
+
+    #[test]
+    fn test_reconcile() {
+        assert_eq!(reconcile(30, 30), Reconciliation::Match);
+        assert_eq!(reconcile(20, 30), Reconciliation::Missing(10));
+        assert_eq!(reconcile(31, 30), Reconciliation::Extra(1));
+
+        let record = OneRecord::new(3, "a".to_string(), ItemType::Weapon, 0);
+        let account_records = OneAccountRecords::new(
+            "1".to_string(),
+            HashMap::from([(BannerType::PermanentWeapon, vec![record; 3])]),
+        );
+        assert_eq!(
+            stored_pulls(Some(&account_records), BannerType::PermanentWeapon),
+            3
+        );
+        assert_eq!(
+            stored_pulls(Some(&account_records), BannerType::Beginner),
+            0
+        );
+        assert_eq!(stored_pulls(None, BannerType::PermanentWeapon), 0);
+    }
+}
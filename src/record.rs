@@ -1,54 +1,110 @@
+use std::cmp::{Ordering, Reverse};
 use std::collections::HashMap;
 use std::fs::File;
+use std::hash::{Hash, Hasher};
 use std::path::Path;
 
-use anyhow::{anyhow, Result};
+use anyhow::{anyhow, Context, Result};
 use chrono::{Local, TimeZone};
+use clap::ValueEnum;
 use enum_iterator::{all, Sequence};
 use serde::{Deserialize, Serialize};
+use sha2::{Digest, Sha256};
 
+use crate::banner_schedule::{banner_schedule, BannerSchedule};
+use crate::data_dir::{data_path, write_atomic};
+use crate::error::{save_error, ExportError};
+use crate::i18n::{format_translation, language, tr, translate};
+use crate::items::canonical_name;
 use crate::language::Language;
+use crate::stats::{guarantee_state, pity_counts, pull_groups};
 
 /// 卡池类型
-#[derive(Debug, Copy, Clone, PartialEq, Eq, Hash, Serialize, Deserialize, Sequence)]
+#[derive(Debug, Copy, Clone, PartialEq, Eq, Hash, Serialize, Deserialize, Sequence, ValueEnum)]
+#[cfg_attr(feature = "schema", derive(schemars::JsonSchema))]
 pub enum BannerType {
     /// 100%限定角色池
+    #[value(name = "limited-character-100")]
     LimitedCharacter100Percent,
     /// 100%限定武器池
+    #[value(name = "limited-weapon-100")]
     LimitedWeapon100Percent,
     /// 50%限定角色池
+    #[value(name = "limited-character-50")]
     LimitedCharacter50Percent,
     /// 50%限定武器池
+    #[value(name = "limited-weapon-50")]
     LimitedWeapon50Percent,
     /// 常驻角色池
+    #[value(name = "permanent-character")]
     PermanentCharacter,
     /// 常驻武器池
+    #[value(name = "permanent-weapon")]
     PermanentWeapon,
     /// 新手池
+    #[value(name = "beginner")]
     Beginner,
 }
 
 impl BannerType {
-    pub fn display_name_for_user(&self, language: Language) -> &str {
-        match language {
-            Language::ChineseSimplified => match self {
-                BannerType::LimitedCharacter100Percent => "100%限定角色池",
-                BannerType::LimitedWeapon100Percent => "100%限定武器池",
-                BannerType::LimitedCharacter50Percent => "50%限定角色池",
-                BannerType::LimitedWeapon50Percent => "50%限定武器池",
-                BannerType::PermanentCharacter => "常驻角色池",
-                BannerType::PermanentWeapon => "常驻武器池",
-                BannerType::Beginner => "新手池",
-            },
-            Language::English => match self {
-                BannerType::LimitedCharacter100Percent => "100% Limited Character Banner",
-                BannerType::LimitedWeapon100Percent => "100% Limited Weapon Banner",
-                BannerType::LimitedCharacter50Percent => "50% Limited Character Banner",
-                BannerType::LimitedWeapon50Percent => "50% Limited Weapon Banner",
-                BannerType::PermanentCharacter => "Permanent Character Banner",
-                BannerType::PermanentWeapon => "Permanent Weapon Banner",
-                BannerType::Beginner => "Beginner Banner",
-            },
+    pub fn display_name_for_user(&self, language: Language) -> &'static str {
+        let key = match self {
+            BannerType::LimitedCharacter100Percent => "banner.limited_character_100",
+            BannerType::LimitedWeapon100Percent => "banner.limited_weapon_100",
+            BannerType::LimitedCharacter50Percent => "banner.limited_character_50",
+            BannerType::LimitedWeapon50Percent => "banner.limited_weapon_50",
+            BannerType::PermanentCharacter => "banner.permanent_character",
+            BannerType::PermanentWeapon => "banner.permanent_weapon",
+            BannerType::Beginner => "banner.beginner",
+        };
+        translate(language, key)
+    }
+
+    /// 按卡池分文件导出时的文件名（不含扩展名）
+    pub fn file_stem(&self) -> &'static str {
+        match self {
+            BannerType::LimitedCharacter100Percent => "Limited100_Character",
+            BannerType::LimitedWeapon100Percent => "Limited100_Weapon",
+            BannerType::LimitedCharacter50Percent => "Limited50_Character",
+            BannerType::LimitedWeapon50Percent => "Limited50_Weapon",
+            BannerType::PermanentCharacter => "Permanent_Character",
+            BannerType::PermanentWeapon => "Permanent_Weapon",
+            BannerType::Beginner => "Beginner",
+        }
+    }
+
+    /// 是否是限定卡池
+    pub fn is_limited(&self) -> bool {
+        matches!(
+            self,
+            BannerType::LimitedCharacter100Percent
+                | BannerType::LimitedWeapon100Percent
+                | BannerType::LimitedCharacter50Percent
+                | BannerType::LimitedWeapon50Percent
+        )
+    }
+
+    /// 卡池的物品类型，新手池没有固定的物品类型
+    pub fn item_type(&self) -> Option<ItemType> {
+        match self {
+            BannerType::LimitedCharacter100Percent
+            | BannerType::LimitedCharacter50Percent
+            | BannerType::PermanentCharacter => Some(ItemType::Character),
+            BannerType::LimitedWeapon100Percent
+            | BannerType::LimitedWeapon50Percent
+            | BannerType::PermanentWeapon => Some(ItemType::Weapon),
+            BannerType::Beginner => None,
+        }
+    }
+
+    /// 这个卡池能否抽到这个物品，用于发现游戏中打开的卡池与选择的不一致
+    ///
+    /// 武器池只有武器；角色池的5星一定是角色，较低星级也可能是武器；新手池不检查。
+    pub fn accepts(&self, item_type: ItemType, star: u8) -> bool {
+        match self.item_type() {
+            Some(ItemType::Weapon) => item_type == ItemType::Weapon,
+            Some(ItemType::Character) => item_type == ItemType::Character || star < 5,
+            None => true,
         }
     }
 
@@ -67,6 +123,7 @@ impl BannerType {
 
 /// 抽卡物品类型
 #[derive(Debug, Copy, Clone, PartialEq, Eq, Hash, Serialize, Deserialize, Sequence)]
+#[cfg_attr(feature = "schema", derive(schemars::JsonSchema))]
 pub enum ItemType {
     /// 角色
     Character,
@@ -75,20 +132,15 @@ pub enum ItemType {
 }
 
 impl ItemType {
-    pub fn display_name_for_user(&self, language: Language) -> &str {
-        match language {
-            Language::ChineseSimplified => match self {
-                ItemType::Character => "角色",
-                ItemType::Weapon => "武器",
-            },
-            Language::English => match self {
-                ItemType::Character => "Operative",
-                ItemType::Weapon => "Weapon",
-            },
-        }
+    pub fn display_name_for_user(&self, language: Language) -> &'static str {
+        let key = match self {
+            ItemType::Character => "item_type.character",
+            ItemType::Weapon => "item_type.weapon",
+        };
+        translate(language, key)
     }
 
-    pub fn display_name_in_record_page_in_game(&self, language: Language) -> &str {
+    pub fn display_name_in_record_page_in_game(&self, language: Language) -> &'static str {
         match language {
             Language::ChineseSimplified => match self {
                 ItemType::Character => "角色",
@@ -101,20 +153,172 @@ impl ItemType {
         }
     }
 
-    pub fn display_names_in_record_page_in_game_in_all_languages(&self) -> Vec<&str> {
+    pub fn display_names_in_record_page_in_game_in_all_languages(&self) -> Vec<&'static str> {
         all::<Language>()
             .map(|language| self.display_name_in_record_page_in_game(language))
             .collect()
     }
 }
 
+/// 抽卡记录的来源
+#[derive(Debug, Copy, Clone, PartialEq, Eq, Serialize, Deserialize)]
+#[cfg_attr(feature = "schema", derive(schemars::JsonSchema))]
+pub enum RecordSource {
+    /// 从游戏截图中识别
+    Ocr,
+    /// 从 JSON 文件导入
+    JsonImport,
+    /// 从接口获取
+    Api,
+    /// 从剪贴板中的截图识别
+    Clipboard,
+    /// 从其他抽卡记录工具导出的文件导入
+    OtherTool,
+}
+
+/// 一次导入，同一次导入新增的抽卡记录属于同一批
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+#[cfg_attr(feature = "schema", derive(schemars::JsonSchema))]
+pub struct ImportBatch {
+    /// 批次ID，导入时间的毫秒时间戳
+    pub id: u64,
+    pub banner_type: BannerType,
+    /// 导入时间戳（秒）
+    pub imported_at: u64,
+    /// 导入时的程序版本
+    pub tool_version: String,
+    pub source: RecordSource,
+    /// 新增抽卡记录数量
+    pub added: u32,
+    /// 宽松模式下识别失败、没有保存的行数
+    #[serde(default)]
+    pub dropped: u32,
+}
+
+impl ImportBatch {
+    pub fn new(banner_type: BannerType, source: RecordSource) -> Self {
+        let now = Local::now();
+        Self {
+            id: now.timestamp_millis() as u64,
+            banner_type,
+            imported_at: now.timestamp() as u64,
+            tool_version: env!("CARGO_PKG_VERSION").to_string(),
+            source,
+            added: 0,
+            dropped: 0,
+        }
+    }
+
+    pub fn readable_imported_at(&self) -> String {
+        let date_time = Local
+            .timestamp_opt(self.imported_at as i64, 0)
+            .single()
+            .unwrap();
+        date_time.format("%Y-%m-%d %H:%M:%S").to_string()
+    }
+
+    /// 标记抽卡记录来自这一批
+    /// # 参数
+    /// - `page`: 抽卡记录所在的页码，不是从截图中识别的记录为 `None`
+    pub fn provenance(&self, page: Option<u32>) -> Provenance {
+        Provenance {
+            batch_id: self.id,
+            page,
+        }
+    }
+}
+
+/// 抽卡记录的出处，用于追查可疑的记录
+#[derive(Debug, Copy, Clone, PartialEq, Eq, Serialize, Deserialize)]
+#[cfg_attr(feature = "schema", derive(schemars::JsonSchema))]
+pub struct Provenance {
+    /// 所属的导入批次ID
+    pub batch_id: u64,
+    /// 所在的页码
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub page: Option<u32>,
+}
+
+/// 手动输入的游戏内保底计数，早期抽卡记录已经过期时用来补全保底计算
+#[derive(Debug, Copy, Clone, PartialEq, Eq, Serialize, Deserialize)]
+#[cfg_attr(feature = "schema", derive(schemars::JsonSchema))]
+pub struct PityAnchor {
+    pub banner_type: BannerType,
+    /// 输入计数时的时间戳，计数包含这个时间之前的所有抽卡
+    pub timestamp: u64,
+    /// 距离上一个5星已经抽了多少次
+    pub pulls_since_5_star: u32,
+    /// 距离上一个4星已经抽了多少次
+    pub pulls_since_4_star: u32,
+}
+
+/// 50%限定卡池的大保底状态，由UP物品排期表推算，抽卡记录过期后仍然保留
+#[derive(Debug, Copy, Clone, PartialEq, Eq, Serialize, Deserialize)]
+#[cfg_attr(feature = "schema", derive(schemars::JsonSchema))]
+pub struct GuaranteeState {
+    pub banner_type: BannerType,
+    /// 上一个5星不是UP物品，下一个5星必定是UP物品
+    pub guaranteed: bool,
+    /// 从上一次赢得小保底起连续歪的次数，大保底获得的5星不计入
+    pub lost_streak: u32,
+    /// 推算时最新一个5星的时间戳
+    pub timestamp: u64,
+}
+
+impl GuaranteeState {
+    /// 给用户看的状态，例如“大保底，已连续歪 1 次”
+    pub fn describe(&self, language: Language) -> String {
+        let state = if self.guaranteed {
+            translate(language, "guarantee.guaranteed")
+        } else {
+            translate(language, "guarantee.not_guaranteed")
+        };
+        if self.lost_streak == 0 {
+            return state.to_string();
+        }
+        let lost_streak = format_translation(
+            translate(language, "guarantee.lost_streak"),
+            &[("num", self.lost_streak.to_string())],
+        );
+        format!("{state}{lost_streak}")
+    }
+}
+
 /// 抽卡记录
-#[derive(Debug, Clone, PartialEq, Eq, Hash, Serialize, Deserialize)]
+/// 比较两条记录是否相同时不考虑出处
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[cfg_attr(feature = "schema", derive(schemars::JsonSchema))]
 pub struct OneRecord {
     pub star: u8,
     pub item_name: String,
     pub item_type: ItemType,
     pub timestamp: u64,
+    /// 旧版本保存的记录没有出处
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub provenance: Option<Provenance>,
+    /// 用户添加的备注，见 `annotate` 子命令，旧版本保存的记录没有备注
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub note: Option<String>,
+}
+
+impl PartialEq for OneRecord {
+    fn eq(&self, other: &Self) -> bool {
+        self.star == other.star
+            && self.item_name == other.item_name
+            && self.item_type == other.item_type
+            && self.timestamp == other.timestamp
+    }
+}
+
+impl Eq for OneRecord {}
+
+impl Hash for OneRecord {
+    fn hash<H: Hasher>(&self, state: &mut H) {
+        self.star.hash(state);
+        self.item_name.hash(state);
+        self.item_type.hash(state);
+        self.timestamp.hash(state);
+    }
 }
 
 impl OneRecord {
@@ -124,25 +328,146 @@ impl OneRecord {
             item_name,
             item_type,
             timestamp,
+            provenance: None,
+            note: None,
         }
     }
 
     pub fn readable_date_time_str(&self) -> String {
-        let date_time = Local
-            .timestamp_opt(self.timestamp as i64, 0)
-            .single()
-            .unwrap();
-        date_time.format("%Y-%m-%d %H:%M").to_string()
+        readable_time(self.timestamp)
     }
 }
 
+/// 时间戳转换为抽卡记录界面中的时间格式
+pub fn readable_time(timestamp: u64) -> String {
+    let date_time = Local.timestamp_opt(timestamp as i64, 0).single().unwrap();
+    date_time.format("%Y-%m-%d %H:%M").to_string()
+}
+
 /// 合并抽卡记录
-/// 两个抽卡记录按时间顺序合并
+/// 两个抽卡记录按时间顺序合并，两边的备注都保留
 /// # 返回
 /// （合并后的抽卡记录，新增抽卡记录数量）
 pub fn merge_gacha_records(
     new_records: &[OneRecord],
     old_records: &[OneRecord],
+) -> Result<(Vec<OneRecord>, u32)> {
+    let (mut merged_records, add_num) = merge_records_in_order(new_records, old_records)?;
+    restore_notes(&mut merged_records, new_records.iter().chain(old_records));
+    Ok((merged_records, add_num))
+}
+
+/// 合并可能缺少记录的抽卡记录，宽松模式下识别失败的行没有保存，两边都可能有缺失
+/// 有缺失时按时间对齐两边重叠的部分，互相补上缺失的记录，无法对齐时按 [`merge_gacha_records`] 合并
+/// # 参数
+/// `old_gaps`、`new_gaps` 分别是旧记录和新记录中缺失的记录数量
+/// # 返回
+/// （合并后的抽卡记录，新增抽卡记录数量，合并后仍然缺失的记录数量）
+pub fn merge_gacha_records_with_gaps(
+    new_records: &[OneRecord],
+    old_records: &[OneRecord],
+    old_gaps: u32,
+    new_gaps: u32,
+) -> Result<(Vec<OneRecord>, u32, u32)> {
+    if old_gaps + new_gaps > 0 {
+        if let Some((mut merged_records, filled, covered)) =
+            align_with_gaps(new_records, old_records, old_gaps, new_gaps)
+        {
+            restore_notes(&mut merged_records, new_records.iter().chain(old_records));
+            let add_num = (merged_records.len() - old_records.len()) as u32;
+            return Ok((
+                merged_records,
+                add_num,
+                old_gaps - filled + new_gaps - covered,
+            ));
+        }
+    }
+    let (merged_records, add_num) = merge_gacha_records(new_records, old_records)?;
+    Ok((merged_records, add_num, old_gaps + new_gaps))
+}
+
+/// 按时间对齐两个有缺失的抽卡记录，重叠部分只在一边出现的记录补到另一边的缺失中
+/// 两边补上的数量分别不超过各自缺失的数量
+/// # 返回
+/// （合并后的抽卡记录，补上的旧记录缺失数量，补上的新记录缺失数量），无法对齐时为 `None`
+fn align_with_gaps(
+    new_records: &[OneRecord],
+    old_records: &[OneRecord],
+    old_gaps: u32,
+    new_gaps: u32,
+) -> Option<(Vec<OneRecord>, u32, u32)> {
+    let first_old = old_records.first()?;
+    // 新记录中在这之前的都比旧记录新
+    let start = new_records
+        .iter()
+        .position(|record| record == first_old)
+        .or_else(|| {
+            new_records
+                .iter()
+                .position(|record| record.timestamp <= first_old.timestamp)
+        })?;
+    let mut merged_records = new_records[..start].to_vec();
+    let (mut i, mut j) = (start, 0);
+    let (mut filled, mut covered) = (0, 0);
+    while i < new_records.len() && j < old_records.len() {
+        if new_records[i] == old_records[j] {
+            // 重叠的记录保留已保存的那条和它的出处
+            merged_records.push(old_records[j].clone());
+            i += 1;
+            j += 1;
+            continue;
+        }
+        // 时间较晚的一边多出了这条记录，同一分钟内优先补旧记录的缺失
+        let is_missing_in_old = match new_records[i].timestamp.cmp(&old_records[j].timestamp) {
+            Ordering::Greater => true,
+            Ordering::Less => false,
+            Ordering::Equal => filled < old_gaps,
+        };
+        if is_missing_in_old {
+            if filled == old_gaps {
+                return None;
+            }
+            filled += 1;
+            merged_records.push(new_records[i].clone());
+            i += 1;
+        } else {
+            if covered == new_gaps {
+                return None;
+            }
+            covered += 1;
+            merged_records.push(old_records[j].clone());
+            j += 1;
+        }
+    }
+    merged_records.extend(new_records[i..].iter().chain(&old_records[j..]).cloned());
+    Some((merged_records, filled, covered))
+}
+
+/// 重叠的记录只保留了其中一边，把另一边的备注补到合并后相同的记录上
+fn restore_notes<'a>(
+    merged_records: &mut [OneRecord],
+    sources: impl IntoIterator<Item = &'a OneRecord>,
+) {
+    for source in sources {
+        if source.note.is_none()
+            || merged_records
+                .iter()
+                .any(|record| record == source && record.note == source.note)
+        {
+            continue;
+        }
+        if let Some(record) = merged_records
+            .iter_mut()
+            .find(|record| record.note.is_none() && *record == source)
+        {
+            record.note.clone_from(&source.note);
+        }
+    }
+}
+
+fn merge_records_in_order(
+    new_records: &[OneRecord],
+    old_records: &[OneRecord],
 ) -> Result<(Vec<OneRecord>, u32)> {
     // 抽卡记录是按时间倒序排列的，最新的在最前面
     if new_records.is_empty() {
@@ -152,39 +477,59 @@ pub fn merge_gacha_records(
         return Ok((new_records.to_vec(), new_records.len() as u32));
     }
 
+    // 一个抽卡记录完整包含另一个时，不需要合并
+    if is_sub_records(new_records, old_records) {
+        return Ok((old_records.to_vec(), 0));
+    }
+    if let Some(start) = sub_records_position(old_records, new_records) {
+        let add_num = (new_records.len() - old_records.len()) as u32;
+        let merged_records = new_records[..start]
+            .iter()
+            .chain(old_records)
+            .chain(&new_records[start + old_records.len()..])
+            .cloned()
+            .collect();
+        return Ok((merged_records, add_num));
+    }
+
     // 现在不知道那个抽卡记录是新的
-    // 比较两个抽卡记录的最新时间
+    // 比较两个抽卡记录的最新时间，相同时再比较最旧时间
     // 时间晚的是新的记录
-    let new_records_first_time = new_records.first().unwrap().timestamp;
-    let old_records_first_time = old_records.first().unwrap().timestamp;
-    if new_records_first_time < old_records_first_time {
-        return merge_gacha_records(old_records, new_records);
-    }
-
-    // 两个抽卡记录的长度
-    let new_records_len = new_records.len();
-    let old_records_len = old_records.len();
-    let min_len = std::cmp::min(new_records_len, old_records_len);
-
-    // 循环 min_len..=0
-    // 如果新纪录的最后i个元素和老记录的前i个元素相同
-    // 说明新纪录的前面records1_len - i个元素是新的
-    // 把新纪录的前面records1_len - i个元素插入到老记录的前面完成合并
-    let same_num = {
-        let mut same_num = 0;
-        for i in (1..=min_len).rev() {
-            if new_records[new_records_len - i..] == old_records[..i] {
-                same_num = i;
-                break;
-            }
+    // 时间都相同时（例如同一分钟内的十连），重叠部分更多的顺序是正确的顺序
+    let time_range = |records: &[OneRecord]| {
+        (
+            records.first().unwrap().timestamp,
+            records.last().unwrap().timestamp,
+        )
+    };
+    let is_old_newer = match time_range(new_records).cmp(&time_range(old_records)) {
+        Ordering::Less => true,
+        Ordering::Greater => false,
+        Ordering::Equal => {
+            overlap_len(old_records, new_records) > overlap_len(new_records, old_records)
         }
-        same_num
     };
-    let merged_records = new_records[..new_records_len - same_num]
-        .iter()
-        .chain(old_records.iter())
-        .cloned()
-        .collect();
+    let (newer_records, older_records) = if is_old_newer {
+        (old_records, new_records)
+    } else {
+        (new_records, old_records)
+    };
+
+    // 把较新记录中不重叠的部分插入到较旧记录的前面完成合并，重叠的部分保留旧记录中的那份
+    let same_num = overlap_len(newer_records, older_records);
+    let merged_records: Vec<OneRecord> = if is_old_newer {
+        old_records
+            .iter()
+            .chain(&new_records[same_num..])
+            .cloned()
+            .collect()
+    } else {
+        new_records[..new_records.len() - same_num]
+            .iter()
+            .chain(old_records)
+            .cloned()
+            .collect()
+    };
 
     // 检查时间戳是递减的
     let is_timestamp_desc = |records: &Vec<OneRecord>| -> (bool, usize) {
@@ -204,31 +549,375 @@ pub fn merge_gacha_records(
         ));
     }
 
-    let add_num = (new_records_len - same_num) as u32;
+    // 合并的位置在同一分钟的多条记录中间时，这些记录应该正好组成若干个十连，
+    // 否则可能有重复或缺失的记录
+    let boundary = newer_records.len() - same_num;
+    if boundary > 0
+        && boundary < merged_records.len()
+        && merged_records[boundary - 1].timestamp == merged_records[boundary].timestamp
+    {
+        let timestamp = merged_records[boundary].timestamp;
+        let has_single = pull_groups(&merged_records).iter().any(|group| {
+            !group.is_ten_pull() && merged_records[group.start].timestamp == timestamp
+        });
+        if has_single {
+            log::warn!(
+                "merged records at {} do not form complete ten-pulls, records may be duplicated or missing",
+                merged_records[boundary].readable_date_time_str()
+            );
+        }
+    }
+
+    // 新增数量以旧记录为准，无论哪个是较新的记录
+    let add_num = (merged_records.len() - old_records.len()) as u32;
 
     Ok((merged_records, add_num))
 }
 
+/// 较新记录的最后 i 个元素和较旧记录的前 i 个元素相同时，最大的 i
+fn overlap_len(newer_records: &[OneRecord], older_records: &[OneRecord]) -> usize {
+    let min_len = std::cmp::min(newer_records.len(), older_records.len());
+    (1..=min_len)
+        .rev()
+        .find(|&i| newer_records[newer_records.len() - i..] == older_records[..i])
+        .unwrap_or(0)
+}
+
+/// `sub_records` 是否是 `records` 中连续的一段
+fn is_sub_records(sub_records: &[OneRecord], records: &[OneRecord]) -> bool {
+    sub_records_position(sub_records, records).is_some()
+}
+
+/// `sub_records` 在 `records` 中作为连续的一段出现的位置
+fn sub_records_position(sub_records: &[OneRecord], records: &[OneRecord]) -> Option<usize> {
+    if sub_records.is_empty() {
+        return Some(0);
+    }
+    if sub_records.len() > records.len() {
+        return None;
+    }
+    records
+        .windows(sub_records.len())
+        .position(|window| window == sub_records)
+}
+
+/// 卡池抽卡记录的校验和，抽卡记录序列化后的 SHA-256
+fn records_checksum(records: &[OneRecord]) -> String {
+    let mut hasher = Sha256::new();
+    hasher.update(serde_json::to_vec(records).unwrap_or_default());
+    format!("{:x}", hasher.finalize())
+}
+
 #[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+#[cfg_attr(feature = "schema", derive(schemars::JsonSchema))]
 pub struct OneAccountRecords {
     pub id: String,
     pub records: HashMap<BannerType, Vec<OneRecord>>,
+    /// 新增过抽卡记录的导入批次，按导入时间排序
+    #[serde(default)]
+    pub batches: Vec<ImportBatch>,
+    /// 每个卡池最近一次手动输入的保底计数
+    #[serde(default)]
+    pub pity_anchors: Vec<PityAnchor>,
+    /// 每个50%限定卡池的大保底状态
+    #[serde(default)]
+    pub guarantee_states: Vec<GuaranteeState>,
+    /// 保存时每个卡池抽卡记录的校验和，见 [`records_checksum`]
+    #[serde(default)]
+    pub checksums: HashMap<BannerType, String>,
+    /// 每个卡池宽松模式下识别失败、还没有补上的记录数量，重新导入或审核修正后补上
+    #[serde(default)]
+    pub gaps: HashMap<BannerType, u32>,
 }
 
 impl OneAccountRecords {
     pub fn new(id: String, records: HashMap<BannerType, Vec<OneRecord>>) -> Self {
-        Self { id, records }
+        Self {
+            id,
+            records,
+            batches: vec![],
+            pity_anchors: vec![],
+            guarantee_states: vec![],
+            checksums: HashMap::new(),
+            gaps: HashMap::new(),
+        }
     }
 
-    pub fn add_record(&mut self, banner_type: BannerType, records: Vec<OneRecord>) -> Result<u32> {
-        let old_records = self.records.entry(banner_type).or_default();
-        let (merged_records, add_num) = merge_gacha_records(&records, old_records)?;
+    /// 卡池中缺失的记录数量
+    pub fn gaps(&self, banner_type: BannerType) -> u32 {
+        self.gaps.get(&banner_type).copied().unwrap_or(0)
+    }
+
+    fn set_gaps(&mut self, banner_type: BannerType, gaps: u32) {
+        if gaps == 0 {
+            self.gaps.remove(&banner_type);
+        } else {
+            self.gaps.insert(banner_type, gaps);
+        }
+    }
+
+    /// 按当前的抽卡记录更新校验和
+    pub fn update_checksums(&mut self) {
+        self.checksums = self
+            .records
+            .iter()
+            .map(|(banner_type, records)| (*banner_type, records_checksum(records)))
+            .collect();
+    }
+
+    /// 校验和与抽卡记录不一致的卡池，旧版本保存的没有校验和的文件不检查
+    pub fn checksum_mismatches(&self) -> Vec<BannerType> {
+        if self.checksums.is_empty() {
+            return vec![];
+        }
+        all::<BannerType>()
+            .filter(|banner_type| {
+                let records = self.records.get(banner_type);
+                let checksum = self.checksums.get(banner_type);
+                match (records, checksum) {
+                    (Some(records), Some(checksum)) => records_checksum(records) != *checksum,
+                    (None, None) => false,
+                    // 卡池被删除或者被手动添加
+                    _ => true,
+                }
+            })
+            .collect()
+    }
+
+    /// 卡池的大保底状态，不是50%限定卡池或者无法推算时返回 None
+    pub fn guarantee_state(&self, banner_type: BannerType) -> Option<&GuaranteeState> {
+        self.guarantee_states
+            .iter()
+            .find(|state| state.banner_type == banner_type)
+    }
+
+    /// 根据抽卡记录重新推算卡池的大保底状态，无法推算时保留之前的状态
+    pub fn update_guarantee_state(&mut self, banner_type: BannerType, schedule: &BannerSchedule) {
+        let records = self
+            .records
+            .get(&banner_type)
+            .map(Vec::as_slice)
+            .unwrap_or_default();
+        let Some(state) = guarantee_state(records, banner_type, schedule) else {
+            return;
+        };
+        match self
+            .guarantee_states
+            .iter_mut()
+            .find(|other| other.banner_type == banner_type)
+        {
+            Some(other) => *other = state,
+            None => self.guarantee_states.push(state),
+        }
+    }
+
+    /// 根据抽卡记录重新推算所有卡池的大保底状态
+    /// 合并、导入、审核等修改抽卡记录的操作很多，只在保存时调用一次，见 [`TotalRecords::with_checksums`]
+    pub fn update_guarantee_states(&mut self, schedule: &BannerSchedule) {
+        for banner_type in all::<BannerType>() {
+            self.update_guarantee_state(banner_type, schedule);
+        }
+    }
+
+    /// 卡池的保底计数锚点
+    pub fn pity_anchor(&self, banner_type: BannerType) -> Option<&PityAnchor> {
+        self.pity_anchors
+            .iter()
+            .find(|anchor| anchor.banner_type == banner_type)
+    }
+
+    /// 保存保底计数锚点，同一卡池只保留时间最新的一个
+    pub fn set_pity_anchor(&mut self, anchor: PityAnchor) {
+        match self
+            .pity_anchors
+            .iter_mut()
+            .find(|other| other.banner_type == anchor.banner_type)
+        {
+            Some(other) => {
+                if other.timestamp <= anchor.timestamp {
+                    *other = anchor;
+                }
+            }
+            None => self.pity_anchors.push(anchor),
+        }
+    }
+
+    /// 移除早于 `cutoff` 的抽卡记录，移除后的保底计数保存为锚点，保底计算不受影响
+    /// # 返回
+    /// 移除的抽卡记录，按卡池分组，按时间倒序
+    pub fn prune_before(&mut self, cutoff: u64) -> HashMap<BannerType, Vec<OneRecord>> {
+        let mut pruned = HashMap::new();
+        for (&banner_type, records) in self.records.iter_mut() {
+            let Some(position) = records.iter().position(|record| record.timestamp < cutoff) else {
+                continue;
+            };
+            // 移除的最新一条记录抽完后的保底计数
+            let (pity_5, pity_4) = pity_counts(
+                records,
+                self.pity_anchors
+                    .iter()
+                    .find(|anchor| anchor.banner_type == banner_type),
+            )[position];
+            let newest = &records[position];
+            let anchor = PityAnchor {
+                banner_type,
+                timestamp: newest.timestamp,
+                pulls_since_5_star: if newest.star == 5 { 0 } else { pity_5 },
+                pulls_since_4_star: if newest.star == 4 { 0 } else { pity_4 },
+            };
+            pruned.insert(banner_type, (anchor, records.split_off(position)));
+        }
+        pruned
+            .into_iter()
+            .map(|(banner_type, (anchor, records))| {
+                self.set_pity_anchor(anchor);
+                (banner_type, records)
+            })
+            .collect()
+    }
+
+    /// 合并一批抽卡记录，有新增记录时保存这一批的信息
+    pub fn add_record(&mut self, mut batch: ImportBatch, records: Vec<OneRecord>) -> Result<u32> {
+        let old_gaps = self.gaps(batch.banner_type);
+        let old_records = self.records.entry(batch.banner_type).or_default();
+        let (merged_records, add_num, gaps) =
+            merge_gacha_records_with_gaps(&records, old_records, old_gaps, batch.dropped).context(
+                ExportError::MergeConflict {
+                    banner_type: batch.banner_type,
+                },
+            )?;
         *old_records = merged_records;
+        self.set_gaps(batch.banner_type, gaps);
+        if add_num > 0 {
+            batch.added = add_num;
+            self.batches.push(batch);
+        }
         Ok(add_num)
     }
+
+    /// 插入一条人工修正过的抽卡记录
+    /// 时间相同的记录中，同一批导入的按页码排在对应的位置，其他的排在最后
+    /// # 返回
+    /// 是否插入了，已经有相同的记录时不插入
+    pub fn insert_record(&mut self, banner_type: BannerType, record: OneRecord) -> bool {
+        let records = self.records.entry(banner_type).or_default();
+        // 同一条记录审核了两次，或者重新导入时已经补上了
+        if records.contains(&record) {
+            return false;
+        }
+        let is_after = |other: &OneRecord| match (record.provenance, other.provenance) {
+            (Some(provenance), Some(other_provenance)) => {
+                provenance.batch_id == other_provenance.batch_id
+                    && provenance.page < other_provenance.page
+            }
+            _ => false,
+        };
+        let position = records
+            .iter()
+            .position(|other| {
+                other.timestamp < record.timestamp
+                    || (other.timestamp == record.timestamp && is_after(other))
+            })
+            .unwrap_or(records.len());
+        if let Some(provenance) = record.provenance {
+            if let Some(batch) = self
+                .batches
+                .iter_mut()
+                .find(|batch| batch.id == provenance.batch_id)
+            {
+                batch.added += 1;
+            }
+        }
+        records.insert(position, record);
+        // 审核修正的记录补上了一条缺失的记录
+        let gaps = self.gaps(banner_type);
+        self.set_gaps(banner_type, gaps.saturating_sub(1));
+        true
+    }
+
+    /// 设置一条抽卡记录的备注，`note` 为 `None` 时删除备注
+    /// # 参数
+    /// - `item_name`: 物品名称，同一时间有多种物品（十连）时必须提供
+    /// - `index`: 同一时间（和名称）的记录中，按游戏中从上到下的顺序是第几条，从 1 开始，
+    ///   十连中有多条相同的物品时必须提供
+    /// # 返回
+    /// 修改的抽卡记录数量
+    pub fn annotate(
+        &mut self,
+        banner_type: BannerType,
+        timestamp: u64,
+        item_name: Option<&str>,
+        index: Option<usize>,
+        note: Option<String>,
+    ) -> Result<usize> {
+        let mut matched = self
+            .records
+            .get_mut(&banner_type)
+            .into_iter()
+            .flatten()
+            .filter(|record| record.timestamp == timestamp)
+            .filter(|record| item_name.is_none_or(|name| canonical_name(name) == record.item_name))
+            .collect::<Vec<_>>();
+        if matched.is_empty() {
+            return Err(anyhow!("{}", tr!("annotate.not_found")));
+        }
+        if let Some(index) = index {
+            let num = matched.len();
+            if index == 0 || index > num {
+                return Err(anyhow!(
+                    "{}",
+                    tr!("annotate.index_out_of_range", index = index, num = num)
+                ));
+            }
+            matched = vec![matched.swap_remove(index - 1)];
+        }
+        let mut names = matched
+            .iter()
+            .map(|record| record.item_name.clone())
+            .collect::<Vec<_>>();
+        names.sort();
+        names.dedup();
+        if names.len() > 1 {
+            return Err(anyhow!(
+                "{}",
+                tr!("annotate.ambiguous", names = names.join(", "))
+            ));
+        }
+        if matched.len() > 1 {
+            return Err(anyhow!(
+                "{}",
+                tr!("annotate.duplicate", name = names[0], num = matched.len())
+            ));
+        }
+        let note = note.filter(|note| !note.trim().is_empty());
+        for record in matched.iter_mut() {
+            record.note.clone_from(&note);
+        }
+        Ok(matched.len())
+    }
+
+    /// 撤销一个卡池最近一次导入，删除这一批新增的抽卡记录
+    /// # 返回
+    /// 被撤销的导入批次，没有可以撤销的导入时返回 `None`
+    pub fn undo_last_batch(&mut self, banner_type: BannerType) -> Option<ImportBatch> {
+        let position = self
+            .batches
+            .iter()
+            .rposition(|batch| batch.banner_type == banner_type)?;
+        let batch = self.batches.remove(position);
+        if let Some(records) = self.records.get_mut(&banner_type) {
+            records.retain(|record| {
+                record
+                    .provenance
+                    .is_none_or(|provenance| provenance.batch_id != batch.id)
+            });
+        }
+        Some(batch)
+    }
 }
 
 #[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+#[cfg_attr(feature = "schema", derive(schemars::JsonSchema))]
 pub struct TotalRecords {
     /// 账号ID -> 账号抽卡记录
     pub records: HashMap<String, OneAccountRecords>,
@@ -242,31 +931,270 @@ impl TotalRecords {
     pub fn add_record(
         &mut self,
         account_id: String,
-        banner_type: BannerType,
+        batch: ImportBatch,
         records: Vec<OneRecord>,
     ) -> Result<u32> {
         let account_records = self
             .records
             .entry(account_id.clone())
             .or_insert_with(|| OneAccountRecords::new(account_id.clone(), HashMap::new()));
-        account_records.add_record(banner_type, records)
+        account_records.add_record(batch, records)
+    }
+
+    /// 合并另一份抽卡记录，例如另一台电脑上的抽卡记录
+    /// 两边宽松模式下缺失的记录按 [`merge_gacha_records_with_gaps`] 互相补上
+    /// # 返回
+    /// 新增抽卡记录数量
+    pub fn merge(&mut self, mut other: TotalRecords) -> Result<u32> {
+        other.normalize_item_names();
+        let mut add_num = 0;
+        for (account_id, other_account_records) in other.records {
+            let account_records = self
+                .records
+                .entry(account_id.clone())
+                .or_insert_with(|| OneAccountRecords::new(account_id, HashMap::new()));
+            for (banner_type, other_records) in other_account_records.records {
+                let old_gaps = account_records.gaps(banner_type);
+                let other_gaps = other_account_records
+                    .gaps
+                    .get(&banner_type)
+                    .copied()
+                    .unwrap_or(0);
+                let records = account_records.records.entry(banner_type).or_default();
+                let (merged_records, merged_add_num, gaps) =
+                    merge_gacha_records_with_gaps(&other_records, records, old_gaps, other_gaps)
+                        .context(ExportError::MergeConflict { banner_type })?;
+                add_num += merged_add_num;
+                *records = merged_records;
+                account_records.set_gaps(banner_type, gaps);
+            }
+            for batch in other_account_records.batches {
+                if !account_records.batches.iter().any(|b| b.id == batch.id) {
+                    account_records.batches.push(batch);
+                }
+            }
+            account_records.batches.sort_by_key(|batch| batch.id);
+            for anchor in other_account_records.pity_anchors {
+                account_records.set_pity_anchor(anchor);
+            }
+        }
+        Ok(add_num)
+    }
+
+    /// 修改账号ID
+    /// # 错误
+    /// 账号不存在，或者新的账号ID已经存在（这时应该合并账号）
+    pub fn rename_account(&mut self, old_id: &str, new_id: &str) -> Result<()> {
+        if self.records.contains_key(new_id) {
+            return Err(anyhow!("account {new_id} already exists"));
+        }
+        let mut account_records = self
+            .records
+            .remove(old_id)
+            .ok_or_else(|| anyhow!("account {old_id} not found"))?;
+        account_records.id = new_id.to_string();
+        self.records.insert(new_id.to_string(), account_records);
+        Ok(())
+    }
+
+    /// 把账号 `from_id` 的抽卡记录按卡池合并到账号 `into_id`，然后删除 `from_id`
+    /// 合并失败时不修改抽卡记录
+    /// # 返回
+    /// `into_id` 新增抽卡记录数量
+    pub fn merge_accounts(&mut self, from_id: &str, into_id: &str) -> Result<u32> {
+        if from_id == into_id {
+            return Err(anyhow!("cannot merge account {from_id} into itself"));
+        }
+        let mut merged = self.clone();
+        let mut from_records = merged
+            .records
+            .remove(from_id)
+            .ok_or_else(|| anyhow!("account {from_id} not found"))?;
+        from_records.id = into_id.to_string();
+        let add_num = merged.merge(TotalRecords::new(HashMap::from([(
+            into_id.to_string(),
+            from_records,
+        )])))?;
+        *self = merged;
+        Ok(add_num)
+    }
+
+    /// 保存一个账号一个卡池的保底计数锚点
+    pub fn set_pity_anchor(&mut self, account_id: String, anchor: PityAnchor) {
+        self.records
+            .entry(account_id.clone())
+            .or_insert_with(|| OneAccountRecords::new(account_id, HashMap::new()))
+            .set_pity_anchor(anchor);
+    }
+
+    /// 插入一条人工修正过的抽卡记录，已经有相同的记录时不插入
+    pub fn insert_record(
+        &mut self,
+        account_id: String,
+        banner_type: BannerType,
+        record: OneRecord,
+    ) -> bool {
+        self.records
+            .entry(account_id.clone())
+            .or_insert_with(|| OneAccountRecords::new(account_id, HashMap::new()))
+            .insert_record(banner_type, record)
+    }
+
+    /// 撤销一个账号一个卡池最近一次导入
+    pub fn undo_last_batch(
+        &mut self,
+        account_id: &str,
+        banner_type: BannerType,
+    ) -> Option<ImportBatch> {
+        self.records
+            .get_mut(account_id)?
+            .undo_last_batch(banner_type)
+    }
+
+    /// 按保留策略移除早于 `cutoff` 的抽卡记录
+    /// # 返回
+    /// 移除的抽卡记录，用于归档
+    pub fn prune_before(&mut self, cutoff: u64) -> TotalRecords {
+        let mut archived = TotalRecords::default();
+        for (account_id, account_records) in self.records.iter_mut() {
+            let pruned = account_records.prune_before(cutoff);
+            if !pruned.is_empty() {
+                archived.records.insert(
+                    account_id.clone(),
+                    OneAccountRecords::new(account_id.clone(), pruned),
+                );
+            }
+        }
+        archived
+    }
+
+    /// 按时间倒序重新排序每个卡池的抽卡记录，时间相同的记录保持原顺序
+    /// # 返回
+    /// 顺序被修正的卡池数量
+    pub fn repair(&mut self) -> usize {
+        let mut repaired_num = 0;
+        for account_records in self.records.values_mut() {
+            for records in account_records.records.values_mut() {
+                if records.windows(2).any(|w| w[0].timestamp < w[1].timestamp) {
+                    records.sort_by_key(|record| Reverse(record.timestamp));
+                    repaired_num += 1;
+                }
+            }
+        }
+        repaired_num
     }
 
     pub fn save(&self) -> Result<()> {
-        let path = "records/records.json";
-        if let Some(parent) = Path::new(path).parent() {
+        self.save_to(data_path("records/records.json"))
+    }
+
+    pub fn save_to(&self, path: impl AsRef<Path>) -> Result<()> {
+        let path = path.as_ref();
+        if let Some(parent) = path.parent() {
             std::fs::create_dir_all(parent)?;
         }
-        let file = File::create(path)?;
-        let writer = std::io::BufWriter::new(file);
-        serde_json::to_writer_pretty(writer, self).map_err(|e| e.into())
+        let contents = serde_json::to_vec_pretty(&self.with_checksums())?;
+        write_atomic(path, &contents).map_err(|e| save_error(e, path))
     }
 
     fn read() -> Result<Self> {
-        let path = "records/records.json";
+        Self::read_from(data_path("records/records.json"))
+    }
+
+    pub fn read_from(path: impl AsRef<Path>) -> Result<Self> {
+        let path = path.as_ref();
         let file = File::open(path)?;
         let reader = std::io::BufReader::new(file);
-        serde_json::from_reader(reader).map_err(|e| e.into())
+        let mut total_records = serde_json::from_reader::<_, Self>(reader)?;
+        total_records.warn_checksum_mismatches(&path.display().to_string());
+        total_records.normalize_item_names();
+        Ok(total_records)
+    }
+
+    /// 物品名称改为物品字典中现在的名称，见 [`canonical_name`]
+    /// 旧版本保存的名称没有规范化，读取、合并时规范化一次，比较记录时直接比较名称
+    fn normalize_item_names(&mut self) {
+        self.records
+            .values_mut()
+            .flat_map(|account_records| account_records.records.values_mut())
+            .flatten()
+            .for_each(|record| record.item_name = canonical_name(&record.item_name));
+    }
+
+    /// 是否有添加了备注的抽卡记录
+    pub fn has_notes(&self) -> bool {
+        self.records
+            .values()
+            .flat_map(|account_records| account_records.records.values())
+            .flatten()
+            .any(|record| record.note.is_some())
+    }
+
+    /// 所有抽卡记录的校验和，与账号、卡池的保存顺序无关
+    pub fn checksum(&self) -> String {
+        let mut account_ids = self.records.keys().collect::<Vec<_>>();
+        account_ids.sort();
+        let mut hasher = Sha256::new();
+        for account_id in account_ids {
+            hasher.update(account_id.as_bytes());
+            let account_records = &self.records[account_id];
+            for banner_type in all::<BannerType>() {
+                if let Some(records) = account_records.records.get(&banner_type) {
+                    hasher.update(format!("{banner_type:?}"));
+                    hasher.update(records_checksum(records));
+                }
+            }
+        }
+        format!("{:x}", hasher.finalize())
+    }
+
+    /// 更新了校验和与大保底状态的抽卡记录，用于保存
+    pub fn with_checksums(&self) -> Self {
+        let mut total_records = self.clone();
+        for account_records in total_records.records.values_mut() {
+            account_records.update_guarantee_states(banner_schedule());
+            account_records.update_checksums();
+        }
+        total_records
+    }
+
+    /// 校验和与抽卡记录不一致的账号ID和卡池
+    pub fn checksum_mismatches(&self) -> Vec<(String, BannerType)> {
+        let mut mismatches = self
+            .records
+            .iter()
+            .flat_map(|(account_id, account_records)| {
+                account_records
+                    .checksum_mismatches()
+                    .into_iter()
+                    .map(|banner_type| (account_id.clone(), banner_type))
+            })
+            .collect::<Vec<_>>();
+        mismatches.sort_by(|a, b| a.0.cmp(&b.0));
+        mismatches
+    }
+
+    /// 校验和不一致时在控制台醒目地警告，在合并抽卡记录之前调用
+    /// # 参数
+    /// - `source`: 抽卡记录的来源，例如文件路径
+    pub fn warn_checksum_mismatches(&self, source: &str) {
+        for (account_id, banner_type) in self.checksum_mismatches() {
+            log::warn!(
+                "checksum mismatch in {}: account {}, banner {:?}",
+                source,
+                account_id,
+                banner_type
+            );
+            println!(
+                "{}",
+                tr!(
+                    "records.checksum_mismatch",
+                    source = source,
+                    account_id = account_id,
+                    banner = banner_type.display_name_for_user(language())
+                )
+            );
+        }
     }
 
     pub fn read_or_default() -> Self {
@@ -282,3 +1210,783 @@ impl Default for TotalRecords {
         Self::new(HashMap::new())
     }
 }
+
+#[cfg(test)]
+mod test {
+    use std::ops::Range;
+
+    use super::*;
+    use crate::banner_schedule::test::test_schedule;
+
+    #[test]
+    fn test_checksum_mismatches() {
+        let mut total_records = TotalRecords::default();
+        total_records
+            .add_record(
+                "id".to_string(),
+                ImportBatch::new(BannerType::PermanentWeapon, RecordSource::Ocr),
+                vec![
+                    OneRecord::new(5, "a".to_string(), ItemType::Weapon, 2),
+                    OneRecord::new(3, "b".to_string(), ItemType::Weapon, 1),
+                ],
+            )
+            .unwrap();
+        // 没有校验和时不检查
+        assert!(total_records.checksum_mismatches().is_empty());
+        let mut total_records = total_records.with_checksums();
+        assert!(total_records.checksum_mismatches().is_empty());
+
+        // 被截断
+        let account_records = total_records.records.get_mut("id").unwrap();
+        account_records
+            .records
+            .get_mut(&BannerType::PermanentWeapon)
+            .unwrap()
+            .pop();
+        assert_eq!(
+            total_records.checksum_mismatches(),
+            vec![("id".to_string(), BannerType::PermanentWeapon)]
+        );
+        // 保存时重新计算
+        assert!(total_records
+            .with_checksums()
+            .checksum_mismatches()
+            .is_empty());
+    }
+
+    #[test]
+    fn test_prune_before() {
+        let mut total_records = TotalRecords::default();
+        total_records
+            .add_record(
+                "id".to_string(),
+                ImportBatch::new(BannerType::PermanentWeapon, RecordSource::Ocr),
+                vec![
+                    OneRecord::new(3, "a".to_string(), ItemType::Weapon, 5),
+                    OneRecord::new(3, "b".to_string(), ItemType::Weapon, 4),
+                    OneRecord::new(4, "c".to_string(), ItemType::Weapon, 3),
+                    OneRecord::new(3, "d".to_string(), ItemType::Weapon, 2),
+                    OneRecord::new(5, "e".to_string(), ItemType::Weapon, 1),
+                ],
+            )
+            .unwrap();
+        let banner_type = BannerType::PermanentWeapon;
+        let pity_before = {
+            let account_records = &total_records.records["id"];
+            crate::stats::current_pity(
+                &account_records.records[&banner_type],
+                account_records.pity_anchor(banner_type),
+            )
+        };
+        let archived = total_records.prune_before(4);
+        assert_eq!(archived.records["id"].records[&banner_type].len(), 3);
+        let account_records = &total_records.records["id"];
+        assert_eq!(account_records.records[&banner_type].len(), 2);
+        let anchor = account_records.pity_anchor(banner_type).unwrap();
+        assert_eq!(
+            (anchor.pulls_since_5_star, anchor.pulls_since_4_star),
+            (2, 0)
+        );
+        // 移除后的保底计数与移除前相同
+        assert_eq!(
+            crate::stats::current_pity(&account_records.records[&banner_type], Some(anchor)),
+            pity_before
+        );
+        assert!(total_records.prune_before(4).records.is_empty());
+    }
+
+    #[test]
+    fn test_banner_accepts() {
+        let weapon_banner = BannerType::LimitedWeapon50Percent;
+        assert!(weapon_banner.accepts(ItemType::Weapon, 5));
+        assert!(!weapon_banner.accepts(ItemType::Character, 4));
+        let character_banner = BannerType::PermanentCharacter;
+        assert!(character_banner.accepts(ItemType::Character, 5));
+        assert!(character_banner.accepts(ItemType::Weapon, 3));
+        assert!(!character_banner.accepts(ItemType::Weapon, 5));
+        assert!(BannerType::Beginner.accepts(ItemType::Weapon, 5));
+    }
+
+    #[test]
+    fn test_annotate() {
+        let batch = ImportBatch::new(BannerType::PermanentWeapon, RecordSource::Ocr);
+        let record = |name: &str, timestamp| {
+            OneRecord::new(3, name.to_string(), ItemType::Weapon, timestamp)
+        };
+        let mut account_records = OneAccountRecords::new("id".to_string(), HashMap::new());
+        account_records
+            .add_record(
+                batch.clone(),
+                vec![record("c", 2), record("b", 2), record("a", 1)],
+            )
+            .unwrap();
+        let banner_type = BannerType::PermanentWeapon;
+        assert!(account_records
+            .annotate(banner_type, 3, None, None, Some("note".to_string()))
+            .is_err());
+        // 同一时间有多种物品时必须提供名称或序号
+        assert!(account_records
+            .annotate(banner_type, 2, None, None, Some("note".to_string()))
+            .is_err());
+        assert_eq!(
+            account_records
+                .annotate(
+                    banner_type,
+                    2,
+                    Some("b"),
+                    None,
+                    Some("event team".to_string())
+                )
+                .unwrap(),
+            1
+        );
+        assert_eq!(
+            account_records
+                .annotate(banner_type, 1, None, None, Some("first".to_string()))
+                .unwrap(),
+            1
+        );
+
+        // 再次导入包含已有记录的更长的记录时备注不丢失
+        let add_num = account_records
+            .add_record(
+                batch,
+                vec![
+                    record("d", 3),
+                    record("c", 2),
+                    record("b", 2),
+                    record("a", 1),
+                ],
+            )
+            .unwrap();
+        assert_eq!(add_num, 1);
+        let notes = account_records.records[&banner_type]
+            .iter()
+            .map(|record| record.note.as_deref())
+            .collect::<Vec<_>>();
+        assert_eq!(notes, [None, None, Some("event team"), Some("first")]);
+
+        // 空的备注表示删除
+        account_records
+            .annotate(banner_type, 1, None, None, Some(" ".to_string()))
+            .unwrap();
+        assert_eq!(account_records.records[&banner_type][3].note, None);
+
+        // 十连中有多条相同的物品时用序号指定其中一条
+        let mut account_records = OneAccountRecords::new("id".to_string(), HashMap::new());
+        account_records
+            .add_record(
+                ImportBatch::new(banner_type, RecordSource::Ocr),
+                vec![record("e", 4), record("f", 4), record("e", 4)],
+            )
+            .unwrap();
+        assert!(account_records
+            .annotate(banner_type, 4, Some("e"), None, Some("note".to_string()))
+            .is_err());
+        assert!(account_records
+            .annotate(banner_type, 4, Some("e"), Some(3), Some("note".to_string()))
+            .is_err());
+        assert_eq!(
+            account_records
+                .annotate(
+                    banner_type,
+                    4,
+                    Some("e"),
+                    Some(2),
+                    Some("second".to_string())
+                )
+                .unwrap(),
+            1
+        );
+        assert_eq!(
+            account_records
+                .annotate(banner_type, 4, None, Some(2), Some("f".to_string()))
+                .unwrap(),
+            1
+        );
+        let notes = account_records.records[&banner_type]
+            .iter()
+            .map(|record| record.note.as_deref())
+            .collect::<Vec<_>>();
+        assert_eq!(notes, [None, Some("f"), Some("second")]);
+    }
+
+    #[test]
+    fn test_serde_round_trip() {
+        let batch = ImportBatch::new(BannerType::LimitedCharacter50Percent, RecordSource::Ocr);
+        let mut account_records = OneAccountRecords::new("id".to_string(), HashMap::new());
+        account_records
+            .add_record(
+                batch.clone(),
+                vec![
+                    OneRecord {
+                        provenance: Some(batch.provenance(Some(1))),
+                        note: Some("备注".to_string()),
+                        ..OneRecord::new(5, "a".to_string(), ItemType::Character, 2)
+                    },
+                    OneRecord::new(3, "b".to_string(), ItemType::Weapon, 1),
+                ],
+            )
+            .unwrap();
+        account_records.set_pity_anchor(PityAnchor {
+            banner_type: BannerType::PermanentWeapon,
+            timestamp: 3,
+            pulls_since_5_star: 10,
+            pulls_since_4_star: 2,
+        });
+        account_records.guarantee_states.push(GuaranteeState {
+            banner_type: BannerType::LimitedCharacter50Percent,
+            guaranteed: true,
+            lost_streak: 1,
+            timestamp: 2,
+        });
+        let total_records =
+            TotalRecords::new(HashMap::from([("id".to_string(), account_records.clone())]))
+                .with_checksums();
+
+        // OneRecord 的比较不考虑出处和备注，比较序列化后的值
+        let json = serde_json::to_value(&total_records).unwrap();
+        let parsed = serde_json::from_value::<TotalRecords>(json.clone()).unwrap();
+        assert_eq!(serde_json::to_value(&parsed).unwrap(), json);
+        let json = serde_json::to_value(&account_records).unwrap();
+        let parsed = serde_json::from_value::<OneAccountRecords>(json.clone()).unwrap();
+        assert_eq!(serde_json::to_value(&parsed).unwrap(), json);
+        let record = &account_records.records[&BannerType::LimitedCharacter50Percent][0];
+        let json = serde_json::to_value(record).unwrap();
+        assert_eq!(json["note"], "备注");
+        let parsed = serde_json::from_value::<OneRecord>(json.clone()).unwrap();
+        assert_eq!(parsed.provenance, record.provenance);
+        assert_eq!(parsed.note, record.note);
+
+        // 旧版本保存的文件没有出处、备注、导入批次等字段
+        let parsed = serde_json::from_str::<TotalRecords>(
+            r#"{"records":{"id":{"id":"id","records":{"PermanentWeapon":[
+                {"star":4,"item_name":"c","item_type":"Weapon","timestamp":5}
+            ]}}}}"#,
+        )
+        .unwrap();
+        let account_records = &parsed.records["id"];
+        assert!(account_records.batches.is_empty());
+        assert!(account_records.checksums.is_empty());
+        let record = &account_records.records[&BannerType::PermanentWeapon][0];
+        assert_eq!((record.provenance, record.note.as_ref()), (None, None));
+        let json = serde_json::to_value(record).unwrap();
+        assert!(json.get("provenance").is_none() && json.get("note").is_none());
+    }
+
+    #[test]
+    fn test_add_record_keeps_provenance() {
+        let record = |name: &str, timestamp| {
+            OneRecord::new(3, name.to_string(), ItemType::Weapon, timestamp)
+        };
+        let mut account_records = OneAccountRecords::new("id".to_string(), HashMap::new());
+        let old_batch = ImportBatch::new(BannerType::PermanentWeapon, RecordSource::Ocr);
+        account_records
+            .add_record(old_batch.clone(), vec![record("a", 2), record("b", 1)])
+            .unwrap();
+
+        // 重叠的记录出处不同也视为相同的记录
+        let mut new_batch = ImportBatch::new(BannerType::PermanentWeapon, RecordSource::Ocr);
+        new_batch.id = old_batch.id + 1;
+        let new_records = [record("c", 3), record("a", 2)]
+            .into_iter()
+            .map(|record| OneRecord {
+                provenance: Some(new_batch.provenance(Some(1))),
+                ..record
+            })
+            .collect();
+        let add_num = account_records
+            .add_record(new_batch.clone(), new_records)
+            .unwrap();
+        assert_eq!(add_num, 1);
+
+        let records = &account_records.records[&BannerType::PermanentWeapon];
+        assert_eq!(records.len(), 3);
+        assert_eq!(records[0].provenance.unwrap().batch_id, new_batch.id);
+        assert_eq!(records[1].provenance, None);
+        assert_eq!(account_records.batches.len(), 2);
+        assert_eq!(account_records.batches[1].added, 1);
+
+        let undone = account_records
+            .undo_last_batch(BannerType::PermanentWeapon)
+            .unwrap();
+        assert_eq!(undone.id, new_batch.id);
+        assert_eq!(
+            account_records.records[&BannerType::PermanentWeapon],
+            vec![record("a", 2), record("b", 1)]
+        );
+        assert_eq!(account_records.batches.len(), 1);
+        assert_eq!(account_records.batches[0].id, old_batch.id);
+
+        let captured = |batch: &ImportBatch, records: Vec<OneRecord>| {
+            records
+                .into_iter()
+                .map(|record| OneRecord {
+                    provenance: Some(batch.provenance(Some(1))),
+                    ..record
+                })
+                .collect::<Vec<_>>()
+        };
+        let provenances = |account_records: &OneAccountRecords| {
+            account_records.records[&BannerType::PermanentWeapon]
+                .iter()
+                .map(|record| record.provenance.map(|provenance| provenance.batch_id))
+                .collect::<Vec<_>>()
+        };
+
+        // 旧记录是新记录中连续的一段
+        new_batch.id = old_batch.id + 2;
+        account_records
+            .add_record(
+                new_batch.clone(),
+                captured(
+                    &new_batch,
+                    vec![record("c", 3), record("a", 2), record("b", 1)],
+                ),
+            )
+            .unwrap();
+        assert_eq!(
+            provenances(&account_records),
+            vec![Some(new_batch.id), None, None]
+        );
+
+        // 旧记录比新记录新
+        new_batch.id = old_batch.id + 3;
+        account_records
+            .add_record(
+                new_batch.clone(),
+                captured(&new_batch, vec![record("b", 1), record("d", 0)]),
+            )
+            .unwrap();
+        assert_eq!(
+            provenances(&account_records),
+            vec![Some(old_batch.id + 2), None, None, Some(new_batch.id)]
+        );
+
+        // 有缺失时按时间对齐
+        new_batch.id = old_batch.id + 4;
+        new_batch.dropped = 1;
+        account_records
+            .add_record(
+                new_batch.clone(),
+                captured(
+                    &new_batch,
+                    vec![record("e", 4), record("a", 2), record("b", 1)],
+                ),
+            )
+            .unwrap();
+        assert_eq!(
+            provenances(&account_records),
+            vec![
+                Some(new_batch.id),
+                Some(old_batch.id + 2),
+                None,
+                None,
+                Some(old_batch.id + 3)
+            ]
+        );
+    }
+
+    #[test]
+    fn test_undo_superset_capture() {
+        let record = |name: &str, timestamp| {
+            OneRecord::new(3, name.to_string(), ItemType::Weapon, timestamp)
+        };
+        let mut account_records = OneAccountRecords::new("id".to_string(), HashMap::new());
+        let old_batch = ImportBatch::new(BannerType::PermanentWeapon, RecordSource::Ocr);
+        let old_records = vec![record("b", 3), record("c", 2)]
+            .into_iter()
+            .map(|record| OneRecord {
+                provenance: Some(old_batch.provenance(Some(1))),
+                ..record
+            })
+            .collect::<Vec<_>>();
+        account_records
+            .add_record(old_batch.clone(), old_records.clone())
+            .unwrap();
+
+        // 新截图的记录完整包含已保存的记录，前后都有新增的记录
+        let mut new_batch = ImportBatch::new(BannerType::PermanentWeapon, RecordSource::Ocr);
+        new_batch.id = old_batch.id + 1;
+        let new_records = [
+            record("a", 4),
+            record("b", 3),
+            record("c", 2),
+            record("d", 1),
+        ]
+        .into_iter()
+        .map(|record| OneRecord {
+            provenance: Some(new_batch.provenance(Some(1))),
+            ..record
+        })
+        .collect();
+        let add_num = account_records
+            .add_record(new_batch.clone(), new_records)
+            .unwrap();
+        assert_eq!(add_num, 2);
+
+        // 撤销只移除新增的记录，已保存的记录和出处不变
+        account_records
+            .undo_last_batch(BannerType::PermanentWeapon)
+            .unwrap();
+        let records = &account_records.records[&BannerType::PermanentWeapon];
+        assert_eq!(records, &old_records);
+        assert!(records
+            .iter()
+            .all(|record| record.provenance.unwrap().batch_id == old_batch.id));
+    }
+
+    #[test]
+    fn test_rename_and_merge_accounts() {
+        let record = |name: &str, timestamp| {
+            OneRecord::new(3, name.to_string(), ItemType::Weapon, timestamp)
+        };
+        let batch = || ImportBatch::new(BannerType::PermanentWeapon, RecordSource::Ocr);
+        let mut total_records = TotalRecords::default();
+        total_records
+            .add_record(
+                "default".to_string(),
+                batch(),
+                vec![record("c", 3), record("b", 2)],
+            )
+            .unwrap();
+        total_records
+            .add_record(
+                "123".to_string(),
+                batch(),
+                vec![record("b", 2), record("a", 1)],
+            )
+            .unwrap();
+
+        assert!(total_records.rename_account("default", "123").is_err());
+        assert!(total_records.rename_account("none", "456").is_err());
+        total_records.rename_account("default", "456").unwrap();
+        assert_eq!(total_records.records["456"].id, "456");
+        assert!(!total_records.records.contains_key("default"));
+
+        assert!(total_records.merge_accounts("123", "123").is_err());
+        assert_eq!(total_records.merge_accounts("456", "123").unwrap(), 1);
+        assert!(!total_records.records.contains_key("456"));
+        assert_eq!(
+            total_records.records["123"].records[&BannerType::PermanentWeapon],
+            vec![record("c", 3), record("b", 2), record("a", 1)]
+        );
+    }
+
+    #[test]
+    fn test_insert_record() {
+        let batch = ImportBatch::new(BannerType::PermanentWeapon, RecordSource::Ocr);
+        let record = |name: &str, timestamp, page| OneRecord {
+            provenance: Some(batch.provenance(Some(page))),
+            ..OneRecord::new(3, name.to_string(), ItemType::Weapon, timestamp)
+        };
+        let mut account_records = OneAccountRecords::new("id".to_string(), HashMap::new());
+        account_records
+            .add_record(
+                batch.clone(),
+                vec![record("a", 2, 1), record("b", 1, 1), record("d", 1, 3)],
+            )
+            .unwrap();
+        assert!(account_records.insert_record(BannerType::PermanentWeapon, record("c", 1, 2)));
+        let names = account_records.records[&BannerType::PermanentWeapon]
+            .iter()
+            .map(|record| record.item_name.as_str())
+            .collect::<Vec<_>>();
+        assert_eq!(names, ["a", "b", "c", "d"]);
+        assert_eq!(account_records.batches[0].added, 4);
+
+        // 已经保存过的记录不重复添加，备注和出处不同也是同一条记录
+        let duplicate = OneRecord {
+            note: Some("备注".to_string()),
+            ..record("c", 1, 2)
+        };
+        assert!(!account_records.insert_record(BannerType::PermanentWeapon, duplicate));
+        assert_eq!(
+            account_records.records[&BannerType::PermanentWeapon].len(),
+            4
+        );
+        assert_eq!(account_records.batches[0].added, 4);
+    }
+
+    #[test]
+    fn test_merge_unnormalized_names() {
+        // 旧版本保存的名称没有规范化，新识别的名称已经规范化
+        let mut total_records = TotalRecords::default();
+        total_records
+            .add_record(
+                "id".to_string(),
+                ImportBatch::new(BannerType::PermanentWeapon, RecordSource::Ocr),
+                vec![
+                    OneRecord::new(3, "Melee".to_string(), ItemType::Weapon, 3),
+                    OneRecord::new(5, "里芙·无限之视".to_string(), ItemType::Character, 2),
+                    OneRecord::new(3, "Melee".to_string(), ItemType::Weapon, 1),
+                ],
+            )
+            .unwrap();
+        let old_records = vec![
+            OneRecord::new(5, "里芙・无限之视".to_string(), ItemType::Character, 2),
+            OneRecord::new(3, "Ｍｅｌｅｅ".to_string(), ItemType::Weapon, 1),
+        ];
+        let other = TotalRecords::new(HashMap::from([(
+            "id".to_string(),
+            OneAccountRecords::new(
+                "id".to_string(),
+                HashMap::from([(BannerType::PermanentWeapon, old_records)]),
+            ),
+        )]));
+        assert_eq!(total_records.merge(other).unwrap(), 0);
+        let records = &total_records.records["id"].records[&BannerType::PermanentWeapon];
+        assert_eq!(records.len(), 3);
+        assert_eq!(records[1].item_name, "里芙·无限之视");
+        assert_eq!(records[2].item_name, "Melee");
+    }
+
+    #[test]
+    fn test_update_guarantee_states() {
+        let start = Local
+            .with_ymd_and_hms(2023, 7, 25, 12, 0, 0)
+            .unwrap()
+            .timestamp() as u64;
+        let record = |name: &str, minutes: u64| {
+            OneRecord::new(
+                5,
+                name.to_string(),
+                ItemType::Character,
+                start + minutes * 60,
+            )
+        };
+        let banner_type = BannerType::LimitedCharacter50Percent;
+        // 不经过 add_record 修改抽卡记录，例如合并账号、导入备份
+        let mut account_records = OneAccountRecords::new(
+            "id".to_string(),
+            HashMap::from([(banner_type, vec![record("芬妮·辉耀", 1)])]),
+        );
+        let schedule = test_schedule();
+        account_records.update_guarantee_states(&schedule);
+        let state = account_records.guarantee_state(banner_type).unwrap();
+        assert!(state.guaranteed);
+
+        account_records
+            .records
+            .get_mut(&banner_type)
+            .unwrap()
+            .insert(0, record("里芙·无限之视", 2));
+        account_records.update_guarantee_states(&schedule);
+        let state = account_records.guarantee_state(banner_type).unwrap();
+        assert!(!state.guaranteed);
+        assert_eq!(state.timestamp, start + 2 * 60);
+
+        // 保存时按内置的排期表推算
+        let mut account_records =
+            OneAccountRecords::new("id".to_string(), account_records.records.clone());
+        let total_records =
+            TotalRecords::new(HashMap::from([("id".to_string(), account_records.clone())]))
+                .with_checksums();
+        account_records.update_guarantee_states(banner_schedule());
+        assert_eq!(
+            total_records.records["id"].guarantee_states,
+            account_records.guarantee_states
+        );
+    }
+
+    #[test]
+    fn test_merge_with_gaps() {
+        let history = (0..8)
+            .map(|i| OneRecord::new(3, i.to_string(), ItemType::Weapon, 8 - i / 2))
+            .collect::<Vec<_>>();
+        let without = |range: Range<usize>, index: usize| {
+            history[range]
+                .iter()
+                .filter(|record| record.item_name != index.to_string())
+                .cloned()
+                .collect::<Vec<_>>()
+        };
+
+        // 旧记录缺少第 4 条，新的导入补上
+        let old_records = without(2..8, 4);
+        assert!(merge_gacha_records(&history[..6], &old_records).is_err());
+        let (merged, add_num, gaps) =
+            merge_gacha_records_with_gaps(&history[..6], &old_records, 1, 0).unwrap();
+        assert_eq!((merged, add_num, gaps), (history.clone(), 3, 0));
+
+        // 新的导入缺少第 3 条，旧记录中有
+        let (merged, add_num, gaps) =
+            merge_gacha_records_with_gaps(&without(0..6, 3), &history[2..], 0, 1).unwrap();
+        assert_eq!((merged, add_num, gaps), (history.clone(), 2, 0));
+
+        // 新的导入中缺少的记录不在重叠部分时仍然缺失
+        let (merged, _, gaps) =
+            merge_gacha_records_with_gaps(&without(0..6, 0), &history[2..], 0, 1).unwrap();
+        assert_eq!(merged, history[1..]);
+        assert_eq!(gaps, 1);
+
+        // 缺失的记录记在账号中，重新导入后补上
+        let mut account_records = OneAccountRecords::new("id".to_string(), HashMap::new());
+        let mut batch = ImportBatch::new(BannerType::PermanentWeapon, RecordSource::Ocr);
+        batch.dropped = 1;
+        account_records.add_record(batch, old_records).unwrap();
+        assert_eq!(account_records.gaps(BannerType::PermanentWeapon), 1);
+        let batch = ImportBatch::new(BannerType::PermanentWeapon, RecordSource::Ocr);
+        assert_eq!(
+            account_records.add_record(batch, history.clone()).unwrap(),
+            3
+        );
+        assert_eq!(
+            account_records.records[&BannerType::PermanentWeapon],
+            history
+        );
+        assert_eq!(account_records.gaps(BannerType::PermanentWeapon), 0);
+
+        // 两台电脑上缺少不同的记录，合并时互相补上
+        let total_records = |records: Vec<OneRecord>, gaps: u32| {
+            let mut account_records = OneAccountRecords::new(
+                "id".to_string(),
+                HashMap::from([(BannerType::PermanentWeapon, records)]),
+            );
+            account_records.set_gaps(BannerType::PermanentWeapon, gaps);
+            TotalRecords::new(HashMap::from([("id".to_string(), account_records)]))
+        };
+        let mut local = total_records(without(2..8, 4), 1);
+        let remote = total_records(without(0..6, 3), 1);
+        assert_eq!(local.merge(remote).unwrap(), 3);
+        let account_records = &local.records["id"];
+        assert_eq!(
+            account_records.records[&BannerType::PermanentWeapon],
+            history
+        );
+        assert_eq!(account_records.gaps(BannerType::PermanentWeapon), 0);
+    }
+
+    mod merge {
+        use proptest::prelude::*;
+
+        use super::*;
+
+        /// 按时间倒序的完整抽卡历史，名称各不相同，很多记录时间相同
+        fn history(len: Range<usize>) -> impl Strategy<Value = Vec<OneRecord>> {
+            prop::collection::vec((3u8..=5, prop::bool::weighted(0.3)), len).prop_map(|rows| {
+                let mut timestamp = 1_700_000_000;
+                rows.into_iter()
+                    .enumerate()
+                    .map(|(i, (star, next_minute))| {
+                        if next_minute {
+                            timestamp -= 60;
+                        }
+                        OneRecord::new(star, format!("item{i}"), ItemType::Weapon, timestamp)
+                    })
+                    .collect()
+            })
+        }
+
+        /// 抽卡历史和其中的两段（可能为空）
+        fn history_and_windows(
+        ) -> impl Strategy<Value = (Vec<OneRecord>, (usize, usize), (usize, usize))> {
+            history(0..40).prop_flat_map(|history| {
+                let len = history.len();
+                let window = (0..=len, 0..=len).prop_map(|(a, b)| (a.min(b), a.max(b)));
+                (Just(history), window.clone(), window)
+            })
+        }
+
+        /// 抽卡历史和其中至少有一条共同记录的两段
+        fn history_and_overlapping_windows(
+        ) -> impl Strategy<Value = (Vec<OneRecord>, (usize, usize), (usize, usize))> {
+            history(1..40).prop_flat_map(|history| {
+                let len = history.len();
+                (0..len).prop_flat_map(move |shared| {
+                    let window = (0..=shared, shared + 1..=len);
+                    (Just(history.clone()), window.clone(), window)
+                })
+            })
+        }
+
+        fn is_timestamp_desc(records: &[OneRecord]) -> bool {
+            records.windows(2).all(|w| w[0].timestamp >= w[1].timestamp)
+        }
+
+        #[test]
+        fn test_empty() {
+            let records = vec![OneRecord::new(3, "a".to_string(), ItemType::Weapon, 1)];
+            assert_eq!(
+                merge_gacha_records(&records, &[]).unwrap(),
+                (records.clone(), 1)
+            );
+            assert_eq!(
+                merge_gacha_records(&[], &records).unwrap(),
+                (records.clone(), 0)
+            );
+            assert_eq!(merge_gacha_records(&[], &[]).unwrap(), (vec![], 0));
+        }
+
+        #[test]
+        fn test_ten_identical_timestamps() {
+            let history = (0..10)
+                .map(|i| OneRecord::new(3, format!("item{i}"), ItemType::Weapon, 1))
+                .collect::<Vec<_>>();
+            // 旧的一段作为新记录导入
+            let (merged, add_num) = merge_gacha_records(&history[4..], &history[..6]).unwrap();
+            assert_eq!(merged, history);
+            assert_eq!(add_num, 4);
+        }
+
+        proptest! {
+            /// 重叠的两段合并后得到两段覆盖的完整历史
+            /// 不重叠且时间完全相同的两段无法判断先后，不在此列
+            #[test]
+            fn test_overlapping_windows(
+                (history, (a0, a1), (b0, b1)) in history_and_overlapping_windows()
+            ) {
+                let (merged, add_num) = merge_gacha_records(&history[a0..a1], &history[b0..b1]).unwrap();
+                prop_assert_eq!(&merged, &history[a0.min(b0)..a1.max(b1)]);
+                prop_assert_eq!(add_num as usize, merged.len() - (b1 - b0));
+            }
+
+            /// 同样的记录再合并一次不会有变化
+            #[test]
+            fn test_idempotence((history, (a0, a1), (b0, b1)) in history_and_overlapping_windows()) {
+                let (merged, _) = merge_gacha_records(&history[a0..a1], &history[b0..b1]).unwrap();
+                let (merged_again, add_num) = merge_gacha_records(&history[a0..a1], &merged).unwrap();
+                prop_assert_eq!(merged_again, merged.clone());
+                prop_assert_eq!(add_num, 0);
+                let (merged_self, add_num) = merge_gacha_records(&merged, &merged).unwrap();
+                prop_assert_eq!(merged_self, merged);
+                prop_assert_eq!(add_num, 0);
+            }
+
+            /// 合并结果按时间倒序，包含两边的全部记录，且不会比任何一边短
+            #[test]
+            fn test_monotonicity((history, (a0, a1), (b0, b1)) in history_and_windows()) {
+                let (new_records, old_records) = (&history[a0..a1], &history[b0..b1]);
+                if let Ok((merged, add_num)) = merge_gacha_records(new_records, old_records) {
+                    prop_assert!(is_timestamp_desc(&merged));
+                    prop_assert!(merged.len() >= new_records.len().max(old_records.len()));
+                    prop_assert!(is_sub_records(new_records, &merged));
+                    prop_assert!(is_sub_records(old_records, &merged));
+                    prop_assert_eq!(add_num as usize, merged.len() - old_records.len());
+                }
+            }
+
+            /// 不相交的两段历史，无论哪个作为新记录都按时间顺序拼接
+            #[test]
+            fn test_disjoint_histories(newer in history(1..20), older in history(1..20)) {
+                // 较旧的一段整体早一天，名称与较新的一段不同
+                let older = older
+                    .into_iter()
+                    .map(|record| OneRecord {
+                        item_name: format!("old_{}", record.item_name),
+                        timestamp: record.timestamp - 86400,
+                        ..record
+                    })
+                    .collect::<Vec<_>>();
+                let history = [newer.clone(), older.clone()].concat();
+                let (newer, older) = (newer.as_slice(), older.as_slice());
+                let (merged, add_num) = merge_gacha_records(newer, older).unwrap();
+                prop_assert_eq!(&merged, &history);
+                prop_assert_eq!(add_num as usize, newer.len());
+                let (merged, add_num) = merge_gacha_records(older, newer).unwrap();
+                prop_assert_eq!(&merged, &history);
+                prop_assert_eq!(add_num as usize, older.len());
+            }
+        }
+    }
+}
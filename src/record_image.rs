@@ -1,13 +1,27 @@
+use std::sync::RwLock;
+
 use anyhow::{anyhow, Result};
-use chrono::{Local, TimeZone};
+use chrono::{Datelike, Local, NaiveDate, NaiveDateTime, TimeZone};
 use enum_iterator::all;
-use image::{DynamicImage, GenericImageView, GrayImage};
-use imageproc::contrast::{otsu_level, threshold, ThresholdType};
+use image::imageops::{invert, resize, FilterType};
+use image::{DynamicImage, GenericImageView, GrayImage, Rgb, RgbImage};
+use imageproc::drawing::draw_hollow_rect_mut;
+use imageproc::filter::median_filter;
+use imageproc::rect::Rect;
 use lazy_static::lazy_static;
-use simple_ocr::ocr;
 
+use crate::config::config;
+use crate::items::{canonical_name, ItemTypes};
+use crate::language::Language;
+use crate::ocr::{ocr_batch, CharsetHint};
+use crate::phash::PerceptualHash;
+use crate::preprocess::Preprocessing;
+use crate::rarity_detector::{RarityDetector, StarColors};
 use crate::record::{ItemType, OneRecord};
 
+/// 坐标常量的基准分辨率
+static REFERENCE_WIDTH: u32 = 1920;
+
 static _MAX_RECORD_NUM: u32 = 10;
 
 static _FIRST_RECORD_Y0: u32 = 207;
@@ -23,7 +37,7 @@ static ITEM_NAME_X0: u32 = 367;
 static ITEM_NAME_X1: u32 = 883;
 static ITEM_TYPE_X0: u32 = 883;
 static TIME_X1: u32 = 1548;
-static ITEM_TYPE_X1: u32 = (ITEM_TYPE_X0 + TIME_X1 + 1) / 2;
+static ITEM_TYPE_X1: u32 = (ITEM_TYPE_X0 + TIME_X1).div_ceil(2);
 static TIME_X0: u32 = ITEM_TYPE_X1;
 
 static _PAGE_BUTTON_X: u32 = 1664;
@@ -33,35 +47,266 @@ static INDEX_X1: u32 = (_PAGE_BUTTON_X - INDEX_X0) + _PAGE_BUTTON_X;
 static INDEX_Y0: u32 = 464;
 static INDEX_Y1: u32 = 577;
 
+/// 识别星级时在颜色条上竖直方向采样的半径
+static STAR_PATCH_RADIUS: u32 = 3;
+
 static _OCR_IMAGE_HEIGHT: u32 = 32;
 /// 字符与图片边界的间距
 static CHAR_MARGIN: u32 = 7;
 static CHAR_HEIGHT: u32 = _OCR_IMAGE_HEIGHT - 2 * CHAR_MARGIN;
 
 lazy_static! {
-    static ref RECORD_Y0S: Vec<u32> = (0.._MAX_RECORD_NUM)
-        .map(
-            |i| ((RECORD_HEIGHT as f32 + SPACE_HEIGHT) * i as f32).round() as u32
-                + _FIRST_RECORD_Y0
-        )
-        .collect();
-    static ref RECORD_Y1S: Vec<u32> = RECORD_Y0S.iter().map(|y0| y0 + RECORD_HEIGHT).collect();
-    static ref STAR_YS: Vec<u32> = RECORD_Y0S
-        .iter()
-        .map(|y0| y0 + (RECORD_HEIGHT + 1) / 2)
+    /// 抽卡记录界面中所有语言的物品类型文本
+    static ref ITEM_TYPE_VOCABULARY: Vec<&'static str> = all::<ItemType>()
+        .flat_map(|item_type| item_type.display_names_in_record_page_in_game_in_all_languages())
         .collect();
+    /// 本次导入中检测到的游戏客户端语言，识别物品类型时优先匹配这种语言
+    static ref CLIENT_LANGUAGE: RwLock<Option<Language>> = RwLock::new(None);
+}
+
+/// 文字与周围背景的最小亮度差，低于这个值的像素视为背景
+static MIN_CHAR_CONTRAST: i16 = 32;
+
+/// 图片中字符的区域
+#[derive(Debug, Copy, Clone, PartialEq, Eq)]
+struct CharArea {
+    /// 字符区域的 (x, y, 宽, 高)，没有字符时为整个图片
+    xywh: (u32, u32, u32, u32),
+    /// 是否是深色背景上的浅色文字
+    light_text: bool,
+}
+
+/// 找出图片中字符的区域
+///
+/// 活动主题、深色界面会改变背景亮度，背景还可能有渐变，全局阈值会把整块背景当作文字。
+/// 这里把每个像素与周围亮度的中位数比较（局部自适应阈值，文字笔画不到一半时中位数就是背景），
+/// 明显更亮和明显更暗的像素中数量多的一类是文字，同时得到文字的极性。
+fn char_area(image: &GrayImage) -> CharArea {
+    let (width, height) = image.dimensions();
+    let radius = (height / 2).max(1);
+    let background = median_filter(image, radius, radius);
+    let contrast =
+        |x: u32, y: u32| image.get_pixel(x, y)[0] as i16 - background.get_pixel(x, y)[0] as i16;
+    let (mut brighter, mut darker) = (0usize, 0usize);
+    for (x, y, _) in image.enumerate_pixels() {
+        match contrast(x, y) {
+            c if c > MIN_CHAR_CONTRAST => brighter += 1,
+            c if c < -MIN_CHAR_CONTRAST => darker += 1,
+            _ => {}
+        }
+    }
+    let light_text = brighter > darker;
+    let is_char = |x: u32, y: u32| {
+        let c = contrast(x, y);
+        if light_text {
+            c > MIN_CHAR_CONTRAST
+        } else {
+            c < -MIN_CHAR_CONTRAST
+        }
+    };
+    let (x_min, x_max, y_min, y_max) = image.enumerate_pixels().fold(
+        (width - 1, 0, height - 1, 0),
+        |(x_min, x_max, y_min, y_max), (x, y, _)| {
+            if is_char(x, y) {
+                (x_min.min(x), x_max.max(x), y_min.min(y), y_max.max(y))
+            } else {
+                (x_min, x_max, y_min, y_max)
+            }
+        },
+    );
+    // 没有字符时使用整个区域，例如识别其他界面时区域中没有文字
+    if x_min > x_max || y_min > y_max {
+        return CharArea {
+            xywh: (0, 0, width, height),
+            light_text,
+        };
+    }
+    CharArea {
+        xywh: (x_min, y_min, x_max - x_min + 1, y_max - y_min + 1),
+        light_text,
+    }
+}
+
+/// 设置本次导入的游戏客户端语言，开始新的导入时设置为 `None`
+pub fn set_client_language(language: Option<Language>) {
+    *CLIENT_LANGUAGE.write().unwrap() = language;
+}
+
+/// 一次识别多张图片中文字的函数，返回与传入图片顺序一致的结果，程序中使用 [`ocr_batch`]
+pub type BatchOcr = dyn Fn(Vec<(GrayImage, CharsetHint)>) -> Result<Vec<String>>;
+
+/// 一页中每行的识别结果
+type Rows = Vec<Result<OneRecord, Box<InvalidRow>>>;
+
+/// 解析一页抽卡记录的设置
+#[derive(Debug, Clone, Default)]
+pub struct ParseOptions {
+    /// 各星级颜色条的参考颜色
+    pub star_colors: StarColors,
+    /// OCR 前的图片预处理
+    pub preprocessing: Preprocessing,
+    /// 游戏客户端语言，识别物品类型时优先匹配这种语言，未知时为 `None`
+    pub client_language: Option<Language>,
+    /// 类型文本识别失败时按物品名称推断物品类型
+    pub item_types: ItemTypes,
+}
+
+impl ParseOptions {
+    /// 配置中的设置、本次导入检测到的客户端语言和当前的物品字典
+    pub fn from_config() -> Self {
+        let config = config();
+        Self {
+            star_colors: config.star_colors,
+            preprocessing: config.ocr_preprocessing,
+            client_language: *CLIENT_LANGUAGE.read().unwrap(),
+            item_types: ItemTypes::snapshot(),
+        }
+    }
+}
+
+/// 解析过程中的诊断信息
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct PageDiagnostics {
+    /// 截图的 (宽, 高)
+    pub size: (u32, u32),
+    /// 每行识别出的星级
+    pub stars: Vec<u8>,
+    /// 页码区域识别出的文本
+    pub index_text: String,
+    /// 第一次识别失败、用最强的预处理重新识别的行数
+    pub retried_rows: usize,
+}
+
+/// 一页抽卡记录的解析结果
+#[derive(Debug)]
+pub struct ParsedPage {
+    /// 页码，不是抽卡记录界面或者页码识别失败时为 `None`
+    pub index: Option<u32>,
+    /// 每行的抽卡记录，不是抽卡记录界面时为空
+    pub rows: Vec<Result<OneRecord, Box<InvalidRow>>>,
+    pub diagnostics: PageDiagnostics,
+}
+
+impl ParsedPage {
+    /// 是否是抽卡记录界面：识别出了星级和页码
+    pub fn is_record_page(&self) -> bool {
+        self.index.is_some() && !self.diagnostics.stars.is_empty()
+    }
+
+    /// 识别成功的抽卡记录
+    pub fn records(&self) -> impl Iterator<Item = &OneRecord> {
+        self.rows.iter().filter_map(|row| row.as_ref().ok())
+    }
+}
+
+/// 解析一张抽卡记录界面的截图
+///
+/// 不截图、不读取配置和其他全局状态，截图导入、剪贴板导入和其他程序都可以直接使用。
+/// # 参数
+/// - `image`: 16:9 的截图，任意分辨率
+/// - `options`: 解析设置，程序中使用 [`ParseOptions::from_config`]
+/// - `ocr`: 识别文字的函数，程序中使用 [`ocr_batch`]
+/// # 错误
+/// `ocr` 返回的错误，识别失败的行不是错误，在 [`ParsedPage::rows`] 中
+pub fn parse_record_page(
+    image: DynamicImage,
+    options: &ParseOptions,
+    ocr: &BatchOcr,
+) -> Result<ParsedPage> {
+    RecordImage::new(image).parse(options, ocr)
+}
+
+/// 抽卡记录界面中各区域在截图中的坐标
+///
+/// 坐标常量以 1920x1080 为基准，按截图的实际宽度缩放，直接在原分辨率的截图上裁剪，
+/// 只把裁剪出的单元格缩放到基准大小，避免把整张截图放大后文字模糊。
+#[derive(Debug, Clone, PartialEq)]
+pub struct Layout {
+    /// 截图宽度与基准宽度的比例
+    pub scale: f32,
+    /// 识别星级的像素的 x 坐标
+    pub star_x: u32,
+    /// 物品名称列的 (x0, x1)
+    pub item_name_x: (u32, u32),
+    /// 物品类型列的 (x0, x1)
+    pub item_type_x: (u32, u32),
+    /// 时间列的 (x0, x1)
+    pub time_x: (u32, u32),
+    /// 页码的区域 (x0, y0, x1, y1)
+    pub index_area: (u32, u32, u32, u32),
+    /// 每行的 (y0, y1)
+    pub record_ys: Vec<(u32, u32)>,
+    /// 每行识别星级的像素的 y 坐标
+    pub star_ys: Vec<u32>,
+}
+
+impl Layout {
+    /// 宽度为 `width` 的 16:9 截图的坐标
+    pub fn new(width: u32) -> Self {
+        let scale = width as f32 / REFERENCE_WIDTH as f32;
+        let scaled = |value: f32| (value * scale).round() as u32;
+        let record_y0s = (0.._MAX_RECORD_NUM)
+            .map(|i| {
+                ((RECORD_HEIGHT as f32 + SPACE_HEIGHT) * i as f32).round() + _FIRST_RECORD_Y0 as f32
+            })
+            .collect::<Vec<_>>();
+        Self {
+            scale,
+            star_x: scaled(STAR_X as f32),
+            item_name_x: (scaled(ITEM_NAME_X0 as f32), scaled(ITEM_NAME_X1 as f32)),
+            item_type_x: (scaled(ITEM_TYPE_X0 as f32), scaled(ITEM_TYPE_X1 as f32)),
+            time_x: (scaled(TIME_X0 as f32), scaled(TIME_X1 as f32)),
+            index_area: (
+                scaled(INDEX_X0 as f32),
+                scaled(INDEX_Y0 as f32),
+                scaled(INDEX_X1 as f32),
+                scaled(INDEX_Y1 as f32),
+            ),
+            record_ys: record_y0s
+                .iter()
+                .map(|&y0| (scaled(y0), scaled(y0 + RECORD_HEIGHT as f32)))
+                .collect(),
+            star_ys: record_y0s
+                .iter()
+                .map(|&y0| scaled(y0 + RECORD_HEIGHT.div_ceil(2) as f32))
+                .collect(),
+        }
+    }
+
+    /// 把以 1920x1080 为基准的区域 (x0, y0, x1, y1) 换算为截图中的坐标
+    pub fn scale_area(&self, (x0, y0, x1, y1): (u32, u32, u32, u32)) -> (u32, u32, u32, u32) {
+        let scaled = |value: u32| (value as f32 * self.scale).round() as u32;
+        (scaled(x0), scaled(y0), scaled(x1), scaled(y1))
+    }
+}
+
+/// 识别失败的一行抽卡记录
+#[derive(Debug)]
+pub struct InvalidRow {
+    /// 在页中的行号，从 0 开始
+    pub row: usize,
+    pub star: u8,
+    pub item_name: String,
+    pub item_type_text: String,
+    pub time_text: String,
+    pub error: anyhow::Error,
+    /// 这一行的截图
+    pub image: DynamicImage,
 }
 
 #[derive(Clone)]
 pub struct RecordImage {
+    /// 原分辨率的截图
     pub image: DynamicImage,
+    pub layout: Layout,
 }
 
 impl RecordImage {
+    /// # 参数
+    /// - `image`: 16:9 的截图，任意分辨率
     pub fn new(image: DynamicImage) -> Self {
-        assert_eq!(1920, image.width());
-        assert_eq!(1080, image.height());
-        Self { image }
+        let layout = Layout::new(image.width());
+        Self { image, layout }
     }
 
     pub fn is_record_image(&self) -> bool {
@@ -74,71 +319,72 @@ impl RecordImage {
         true
     }
 
-    fn stars(&self) -> Vec<u8> {
-        /// RGB 颜色转换为星级
-        fn rgb_to_star(rgb: (u8, u8, u8)) -> Result<u8> {
-            /// 计算两个 RGB 颜色的欧氏距离
-            fn rgb_distance(rgb1: (u8, u8, u8), rgb2: (u8, u8, u8)) -> f32 {
-                let r = (rgb1.0 as f32 - rgb2.0 as f32).powi(2);
-                let g = (rgb1.1 as f32 - rgb2.1 as f32).powi(2);
-                let b = (rgb1.2 as f32 - rgb2.2 as f32).powi(2);
-                (r + g + b).sqrt()
-            }
+    /// 用指定的设置判断是否是抽卡记录界面，不读取配置，见 [`RecordImage::is_record_image`]
+    pub fn is_record_image_with(&self, options: &ParseOptions, ocr: &BatchOcr) -> bool {
+        !self.stars_with(&options.star_colors).is_empty() && self.index_with(options, ocr).is_ok()
+    }
 
-            static STAR_3_RGB: (u8, u8, u8) = (55, 98, 242);
-            static STAR_4_RGB: (u8, u8, u8) = (192, 105, 214);
-            static STAR_5_RGB: (u8, u8, u8) = (233, 155, 55);
+    /// 通过每行左侧的颜色识别星级，遇到无法识别的颜色时停止
+    pub fn stars(&self) -> Vec<u8> {
+        self.stars_with(&config().star_colors)
+    }
 
-            static ACCURACY: f32 = 5.0;
+    /// 用指定的参考颜色识别星级，见 [`RecordImage::stars`]
+    pub fn stars_with(&self, star_colors: &StarColors) -> Vec<u8> {
+        let detector = RarityDetector::new(star_colors);
+        self.layout
+            .star_ys
+            .iter()
+            .map_while(|&y| detector.classify_patch(&self.star_patch(y)))
+            .collect()
+    }
 
-            if rgb_distance(rgb, STAR_3_RGB) < ACCURACY {
-                Ok(3)
-            } else if rgb_distance(rgb, STAR_4_RGB) < ACCURACY {
-                Ok(4)
-            } else if rgb_distance(rgb, STAR_5_RGB) < ACCURACY {
-                Ok(5)
-            } else {
-                Err(anyhow!("Unknown star RGB: {:?}", rgb))
-            }
-        }
+    /// 颜色条上以 (star_x, y) 为中心竖直方向的一列采样像素
+    fn star_patch(&self, y: u32) -> Vec<[u8; 3]> {
+        let radius = (STAR_PATCH_RADIUS as f32 * self.layout.scale).round() as u32;
+        let y_max = self.image.height() - 1;
+        (y.saturating_sub(radius)..=(y + radius).min(y_max))
+            .map(|y| {
+                let rgba = self.image.get_pixel(self.layout.star_x, y);
+                [rgba[0], rgba[1], rgba[2]]
+            })
+            .collect()
+    }
 
-        STAR_YS
+    /// 每行左侧用于识别星级的像素颜色
+    pub fn star_pixels(&self) -> Vec<[u8; 3]> {
+        self.layout
+            .star_ys
             .iter()
-            .map_while(|&y| {
-                let rgba = self.image.get_pixel(STAR_X, y);
-                let rgb = (rgba[0], rgba[1], rgba[2]);
-                rgb_to_star(rgb).ok()
+            .map(|&y| {
+                let rgba = self.image.get_pixel(self.layout.star_x, y);
+                [rgba[0], rgba[1], rgba[2]]
             })
             .collect()
     }
 
-    /// 传入包含字符的区域的左上角和右下角坐标，返回用于 OCR 的图片。
+    /// 传入包含字符的区域在截图中的左上角和右下角坐标，返回用于 OCR 的图片。
+    /// 截图不是 1920x1080 时，裁剪出的图片缩放到基准大小。
     /// # 参数
     /// - x0: 左上角 x 坐标
     /// - y0: 左上角 y 坐标
     /// - x1: 右下角 x 坐标
     /// - y1: 右下角 y 坐标
     /// # 返回
-    /// 用于 OCR 的图片
-    fn get_ocr_image(&self, x0: u32, y0: u32, x1: u32, y1: u32) -> GrayImage {
-        /// 找出图片中字符的区域
-        fn get_char_xywh(image: DynamicImage) -> (u32, u32, u32, u32) {
-            let image = image.to_luma8();
-            let otsu = otsu_level(&image);
-            let image = threshold(&image, otsu, ThresholdType::Binary);
-            let (x_min, x_max, y_min, y_max) = image.enumerate_pixels().fold(
-                (image.width() - 1, 0, image.height() - 1, 0),
-                |(x_min, x_max, y_min, y_max), (x, y, pixel)| {
-                    if pixel[0] == 0 {
-                        (x_min.min(x), x_max.max(x), y_min.min(y), y_max.max(y))
-                    } else {
-                        (x_min, x_max, y_min, y_max)
-                    }
-                },
-            );
-            (x_min, y_min, x_max - x_min + 1, y_max - y_min + 1)
-        }
+    /// 用于 OCR 的图片，按配置预处理
+    pub fn get_ocr_image(&self, x0: u32, y0: u32, x1: u32, y1: u32) -> GrayImage {
+        self.get_ocr_image_with(x0, y0, x1, y1, config().ocr_preprocessing)
+    }
 
+    /// 用指定的预处理获取用于 OCR 的图片，参数见 [`RecordImage::get_ocr_image`]
+    pub fn get_ocr_image_with(
+        &self,
+        x0: u32,
+        y0: u32,
+        x1: u32,
+        y1: u32,
+        preprocessing: Preprocessing,
+    ) -> GrayImage {
         /// 通过字符的高度计算字符与图片边界应该的间距
         fn calculate_char_margin(char_height: u32) -> u32 {
             (char_height as f32 / CHAR_HEIGHT as f32 * CHAR_MARGIN as f32).round() as u32
@@ -148,97 +394,387 @@ impl RecordImage {
         // 2. 找出字符的区域
         // 3. 计算字符与图片边界的间距
         // 4. 从原图裁剪出用于 OCR 的图片
-        let image = self.image.crop_imm(x0, y0, x1 - x0, y1 - y0);
-        let (x, y, w, h) = get_char_xywh(image);
+        let image = self.image.crop_imm(x0, y0, x1 - x0, y1 - y0).to_luma8();
+        let CharArea {
+            xywh: (x, y, w, h),
+            light_text,
+        } = char_area(&image);
         let char_margin = calculate_char_margin(h);
-        let x = x0 + x - char_margin;
-        let y = y0 + y - char_margin;
+        let x = (x0 + x).saturating_sub(char_margin);
+        let y = (y0 + y).saturating_sub(char_margin);
         let w = w + 2 * char_margin;
         let h = h + 2 * char_margin;
-        self.image.crop_imm(x, y, w, h).to_luma8()
+        let mut image = self.image.crop_imm(x, y, w, h).to_luma8();
+        // 统一为浅色背景上的深色文字
+        if light_text {
+            invert(&mut image);
+        }
+        let image = if self.layout.scale == 1.0 {
+            image
+        } else {
+            let normalized =
+                |value: u32| ((value as f32 / self.layout.scale).round() as u32).max(1);
+            resize(&image, normalized(w), normalized(h), FilterType::Lanczos3)
+        };
+        preprocessing.apply(image)
     }
 
-    fn index_ocr_image(&self) -> GrayImage {
-        self.get_ocr_image(INDEX_X0, INDEX_Y0, INDEX_X1, INDEX_Y1)
+    /// 页码区域识别出的文本
+    fn index_text(&self, preprocessing: Preprocessing, ocr: &BatchOcr) -> Result<String> {
+        let (x0, y0, x1, y1) = self.layout.index_area;
+        let image = self.get_ocr_image_with(x0, y0, x1, y1, preprocessing);
+        Ok(ocr(vec![(image, CharsetHint::Digits)])?
+            .pop()
+            .unwrap_or_default())
     }
 
-    fn item_name_ocr_image(&self, index: usize) -> GrayImage {
-        let y0 = RECORD_Y0S[index];
-        let y1 = RECORD_Y1S[index];
-        self.get_ocr_image(ITEM_NAME_X0, y0, ITEM_NAME_X1, y1)
+    /// 一行中名称、类型、时间三个单元格用于 OCR 的图片和字符集提示
+    fn cell_ocr_images(
+        &self,
+        index: usize,
+        preprocessing: Preprocessing,
+    ) -> [(GrayImage, CharsetHint); 3] {
+        let (y0, y1) = self.layout.record_ys[index];
+        let cell = |(x0, x1): (u32, u32)| self.get_ocr_image_with(x0, y0, x1, y1, preprocessing);
+        [
+            (cell(self.layout.item_name_x), CharsetHint::Name),
+            (
+                cell(self.layout.item_type_x),
+                CharsetHint::Vocabulary(&ITEM_TYPE_VOCABULARY),
+            ),
+            (cell(self.layout.time_x), CharsetHint::DateTime),
+        ]
     }
 
-    fn item_type_ocr_image(&self, index: usize) -> GrayImage {
-        let y0 = RECORD_Y0S[index];
-        let y1 = RECORD_Y1S[index];
-        self.get_ocr_image(ITEM_TYPE_X0, y0, ITEM_TYPE_X1, y1)
+    /// 抽卡记录表格区域的感知哈希，不包含页码，用于判断翻页是否生效
+    pub fn page_hash(&self) -> PerceptualHash {
+        let y0 = self.layout.record_ys[0].0;
+        let y1 = self.layout.record_ys[self.layout.record_ys.len() - 1].1;
+        let x0 = self.layout.item_name_x.0;
+        let x1 = self.layout.time_x.1;
+        let table_image = self.image.crop_imm(x0, y0, x1 - x0, y1 - y0);
+        PerceptualHash::dhash(&table_image, 64, 40)
     }
 
-    fn time_ocr_image(&self, index: usize) -> GrayImage {
-        let y0 = RECORD_Y0S[index];
-        let y1 = RECORD_Y1S[index];
-        self.get_ocr_image(TIME_X0, y0, TIME_X1, y1)
+    pub fn index(&self) -> Result<u32> {
+        parse_index(&self.index_text(config().ocr_preprocessing, &ocr_batch)?)
     }
 
-    fn index_str(&self) -> String {
-        let image = self.index_ocr_image();
-        ocr(DynamicImage::ImageLuma8(image)).0
+    /// 用指定的设置识别页码，不读取配置，见 [`RecordImage::index`]
+    pub fn index_with(&self, options: &ParseOptions, ocr: &BatchOcr) -> Result<u32> {
+        parse_index(&self.index_text(options.preprocessing, ocr)?)
     }
 
-    fn item_name_str(&self, index: usize) -> String {
-        let image = self.item_name_ocr_image(index);
-        ocr(DynamicImage::ImageLuma8(image)).0
+    /// 在截图上画出每行中名称、类型、时间单元格、页码区域和星级采样点，
+    /// 用于排查坐标与实际界面不一致的问题
+    pub fn debug_boxes_image(&self) -> RgbImage {
+        let mut image = self.image.to_rgb8();
+        let (width, height) = image.dimensions();
+        let mut draw_box = |(x0, y0, x1, y1): (u32, u32, u32, u32), color: [u8; 3]| {
+            let (x1, y1) = (x1.min(width), y1.min(height));
+            if x1 > x0 && y1 > y0 {
+                let rect = Rect::at(x0 as i32, y0 as i32).of_size(x1 - x0, y1 - y0);
+                draw_hollow_rect_mut(&mut image, rect, Rgb(color));
+            }
+        };
+        for &(y0, y1) in self.layout.record_ys.iter() {
+            draw_box(
+                (self.layout.item_name_x.0, y0, self.layout.item_name_x.1, y1),
+                [255, 0, 0],
+            );
+            draw_box(
+                (self.layout.item_type_x.0, y0, self.layout.item_type_x.1, y1),
+                [0, 255, 0],
+            );
+            draw_box(
+                (self.layout.time_x.0, y0, self.layout.time_x.1, y1),
+                [0, 128, 255],
+            );
+        }
+        draw_box(self.layout.index_area, [255, 255, 0]);
+        for &y in self.layout.star_ys.iter() {
+            let x = self.layout.star_x;
+            draw_box(
+                (x.saturating_sub(3), y.saturating_sub(3), x + 4, y + 4),
+                [255, 0, 255],
+            );
+        }
+        image
     }
 
-    fn item_type_str(&self, index: usize) -> String {
-        let image = self.item_type_ocr_image(index);
-        ocr(DynamicImage::ImageLuma8(image)).0
+    /// 一行抽卡记录的图片，包含名称、类型和时间
+    fn row_image(&self, index: usize) -> DynamicImage {
+        let (y0, y1) = self.layout.record_ys[index];
+        let x0 = self.layout.star_x;
+        let x1 = self.layout.time_x.1;
+        self.image.crop_imm(x0, y0, x1 - x0, y1 - y0)
     }
 
-    fn time_str(&self, index: usize) -> String {
-        let image = self.time_ocr_image(index);
-        ocr(DynamicImage::ImageLuma8(image)).0
+    /// 识别星级、页码和每行的抽卡记录，见 [`parse_record_page`]
+    pub fn parse(&self, options: &ParseOptions, ocr: &BatchOcr) -> Result<ParsedPage> {
+        let stars = self.stars_with(&options.star_colors);
+        let index_text = self.index_text(options.preprocessing, ocr)?;
+        let index = parse_index(&index_text).ok();
+        let (rows, retried_rows) = match index {
+            Some(_) if !stars.is_empty() => self.parse_rows(&stars, options, ocr)?,
+            _ => (vec![], 0),
+        };
+        Ok(ParsedPage {
+            index,
+            rows,
+            diagnostics: PageDiagnostics {
+                size: self.image.dimensions(),
+                stars,
+                index_text,
+                retried_rows,
+            },
+        })
     }
 
-    pub fn index(&self) -> Result<u32> {
-        self.index_str()
-            .parse()
-            .map_err(|e| anyhow!("Failed to parse index, {:?}", e))
+    /// 逐行识别一页中的抽卡记录
+    pub fn rows(&self) -> Result<Vec<Result<OneRecord, Box<InvalidRow>>>> {
+        let options = ParseOptions::from_config();
+        let stars = self.stars_with(&options.star_colors);
+        Ok(self.parse_rows(&stars, &options, &ocr_batch)?.0)
     }
 
-    fn item_type(&self, index: usize) -> Result<ItemType> {
-        let item_type = self.item_type_str(index);
-        all::<ItemType>()
-            .find(|&item| {
-                item.display_names_in_record_page_in_game_in_all_languages()
-                    .contains(&item_type.as_str())
-            })
-            .ok_or(anyhow!("Unknown item type: {}", item_type))
-    }
-
-    fn timestamp(&self, index: usize) -> Result<u64> {
-        let time_str = self.time_str(index);
-        let time = chrono::NaiveDateTime::parse_from_str(&time_str, "%Y-%m-%d %H:%M")
-            .map_err(|e| anyhow!("Failed to parse date time: {:?}", e))?;
-        let local_date_time = Local
-            .from_local_datetime(&time)
-            .single()
-            .ok_or(anyhow!("Invalid local date time: {}", time_str))?;
-        Ok(local_date_time.timestamp() as u64)
-    }
-
-    pub fn records(&self) -> Vec<OneRecord> {
-        let stars = self.stars();
-        stars
-            .into_iter()
-            .enumerate()
-            .map(|(i, star)| {
-                let item_name = self.item_name_str(i);
-                let item_type = self.item_type(i).unwrap();
-                let time = self.timestamp(i).unwrap();
-                OneRecord::new(star, item_name, item_type, time)
-            })
-            .collect()
+    /// 识别每行的抽卡记录
+    /// 整页的名称、类型、时间图片一次性交给 OCR 批量识别，
+    /// 开启自动重试时，识别失败的行用全部预处理重新识别一次
+    /// # 返回
+    /// （每行的识别结果, 重新识别的行数）
+    /// # 错误
+    /// `ocr` 返回的错误
+    fn parse_rows(
+        &self,
+        stars: &[u8],
+        options: &ParseOptions,
+        ocr: &BatchOcr,
+    ) -> Result<(Rows, usize)> {
+        // 名称、类型、时间文本转换为抽卡记录
+        let parse_row = |star: u8, texts: &[String]| -> Result<OneRecord> {
+            let item_type = resolve_item_type(&texts[0], star, &texts[1], options)?;
+            let time = parse_timestamp(&texts[2])?;
+            Ok(OneRecord::new(
+                star,
+                canonical_name(&texts[0]),
+                item_type,
+                time,
+            ))
+        };
+
+        let preprocessing = options.preprocessing;
+        let ocr_images = (0..stars.len())
+            .flat_map(|i| self.cell_ocr_images(i, preprocessing))
+            .collect::<Vec<_>>();
+        let texts = ocr(ocr_images)?;
+        let mut retried_rows = 0;
+        let mut rows = vec![];
+        for (row, (star, texts)) in stars.iter().copied().zip(texts.chunks(3)).enumerate() {
+            let mut result = parse_row(star, texts);
+            if let Err(error) = &result {
+                if preprocessing.can_retry() {
+                    log::debug!(
+                        "row {} failed, retry with preprocessing: {:?}",
+                        row + 1,
+                        error
+                    );
+                    retried_rows += 1;
+                    let retry_texts = ocr(self
+                        .cell_ocr_images(row, Preprocessing::strongest())
+                        .to_vec())?;
+                    if let Ok(record) = parse_row(star, &retry_texts) {
+                        result = Ok(record);
+                    }
+                }
+            }
+            rows.push(result.map_err(|error| {
+                Box::new(InvalidRow {
+                    row,
+                    star,
+                    item_name: texts[0].clone(),
+                    item_type_text: texts[1].clone(),
+                    time_text: texts[2].clone(),
+                    error,
+                    image: self.row_image(row),
+                })
+            }));
+        }
+        Ok((rows, retried_rows))
+    }
+}
+
+/// 识别页码文本
+fn parse_index(index_text: &str) -> Result<u32> {
+    index_text
+        .parse()
+        .map_err(|e| anyhow!("Failed to parse index, {:?}", e))
+}
+
+/// 识别类型文本，允许少量识别错误，例如 "Weanon"
+/// 匹配所有语言，已经检测到游戏客户端语言时先只匹配这种语言
+pub fn parse_item_type(item_type: &str) -> Result<ItemType> {
+    parse_item_type_in(item_type, *CLIENT_LANGUAGE.read().unwrap())
+}
+
+/// 识别类型文本，`client_language` 不为 `None` 时先只匹配这种语言，见 [`parse_item_type`]
+fn parse_item_type_in(item_type: &str, client_language: Option<Language>) -> Result<ItemType> {
+    if let Some(language) = client_language {
+        let names = all::<ItemType>()
+            .map(|item| (item, item.display_name_in_record_page_in_game(language)))
+            .collect::<Vec<_>>();
+        if let Ok(item) = closest_item_type(item_type, &names) {
+            return Ok(item);
+        }
+    }
+    let names = all::<ItemType>()
+        .flat_map(|item| {
+            item.display_names_in_record_page_in_game_in_all_languages()
+                .into_iter()
+                .map(move |name| (item, name))
+        })
+        .collect::<Vec<_>>();
+    closest_item_type(item_type, &names)
+}
+
+/// 与类型文本编辑距离最小的物品类型，最接近的不止一个或者差别太大时报错
+fn closest_item_type(item_type: &str, names: &[(ItemType, &str)]) -> Result<ItemType> {
+    let candidates = names
+        .iter()
+        .map(|(item, name)| (*item, edit_distance(name, item_type), name.chars().count()))
+        .collect::<Vec<_>>();
+    let min_distance = candidates
+        .iter()
+        .map(|(_, distance, _)| *distance)
+        .min()
+        .unwrap();
+    let mut closest = candidates
+        .iter()
+        .filter(|(_, distance, _)| *distance == min_distance);
+    match (closest.next(), closest.next()) {
+        (Some(&(item, distance, len)), None) if distance <= (len / 4).max(1) => Ok(item),
+        _ => Err(anyhow!("Unknown item type: {}", item_type)),
+    }
+}
+
+/// 编辑距离
+fn edit_distance(a: &str, b: &str) -> usize {
+    let b = b.chars().collect::<Vec<_>>();
+    let mut previous_row = (0..=b.len()).collect::<Vec<_>>();
+    for (i, a_char) in a.chars().enumerate() {
+        let mut row = vec![i + 1];
+        for (j, b_char) in b.iter().enumerate() {
+            let substitution = previous_row[j] + usize::from(a_char != *b_char);
+            row.push(substitution.min(previous_row[j + 1] + 1).min(row[j] + 1));
+        }
+        previous_row = row;
+    }
+    previous_row[b.len()]
+}
+
+/// 确定物品类型
+/// 类型文本和物品名称（结合星级）是两个独立的来源，只有两者冲突或都无法确定时才报错
+fn resolve_item_type(
+    item_name: &str,
+    star: u8,
+    item_type_text: &str,
+    options: &ParseOptions,
+) -> Result<ItemType> {
+    let from_text = parse_item_type_in(item_type_text, options.client_language);
+    let from_name = options.item_types.get(item_name, star);
+    match (from_text, from_name) {
+        (Ok(from_text), Some(from_name)) if from_text != from_name => Err(anyhow!(
+            "Item type conflict: {} is {:?} but type text is {}",
+            item_name,
+            from_name,
+            item_type_text
+        )),
+        (Ok(item_type), _) => Ok(item_type),
+        (Err(e), Some(item_type)) => {
+            log::warn!("{:?}, inferred {:?} from {}", e, item_type, item_name);
+            Ok(item_type)
+        }
+        (Err(e), None) => Err(e),
+    }
+}
+
+/// 识别时间文本
+/// 时间不在游戏上线到现在之间时，尝试修正年份中识别错的一位数字（例如把 "2024" 识别成 "2924"），
+/// 只有唯一的修正结果时才使用，否则返回错误
+pub fn parse_timestamp(time_str: &str) -> Result<u64> {
+    parse_timestamp_before(time_str, Local::now().timestamp() as u64)
+}
+
+/// 游戏上线日期，之前不可能有抽卡记录
+static GAME_LAUNCH_DATE: (i32, u32, u32) = (2023, 7, 20);
+/// 允许晚于当前时间的范围，避免时区或时钟偏差导致误判
+static FUTURE_TOLERANCE_SECS: u64 = 24 * 60 * 60;
+
+fn local_timestamp(time: NaiveDateTime) -> Option<u64> {
+    Local
+        .from_local_datetime(&time)
+        .single()
+        .map(|date_time| date_time.timestamp() as u64)
+}
+
+/// 年份中一位数字替换或相邻两位数字交换后的年份
+fn year_candidates(year: i32) -> Vec<i32> {
+    let digits = format!("{year:04}").into_bytes();
+    let mut candidates = vec![];
+    for i in 0..digits.len() {
+        for digit in b'0'..=b'9' {
+            let mut candidate = digits.clone();
+            candidate[i] = digit;
+            candidates.push(candidate);
+        }
+        if i + 1 < digits.len() {
+            let mut candidate = digits.clone();
+            candidate.swap(i, i + 1);
+            candidates.push(candidate);
+        }
+    }
+    let mut years = candidates
+        .into_iter()
+        .filter_map(|candidate| String::from_utf8(candidate).ok()?.parse().ok())
+        .filter(|&candidate| candidate != year)
+        .collect::<Vec<_>>();
+    years.sort();
+    years.dedup();
+    years
+}
+
+/// # 参数
+/// - `now`: 当前时间戳，时间不能晚于这个时间太多
+fn parse_timestamp_before(time_str: &str, now: u64) -> Result<u64> {
+    let time = NaiveDateTime::parse_from_str(time_str, "%Y-%m-%d %H:%M")
+        .map_err(|e| anyhow!("Failed to parse date time: {:?}", e))?;
+    let timestamp =
+        local_timestamp(time).ok_or(anyhow!("Invalid local date time: {}", time_str))?;
+
+    let (year, month, day) = GAME_LAUNCH_DATE;
+    let min_timestamp = NaiveDate::from_ymd_opt(year, month, day)
+        .and_then(|date| date.and_hms_opt(0, 0, 0))
+        .and_then(local_timestamp)
+        .unwrap();
+    let max_timestamp = now + FUTURE_TOLERANCE_SECS;
+    let in_range = |timestamp: &u64| (min_timestamp..=max_timestamp).contains(timestamp);
+    if in_range(&timestamp) {
+        return Ok(timestamp);
+    }
+
+    let corrected = year_candidates(time.year())
+        .into_iter()
+        .filter_map(|year| {
+            let timestamp = time.with_year(year).and_then(local_timestamp)?;
+            in_range(&timestamp).then_some((year, timestamp))
+        })
+        .collect::<Vec<_>>();
+    match corrected[..] {
+        [(year, corrected)] => {
+            log::warn!("corrected out of range time {time_str} to year {year}");
+            Ok(corrected)
+        }
+        _ => Err(anyhow!("Time out of range: {}", time_str)),
     }
 }
 
@@ -253,7 +789,7 @@ mod test {
     fn test() {
         let image_dir = "not_in_git/images";
         // let image_dir = r"D:\PortableSoftware\ShareX\ShareX\Screenshots\2024-06";
-        let image_file_paths = Path::new(image_dir)
+        let _image_file_paths = Path::new(image_dir)
             .read_dir()
             .unwrap()
             .map(|entry| entry.unwrap().path())
@@ -269,11 +805,177 @@ mod test {
             }
             println!("index: {}", record_image.index().unwrap());
             let start = Instant::now();
-            let records = record_image.records();
+            let records = record_image
+                .rows()
+                .unwrap()
+                .into_iter()
+                .map(|row| row.unwrap())
+                .collect::<Vec<_>>();
             println!("ocr records spend: {:?}", start.elapsed());
             for record in records {
                 println!("{} {}", record.item_name, record.readable_date_time_str());
             }
         }
     }
+
+    #[test]
+    fn test_char_area() {
+        // 合成的截图：浅色背景深色文字、深色主题、渐变背景，文字都在 (20, 8)-(58, 23)
+        let fixture = |background: &dyn Fn(u32) -> u8, text: u8| {
+            GrayImage::from_fn(100, 32, |x, y| {
+                let is_text = (20..60).contains(&x) && (8..24).contains(&y) && x % 4 != 3;
+                image::Luma([if is_text { text } else { background(x) }])
+            })
+        };
+        let light = fixture(&|_| 230, 40);
+        let dark = fixture(&|_| 30, 220);
+        let gradient = fixture(&|x| (60 + x * 2) as u8, 10);
+        for (image, light_text) in [(light, false), (dark, true), (gradient, false)] {
+            let area = char_area(&image);
+            assert_eq!(area.light_text, light_text);
+            assert_eq!(area.xywh, (20, 8, 39, 16));
+        }
+        // 没有文字
+        let blank = fixture(&|x| x as u8, 0);
+        let blank = GrayImage::from_fn(100, 32, |x, _| blank.get_pixel(x, 0).to_owned());
+        assert_eq!(char_area(&blank).xywh, (0, 0, 100, 32));
+    }
+
+    /// 真实截图中裁剪出的名称单元格（1920x1080 截图中一行的高度），浅色主题和深色主题各一张
+    #[test]
+    #[ignore = "needs real record screenshots in not_in_git/images"]
+    fn test_char_area_themes() {
+        for (path, light_text) in [
+            ("not_in_git/images/theme_light_cell.png", false),
+            ("not_in_git/images/theme_dark_cell.png", true),
+        ] {
+            let image = image::open(path).unwrap().to_luma8();
+            let area = char_area(&image);
+            let (_, _, w, h) = area.xywh;
+            assert_eq!(area.light_text, light_text, "{path}");
+            // 找到的是文字而不是整个单元格
+            assert!(w < image.width(), "{path}: {:?}", area.xywh);
+            assert!(
+                (CHAR_HEIGHT / 2..=CHAR_HEIGHT + CHAR_MARGIN).contains(&h),
+                "{path}: {:?}",
+                area.xywh
+            );
+        }
+    }
+
+    #[test]
+    fn test_layout() {
+        let layout = Layout::new(1920);
+        assert_eq!(layout.record_ys[0], (207, 239));
+        assert_eq!(layout.record_ys[9], (814, 846));
+        assert_eq!(layout.star_ys[0], 223);
+        let layout = Layout::new(1280);
+        assert_eq!(layout.item_name_x, (245, 589));
+        assert_eq!(layout.record_ys[0], (138, 159));
+        assert_eq!(
+            layout.scale_area((1560, 960, 1700, 1010)),
+            (1040, 640, 1133, 673)
+        );
+
+        // 低分辨率截图裁剪出的单元格缩放到基准大小
+        let mut image = image::RgbImage::from_pixel(1280, 720, image::Rgb([230, 230, 230]));
+        for x in 300..400 {
+            for y in 143..155 {
+                image.put_pixel(x, y, image::Rgb([20, 20, 20]));
+            }
+        }
+        let record_image = RecordImage::new(DynamicImage::ImageRgb8(image));
+        let (x0, x1) = record_image.layout.item_name_x;
+        let (y0, y1) = record_image.layout.record_ys[0];
+        let ocr_image = record_image.get_ocr_image_with(x0, y0, x1, y1, Preprocessing::default());
+        assert_eq!(ocr_image.height(), 33);
+    }
+
+    #[test]
+    fn test_parse_record_page() {
+        // 合成的截图：浅色背景，每行左侧是星级颜色条，文字由假的 OCR 返回
+        let options = ParseOptions::default();
+        let colors = [
+            options.star_colors.star_3,
+            options.star_colors.star_4,
+            options.star_colors.star_5,
+        ];
+        let layout = Layout::new(1920);
+        let mut image = RgbImage::from_pixel(1920, 1080, Rgb([228, 228, 228]));
+        for (row, &(y0, y1)) in layout.record_ys.iter().enumerate() {
+            for y in y0..y1 {
+                for x in layout.star_x - 4..layout.star_x + 4 {
+                    image.put_pixel(x, y, Rgb(colors[row % 3]));
+                }
+            }
+        }
+        let weapon = ItemType::Weapon.display_name_in_record_page_in_game(Language::English);
+        let fake_ocr = |images: Vec<(GrayImage, CharsetHint)>| {
+            Ok(images
+                .into_iter()
+                .map(|(_, hint)| match hint {
+                    CharsetHint::Digits => "2".to_string(),
+                    CharsetHint::Name => "Test Item".to_string(),
+                    CharsetHint::DateTime => "2024-06-01 10:00".to_string(),
+                    _ => weapon.to_string(),
+                })
+                .collect::<Vec<_>>())
+        };
+        let image = DynamicImage::ImageRgb8(image);
+
+        // OCR 出错时返回错误，不当作识别失败的行
+        let failed_ocr = |_| Err(anyhow!("ocr workers stopped"));
+        assert!(parse_record_page(image.clone(), &options, &failed_ocr).is_err());
+
+        let page = parse_record_page(image, &options, &fake_ocr).unwrap();
+        assert!(page.is_record_page());
+        assert_eq!(page.index, Some(2));
+        assert_eq!(page.diagnostics.size, (1920, 1080));
+        assert_eq!(page.diagnostics.stars[..3], [3, 4, 5]);
+        assert_eq!(page.diagnostics.retried_rows, 0);
+        assert_eq!(page.records().count(), 10);
+        assert!(page
+            .records()
+            .all(|record| record.item_type == ItemType::Weapon));
+
+        // 不是抽卡记录界面时不识别每行
+        let blank = DynamicImage::ImageRgb8(RgbImage::new(1920, 1080));
+        let page = parse_record_page(blank, &options, &fake_ocr).unwrap();
+        assert!(!page.is_record_page());
+        assert!(page.rows.is_empty());
+    }
+
+    #[test]
+    fn test_parse_timestamp() {
+        let now = parse_timestamp("2024-12-31 12:00").unwrap();
+        let parse = |time_str| parse_timestamp_before(time_str, now);
+        let expected = parse("2024-06-01 10:00").unwrap();
+        // 年份中一位数字识别错误
+        assert_eq!(parse("2924-06-01 10:00").unwrap(), expected);
+        assert_eq!(parse("2042-06-01 10:00").unwrap(), expected);
+        // 2023 和 2024 都可能，无法修正
+        assert!(parse("2029-08-01 10:00").is_err());
+        assert!(parse("1999-08-01 10:00").is_err());
+    }
+
+    #[test]
+    fn test_parse_item_type() {
+        assert_eq!(parse_item_type("Weapon").unwrap(), ItemType::Weapon);
+        assert_eq!(parse_item_type("Weanon").unwrap(), ItemType::Weapon);
+        assert_eq!(parse_item_type("角色").unwrap(), ItemType::Character);
+        assert!(parse_item_type("").is_err());
+        assert!(parse_item_type("Operative Weapon").is_err());
+        let options = ParseOptions {
+            item_types: ItemTypes::snapshot(),
+            ..Default::default()
+        };
+        assert_eq!(
+            resolve_item_type("Lyfe - Wild Hunt", 5, "", &options).unwrap(),
+            ItemType::Character
+        );
+        assert!(resolve_item_type("Lyfe - Wild Hunt", 5, "Weapon", &options).is_err());
+        // 没有传入物品字典时不按名称推断
+        let options = ParseOptions::default();
+        assert!(resolve_item_type("Lyfe - Wild Hunt", 5, "", &options).is_err());
+    }
 }
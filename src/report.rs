@@ -0,0 +1,216 @@
+//! 导入报告
+//!
+//! 每次从游戏导入后，在数据目录的 reports 中写入一份文本报告，逐页列出识别出的行和警告，
+//! 用户怀疑漏了抽卡记录时可以核对具体导入了什么。
+
+use std::fmt::Write;
+use std::path::PathBuf;
+
+use anyhow::Result;
+use chrono::{DateTime, Local};
+use clap::ValueEnum;
+
+use crate::data_dir::data_path;
+use crate::language::Language;
+use crate::record::{BannerType, OneRecord};
+use crate::record_image::InvalidRow;
+
+/// 一行的识别结果
+#[derive(Debug, Clone)]
+enum RowOutcome {
+    Parsed {
+        star: u8,
+        item_name: String,
+        item_type: String,
+        time: String,
+    },
+    /// 识别失败，宽松模式下放入待审核列表
+    Failed { error: String },
+}
+
+/// 一页的识别结果
+#[derive(Debug, Clone)]
+struct PageReport {
+    page: u32,
+    rows: Vec<RowOutcome>,
+}
+
+/// 一次导入的报告
+#[derive(Debug, Clone)]
+pub struct SessionReport {
+    account_id: String,
+    banner_type: BannerType,
+    language: Language,
+    started_at: DateTime<Local>,
+    pages: Vec<PageReport>,
+    warnings: Vec<String>,
+    /// 新增抽卡记录数量，合并保存前为 `None`
+    added: Option<u32>,
+    /// 增量导入提前停止时大约跳过的页数，没有提前停止时为 `None`
+    skipped_pages: Option<u32>,
+}
+
+impl SessionReport {
+    pub fn new(account_id: &str, banner_type: BannerType, language: Language) -> Self {
+        Self {
+            account_id: account_id.to_string(),
+            banner_type,
+            language,
+            started_at: Local::now(),
+            pages: vec![],
+            warnings: vec![],
+            added: None,
+            skipped_pages: None,
+        }
+    }
+
+    /// 记录一页中每一行的识别结果
+    pub fn add_page(&mut self, page: u32, rows: &[Result<OneRecord, Box<InvalidRow>>]) {
+        let rows = rows
+            .iter()
+            .map(|row| match row {
+                Ok(record) => RowOutcome::Parsed {
+                    star: record.star,
+                    item_name: record.item_name.clone(),
+                    item_type: record
+                        .item_type
+                        .display_name_for_user(self.language)
+                        .to_string(),
+                    time: record.readable_date_time_str(),
+                },
+                Err(invalid_row) => RowOutcome::Failed {
+                    error: format!("{:#}", invalid_row.error),
+                },
+            })
+            .collect();
+        self.pages.push(PageReport { page, rows });
+    }
+
+    pub fn add_warning(&mut self, warning: impl Into<String>) {
+        self.warnings.push(warning.into());
+    }
+
+    pub fn set_added(&mut self, added: u32) {
+        self.added = Some(added);
+    }
+
+    pub fn set_skipped_pages(&mut self, skipped_pages: u32) {
+        self.skipped_pages = Some(skipped_pages);
+    }
+
+    /// 报告文件名，例如 `2024-07-06_153000_limited-weapon-50.txt`
+    pub fn file_name(&self) -> String {
+        let banner = self
+            .banner_type
+            .to_possible_value()
+            .map(|value| value.get_name().to_string())
+            .unwrap_or_default();
+        format!("{}_{banner}.txt", self.started_at.format("%Y-%m-%d_%H%M%S"))
+    }
+
+    /// 报告文本
+    pub fn render(&self) -> String {
+        let mut text = String::new();
+        let row_num = self.pages.iter().map(|page| page.rows.len()).sum::<usize>();
+        let _ = writeln!(text, "Account: {}", self.account_id);
+        let _ = writeln!(
+            text,
+            "Banner: {}",
+            self.banner_type.display_name_for_user(self.language)
+        );
+        let _ = writeln!(
+            text,
+            "Started: {}",
+            self.started_at.format("%Y-%m-%d %H:%M:%S")
+        );
+        let _ = writeln!(text, "Pages: {}, rows: {row_num}", self.pages.len());
+        if let Some(skipped_pages) = self.skipped_pages {
+            let _ = writeln!(text, "Skipped pages (incremental): about {skipped_pages}");
+        }
+        match self.added {
+            Some(added) => {
+                let _ = writeln!(text, "Added: {added}");
+            }
+            None => {
+                let _ = writeln!(text, "Added: none, records not saved");
+            }
+        }
+        for page in self.pages.iter() {
+            let _ = writeln!(text, "\n[Page {}]", page.page);
+            for (row, outcome) in page.rows.iter().enumerate() {
+                let _ = match outcome {
+                    RowOutcome::Parsed {
+                        star,
+                        item_name,
+                        item_type,
+                        time,
+                    } => writeln!(
+                        text,
+                        "{:>3}. {star}★ {item_name} | {item_type} | {time}",
+                        row + 1
+                    ),
+                    RowOutcome::Failed { error } => {
+                        writeln!(text, "{:>3}. FAILED: {error}", row + 1)
+                    }
+                };
+            }
+        }
+        if !self.warnings.is_empty() {
+            let _ = writeln!(text, "\n[Warnings]");
+            for warning in self.warnings.iter() {
+                let _ = writeln!(text, "- {warning}");
+            }
+        }
+        text
+    }
+
+    /// 保存到数据目录的 reports 中
+    /// # 返回
+    /// 报告文件的路径
+    pub fn save(&self) -> Result<PathBuf> {
+        let path = data_path("reports").join(self.file_name());
+        if let Some(parent) = path.parent() {
+            std::fs::create_dir_all(parent)?;
+        }
+        std::fs::write(&path, self.render())?;
+        Ok(path)
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use image::DynamicImage;
+
+    use super::*;
+    use crate::record::ItemType;
+
+    #[test]
+    fn test_render() {
+        let mut report =
+            SessionReport::new("123", BannerType::LimitedWeapon50Percent, Language::English);
+        assert!(report.file_name().ends_with("_limited-weapon-50.txt"));
+        let invalid_row = InvalidRow {
+            row: 1,
+            star: 3,
+            item_name: "b".to_string(),
+            item_type_text: String::new(),
+            time_text: String::new(),
+            error: anyhow::anyhow!("unknown item type"),
+            image: DynamicImage::new_rgb8(1, 1),
+        };
+        report.add_page(
+            1,
+            &[
+                Ok(OneRecord::new(5, "a".to_string(), ItemType::Weapon, 0)),
+                Err(Box::new(invalid_row)),
+            ],
+        );
+        report.add_warning("page 1 row 2 quarantined");
+        report.set_added(1);
+        let text = report.render();
+        assert!(text.contains("Pages: 1, rows: 2\nAdded: 1\n"));
+        assert!(text.contains("\n[Page 1]\n  1. 5★ a | "));
+        assert!(text.contains("\n  2. FAILED: unknown item type\n"));
+        assert!(text.contains("\n[Warnings]\n- page 1 row 2 quarantined\n"));
+    }
+}
@@ -0,0 +1,114 @@
+//! 待审核的抽卡记录
+//!
+//! 宽松模式下识别失败的行不会中止导入，而是连同截图保存到 `pending_review.json`，之后再人工修正。
+
+use std::fs::File;
+use std::path::PathBuf;
+
+use anyhow::Result;
+use serde::{Deserialize, Serialize};
+
+use crate::data_dir::{data_path, write_atomic};
+use crate::error::save_error;
+use crate::record::BannerType;
+use crate::record_image::InvalidRow;
+
+/// 一条待审核的抽卡记录
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct PendingRecord {
+    pub account_id: String,
+    pub banner_type: BannerType,
+    /// 所属的导入批次ID
+    pub batch_id: u64,
+    pub page: u32,
+    /// 在页中的行号，从 0 开始
+    pub row: usize,
+    pub star: u8,
+    /// OCR 识别的名称
+    pub item_name: String,
+    /// OCR 识别的类型文本
+    pub item_type_text: String,
+    /// OCR 识别的时间文本
+    pub time_text: String,
+    pub error: String,
+    /// 这一行截图的路径
+    pub image_path: Option<PathBuf>,
+}
+
+#[derive(Debug, Default, Serialize, Deserialize)]
+pub struct PendingReview {
+    pub records: Vec<PendingRecord>,
+}
+
+fn pending_review_path() -> PathBuf {
+    data_path("records/pending_review.json")
+}
+
+impl PendingReview {
+    pub fn read_or_default() -> Self {
+        let path = pending_review_path();
+        if !path.exists() {
+            return Self::default();
+        }
+        let read = || -> Result<Self> {
+            let reader = std::io::BufReader::new(File::open(&path)?);
+            Ok(serde_json::from_reader(reader)?)
+        };
+        read().unwrap_or_else(|e| {
+            log::error!("Failed to read pending review: {:?}", e);
+            Self::default()
+        })
+    }
+
+    pub fn save(&self) -> Result<()> {
+        let path = pending_review_path();
+        if let Some(parent) = path.parent() {
+            std::fs::create_dir_all(parent)?;
+        }
+        let contents = serde_json::to_vec_pretty(self)?;
+        write_atomic(&path, &contents).map_err(|e| save_error(e, &path))
+    }
+}
+
+/// 把识别失败的行加入待审核列表，截图保存到 `records/review` 目录
+/// # 参数
+/// - `invalid_rows`: （页码, 识别失败的行）
+pub fn quarantine(
+    account_id: &str,
+    banner_type: BannerType,
+    batch_id: u64,
+    invalid_rows: Vec<(u32, Box<InvalidRow>)>,
+) -> Result<Vec<PendingRecord>> {
+    let mut pending_review = PendingReview::read_or_default();
+    let image_dir = data_path("records/review");
+    std::fs::create_dir_all(&image_dir)?;
+    let mut pending_records = vec![];
+    for (page, invalid_row) in invalid_rows {
+        let image_path = image_dir.join(format!("{}_{}_{}.png", batch_id, page, invalid_row.row));
+        let image_path = match invalid_row.image.save(&image_path) {
+            Ok(()) => Some(image_path),
+            Err(e) => {
+                log::warn!("failed to save row image: {:?}", e);
+                None
+            }
+        };
+        pending_records.push(PendingRecord {
+            account_id: account_id.to_string(),
+            banner_type,
+            batch_id,
+            page,
+            row: invalid_row.row,
+            star: invalid_row.star,
+            item_name: invalid_row.item_name,
+            item_type_text: invalid_row.item_type_text,
+            time_text: invalid_row.time_text,
+            error: format!("{:#}", invalid_row.error),
+            image_path,
+        });
+    }
+    pending_review
+        .records
+        .extend(pending_records.iter().cloned());
+    pending_review.save()?;
+    Ok(pending_records)
+}
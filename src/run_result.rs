@@ -0,0 +1,100 @@
+use std::collections::HashMap;
+use std::fs::File;
+
+use anyhow::Result;
+use serde::Serialize;
+
+use crate::data_dir::data_path;
+use crate::error::{find_export_error, remediation_hint, ExportError};
+use crate::record::BannerType;
+
+/// 运行失败的类型，对应不同的进程退出码
+#[derive(Debug, Copy, Clone, PartialEq, Eq, Serialize)]
+pub enum FailureKind {
+    /// 其他错误
+    Other,
+    /// 截图或游戏窗口相关的错误
+    Capture,
+    /// OCR 识别错误
+    Ocr,
+    /// 新旧抽卡记录合并冲突
+    MergeConflict,
+}
+
+impl FailureKind {
+    pub fn exit_code(&self) -> u8 {
+        match self {
+            FailureKind::Other => 1,
+            FailureKind::Capture => 2,
+            FailureKind::Ocr => 3,
+            FailureKind::MergeConflict => 4,
+        }
+    }
+}
+
+/// 运行失败
+#[derive(Debug)]
+pub struct Failure {
+    pub kind: FailureKind,
+    pub error: anyhow::Error,
+}
+
+impl Failure {
+    pub fn new(kind: FailureKind, error: impl Into<anyhow::Error>) -> Self {
+        Self {
+            kind,
+            error: error.into(),
+        }
+    }
+
+    /// 进程退出码，错误链中有 [`ExportError`] 时使用更具体的退出码
+    pub fn exit_code(&self) -> u8 {
+        find_export_error(&self.error)
+            .map(ExportError::exit_code)
+            .unwrap_or_else(|| self.kind.exit_code())
+    }
+}
+
+/// 运行结果，非交互模式下保存到 result.json 供自动化脚本读取
+#[derive(Debug, Default, Serialize)]
+pub struct RunResult {
+    pub success: bool,
+    pub exit_code: u8,
+    pub failure: Option<FailureKind>,
+    pub error: Option<String>,
+    /// 给用户的处理建议
+    pub hint: Option<String>,
+    /// 卡池 -> 新增抽卡记录数量
+    pub added: HashMap<BannerType, u32>,
+    pub warnings: Vec<String>,
+}
+
+impl RunResult {
+    pub fn success(added: HashMap<BannerType, u32>, warnings: Vec<String>) -> Self {
+        Self {
+            success: true,
+            exit_code: 0,
+            added,
+            warnings,
+            ..Default::default()
+        }
+    }
+
+    pub fn failure(failure: &Failure) -> Self {
+        Self {
+            success: false,
+            exit_code: failure.exit_code(),
+            failure: Some(failure.kind),
+            error: Some(format!("{:#}", failure.error)),
+            hint: remediation_hint(&failure.error),
+            ..Default::default()
+        }
+    }
+
+    pub fn save(&self) -> Result<()> {
+        let path = data_path("result.json");
+        let file = File::create(path)?;
+        let writer = std::io::BufWriter::new(file);
+        serde_json::to_writer_pretty(writer, self).map_err(|e| e.into())
+    }
+}
@@ -1,57 +1,288 @@
-use rust_xlsxwriter::{Format, Workbook};
+use std::collections::HashSet;
+use std::path::{Path, PathBuf};
 
+use anyhow::Result;
+use rust_xlsxwriter::{
+    Chart, ChartType, DocProperties, Format, FormatBorder, Workbook, Worksheet, XlsxError,
+};
+
+use crate::error::save_error;
+use crate::export::meta::ExportMeta;
+use crate::export::template::{rows, Cell, Column};
+use crate::i18n::{format_translation, translate};
 use crate::language::Language;
-use crate::record::{BannerType, OneRecord, TotalRecords};
+use crate::record::{readable_time, BannerType, OneAccountRecords, TotalRecords};
+use crate::stats::{five_star_history, period_pulls, ItemCount, Period, TEN_PULL};
+
+fn item_count_headers(language: Language) -> Vec<&'static str> {
+    ["excel.star", "excel.name", "excel.type", "excel.copies"]
+        .into_iter()
+        .map(|key| translate(language, key))
+        .collect()
+}
+
+fn period_pulls_headers(language: Language) -> Vec<&'static str> {
+    [
+        "excel.period",
+        "export.banner",
+        "excel.pulls",
+        "excel.5_star_count",
+    ]
+    .into_iter()
+    .map(|key| translate(language, key))
+    .collect()
+}
+
+/// Excel 表名称的最大字符数
+const MAX_SHEET_NAME_LEN: usize = 31;
+
+/// Excel 表名称中不能出现的字符
+const ILLEGAL_SHEET_NAME_CHARS: [char; 7] = ['*', '?', ':', '[', ']', '\\', '/'];
+
+/// 转换为合法的表名称：不能出现的字符替换为 `_`，去掉开头和结尾的单引号，截断到 31 个字符
+fn sanitize_sheet_name(name: &str) -> String {
+    let name = name
+        .chars()
+        .map(|c| {
+            if ILLEGAL_SHEET_NAME_CHARS.contains(&c) {
+                '_'
+            } else {
+                c
+            }
+        })
+        .collect::<String>();
+    let name = name
+        .trim_start_matches('\'')
+        .chars()
+        .take(MAX_SHEET_NAME_LEN)
+        .collect::<String>();
+    let name = name.trim_end_matches('\'');
+    if name.is_empty() {
+        "Sheet".to_string()
+    } else {
+        name.to_string()
+    }
+}
 
-fn headers(language: Language) -> Vec<&'static str> {
-    match language {
-        Language::ChineseSimplified => {
-            vec!["品质", "名称", "类型", "时间", "5星后", "5星保底", "4星后"]
+/// 一个工作簿中的表名称
+///
+/// 账号ID较长或者含有不能出现的字符时，表名称会被缩短或替换，重名时加上 `~2`、`~3` 等后缀，
+/// 缩短的名称和完整名称记录下来，最后写入图例表
+#[derive(Default)]
+struct SheetNames {
+    /// 已经使用的名称，Excel 比较表名称时不区分大小写
+    used: HashSet<String>,
+    /// 缩短的名称和完整名称
+    legend: Vec<(String, String)>,
+}
+
+impl SheetNames {
+    /// 完整名称对应的表名称
+    fn name(&mut self, full_name: &str) -> String {
+        let base = sanitize_sheet_name(full_name);
+        let mut name = base.clone();
+        let mut n = 1;
+        while self.used.contains(&name.to_lowercase()) {
+            n += 1;
+            let suffix = format!("~{n}");
+            let prefix = base
+                .chars()
+                .take(MAX_SHEET_NAME_LEN - suffix.len())
+                .collect::<String>();
+            name = format!("{prefix}{suffix}");
+        }
+        self.used.insert(name.to_lowercase());
+        if name != full_name {
+            self.legend.push((name.clone(), full_name.to_string()));
+        }
+        name
+    }
+
+    /// 有缩短的表名称时添加图例表，列出缩短的名称和完整名称
+    fn write_legend(&mut self, workbook: &mut Workbook, language: Language) -> Result<()> {
+        if self.legend.is_empty() {
+            return Ok(());
+        }
+        let sheet_name = self.name(translate(language, "excel.legend_sheet"));
+        let worksheet = workbook.add_worksheet();
+        worksheet.set_name(sheet_name)?;
+        worksheet.write(0, 0, translate(language, "excel.sheet_name"))?;
+        worksheet.write(0, 1, translate(language, "excel.full_sheet_name"))?;
+        worksheet.set_column_width(0, 35)?;
+        worksheet.set_column_width(1, 60)?;
+        for (row, (name, full_name)) in self.legend.iter().enumerate() {
+            worksheet.write(row as u32 + 1, 0, name)?;
+            worksheet.write(row as u32 + 1, 1, full_name)?;
         }
-        Language::English => vec![
-            "Star", "Name", "Type", "Time", "After 5*", "5* Pity", "After 4*",
-        ],
+        Ok(())
     }
 }
 
-fn get_other_data(
-    one_records: Vec<OneRecord>,
+/// 汇总图表表名称
+fn summary_sheet_name(account_id: &str, language: Language) -> String {
+    format_translation(
+        translate(language, "excel.summary_sheet"),
+        &[("account_id", account_id.to_string())],
+    )
+}
+
+/// 写入汇总表：星级分布的饼图，每个5星用的抽数随时间变化的折线图，图表的数据写在左侧
+fn write_summary_sheet(
+    workbook: &mut Workbook,
+    sheet_name: &str,
+    account_record: &OneAccountRecords,
+    language: Language,
+) -> Result<()> {
+    let worksheet = workbook.add_worksheet();
+    worksheet.set_name(sheet_name)?;
+
+    // 星级分布
+    worksheet.write(0, 0, translate(language, "excel.star"))?;
+    worksheet.write(0, 1, translate(language, "excel.copies"))?;
+    let stars = [5u8, 4, 3];
+    for (i, star) in stars.iter().enumerate() {
+        let count = account_record
+            .records
+            .values()
+            .flatten()
+            .filter(|record| record.star == *star)
+            .count() as u32;
+        worksheet.write(i as u32 + 1, 0, format!("{star}★"))?;
+        worksheet.write(i as u32 + 1, 1, count)?;
+    }
+    let last_star_row = stars.len() as u32;
+    let mut pie = Chart::new(ChartType::Pie);
+    pie.title()
+        .set_name(translate(language, "excel.rarity_chart"));
+    pie.add_series()
+        .set_categories((sheet_name, 1, 0, last_star_row, 0))
+        .set_values((sheet_name, 1, 1, last_star_row, 1));
+
+    // 每个5星用的抽数
+    let headers = [
+        "excel.time",
+        "export.banner",
+        "excel.name",
+        "excel.pulls_for_5_star",
+    ];
+    let colum_widths = [20, 16, 20, 8];
+    for (i, key) in headers.iter().enumerate() {
+        let col = 3 + i as u16;
+        worksheet.write(0, col, translate(language, key))?;
+        worksheet.set_column_width(col, colum_widths[i])?;
+    }
+    let history = five_star_history(account_record);
+    for (i, five_star_pull) in history.iter().enumerate() {
+        let row = i as u32 + 1;
+        worksheet.write(row, 3, readable_time(five_star_pull.timestamp))?;
+        worksheet.write(
+            row,
+            4,
+            five_star_pull.banner_type.display_name_for_user(language),
+        )?;
+        worksheet.write(row, 5, five_star_pull.item_name.clone())?;
+        worksheet.write(row, 6, five_star_pull.pulls)?;
+    }
+
+    worksheet.insert_chart(0, 8, &pie)?;
+    if !history.is_empty() {
+        let last_row = history.len() as u32;
+        let mut line = Chart::new(ChartType::Line);
+        line.title()
+            .set_name(translate(language, "excel.pity_chart"));
+        line.add_series()
+            .set_name(translate(language, "excel.pulls_for_5_star"))
+            .set_categories((sheet_name, 1, 3, last_row, 3))
+            .set_values((sheet_name, 1, 6, last_row, 6));
+        line.legend().set_hidden();
+        worksheet.insert_chart(16, 8, &line)?;
+    }
+    Ok(())
+}
+
+/// 按周期汇总的抽卡次数表名称
+fn period_pulls_sheet_name(account_id: &str, language: Language) -> String {
+    format_translation(
+        translate(language, "excel.period_pulls_sheet"),
+        &[("account_id", account_id.to_string())],
+    )
+}
+
+/// 物品数量统计表名称
+fn item_count_sheet_name(account_id: &str, language: Language) -> String {
+    format_translation(
+        translate(language, "excel.item_count_sheet"),
+        &[("account_id", account_id.to_string())],
+    )
+}
+
+/// 写入一个卡池的抽卡记录表
+/// # 参数
+/// - `first_row`: 表头所在的行，前面的行留给摘要
+fn write_records_sheet(
+    worksheet: &mut Worksheet,
+    account_id: &str,
+    account_record: &OneAccountRecords,
     banner_type: BannerType,
-) -> (Vec<u32>, Vec<u32>, Vec<u32>) {
-    let mut count_after_5_star = 1;
-    let mut count_after_4_star = 1;
-    let mut counts_after_5_star = vec![];
-    let mut counts_after_4_star = vec![];
-    for one_record in one_records.iter().rev() {
-        counts_after_5_star.push(count_after_5_star);
-        counts_after_4_star.push(count_after_4_star);
-        if one_record.star == 5 {
-            count_after_4_star += 1;
-            count_after_5_star = 1;
-        } else if one_record.star == 4 {
-            count_after_4_star = 1;
-            count_after_5_star += 1;
-        } else {
-            count_after_5_star += 1;
-            count_after_4_star += 1;
+    language: Language,
+    columns: &[Column],
+    first_row: u32,
+) -> Result<()> {
+    // 五星格式
+    let format_5_star = Format::new().set_background_color(0xe99b37);
+    // 四星格式
+    let format_4_star = Format::new().set_background_color(0xc069d6);
+    // 其他格式
+    let format_other = Format::new();
+    for (col, column) in columns.iter().enumerate() {
+        worksheet.write(first_row, col as u16, column.header(language))?;
+        worksheet.set_column_width(col as u16, column.width())?;
+    }
+    let rows = rows(columns, account_id, account_record, banner_type, language);
+    for (i, row) in rows.into_iter().enumerate() {
+        let format = match row.star {
+            5 => &format_5_star,
+            4 => &format_4_star,
+            _ => &format_other,
+        };
+        // 十连的第一条和最后一条记录加上分隔线
+        let format = &match row.ten_pull_number {
+            Some(number) if number as usize == TEN_PULL => {
+                format.clone().set_border_top(FormatBorder::Medium)
+            }
+            Some(1) => format.clone().set_border_bottom(FormatBorder::Medium),
+            _ => format.clone(),
+        };
+        let row_index = first_row + i as u32 + 1;
+        for (col, cell) in row.cells.into_iter().enumerate() {
+            match cell {
+                Cell::Number(number) => {
+                    worksheet.write_with_format(row_index, col as u16, number, format)?
+                }
+                Cell::Text(text) => {
+                    worksheet.write_with_format(row_index, col as u16, text, format)?
+                }
+            };
         }
     }
-    let counts_to_5_star_pity = counts_after_5_star
-        .iter()
-        .map(|count| banner_type.pity_count() - count)
-        .collect::<Vec<_>>();
-    let counts_after_5_star = counts_after_5_star.into_iter().rev().collect();
-    let counts_to_5_star_pity = counts_to_5_star_pity.into_iter().rev().collect();
-    let counts_after_4_star = counts_after_4_star.into_iter().rev().collect();
-    (
-        counts_after_5_star,
-        counts_to_5_star_pity,
-        counts_after_4_star,
-    )
+    Ok(())
 }
 
 // Save the records to an Excel file.
-pub fn save_excel(total_records: TotalRecords, language: Language) {
+/// # 参数
+/// - `columns`: 抽卡记录表的列
+/// - `charts`: 每个账号附加汇总图表表，文件会变大
+pub fn save_excel(
+    total_records: &TotalRecords,
+    language: Language,
+    columns: &[Column],
+    meta: &ExportMeta,
+    charts: bool,
+    path: &Path,
+) -> Result<()> {
+    if let Some(parent) = path.parent() {
+        std::fs::create_dir_all(parent)?;
+    }
     let mut workbook = Workbook::new();
     // 五星格式
     let format_5_star = Format::new().set_background_color(0xe99b37);
@@ -59,92 +290,300 @@ pub fn save_excel(total_records: TotalRecords, language: Language) {
     let format_4_star = Format::new().set_background_color(0xc069d6);
     // 其他格式
     let format_other = Format::new();
+    let mut sheet_names = SheetNames::default();
 
-    for (account_id, account_record) in total_records.records {
-        for (banner_type, one_records) in account_record.records {
+    for (account_id, account_record) in total_records.records.iter() {
+        let item_counts = crate::stats::item_counts(account_record);
+        for &banner_type in account_record.records.keys() {
             let worksheet = workbook.add_worksheet();
-            worksheet
-                .set_name(format!(
+            worksheet.set_name(sheet_names.name(&format!(
+                "{}-{}",
+                account_id,
+                banner_type.display_name_for_user(language)
+            )))?;
+            write_records_sheet(
+                worksheet,
+                account_id,
+                account_record,
+                banner_type,
+                language,
+                columns,
+                0,
+            )?;
+        }
+
+        // 物品数量统计
+        let worksheet = workbook.add_worksheet();
+        worksheet.set_name(sheet_names.name(&item_count_sheet_name(account_id, language)))?;
+        let headers = item_count_headers(language);
+        let colum_widths = [5, 20, 5, 8];
+        for i in 0..headers.len() {
+            worksheet.write(0, i as u16, headers[i])?;
+            worksheet.set_column_width(i as u16, colum_widths[i])?;
+        }
+        for (
+            i,
+            ItemCount {
+                item_name,
+                item_type,
+                star,
+                count,
+            },
+        ) in item_counts.iter().enumerate()
+        {
+            let format = match star {
+                5 => &format_5_star,
+                4 => &format_4_star,
+                _ => &format_other,
+            };
+            let row = i as u32 + 1;
+            worksheet.write_with_format(row, 0, *star, format)?;
+            worksheet.write_with_format(row, 1, item_name.clone(), format)?;
+            worksheet.write_with_format(
+                row,
+                2,
+                item_type.display_name_for_user(language),
+                format,
+            )?;
+            worksheet.write_with_format(row, 3, *count, format)?;
+        }
+
+        if charts {
+            let sheet_name = sheet_names.name(&summary_sheet_name(account_id, language));
+            write_summary_sheet(&mut workbook, &sheet_name, account_record, language)?;
+        }
+
+        // 按月、按周汇总的抽卡次数，月在前，空一行后是周
+        let worksheet = workbook.add_worksheet();
+        worksheet.set_name(sheet_names.name(&period_pulls_sheet_name(account_id, language)))?;
+        let headers = period_pulls_headers(language);
+        let colum_widths = [10, 20, 8, 8];
+        for i in 0..headers.len() {
+            worksheet.write(0, i as u16, headers[i])?;
+            worksheet.set_column_width(i as u16, colum_widths[i])?;
+        }
+        let mut row = 1;
+        for period in [Period::Month, Period::Week] {
+            for period_pulls in period_pulls(account_record, period) {
+                worksheet.write(row, 0, period_pulls.period)?;
+                worksheet.write(
+                    row,
+                    1,
+                    period_pulls.banner_type.display_name_for_user(language),
+                )?;
+                worksheet.write(row, 2, period_pulls.pulls)?;
+                worksheet.write(row, 3, period_pulls.five_star)?;
+                row += 1;
+            }
+            row += 1;
+        }
+    }
+    sheet_names.write_legend(&mut workbook, language)?;
+    save_workbook(&mut workbook, meta, path)
+}
+
+/// 每个账号每个卡池保存为一个 Excel 文件，例如 `<dir>/<账号ID>/Limited50_Weapon.xlsx`，
+/// 文件开头是这个卡池的摘要，便于单独分享
+/// # 返回
+/// 保存的文件路径
+pub fn save_excel_per_banner(
+    total_records: &TotalRecords,
+    language: Language,
+    columns: &[Column],
+    meta: &ExportMeta,
+    dir: &Path,
+) -> Result<Vec<PathBuf>> {
+    let mut paths = vec![];
+    for (account_id, account_record) in total_records.records.iter() {
+        let account_dir = dir.join(account_id);
+        std::fs::create_dir_all(&account_dir)?;
+        for (&banner_type, one_records) in account_record.records.iter() {
+            let mut workbook = Workbook::new();
+            let worksheet = workbook.add_worksheet();
+            worksheet.set_name(sanitize_sheet_name(
+                banner_type.display_name_for_user(language),
+            ))?;
+            worksheet.write(
+                0,
+                0,
+                format!(
                     "{}-{}",
                     account_id,
                     banner_type.display_name_for_user(language)
-                ))
-                .unwrap();
-            let headers = headers(language);
-            let colum_widths = [5, 20, 5, 20, 8, 8, 8];
-            for i in 0..headers.len() {
-                worksheet.write(0, i as u16, headers[i]).unwrap();
-                worksheet
-                    .set_column_width(i as u16, colum_widths[i])
-                    .unwrap();
+                ),
+            )?;
+            let count_star = |star| {
+                one_records
+                    .iter()
+                    .filter(|record| record.star == star)
+                    .count()
+            };
+            worksheet.write(
+                1,
+                0,
+                format_translation(
+                    translate(language, "excel.banner_summary"),
+                    &[
+                        ("pulls", one_records.len().to_string()),
+                        ("five_star", count_star(5).to_string()),
+                        ("four_star", count_star(4).to_string()),
+                    ],
+                ),
+            )?;
+            if let Some(state) = account_record.guarantee_state(banner_type) {
+                worksheet.write(1, 4, state.describe(language))?;
             }
-            let (counts_after_5_star, counts_to_5_star_pity, counts_after_4_star) =
-                get_other_data(one_records.clone(), banner_type);
-            one_records
-                .iter()
-                .zip(counts_after_5_star)
-                .zip(counts_to_5_star_pity)
-                .zip(counts_after_4_star)
-                .enumerate()
-                .for_each(
-                    |(
-                        i,
-                        (
-                            ((one_record, count_after_5_star), count_to_5_star_pity),
-                            count_after_4_star,
-                        ),
-                    )| {
-                        let format = match one_record.star {
-                            5 => &format_5_star,
-                            4 => &format_4_star,
-                            _ => &format_other,
-                        };
-                        let i = i as u32;
-                        worksheet
-                            .write_with_format(i + 1, 0, one_record.star, format)
-                            .unwrap();
-                        worksheet
-                            .write_with_format(i + 1, 1, one_record.item_name.clone(), format)
-                            .unwrap();
-                        worksheet
-                            .write_with_format(
-                                i + 1,
-                                2,
-                                one_record.item_type.display_name_for_user(language),
-                                format,
-                            )
-                            .unwrap();
-                        worksheet
-                            .write_with_format(
-                                i + 1,
-                                3,
-                                one_record.readable_date_time_str(),
-                                format,
-                            )
-                            .unwrap();
-                        worksheet
-                            .write_with_format(i + 1, 4, count_after_5_star, format)
-                            .unwrap();
-                        worksheet
-                            .write_with_format(i + 1, 5, count_to_5_star_pity, format)
-                            .unwrap();
-                        worksheet
-                            .write_with_format(i + 1, 6, count_after_4_star, format)
-                            .unwrap();
-                    },
-                );
-            workbook.save("records.xlsx").unwrap()
+            write_records_sheet(
+                worksheet,
+                account_id,
+                account_record,
+                banner_type,
+                language,
+                columns,
+                3,
+            )?;
+            let path = account_dir.join(format!("{}.xlsx", banner_type.file_stem()));
+            save_workbook(&mut workbook, meta, &path)?;
+            paths.push(path);
         }
     }
+    Ok(paths)
+}
+
+/// 在文档属性中写入导出信息后保存工作簿，
+/// 文件被其他程序占用时返回 [`ExportError::SaveLocked`](crate::error::ExportError::SaveLocked)
+fn save_workbook(workbook: &mut Workbook, meta: &ExportMeta, path: &Path) -> Result<()> {
+    let properties = DocProperties::new()
+        .set_author(meta.tool())
+        .set_comment(format!("records checksum: {}", meta.records_checksum))
+        .set_custom_property("tool_version", meta.tool_version.as_str())
+        .set_custom_property("exported_at", meta.exported_at.as_str())
+        .set_custom_property("accounts", meta.accounts.join(", ").as_str())
+        .set_custom_property("records_checksum", meta.records_checksum.as_str());
+    workbook.set_properties(&properties);
+    workbook.save(path).map_err(|e| match e {
+        XlsxError::IoError(e) => save_error(e, path),
+        e => e.into(),
+    })?;
+    Ok(())
 }
 
 #[cfg(test)]
 mod test {
+    use enum_iterator::all;
+
     use super::*;
+    use crate::record::OneRecord;
 
     #[test]
     fn test_save_excel() {
         let total_records = TotalRecords::read_or_default();
-        save_excel(total_records, Language::ChineseSimplified);
+        save_excel(
+            &total_records,
+            Language::ChineseSimplified,
+            &all::<Column>().collect::<Vec<_>>(),
+            &ExportMeta::new(&total_records),
+            true,
+            Path::new("records.xlsx"),
+        )
+        .unwrap();
+    }
+
+    #[test]
+    fn test_sheet_names() {
+        assert_eq!(sanitize_sheet_name("a[1]/b:c"), "a_1__b_c");
+        assert_eq!(sanitize_sheet_name("'quoted'"), "quoted");
+        assert_eq!(sanitize_sheet_name("''"), "Sheet");
+        let long_id = "测试账号".repeat(10);
+        assert_eq!(sanitize_sheet_name(&long_id).chars().count(), 31);
+
+        let mut sheet_names = SheetNames::default();
+        assert_eq!(sheet_names.name("id-Beginner"), "id-Beginner");
+        let first = sheet_names.name(&format!("{long_id}-Beginner"));
+        let second = sheet_names.name(&format!("{long_id}-Limited"));
+        assert_eq!(first.chars().count(), 31);
+        assert_eq!(second.chars().count(), 31);
+        assert!(second.ends_with("~2"));
+        assert_eq!(sheet_names.name("ID-BEGINNER"), "ID-BEGINNER~2");
+        assert_eq!(sheet_names.legend.len(), 3);
+        assert_eq!(sheet_names.legend[1].1, format!("{long_id}-Limited"));
+    }
+
+    #[test]
+    fn test_save_excel_long_account_id() {
+        let path = std::env::temp_dir().join("snowbreak_gacha_export_test_long_id.xlsx");
+        let mut total_records = TotalRecords::new(Default::default());
+        for account_id in [
+            "测试账号测试账号测试账号测试账号测试账号[1]",
+            "测试账号测试账号测试账号测试账号测试账号[2]",
+        ] {
+            for banner_type in [BannerType::LimitedWeapon50Percent, BannerType::Beginner] {
+                total_records
+                    .add_record(
+                        account_id.to_string(),
+                        crate::record::ImportBatch::new(
+                            banner_type,
+                            crate::record::RecordSource::Ocr,
+                        ),
+                        vec![OneRecord::new(
+                            5,
+                            "a".to_string(),
+                            crate::record::ItemType::Weapon,
+                            1,
+                        )],
+                    )
+                    .unwrap();
+            }
+        }
+        save_excel(
+            &total_records,
+            Language::ChineseSimplified,
+            &[Column::Name],
+            &ExportMeta::new(&total_records),
+            true,
+            &path,
+        )
+        .unwrap();
+        assert!(path.is_file());
+        let _ = std::fs::remove_file(&path);
+    }
+
+    #[test]
+    fn test_save_excel_per_banner() {
+        let dir = std::env::temp_dir().join("snowbreak_gacha_export_test_per_banner");
+        let _ = std::fs::remove_dir_all(&dir);
+        let mut total_records = TotalRecords::new(Default::default());
+        for banner_type in [BannerType::LimitedWeapon50Percent, BannerType::Beginner] {
+            total_records
+                .add_record(
+                    "id".to_string(),
+                    crate::record::ImportBatch::new(banner_type, crate::record::RecordSource::Ocr),
+                    vec![OneRecord::new(
+                        5,
+                        "a".to_string(),
+                        crate::record::ItemType::Weapon,
+                        1,
+                    )],
+                )
+                .unwrap();
+        }
+        let mut paths = save_excel_per_banner(
+            &total_records,
+            Language::English,
+            &[Column::Name],
+            &ExportMeta::new(&total_records),
+            &dir,
+        )
+        .unwrap();
+        paths.sort();
+        assert_eq!(
+            paths,
+            vec![
+                dir.join("id").join("Beginner.xlsx"),
+                dir.join("id").join("Limited50_Weapon.xlsx"),
+            ]
+        );
+        assert!(paths.iter().all(|path| path.is_file()));
+        let _ = std::fs::remove_dir_all(&dir);
     }
 }
@@ -0,0 +1,69 @@
+//! 抽卡记录文件的 JSON Schema
+//!
+//! `records.json` 和 JSON 导出的格式由 [`TotalRecords`] 等类型定义，
+//! 生成的 Schema 发布在 `doc/records.schema.json`，其他工具可以用它校验文件。
+//! 修改这些类型后用 `UPDATE_SCHEMA=1 cargo test --features schema` 重新生成。
+
+use schemars::schema::RootSchema;
+use schemars::schema_for;
+
+use crate::record::TotalRecords;
+
+/// 发布的 Schema 文件，相对于仓库根目录
+pub static SCHEMA_FILE: &str = "doc/records.schema.json";
+
+/// `records.json` 的 Schema
+pub fn records_schema() -> RootSchema {
+    schema_for!(TotalRecords)
+}
+
+/// 格式化的 Schema 文本，末尾有换行
+pub fn records_schema_json() -> String {
+    serde_json::to_string_pretty(&records_schema()).unwrap() + "\n"
+}
+
+#[cfg(test)]
+mod test {
+    use std::path::Path;
+
+    use enum_iterator::all;
+
+    use super::*;
+    use crate::record::BannerType;
+
+    #[test]
+    fn test_schema_file_up_to_date() {
+        let path = Path::new(env!("CARGO_MANIFEST_DIR")).join(SCHEMA_FILE);
+        let schema = records_schema_json();
+        if std::env::var_os("UPDATE_SCHEMA").is_some() {
+            std::fs::write(&path, &schema).unwrap();
+        }
+        let published = std::fs::read_to_string(&path).unwrap();
+        assert!(
+            published == schema,
+            "{SCHEMA_FILE} is outdated, run `UPDATE_SCHEMA=1 cargo test --features schema`"
+        );
+    }
+
+    #[test]
+    fn test_schema_fields() {
+        let schema = serde_json::to_value(records_schema()).unwrap();
+        let definitions = &schema["definitions"];
+        assert_eq!(schema["required"], serde_json::json!(["records"]));
+        assert_eq!(
+            definitions["OneRecord"]["required"],
+            serde_json::json!(["item_name", "item_type", "star", "timestamp"])
+        );
+        // 旧版本保存的文件没有这些字段
+        let required = definitions["OneAccountRecords"]["required"]
+            .as_array()
+            .unwrap();
+        assert!(!required.contains(&"batches".into()));
+        assert!(!required.contains(&"checksums".into()));
+        // 每个卡池都有说明，生成为 oneOf
+        assert_eq!(
+            definitions["BannerType"]["oneOf"].as_array().unwrap().len(),
+            all::<BannerType>().count()
+        );
+    }
+}
@@ -0,0 +1,116 @@
+//! 识别游戏当前所在的界面
+//!
+//! 不在抽卡记录界面时，识别几个界面中固定位置的文字，判断用户实际所在的界面，
+//! 给出具体的操作提示。坐标以 1920x1080 的窗口客户区为基准。
+//!
+//! 这是实验性的功能：`SCREEN_ANCHORS` 的坐标还没有用真实截图验证过，
+//! 默认关闭，config.json 中的 `screen_guidance` 为 `true` 时才识别。
+
+use crate::config::config;
+use crate::i18n::tr;
+use crate::ocr::{ocr, CharsetHint};
+use crate::record_image::RecordImage;
+
+/// 抽卡记录界面以外的游戏界面
+#[derive(Debug, Copy, Clone, PartialEq, Eq)]
+pub enum Screen {
+    /// 游戏主界面
+    MainMenu,
+    /// 共鸣（卡池）界面
+    BannerScreen,
+    /// 卡池详情弹窗
+    DetailsPopup,
+    /// 无法识别的界面
+    Unknown,
+}
+
+impl Screen {
+    /// 从这个界面打开抽卡记录界面的操作提示
+    pub fn guidance(&self) -> &'static str {
+        match self {
+            Screen::MainMenu => tr!("screen.main_menu"),
+            Screen::BannerScreen => tr!("screen.banner_screen"),
+            Screen::DetailsPopup => tr!("screen.details_popup"),
+            Screen::Unknown => tr!("error.not_on_record_screen"),
+        }
+    }
+}
+
+/// 界面中固定位置的文字
+struct ScreenAnchor {
+    screen: Screen,
+    /// 文字的区域 (x0, y0, x1, y1)
+    area: (u32, u32, u32, u32),
+    /// 识别出的文字包含其中任意一个时视为这个界面
+    expect_texts: &'static [&'static str],
+}
+
+/// 按顺序检查的界面，弹窗覆盖在卡池界面上，先检查弹窗
+static SCREEN_ANCHORS: [ScreenAnchor; 3] = [
+    ScreenAnchor {
+        screen: Screen::DetailsPopup,
+        area: (760, 60, 1160, 130),
+        expect_texts: &["详情", "Details"],
+    },
+    ScreenAnchor {
+        screen: Screen::BannerScreen,
+        area: (1560, 960, 1700, 1010),
+        expect_texts: &["记录", "Record"],
+    },
+    ScreenAnchor {
+        screen: Screen::MainMenu,
+        area: (1680, 490, 1810, 550),
+        expect_texts: &["共鸣", "Resonance"],
+    },
+];
+
+impl ScreenAnchor {
+    /// 识别出的文字是否符合这个界面，忽略空白
+    fn matches(&self, text: &str) -> bool {
+        let text = text.split_whitespace().collect::<String>();
+        self.expect_texts
+            .iter()
+            .any(|expect_text| text.contains(expect_text))
+    }
+}
+
+/// 识别不是抽卡记录界面的截图所在的界面，没有开启 `screen_guidance` 时返回 [`Screen::Unknown`]
+pub fn classify_screen(record_image: &RecordImage) -> Screen {
+    if !config().screen_guidance {
+        return Screen::Unknown;
+    }
+    SCREEN_ANCHORS
+        .iter()
+        .find(|anchor| {
+            let (x0, y0, x1, y1) = record_image.layout.scale_area(anchor.area);
+            let text = ocr(
+                record_image.get_ocr_image(x0, y0, x1, y1),
+                CharsetHint::Name,
+            )
+            .unwrap_or_else(|e| {
+                log::warn!("failed to recognize screen anchor: {:?}", e);
+                String::new()
+            });
+            log::debug!("screen anchor {:?}: {:?}", anchor.screen, text);
+            anchor.matches(&text)
+        })
+        .map(|anchor| anchor.screen)
+        .unwrap_or(Screen::Unknown)
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    #[test]
+    fn test_anchor_matches() {
+        let anchor = &SCREEN_ANCHORS[0];
+        assert_eq!(anchor.screen, Screen::DetailsPopup);
+        assert!(anchor.matches("卡池 详情"));
+        assert!(!anchor.matches("记录"));
+        for anchor in SCREEN_ANCHORS.iter() {
+            let (x0, y0, x1, y1) = anchor.area;
+            assert!(x0 < x1 && x1 <= 1920 && y0 < y1 && y1 <= 1080);
+        }
+    }
+}
@@ -0,0 +1,105 @@
+//! 按物品名称查找抽卡记录
+//!
+//! 在所有账号、卡池的抽卡记录上建立物品名称的索引，列出某个物品的每一次抽取和当时的保底计数。
+
+use std::collections::HashMap;
+
+use crate::record::{BannerType, OneRecord, TotalRecords};
+use crate::stats::pity_counts;
+
+/// 一次抽取
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct PullHit<'a> {
+    pub account_id: &'a str,
+    pub banner_type: BannerType,
+    pub record: &'a OneRecord,
+    /// 距离上一个5星的抽数，包含这一抽
+    pub pulls_since_5_star: u32,
+    /// 距离上一个4星的抽数，包含这一抽
+    pub pulls_since_4_star: u32,
+}
+
+/// 物品名称到抽取的索引
+pub struct RecordIndex<'a> {
+    by_name: HashMap<&'a str, Vec<PullHit<'a>>>,
+}
+
+impl<'a> RecordIndex<'a> {
+    pub fn new(total_records: &'a TotalRecords) -> Self {
+        let mut by_name: HashMap<&str, Vec<PullHit>> = HashMap::new();
+        for (account_id, account_records) in total_records.records.iter() {
+            for (&banner_type, records) in account_records.records.iter() {
+                let counts = pity_counts(records, account_records.pity_anchor(banner_type));
+                for (record, (pulls_since_5_star, pulls_since_4_star)) in records.iter().zip(counts)
+                {
+                    by_name.entry(&record.item_name).or_default().push(PullHit {
+                        account_id,
+                        banner_type,
+                        record,
+                        pulls_since_5_star,
+                        pulls_since_4_star,
+                    });
+                }
+            }
+        }
+        Self { by_name }
+    }
+
+    /// 查找物品的所有抽取，按时间倒序
+    /// 没有名称完全相同的物品时，查找名称包含 `name` 的物品，忽略大小写
+    pub fn find(&self, name: &str) -> Vec<&PullHit<'a>> {
+        let mut hits = match self.by_name.get(name) {
+            Some(hits) => hits.iter().collect::<Vec<_>>(),
+            None => {
+                let name = name.to_lowercase();
+                self.by_name
+                    .iter()
+                    .filter(|(item_name, _)| item_name.to_lowercase().contains(&name))
+                    .flat_map(|(_, hits)| hits)
+                    .collect()
+            }
+        };
+        hits.sort_by_key(|hit| std::cmp::Reverse(hit.record.timestamp));
+        hits
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use crate::record::{ImportBatch, ItemType, RecordSource};
+
+    #[test]
+    fn test_find() {
+        let record = |star, name: &str, timestamp| {
+            OneRecord::new(star, name.to_string(), ItemType::Weapon, timestamp)
+        };
+        let mut total_records = TotalRecords::default();
+        total_records
+            .add_record(
+                "id".to_string(),
+                ImportBatch::new(BannerType::PermanentWeapon, RecordSource::Ocr),
+                vec![
+                    record(5, "Big Gun", 4),
+                    record(3, "a", 3),
+                    record(4, "b", 2),
+                    record(5, "Big Gun", 1),
+                ],
+            )
+            .unwrap();
+        let index = RecordIndex::new(&total_records);
+        let hits = index.find("Big Gun");
+        assert_eq!(hits.len(), 2);
+        assert_eq!(hits[0].record.timestamp, 4);
+        assert_eq!(
+            (hits[0].pulls_since_5_star, hits[0].pulls_since_4_star),
+            (3, 2)
+        );
+        assert_eq!(
+            (hits[1].pulls_since_5_star, hits[1].pulls_since_4_star),
+            (1, 1)
+        );
+        assert_eq!(index.find("big").len(), 2);
+        assert!(index.find("c").is_empty());
+    }
+}
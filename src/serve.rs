@@ -0,0 +1,148 @@
+//! 本地统计页面
+//!
+//! `serve` 子命令在本机启动一个简单的 HTTP 服务器，页面内容与 HTML 导出相同，
+//! 每次打开页面时重新读取抽卡记录，导入新的记录后刷新页面即可，可以把地址加入书签。
+//! 只监听 127.0.0.1，局域网中的其他设备无法访问。
+//!
+//! 页面只读取 records.json，不写入任何文件，运行时不占用单实例锁，可以同时导入。
+
+use std::io::{BufRead, BufReader, Write};
+use std::net::{Ipv4Addr, TcpListener, TcpStream};
+use std::time::Duration;
+
+use anyhow::Result;
+
+use crate::data_dir::data_path;
+use crate::export::{render_html, ExportOptions};
+use crate::i18n::tr;
+use crate::record::TotalRecords;
+
+/// 默认端口
+pub static DEFAULT_PORT: u16 = 8737;
+
+/// 读取请求的超时时间，避免一个没有发送请求的连接阻塞服务器
+static READ_TIMEOUT: Duration = Duration::from_secs(5);
+
+/// 一个 HTTP 响应
+#[derive(Debug, Clone, PartialEq, Eq)]
+struct Response {
+    status: &'static str,
+    content_type: &'static str,
+    body: String,
+}
+
+impl Response {
+    fn text(status: &'static str) -> Self {
+        Self {
+            status,
+            content_type: "text/plain; charset=utf-8",
+            body: status.to_string(),
+        }
+    }
+
+    fn to_bytes(&self) -> Vec<u8> {
+        format!(
+            "HTTP/1.1 {}\r\nContent-Type: {}\r\nContent-Length: {}\r\nCache-Control: no-store\r\nConnection: close\r\n\r\n{}",
+            self.status,
+            self.content_type,
+            self.body.len(),
+            self.body
+        )
+        .into_bytes()
+    }
+}
+
+/// 根据请求行生成响应，例如 `GET / HTTP/1.1`
+/// # 参数
+/// - `render`: 生成统计页面
+fn route(request_line: &str, render: impl FnOnce() -> String) -> Response {
+    let mut parts = request_line.split_whitespace();
+    let (Some(method), Some(target)) = (parts.next(), parts.next()) else {
+        return Response::text("400 Bad Request");
+    };
+    if method != "GET" {
+        return Response::text("405 Method Not Allowed");
+    }
+    let path = target.split('?').next().unwrap_or_default();
+    match path {
+        "/" | "/index.html" => Response {
+            status: "200 OK",
+            content_type: "text/html; charset=utf-8",
+            body: render(),
+        },
+        _ => Response::text("404 Not Found"),
+    }
+}
+
+fn handle(stream: TcpStream) -> Result<()> {
+    stream.set_read_timeout(Some(READ_TIMEOUT))?;
+    let mut reader = BufReader::new(&stream);
+    let mut request_line = String::new();
+    reader.read_line(&mut request_line)?;
+    // 读完请求头，不需要其中的内容
+    let mut line = String::new();
+    while reader.read_line(&mut line)? > 0 && line != "\r\n" && line != "\n" {
+        line.clear();
+    }
+    let response = route(&request_line, || {
+        render_html(&read_records(), &ExportOptions::from_config())
+    });
+    log::debug!("{} -> {}", request_line.trim_end(), response.status);
+    (&stream).write_all(&response.to_bytes())?;
+    Ok(())
+}
+
+/// 只读地打开抽卡记录文件，还没有导入过时显示空的页面
+fn read_records() -> TotalRecords {
+    let path = data_path("records/records.json");
+    if !path.exists() {
+        return TotalRecords::default();
+    }
+    TotalRecords::read_from(&path).unwrap_or_else(|e| {
+        log::error!("Failed to read records: {:?}", e);
+        TotalRecords::default()
+    })
+}
+
+/// 启动本地统计页面，直到程序被关闭
+pub fn serve(port: u16) -> Result<()> {
+    let listener = TcpListener::bind((Ipv4Addr::LOCALHOST, port))?;
+    let url = format!("http://127.0.0.1:{}/", listener.local_addr()?.port());
+    log::info!("serving stats at {url}");
+    println!("{}", tr!("serve.listening", url = url));
+    for stream in listener.incoming() {
+        let result = stream.map_err(Into::into).and_then(handle);
+        if let Err(e) = result {
+            log::warn!("failed to handle request: {:?}", e);
+        }
+    }
+    Ok(())
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    #[test]
+    fn test_route() {
+        let render = || "<html></html>".to_string();
+        let response = route("GET / HTTP/1.1\r\n", render);
+        assert_eq!(response.status, "200 OK");
+        assert_eq!(response.body, "<html></html>");
+        assert_eq!(
+            route("GET /index.html?refresh=1 HTTP/1.1", render).status,
+            "200 OK"
+        );
+        assert_eq!(route("GET /other HTTP/1.1", render).status, "404 Not Found");
+        assert_eq!(
+            route("POST / HTTP/1.1", render).status,
+            "405 Method Not Allowed"
+        );
+        assert_eq!(route("", render).status, "400 Bad Request");
+
+        let bytes = String::from_utf8(response.to_bytes()).unwrap();
+        assert!(bytes.starts_with("HTTP/1.1 200 OK\r\n"));
+        assert!(bytes.contains("Content-Length: 13\r\n"));
+        assert!(bytes.ends_with("\r\n\r\n<html></html>"));
+    }
+}
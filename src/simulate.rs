@@ -0,0 +1,136 @@
+//! 抽卡模拟
+//!
+//! 从账号当前的保底计数开始，用蒙特卡洛方法模拟接下来若干次抽卡，
+//! 估计获得5星数量的分布和获得当期限定物品的概率，用于规划抽卡。
+
+use crate::rates::rate_model;
+use crate::record::BannerType;
+
+/// 模拟的起始状态
+#[derive(Debug, Copy, Clone, PartialEq, Eq)]
+pub struct SimulationStart {
+    pub banner_type: BannerType,
+    /// 距离上一个5星已经抽了多少次
+    pub pity: u32,
+    /// 上一个5星不是当期限定物品，下一个5星必定是当期限定物品
+    pub guaranteed: bool,
+}
+
+/// 模拟结果
+#[derive(Debug, Clone, PartialEq)]
+pub struct SimulationResult {
+    pub trials: u32,
+    /// 第 k 项为获得 k 个5星的模拟次数
+    pub five_star_distribution: Vec<u32>,
+    /// 获得至少一个当期限定物品的模拟次数，非限定卡池为 None
+    pub featured_trials: Option<u32>,
+}
+
+impl SimulationResult {
+    /// 5星数量的期望
+    pub fn expected_five_star(&self) -> f64 {
+        let total = self
+            .five_star_distribution
+            .iter()
+            .enumerate()
+            .map(|(count, &trials)| count as f64 * trials as f64)
+            .sum::<f64>();
+        total / self.trials as f64
+    }
+
+    /// 获得恰好 `count` 个5星的概率
+    pub fn probability_of(&self, count: usize) -> f64 {
+        self.five_star_distribution.get(count).copied().unwrap_or(0) as f64 / self.trials as f64
+    }
+
+    /// 获得至少一个当期限定物品的概率
+    pub fn probability_featured(&self) -> Option<f64> {
+        self.featured_trials
+            .map(|featured_trials| featured_trials as f64 / self.trials as f64)
+    }
+}
+
+/// 模拟 `trials` 次，每次抽 `pulls` 抽
+pub fn simulate(
+    start: SimulationStart,
+    pulls: u32,
+    trials: u32,
+    rng: &mut fastrand::Rng,
+) -> SimulationResult {
+    let model = rate_model(start.banner_type);
+    let mut five_star_distribution = vec![];
+    let mut featured_trials = 0;
+    for _ in 0..trials {
+        let mut pity = start.pity;
+        let mut guaranteed = start.guaranteed;
+        let mut five_star = 0;
+        let mut featured = false;
+        for _ in 0..pulls {
+            pity += 1;
+            if rng.f64() >= model.five_star_probability(pity) {
+                continue;
+            }
+            five_star += 1;
+            pity = 0;
+            if let Some(featured_rate) = model.featured_rate {
+                if guaranteed || rng.f64() < featured_rate {
+                    featured = true;
+                    guaranteed = false;
+                } else {
+                    guaranteed = true;
+                }
+            }
+        }
+        if five_star_distribution.len() <= five_star {
+            five_star_distribution.resize(five_star + 1, 0);
+        }
+        five_star_distribution[five_star] += 1;
+        if featured {
+            featured_trials += 1;
+        }
+    }
+    SimulationResult {
+        trials,
+        five_star_distribution,
+        featured_trials: model.featured_rate.map(|_| featured_trials),
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    #[test]
+    fn test_simulate() {
+        let mut rng = fastrand::Rng::with_seed(0);
+        let start = SimulationStart {
+            banner_type: BannerType::LimitedCharacter100Percent,
+            pity: 90,
+            guaranteed: false,
+        };
+        // 剩余抽数足够触发保底时必定获得5星，100%卡池的5星都是当期限定物品
+        let result = simulate(start, 10, 1000, &mut rng);
+        assert_eq!(result.probability_of(0), 0.0);
+        assert_eq!(result.probability_featured(), Some(1.0));
+        assert!(result.expected_five_star() >= 1.0);
+
+        // 有大保底时第一个5星必定是当期限定物品
+        let start = SimulationStart {
+            banner_type: BannerType::LimitedCharacter50Percent,
+            pity: 79,
+            guaranteed: true,
+        };
+        let result = simulate(start, 1, 1000, &mut rng);
+        assert_eq!(result.five_star_distribution, [0, 1000]);
+        assert_eq!(result.probability_featured(), Some(1.0));
+
+        let start = SimulationStart {
+            banner_type: BannerType::PermanentWeapon,
+            pity: 0,
+            guaranteed: false,
+        };
+        let result = simulate(start, 0, 10, &mut rng);
+        assert_eq!(result.five_star_distribution, [10]);
+        assert_eq!(result.probability_featured(), None);
+    }
+}
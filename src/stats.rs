@@ -0,0 +1,736 @@
+use std::collections::{BTreeMap, HashMap};
+use std::ops::RangeInclusive;
+
+use chrono::{DateTime, Datelike, Local};
+use enum_iterator::{all, Sequence};
+
+use crate::banner_schedule::{banner_schedule, BannerSchedule};
+use crate::items::{item_category, ItemCategory};
+use crate::language::Language;
+use crate::rates::rate_model;
+use crate::record::{
+    BannerType, GuaranteeState, ItemType, OneAccountRecords, OneRecord, PityAnchor,
+};
+
+/// 单个物品的抽取数量
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct ItemCount {
+    pub item_name: String,
+    pub item_type: ItemType,
+    pub star: u8,
+    pub count: u32,
+}
+
+/// 统计账号中每个5星/4星物品的抽取数量（所有卡池合计）
+/// # 返回
+/// 按星级、数量从高到低排序的物品数量
+pub fn item_counts(account_records: &OneAccountRecords) -> Vec<ItemCount> {
+    let mut counts: HashMap<(&str, ItemType, u8), u32> = HashMap::new();
+    account_records
+        .records
+        .values()
+        .flatten()
+        .filter(|record| record.star >= 4)
+        .for_each(|record| {
+            *counts
+                .entry((&record.item_name, record.item_type, record.star))
+                .or_default() += 1;
+        });
+    let mut item_counts = counts
+        .into_iter()
+        .map(|((item_name, item_type, star), count)| ItemCount {
+            item_name: item_name.to_string(),
+            item_type,
+            star,
+            count,
+        })
+        .collect::<Vec<_>>();
+    item_counts.sort_by(|a, b| {
+        b.star
+            .cmp(&a.star)
+            .then(b.count.cmp(&a.count))
+            .then(a.item_name.cmp(&b.item_name))
+    });
+    item_counts
+}
+
+/// 同一分类的物品抽取数量
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct CategoryCount {
+    /// 分类在指定语言中的名称，例如武器类型
+    pub name: String,
+    pub count: u32,
+}
+
+/// 按物品字典中的分类汇总5星/4星物品的抽取数量，例如抽到了多少把霰弹枪
+/// 字典中没有分类的物品不统计
+/// # 返回
+/// 按数量从高到低排序的分类数量
+pub fn category_counts(
+    item_counts: &[ItemCount],
+    category: ItemCategory,
+    language: Language,
+) -> Vec<CategoryCount> {
+    let mut counts: HashMap<&str, u32> = HashMap::new();
+    for item_count in item_counts {
+        if let Some(name) = item_category(&item_count.item_name, category, language) {
+            *counts.entry(name).or_default() += item_count.count;
+        }
+    }
+    let mut category_counts = counts
+        .into_iter()
+        .map(|(name, count)| CategoryCount {
+            name: name.to_string(),
+            count,
+        })
+        .collect::<Vec<_>>();
+    category_counts.sort_by(|a, b| b.count.cmp(&a.count).then_with(|| a.name.cmp(&b.name)));
+    category_counts
+}
+
+/// 最早一条抽卡记录之前已经累积的保底计数
+///
+/// 锚点时间之前的记录中没有5星（4星）时，缺失的抽卡次数为锚点的计数减去这些记录的数量。
+/// # 参数
+/// - `records`: 一个卡池的抽卡记录，按时间倒序
+/// # 返回
+/// （距离5星的计数, 距离4星的计数）
+pub fn pity_offsets(records: &[OneRecord], anchor: Option<&PityAnchor>) -> (u32, u32) {
+    let Some(anchor) = anchor else {
+        return (0, 0);
+    };
+    let covered = records
+        .iter()
+        .filter(|record| record.timestamp <= anchor.timestamp)
+        .collect::<Vec<_>>();
+    let offset = |star: u8, pulls_since: u32| {
+        if covered.iter().any(|record| record.star == star) {
+            0
+        } else {
+            pulls_since.saturating_sub(covered.len() as u32)
+        }
+    };
+    (
+        offset(5, anchor.pulls_since_5_star),
+        offset(4, anchor.pulls_since_4_star),
+    )
+}
+
+/// 根据UP物品排期表推算50%限定卡池的大保底状态
+/// # 参数
+/// - `records`: 一个卡池的抽卡记录，按时间倒序
+/// # 返回
+/// 不是50%限定卡池、没有5星或者最新的5星不在排期表中时返回 None
+pub fn guarantee_state(
+    records: &[OneRecord],
+    banner_type: BannerType,
+    schedule: &BannerSchedule,
+) -> Option<GuaranteeState> {
+    if !matches!(
+        banner_type,
+        BannerType::LimitedCharacter50Percent | BannerType::LimitedWeapon50Percent
+    ) {
+        return None;
+    }
+    let mut state: Option<GuaranteeState> = None;
+    for record in records.iter().rev().filter(|record| record.star == 5) {
+        // 排期表中没有的5星无法判断是否歪了，之前的状态不再可信
+        let Some(featured) = schedule.featured_items(record.timestamp, banner_type) else {
+            state = None;
+            continue;
+        };
+        let (guaranteed, lost_streak) =
+            state.map_or((false, 0), |state| (state.guaranteed, state.lost_streak));
+        let (guaranteed, lost_streak) = match (featured.is_featured(&record.item_name), guaranteed)
+        {
+            // 大保底
+            (true, true) => (false, lost_streak),
+            // 赢得小保底
+            (true, false) => (false, 0),
+            (false, _) => (true, lost_streak + 1),
+        };
+        state = Some(GuaranteeState {
+            banner_type,
+            guaranteed,
+            lost_streak,
+            timestamp: record.timestamp,
+        });
+    }
+    state
+}
+
+/// 距离上一个5星已经抽了多少次，包含最早一条记录之前已经累积的保底计数
+/// # 参数
+/// - `records`: 一个卡池的抽卡记录，按时间倒序
+pub fn current_pity(records: &[OneRecord], anchor: Option<&PityAnchor>) -> u32 {
+    match records.iter().position(|record| record.star == 5) {
+        Some(position) => position as u32,
+        None => records.len() as u32 + pity_offsets(records, anchor).0,
+    }
+}
+
+/// 距离上一个4星已经抽了多少次，包含最早一条记录之前已经累积的保底计数
+/// # 参数
+/// - `records`: 一个卡池的抽卡记录，按时间倒序
+pub fn current_4_star_pity(records: &[OneRecord], anchor: Option<&PityAnchor>) -> u32 {
+    match records.iter().position(|record| record.star == 4) {
+        Some(position) => position as u32,
+        None => records.len() as u32 + pity_offsets(records, anchor).1,
+    }
+}
+
+/// 出5星的平均抽数与概率模型的期望比较
+#[derive(Debug, Clone, PartialEq)]
+pub struct Luck {
+    pub banner_type: BannerType,
+    /// 出了多少个5星
+    pub five_star: u32,
+    /// 实际出5星的平均抽数
+    pub average_pulls: f64,
+    /// 概率模型中出5星的期望抽数
+    pub expected_pulls: f64,
+    /// 限定卡池中5星是当期限定物品的比例，非限定卡池为 None
+    pub win_rate: Option<WinRate>,
+}
+
+/// 95% 置信区间对应的标准正态分位数
+static Z_95: f64 = 1.96;
+
+/// 限定卡池中5星是当期限定物品的比例与概率模型比较
+#[derive(Debug, Copy, Clone, PartialEq)]
+pub struct WinRate {
+    /// 不是大保底的5星数量，即与常驻物品竞争的5星
+    pub contested: u32,
+    /// 其中是当期限定物品的数量
+    pub won: u32,
+    /// 最长连续歪的次数
+    pub max_lost_streak: u32,
+    /// 概率模型中5星是当期限定物品的概率
+    pub expected_rate: f64,
+}
+
+impl WinRate {
+    pub fn rate(&self) -> f64 {
+        self.won as f64 / self.contested as f64
+    }
+
+    /// 实际比例的 95% 置信区间（Wilson 区间），5星少时区间很宽
+    pub fn confidence_interval(&self) -> (f64, f64) {
+        let n = self.contested as f64;
+        let p = self.rate();
+        let z2 = Z_95 * Z_95;
+        let denominator = 1.0 + z2 / n;
+        let center = (p + z2 / (2.0 * n)) / denominator;
+        let half_width = Z_95 * (p * (1.0 - p) / n + z2 / (4.0 * n * n)).sqrt() / denominator;
+        (
+            (center - half_width).max(0.0),
+            (center + half_width).min(1.0),
+        )
+    }
+
+    /// 期望的概率是否在置信区间内，不在时说明运气明显偏好或偏差
+    pub fn is_consistent(&self) -> bool {
+        let (low, high) = self.confidence_interval();
+        (low..=high).contains(&self.expected_rate)
+    }
+}
+
+/// 限定卡池中5星是当期限定物品的比例
+/// 排期表中没有的5星无法判断是否歪了，不计入，之后的5星也不知道是否是大保底
+/// # 参数
+/// - `records`: 一个卡池的抽卡记录，按时间倒序
+/// # 返回
+/// 不是限定卡池或者没有可以判断的5星时返回 None
+pub fn win_rate(
+    records: &[OneRecord],
+    banner_type: BannerType,
+    schedule: &BannerSchedule,
+) -> Option<WinRate> {
+    let expected_rate = rate_model(banner_type).featured_rate?;
+    let mut win_rate = WinRate {
+        contested: 0,
+        won: 0,
+        max_lost_streak: 0,
+        expected_rate,
+    };
+    // None 表示不知道下一个5星是否是大保底
+    let mut guaranteed = Some(false);
+    let mut lost_streak = 0;
+    for record in records.iter().rev().filter(|record| record.star == 5) {
+        let Some(featured) = schedule.featured_items(record.timestamp, banner_type) else {
+            guaranteed = None;
+            continue;
+        };
+        let is_featured = featured.is_featured(&record.item_name);
+        if guaranteed == Some(false) {
+            win_rate.contested += 1;
+            if is_featured {
+                win_rate.won += 1;
+                lost_streak = 0;
+            } else {
+                lost_streak += 1;
+                win_rate.max_lost_streak = win_rate.max_lost_streak.max(lost_streak);
+            }
+        }
+        // 100% 限定卡池没有大保底
+        guaranteed = Some(!is_featured && expected_rate < 1.0);
+    }
+    (win_rate.contested > 0).then_some(win_rate)
+}
+
+/// 每条记录距离上一个5星、4星的抽数，包含这一抽，包含最早一条记录之前已经累积的保底计数
+/// # 参数
+/// - `records`: 一个卡池的抽卡记录，按时间倒序
+/// # 返回
+/// 与 `records` 顺序相同的（距离5星的抽数, 距离4星的抽数）
+pub fn pity_counts(records: &[OneRecord], anchor: Option<&PityAnchor>) -> Vec<(u32, u32)> {
+    let (offset_5_star, offset_4_star) = pity_offsets(records, anchor);
+    let mut count_after_5_star = 1 + offset_5_star;
+    let mut count_after_4_star = 1 + offset_4_star;
+    let mut counts = vec![];
+    for record in records.iter().rev() {
+        counts.push((count_after_5_star, count_after_4_star));
+        if record.star == 5 {
+            count_after_4_star += 1;
+            count_after_5_star = 1;
+        } else if record.star == 4 {
+            count_after_4_star = 1;
+            count_after_5_star += 1;
+        } else {
+            count_after_5_star += 1;
+            count_after_4_star += 1;
+        }
+    }
+    counts.reverse();
+    counts
+}
+
+/// 每个5星用了多少抽，按时间正序，包含最早一条记录之前已经累积的保底计数
+/// # 参数
+/// - `records`: 一个卡池的抽卡记录，按时间倒序
+pub fn five_star_pulls(records: &[OneRecord], anchor: Option<&PityAnchor>) -> Vec<u32> {
+    let (mut pulls, _) = pity_offsets(records, anchor);
+    let mut five_star_pulls = vec![];
+    for record in records.iter().rev() {
+        pulls += 1;
+        if record.star == 5 {
+            five_star_pulls.push(pulls);
+            pulls = 0;
+        }
+    }
+    five_star_pulls
+}
+
+/// 所有卡池出5星用的抽数分布，每 `bucket_size` 抽一组，从 1 开始到最大的抽数所在的组
+pub fn pity_histogram(
+    account_records: &OneAccountRecords,
+    bucket_size: u32,
+) -> Vec<(RangeInclusive<u32>, u32)> {
+    let mut counts: Vec<u32> = vec![];
+    for (banner_type, records) in account_records.records.iter() {
+        for pulls in five_star_pulls(records, account_records.pity_anchor(*banner_type)) {
+            let bucket = ((pulls - 1) / bucket_size) as usize;
+            if counts.len() <= bucket {
+                counts.resize(bucket + 1, 0);
+            }
+            counts[bucket] += 1;
+        }
+    }
+    counts
+        .into_iter()
+        .enumerate()
+        .map(|(bucket, count)| {
+            let start = bucket as u32 * bucket_size + 1;
+            (start..=start + bucket_size - 1, count)
+        })
+        .collect()
+}
+
+/// 每个出过5星的卡池的运气分析
+pub fn luck(account_records: &OneAccountRecords) -> Vec<Luck> {
+    all::<BannerType>()
+        .filter_map(|banner_type| {
+            let records = account_records.records.get(&banner_type)?;
+            let five_star_pulls =
+                five_star_pulls(records, account_records.pity_anchor(banner_type));
+            if five_star_pulls.is_empty() {
+                return None;
+            }
+            let five_star = five_star_pulls.len() as u32;
+            Some(Luck {
+                banner_type,
+                five_star,
+                average_pulls: five_star_pulls.iter().sum::<u32>() as f64 / five_star as f64,
+                expected_pulls: rate_model(banner_type).expected_pulls(),
+                win_rate: win_rate(records, banner_type, banner_schedule()),
+            })
+        })
+        .collect()
+}
+
+/// 一个5星及出这个5星用的抽数
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct FiveStarPull {
+    pub timestamp: u64,
+    pub banner_type: BannerType,
+    pub item_name: String,
+    pub pulls: u32,
+}
+
+/// 所有卡池的5星及用的抽数，按时间正序
+pub fn five_star_history(account_records: &OneAccountRecords) -> Vec<FiveStarPull> {
+    let mut history = all::<BannerType>()
+        .filter_map(|banner_type| {
+            let records = account_records.records.get(&banner_type)?;
+            let pulls = five_star_pulls(records, account_records.pity_anchor(banner_type));
+            let five_stars = records.iter().rev().filter(|record| record.star == 5);
+            Some(
+                five_stars
+                    .zip(pulls)
+                    .map(move |(record, pulls)| FiveStarPull {
+                        timestamp: record.timestamp,
+                        banner_type,
+                        item_name: record.item_name.clone(),
+                        pulls,
+                    })
+                    .collect::<Vec<_>>(),
+            )
+        })
+        .flatten()
+        .collect::<Vec<_>>();
+    history.sort_by_key(|five_star_pull| five_star_pull.timestamp);
+    history
+}
+
+/// 十连的抽卡次数
+pub static TEN_PULL: usize = 10;
+
+/// 一次抽卡（单抽或十连）对应的连续抽卡记录
+#[derive(Debug, Copy, Clone, PartialEq, Eq)]
+pub struct PullGroup {
+    /// 第一条记录（最新的）在抽卡记录中的下标
+    pub start: usize,
+    pub len: usize,
+}
+
+impl PullGroup {
+    pub fn is_ten_pull(&self) -> bool {
+        self.len == TEN_PULL
+    }
+}
+
+/// 把抽卡记录分成单抽和十连
+///
+/// 抽卡记录的时间精确到分钟，同一分钟的记录每 10 条视为一次十连，从最旧的开始划分，
+/// 剩下不足 10 条的视为较新的单抽。
+/// # 参数
+/// - `records`: 一个卡池的抽卡记录，按时间倒序
+pub fn pull_groups(records: &[OneRecord]) -> Vec<PullGroup> {
+    let mut groups = vec![];
+    let mut start = 0;
+    for run in records.chunk_by(|a, b| a.timestamp == b.timestamp) {
+        let single_num = run.len() % TEN_PULL;
+        groups.extend((start..start + single_num).map(|start| PullGroup { start, len: 1 }));
+        groups.extend(
+            (start + single_num..start + run.len())
+                .step_by(TEN_PULL)
+                .map(|start| PullGroup {
+                    start,
+                    len: TEN_PULL,
+                }),
+        );
+        start += run.len();
+    }
+    groups
+}
+
+/// 每条抽卡记录是所在十连的第几抽，从 1 开始，单抽为 `None`
+/// 十连中最新的记录在最前面，所以一个十连的记录依次为第 10 到第 1 抽
+pub fn ten_pull_numbers(records: &[OneRecord]) -> Vec<Option<u32>> {
+    let mut numbers = vec![None; records.len()];
+    for group in pull_groups(records) {
+        if group.is_ten_pull() {
+            for i in 0..group.len {
+                numbers[group.start + i] = Some((group.len - i) as u32);
+            }
+        }
+    }
+    numbers
+}
+
+/// 汇总抽卡次数的周期
+#[derive(Debug, Copy, Clone, PartialEq, Eq, Sequence)]
+pub enum Period {
+    /// 自然周（ISO 周，周一开始）
+    Week,
+    /// 自然月
+    Month,
+}
+
+impl Period {
+    /// 时间戳所在周期的名称，例如 `2024-W23`、`2024-06`，按字符串排序即按时间排序
+    fn label(&self, timestamp: u64) -> String {
+        let date_time = DateTime::from_timestamp(timestamp as i64, 0)
+            .unwrap_or_default()
+            .with_timezone(&Local);
+        match self {
+            Period::Week => {
+                let week = date_time.iso_week();
+                format!("{}-W{:02}", week.year(), week.week())
+            }
+            Period::Month => format!("{}-{:02}", date_time.year(), date_time.month()),
+        }
+    }
+}
+
+/// 一个周期内一个卡池的抽卡次数
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct PeriodPulls {
+    /// 周期名称，见 [`Period::label`]
+    pub period: String,
+    pub banner_type: BannerType,
+    pub pulls: u32,
+    pub five_star: u32,
+}
+
+/// 按周期、卡池汇总账号的抽卡次数和5星数量
+/// # 返回
+/// 按周期从早到晚、卡池顺序排列，没有抽卡的周期不出现
+pub fn period_pulls(account_records: &OneAccountRecords, period: Period) -> Vec<PeriodPulls> {
+    let mut pulls: BTreeMap<(String, usize), PeriodPulls> = BTreeMap::new();
+    for (banner_index, banner_type) in all::<BannerType>().enumerate() {
+        let Some(records) = account_records.records.get(&banner_type) else {
+            continue;
+        };
+        for record in records {
+            let label = period.label(record.timestamp);
+            let period_pulls = pulls
+                .entry((label.clone(), banner_index))
+                .or_insert_with(|| PeriodPulls {
+                    period: label,
+                    banner_type,
+                    pulls: 0,
+                    five_star: 0,
+                });
+            period_pulls.pulls += 1;
+            if record.star == 5 {
+                period_pulls.five_star += 1;
+            }
+        }
+    }
+    pulls.into_values().collect()
+}
+
+#[cfg(test)]
+mod test {
+    use chrono::TimeZone;
+
+    use super::*;
+    use crate::banner_schedule::test::test_schedule;
+    use crate::record::OneRecord;
+
+    #[test]
+    fn test_five_star_history() {
+        let record = |star, name: &str, timestamp| {
+            OneRecord::new(star, name.to_string(), ItemType::Weapon, timestamp)
+        };
+        let account_records = OneAccountRecords::new(
+            "id".to_string(),
+            HashMap::from([
+                (
+                    BannerType::PermanentWeapon,
+                    vec![record(5, "b", 4), record(3, "x", 3), record(5, "a", 1)],
+                ),
+                (BannerType::Beginner, vec![record(5, "c", 2)]),
+            ]),
+        );
+        let history = five_star_history(&account_records)
+            .into_iter()
+            .map(|five_star_pull| (five_star_pull.item_name, five_star_pull.pulls))
+            .collect::<Vec<_>>();
+        assert_eq!(
+            history,
+            vec![
+                ("a".to_string(), 1),
+                ("c".to_string(), 1),
+                ("b".to_string(), 2)
+            ]
+        );
+    }
+
+    #[test]
+    fn test_guarantee_state() {
+        let start = Local
+            .with_ymd_and_hms(2023, 7, 25, 12, 0, 0)
+            .unwrap()
+            .timestamp() as u64;
+        let record = |star, name: &str, minutes: u64| {
+            OneRecord::new(
+                star,
+                name.to_string(),
+                ItemType::Character,
+                start + minutes * 60,
+            )
+        };
+        let banner_type = BannerType::LimitedCharacter50Percent;
+        let schedule = test_schedule();
+        // 按时间倒序：歪、大保底、歪
+        let mut records = vec![
+            record(5, "芬妮·辉耀", 4),
+            record(3, "a", 3),
+            record(5, "里芙·无限之视", 2),
+            record(5, "芬妮·辉耀", 1),
+        ];
+        let state = guarantee_state(&records, banner_type, &schedule).unwrap();
+        assert!(state.guaranteed);
+        assert_eq!(state.lost_streak, 2);
+        assert_eq!(state.timestamp, start + 4 * 60);
+
+        let rate = win_rate(&records, banner_type, &schedule).unwrap();
+        // 大保底的5星不计入
+        assert_eq!((rate.won, rate.contested, rate.max_lost_streak), (0, 2, 2));
+        assert!(rate.confidence_interval().1 > 0.5);
+        assert!(rate.is_consistent());
+        assert!(win_rate(&records, BannerType::PermanentCharacter, &schedule).is_none());
+        // 排期表没有录入完整时不推算
+        assert!(win_rate(&records, banner_type, &BannerSchedule::default()).is_none());
+        assert!(guarantee_state(&records, banner_type, &BannerSchedule::default()).is_none());
+
+        // 赢得小保底后连续歪的次数清零
+        records.insert(0, record(5, "里芙·无限之视", 5));
+        records.insert(0, record(5, "里芙·无限之视", 6));
+        let state = guarantee_state(&records, banner_type, &schedule).unwrap();
+        assert!(!state.guaranteed);
+        assert_eq!(state.lost_streak, 0);
+
+        assert!(guarantee_state(&records, BannerType::PermanentCharacter, &schedule).is_none());
+        assert!(guarantee_state(&[record(3, "a", 1)], banner_type, &schedule).is_none());
+    }
+
+    #[test]
+    fn test_item_counts() {
+        let record = |star, name: &str, timestamp| {
+            OneRecord::new(star, name.to_string(), ItemType::Character, timestamp)
+        };
+        let records = HashMap::from([
+            (
+                BannerType::LimitedCharacter50Percent,
+                vec![record(5, "a", 4), record(3, "c", 3), record(4, "b", 2)],
+            ),
+            (BannerType::PermanentCharacter, vec![record(5, "a", 1)]),
+        ]);
+        let account_records = OneAccountRecords::new("id".to_string(), records);
+        let counts = item_counts(&account_records);
+        assert_eq!(counts.len(), 2);
+        assert_eq!(counts[0].item_name, "a");
+        assert_eq!(counts[0].count, 2);
+        assert_eq!(counts[1].item_name, "b");
+        assert_eq!(counts[1].count, 1);
+    }
+
+    #[test]
+    fn test_period_pulls() {
+        let timestamp = |month, day| {
+            Local
+                .with_ymd_and_hms(2024, month, day, 12, 0, 0)
+                .unwrap()
+                .timestamp() as u64
+        };
+        let record =
+            |star, timestamp| OneRecord::new(star, "a".to_string(), ItemType::Character, timestamp);
+        let records = HashMap::from([
+            (
+                BannerType::PermanentCharacter,
+                vec![
+                    record(3, timestamp(7, 1)),
+                    record(5, timestamp(6, 30)),
+                    record(3, timestamp(6, 3)),
+                ],
+            ),
+            (
+                BannerType::LimitedCharacter50Percent,
+                vec![record(5, timestamp(6, 5))],
+            ),
+        ]);
+        let account_records = OneAccountRecords::new("id".to_string(), records);
+
+        let pulls = period_pulls(&account_records, Period::Month);
+        let pulls = pulls
+            .iter()
+            .map(|p| (p.period.as_str(), p.banner_type, p.pulls, p.five_star))
+            .collect::<Vec<_>>();
+        assert_eq!(
+            pulls,
+            [
+                ("2024-06", BannerType::LimitedCharacter50Percent, 1, 1),
+                ("2024-06", BannerType::PermanentCharacter, 2, 1),
+                ("2024-07", BannerType::PermanentCharacter, 1, 0),
+            ]
+        );
+
+        // 2024-06-30 是周日，与 2024-07-01 不在同一周
+        let pulls = period_pulls(&account_records, Period::Week);
+        let periods = pulls.iter().map(|p| p.period.as_str()).collect::<Vec<_>>();
+        assert_eq!(periods, ["2024-W23", "2024-W23", "2024-W26", "2024-W27"]);
+    }
+
+    #[test]
+    fn test_pity_offsets() {
+        let record =
+            |star, timestamp| OneRecord::new(star, "a".to_string(), ItemType::Character, timestamp);
+        let anchor = PityAnchor {
+            banner_type: BannerType::PermanentCharacter,
+            timestamp: 10,
+            pulls_since_5_star: 30,
+            pulls_since_4_star: 5,
+        };
+        assert_eq!(pity_offsets(&[record(3, 1)], None), (0, 0));
+        // 锚点之后的记录不计入
+        let records = [record(3, 11), record(3, 2), record(3, 1)];
+        assert_eq!(pity_offsets(&records, Some(&anchor)), (28, 3));
+        // 已有4星时不需要补全4星的计数
+        let records = [record(3, 2), record(4, 1)];
+        assert_eq!(pity_offsets(&records, Some(&anchor)), (28, 0));
+        assert_eq!(current_pity(&records, Some(&anchor)), 30);
+        assert_eq!(current_4_star_pity(&records, Some(&anchor)), 1);
+        let records = [record(3, 12), record(5, 11), record(3, 2)];
+        assert_eq!(current_pity(&records, Some(&anchor)), 1);
+
+        let mut account_records = OneAccountRecords::new("id".to_string(), HashMap::new());
+        account_records.records.insert(
+            BannerType::PermanentCharacter,
+            vec![record(5, 14), record(3, 13), record(5, 12), record(3, 11)],
+        );
+        account_records.set_pity_anchor(anchor);
+        let luck = luck(&account_records);
+        assert_eq!(luck.len(), 1);
+        assert_eq!(luck[0].five_star, 2);
+        // 第一个5星之前还有锚点补全的 30 抽
+        assert_eq!(luck[0].average_pulls, (32.0 + 2.0) / 2.0);
+        assert_eq!(
+            pity_histogram(&account_records, 10),
+            [(1..=10, 1), (11..=20, 0), (21..=30, 0), (31..=40, 1)]
+        );
+    }
+
+    #[test]
+    fn test_pull_groups() {
+        let record = |timestamp| OneRecord::new(3, "a".to_string(), ItemType::Weapon, timestamp);
+        // 一次单抽、同一分钟内的一次单抽和一次十连、一次单抽
+        let records = [vec![record(3)], vec![record(2); 11], vec![record(1)]].concat();
+        let groups = pull_groups(&records);
+        assert_eq!(
+            groups,
+            [
+                PullGroup { start: 0, len: 1 },
+                PullGroup { start: 1, len: 1 },
+                PullGroup { start: 2, len: 10 },
+                PullGroup { start: 12, len: 1 },
+            ]
+        );
+        let numbers = ten_pull_numbers(&records);
+        assert_eq!(numbers[..3], [None, None, Some(10)]);
+        assert_eq!(numbers[11..], [Some(1), None]);
+        assert!(pull_groups(&[]).is_empty());
+    }
+}
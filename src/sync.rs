@@ -0,0 +1,179 @@
+//! 远程同步（WebDAV）
+//!
+//! 导入前从远程拉取抽卡记录与本地合并，导入后把合并后的抽卡记录推送到远程。
+//! 推送时带上拉取时的 ETag（服务器不支持 ETag 时用修改时间），
+//! 远程在此期间被另一台电脑修改时重新拉取、合并后再推送。
+
+use anyhow::{anyhow, Result};
+use reqwest::{header, Client, StatusCode};
+
+use crate::config::{config, SyncConfig};
+use crate::data_dir::data_path;
+use crate::record::TotalRecords;
+
+static REMOTE_FILE_NAME: &str = "records.json";
+/// 远程被同时修改时最多重试的次数
+static MAX_PUSH_RETRIES: u32 = 3;
+
+/// 拉取时远程抽卡记录的版本，推送时作为条件，远程在此期间被修改时不覆盖
+#[derive(Debug, Clone, PartialEq, Eq)]
+enum RemoteVersion {
+    /// 远程还没有抽卡记录
+    Missing,
+    ETag(String),
+    /// 服务器不返回 ETag 时使用修改时间
+    LastModified(String),
+    /// 服务器不返回 ETag 和修改时间，无法发现同时修改，直接覆盖
+    Unknown,
+}
+
+impl RemoteVersion {
+    fn from_headers(headers: &header::HeaderMap) -> Self {
+        let value = |name| {
+            headers
+                .get(name)
+                .and_then(|value| value.to_str().ok())
+                .map(str::to_string)
+        };
+        if let Some(etag) = value(header::ETAG) {
+            RemoteVersion::ETag(etag)
+        } else if let Some(last_modified) = value(header::LAST_MODIFIED) {
+            RemoteVersion::LastModified(last_modified)
+        } else {
+            RemoteVersion::Unknown
+        }
+    }
+}
+
+struct Remote {
+    client: Client,
+    url: String,
+    sync_config: SyncConfig,
+}
+
+impl Remote {
+    /// 没有配置远程同步时返回 `None`
+    fn from_config() -> Option<Self> {
+        let sync_config = config().sync.filter(|sync_config| sync_config.enabled)?;
+        let url = format!(
+            "{}/{}",
+            sync_config.endpoint.trim_end_matches('/'),
+            REMOTE_FILE_NAME
+        );
+        let client = Client::builder()
+            .user_agent("snowbreak_gacha_export")
+            .build()
+            .ok()?;
+        Some(Self {
+            client,
+            url,
+            sync_config,
+        })
+    }
+
+    /// 获取远程的抽卡记录和版本，远程还没有抽卡记录时返回 `None`
+    async fn get(&self) -> Result<Option<(TotalRecords, RemoteVersion)>> {
+        let response = self
+            .client
+            .get(&self.url)
+            .basic_auth(&self.sync_config.username, Some(&self.sync_config.password))
+            .send()
+            .await?;
+        if response.status() == StatusCode::NOT_FOUND {
+            return Ok(None);
+        }
+        let response = response.error_for_status()?;
+        let version = RemoteVersion::from_headers(response.headers());
+        let total_records: TotalRecords = serde_json::from_slice(&response.bytes().await?)?;
+        total_records.warn_checksum_mismatches(&self.url);
+        Ok(Some((total_records, version)))
+    }
+
+    /// 上传抽卡记录
+    /// # 参数
+    /// - `version`: 拉取时远程抽卡记录的版本
+    /// # 返回
+    /// 远程在拉取后被修改时返回 `false`
+    async fn put(&self, total_records: &TotalRecords, version: &RemoteVersion) -> Result<bool> {
+        let request = self
+            .client
+            .put(&self.url)
+            .basic_auth(&self.sync_config.username, Some(&self.sync_config.password))
+            .header(header::CONTENT_TYPE, "application/json")
+            .body(serde_json::to_vec_pretty(&total_records.with_checksums())?);
+        let request = match version {
+            RemoteVersion::Missing => request.header(header::IF_NONE_MATCH, "*"),
+            RemoteVersion::ETag(etag) => request.header(header::IF_MATCH, etag),
+            RemoteVersion::LastModified(last_modified) => {
+                request.header(header::IF_UNMODIFIED_SINCE, last_modified)
+            }
+            RemoteVersion::Unknown => {
+                log::warn!("remote returned neither ETag nor Last-Modified, overwrite it");
+                request
+            }
+        };
+        let response = request.send().await?;
+        if response.status() == StatusCode::PRECONDITION_FAILED {
+            return Ok(false);
+        }
+        response.error_for_status()?;
+        Ok(true)
+    }
+}
+
+/// 读取本地的抽卡记录，文件损坏时返回错误，不能用空的抽卡记录覆盖
+fn read_local_records() -> Result<TotalRecords> {
+    let path = data_path("records/records.json");
+    if path.exists() {
+        TotalRecords::read_from(&path)
+    } else {
+        Ok(TotalRecords::default())
+    }
+}
+
+/// 拉取远程的抽卡记录并与本地合并
+/// # 返回
+/// 本地新增的抽卡记录数量，没有配置远程同步时为 0
+pub async fn pull() -> Result<u32> {
+    let Some(remote) = Remote::from_config() else {
+        return Ok(0);
+    };
+    let Some((remote_records, _)) = remote.get().await? else {
+        return Ok(0);
+    };
+    let mut total_records = read_local_records()?;
+    let add_num = total_records.merge(remote_records)?;
+    if add_num > 0 {
+        total_records.save()?;
+    }
+    log::info!("pulled {} records from remote", add_num);
+    Ok(add_num)
+}
+
+/// 把本地的抽卡记录与远程合并后推送到远程
+pub async fn push() -> Result<()> {
+    let Some(remote) = Remote::from_config() else {
+        return Ok(());
+    };
+    for _ in 0..MAX_PUSH_RETRIES {
+        let mut total_records = read_local_records()?;
+        let version = match remote.get().await? {
+            Some((remote_records, version)) => {
+                if total_records.merge(remote_records)? > 0 {
+                    total_records.save()?;
+                }
+                version
+            }
+            None => RemoteVersion::Missing,
+        };
+        if remote.put(&total_records, &version).await? {
+            log::info!("pushed records to remote");
+            return Ok(());
+        }
+        log::warn!("remote records changed while pushing, retry");
+    }
+    Err(anyhow!(
+        "remote records kept changing, failed to push after {} retries",
+        MAX_PUSH_RETRIES
+    ))
+}
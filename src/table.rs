@@ -0,0 +1,230 @@
+//! 控制台表格
+//!
+//! 中文等全角字符在控制台中占两列，直接用 `{:<width$}` 按字符数对齐会错位，
+//! 这里按显示宽度对齐每一列。
+
+/// 字符在控制台中是否占两列：中日韩文字、全角标点和符号
+fn is_wide(c: char) -> bool {
+    matches!(
+        c as u32,
+        0x1100..=0x115F
+            | 0x2E80..=0x303E
+            | 0x3041..=0x33FF
+            | 0x3400..=0x4DBF
+            | 0x4E00..=0x9FFF
+            | 0xA000..=0xA4CF
+            | 0xAC00..=0xD7A3
+            | 0xF900..=0xFAFF
+            | 0xFE30..=0xFE4F
+            | 0xFF00..=0xFF60
+            | 0xFFE0..=0xFFE6
+            | 0x1F300..=0x1F64F
+            | 0x1F900..=0x1F9FF
+            | 0x20000..=0x3FFFD
+    )
+}
+
+/// 文本在控制台中的显示宽度
+pub fn display_width(text: &str) -> usize {
+    text.chars()
+        .map(|c| match c {
+            c if c.is_control() => 0,
+            c if is_wide(c) => 2,
+            _ => 1,
+        })
+        .sum()
+}
+
+/// 列的对齐方式
+#[derive(Debug, Copy, Clone, PartialEq, Eq)]
+pub enum Align {
+    Left,
+    Right,
+}
+
+/// 用空格把文本补齐到指定的显示宽度，超过时原样返回
+pub fn pad(text: &str, width: usize, align: Align) -> String {
+    let padding = " ".repeat(width.saturating_sub(display_width(text)));
+    match align {
+        Align::Left => format!("{text}{padding}"),
+        Align::Right => format!("{padding}{text}"),
+    }
+}
+
+/// 编号列表，编号右对齐，例如 ` 9. ` 和 `10. `
+pub fn numbered<S: AsRef<str>>(items: &[S]) -> Vec<String> {
+    let width = items.len().to_string().len();
+    items
+        .iter()
+        .enumerate()
+        .map(|(i, item)| format!("{:>width$}. {}", i + 1, item.as_ref()))
+        .collect()
+}
+
+/// 把行分成（名称和分隔符, 值），中文使用全角冒号，英文使用 `: `
+fn split_label(line: &str) -> Option<(&str, &str)> {
+    ["：", ": "]
+        .into_iter()
+        .filter_map(|separator| line.find(separator).map(|i| i + separator.len()))
+        .min()
+        .map(|i| line.split_at(i))
+}
+
+/// 把“名称：值”形式的各行按名称的显示宽度对齐，值从同一列开始，没有分隔符的行原样返回
+pub fn align_labels<S: AsRef<str>>(lines: &[S]) -> Vec<String> {
+    let width = lines
+        .iter()
+        .filter_map(|line| split_label(line.as_ref()))
+        .map(|(label, _)| display_width(label))
+        .max()
+        .unwrap_or_default();
+    lines
+        .iter()
+        .map(|line| match split_label(line.as_ref()) {
+            Some((label, value)) => format!("{}{value}", pad(label, width, Align::Left)),
+            None => line.as_ref().to_string(),
+        })
+        .collect()
+}
+
+/// 列之间的空格
+static COLUMN_GAP: &str = "  ";
+/// 每行开头的缩进，与其他统计输出一致
+static INDENT: &str = "  ";
+
+/// 控制台表格，第一行是表头，表头下面是分隔线
+#[derive(Debug, Clone)]
+pub struct Table {
+    headers: Vec<String>,
+    aligns: Vec<Align>,
+    rows: Vec<Vec<String>>,
+}
+
+impl Table {
+    /// 所有列默认左对齐
+    pub fn new<S: Into<String>>(headers: impl IntoIterator<Item = S>) -> Self {
+        let headers = headers.into_iter().map(Into::into).collect::<Vec<_>>();
+        Self {
+            aligns: vec![Align::Left; headers.len()],
+            headers,
+            rows: vec![],
+        }
+    }
+
+    /// 数字列右对齐
+    pub fn align_right(mut self, columns: &[usize]) -> Self {
+        for &column in columns {
+            self.aligns[column] = Align::Right;
+        }
+        self
+    }
+
+    /// 添加一行，单元格数量少于表头时后面的列为空
+    pub fn add_row(&mut self, row: Vec<String>) {
+        self.rows.push(row);
+    }
+
+    /// 每一列的宽度，为表头和所有单元格中最宽的
+    fn widths(&self) -> Vec<usize> {
+        (0..self.headers.len())
+            .map(|column| {
+                self.rows
+                    .iter()
+                    .filter_map(|row| row.get(column))
+                    .chain([&self.headers[column]])
+                    .map(|cell| display_width(cell))
+                    .max()
+                    .unwrap_or_default()
+            })
+            .collect()
+    }
+
+    fn render_row(&self, cells: &[String], widths: &[usize]) -> String {
+        let line = widths
+            .iter()
+            .enumerate()
+            .map(|(column, &width)| {
+                let cell = cells.get(column).map_or("", String::as_str);
+                pad(cell, width, self.aligns[column])
+            })
+            .collect::<Vec<_>>()
+            .join(COLUMN_GAP);
+        format!("{INDENT}{}", line.trim_end())
+    }
+
+    /// 表格文本，每行末尾有换行
+    pub fn render(&self) -> String {
+        let widths = self.widths();
+        let separator = widths
+            .iter()
+            .map(|width| "-".repeat(*width))
+            .collect::<Vec<_>>();
+        [&self.headers, &separator]
+            .into_iter()
+            .chain(self.rows.iter())
+            .map(|cells| self.render_row(cells, &widths) + "\n")
+            .collect()
+    }
+
+    pub fn print(&self) {
+        print!("{}", self.render());
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    #[test]
+    fn test_display_width() {
+        assert_eq!(display_width("abc"), 3);
+        assert_eq!(display_width("里芙"), 4);
+        assert_eq!(display_width("5★ 里芙·无限之视"), 3 + 4 + 1 + 8);
+        assert_eq!(display_width("（角色）"), 8);
+        assert_eq!(pad("里芙", 6, Align::Left), "里芙  ");
+        assert_eq!(pad("12", 4, Align::Right), "  12");
+        assert_eq!(pad("toolong", 3, Align::Left), "toolong");
+    }
+
+    #[test]
+    fn test_numbered_and_align_labels() {
+        let items = (1..=10).map(|i| i.to_string()).collect::<Vec<_>>();
+        let lines = numbered(&items);
+        assert_eq!(lines[0], " 1. 1");
+        assert_eq!(lines[9], "10. 10");
+
+        let lines = align_labels(&["语言：简体中文", "翻页等待时间：200 毫秒", "返回"]);
+        assert_eq!(
+            lines,
+            ["语言：        简体中文", "翻页等待时间：200 毫秒", "返回"]
+        );
+        let lines = align_labels(&["Language: English", "Page turn delay: 200 ms"]);
+        assert_eq!(
+            lines,
+            ["Language:        English", "Page turn delay: 200 ms"]
+        );
+    }
+
+    #[test]
+    fn test_table() {
+        let mut table = Table::new(["名称", "Count"]).align_right(&[1]);
+        table.add_row(vec!["里芙".to_string(), "12".to_string()]);
+        table.add_row(vec!["Lyfe".to_string(), "3".to_string()]);
+        let text = table.render();
+        let lines = text.lines().collect::<Vec<_>>();
+        assert_eq!(
+            lines,
+            [
+                "  名称  Count",
+                "  ----  -----",
+                "  里芙     12",
+                "  Lyfe      3",
+            ]
+        );
+        let widths = lines
+            .iter()
+            .map(|line| display_width(line))
+            .collect::<Vec<_>>();
+        assert!(widths.iter().all(|width| *width == widths[0]));
+    }
+}
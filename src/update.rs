@@ -1,14 +1,43 @@
+use chrono::Local;
 use semver::Version;
+use serde::{Deserialize, Serialize};
 
-/// 检查是否已经是最新版本
-/// # 返回
-/// - (是否是最新版本, 最新版本号)
-pub async fn is_up_to_date() -> Result<(bool, String), Box<dyn std::error::Error>> {
-    // 获取当前程序的版本
-    let current_version = Version::parse(env!("CARGO_PKG_VERSION"))?;
+use crate::data_dir::data_path;
+
+/// 缓存最新版本号的文件名
+static CACHE_FILE: &str = "update_check.json";
+
+/// 缓存的有效时间（秒），一天内只请求一次 GitHub API
+static CACHE_TTL_SECS: u64 = 24 * 60 * 60;
 
-    // 从GitHub API获取最新的release信息
+/// 上次检查更新的结果
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+struct UpdateCheckCache {
+    /// 检查时间（Unix 时间戳）
+    checked_at: u64,
+    /// 最新版本的 tag，例如 `v1.2.3`，从来没有获取成功时为 `None`
+    #[serde(default)]
+    latest_tag_name: Option<String>,
+}
+
+impl UpdateCheckCache {
+    fn read() -> Option<Self> {
+        let content = std::fs::read_to_string(data_path(CACHE_FILE)).ok()?;
+        serde_json::from_str(&content).ok()
+    }
+
+    fn write(&self) -> anyhow::Result<()> {
+        std::fs::write(data_path(CACHE_FILE), serde_json::to_string_pretty(self)?)?;
+        Ok(())
+    }
+
+    fn is_fresh(&self, now: u64) -> bool {
+        now.saturating_sub(self.checked_at) < CACHE_TTL_SECS
+    }
+}
 
+/// 从GitHub API获取最新版本的 tag
+async fn fetch_latest_tag_name() -> Result<String, Box<dyn std::error::Error>> {
     // 设置ua
     let client = reqwest::Client::builder()
         .user_agent("snowbreak_gacha_export")
@@ -20,14 +49,53 @@ pub async fn is_up_to_date() -> Result<(bool, String), Box<dyn std::error::Error
     let latest_release = response.text().await?;
     let json = serde_json::from_str::<serde_json::Value>(&latest_release)?;
     let latest_release_tag_name = json["tag_name"].as_str().ok_or("tag_name not found")?;
-    let latest_version_str = latest_release_tag_name.trim_start_matches('v');
-    let latest_version = Version::parse(latest_version_str)?;
+    Ok(latest_release_tag_name.to_string())
+}
 
-    if latest_version > current_version {
-        Ok((false, latest_release_tag_name.to_string()))
-    } else {
-        Ok((true, latest_release_tag_name.to_string()))
+/// 最新版本的 tag，一天内使用缓存，请求失败时使用过期的缓存
+/// 请求失败时也记录检查时间，一天内不再重试
+async fn latest_tag_name() -> Result<String, Box<dyn std::error::Error>> {
+    let now = Local::now().timestamp() as u64;
+    let cache = UpdateCheckCache::read();
+    if let Some(cache) = cache.as_ref().filter(|cache| cache.is_fresh(now)) {
+        log::debug!("using cached latest version {:?}", cache.latest_tag_name);
+        return cache
+            .latest_tag_name
+            .clone()
+            .ok_or_else(|| "update check failed recently".into());
     }
+    let result = fetch_latest_tag_name().await;
+    let latest_tag_name = match &result {
+        Ok(latest_tag_name) => Some(latest_tag_name.clone()),
+        Err(e) => {
+            log::warn!("failed to check update: {:?}", e);
+            cache.and_then(|cache| cache.latest_tag_name)
+        }
+    };
+    let cache = UpdateCheckCache {
+        checked_at: now,
+        latest_tag_name: latest_tag_name.clone(),
+    };
+    if let Err(e) = cache.write() {
+        log::warn!("failed to write update check cache: {:?}", e);
+    }
+    result.or_else(|e| latest_tag_name.ok_or(e))
+}
+
+/// 比较当前版本和最新版本的 tag
+fn is_newer_than_current(latest_tag_name: &str) -> Result<bool, semver::Error> {
+    let current_version = Version::parse(env!("CARGO_PKG_VERSION"))?;
+    let latest_version = Version::parse(latest_tag_name.trim_start_matches('v'))?;
+    Ok(latest_version > current_version)
+}
+
+/// 检查是否已经是最新版本
+/// # 返回
+/// - (是否是最新版本, 最新版本号)
+pub async fn is_up_to_date() -> Result<(bool, String), Box<dyn std::error::Error>> {
+    let latest_release_tag_name = latest_tag_name().await?;
+    let is_newer = is_newer_than_current(&latest_release_tag_name)?;
+    Ok((!is_newer, latest_release_tag_name))
 }
 
 #[cfg(test)]
@@ -39,4 +107,25 @@ mod test {
         let result = is_up_to_date().await;
         println!("{:?}", result)
     }
+
+    #[test]
+    fn test_cache() {
+        let cache = UpdateCheckCache {
+            checked_at: 1_000_000,
+            latest_tag_name: Some("v1.0.0".to_string()),
+        };
+        assert!(cache.is_fresh(1_000_000 + CACHE_TTL_SECS - 1));
+        assert!(!cache.is_fresh(1_000_000 + CACHE_TTL_SECS));
+        // 请求失败时只记录检查时间
+        let failed: UpdateCheckCache = serde_json::from_str(r#"{"checked_at":1000000}"#).unwrap();
+        assert_eq!(failed.latest_tag_name, None);
+        assert_eq!(
+            serde_json::from_str::<UpdateCheckCache>(&serde_json::to_string(&cache).unwrap())
+                .unwrap(),
+            cache
+        );
+        assert!(is_newer_than_current("v999.0.0").unwrap());
+        assert!(!is_newer_than_current("v0.0.1").unwrap());
+        assert!(is_newer_than_current("latest").is_err());
+    }
 }
@@ -1,24 +1,76 @@
 use std::io::{stdin, stdout, Write};
+use std::sync::mpsc::{channel, Receiver, TryRecvError};
+use std::sync::{Mutex, PoisonError};
 
 use enum_iterator::all;
+use lazy_static::lazy_static;
 
+use crate::config::config;
+use crate::error::remediation_hint;
+use crate::i18n::{self, tr};
+use crate::items::ItemCategory;
 use crate::language::Language;
-use crate::record::BannerType;
+use crate::record::{BannerType, OneAccountRecords};
+use crate::stats::{category_counts, ItemCount, Luck, Period, PeriodPulls, WinRate};
+use crate::table::{align_labels, numbered, Table};
 
-fn print_invalid_input(input: String, language: Language) {
-    let tip = match language {
-        Language::ChineseSimplified => format!("无效输入：{input}，请重新输入"),
-        Language::English => format!("Invalid input: {input}, please input again"),
+lazy_static! {
+    /// 标准输入的每一行，由同一个后台线程读取
+    /// 放弃等待的读取（例如剪贴板导入中等待回车）不会吞掉之后的输入，下一次读取会拿到这一行
+    static ref STDIN_LINES: Mutex<Receiver<String>> = {
+        let (sender, receiver) = channel();
+        std::thread::spawn(move || loop {
+            let mut line = String::new();
+            match stdin().read_line(&mut line) {
+                Ok(0) | Err(_) => break,
+                Ok(_) => {
+                    if sender.send(line).is_err() {
+                        break;
+                    }
+                }
+            }
+        });
+        Mutex::new(receiver)
     };
-    println!("{}", tip);
 }
 
-fn print_input_tip(language: Language) {
-    let tip = match language {
-        Language::ChineseSimplified => "输入：",
-        Language::English => "input: ",
-    };
-    print!("{}", tip);
+/// 读取一行输入，输入结束时为空字符串
+pub fn read_line() -> String {
+    STDIN_LINES
+        .lock()
+        .unwrap_or_else(PoisonError::into_inner)
+        .recv()
+        .unwrap_or_default()
+}
+
+/// 不等待地读取一行输入
+/// # 返回
+/// 还没有输入时为 `None`，输入结束时为空字符串
+pub fn try_read_line() -> Option<String> {
+    match STDIN_LINES
+        .lock()
+        .unwrap_or_else(PoisonError::into_inner)
+        .try_recv()
+    {
+        Ok(line) => Some(line),
+        Err(TryRecvError::Empty) => None,
+        Err(TryRecvError::Disconnected) => Some(String::new()),
+    }
+}
+
+/// 错误链中有 [`ExportError`](crate::error::ExportError) 时显示处理建议
+pub fn print_remediation_hint(error: &anyhow::Error) {
+    if let Some(hint) = remediation_hint(error) {
+        println!("{}", tr!("error.hint", hint = hint));
+    }
+}
+
+pub fn print_invalid_input(input: String) {
+    println!("{}", tr!("invalid_input", input = input));
+}
+
+fn print_input_tip() {
+    print!("{}", tr!("input_tip"));
 }
 
 pub fn language() -> Language {
@@ -37,10 +89,9 @@ pub fn language() -> Language {
         .join("\n");
     println!("{}", tip);
     loop {
-        let mut input = String::new();
         print!("输入/input: ");
         stdout().flush().unwrap();
-        stdin().read_line(&mut input).unwrap();
+        let input = read_line();
         let input = input.trim();
         match input.parse::<usize>() {
             Ok(index) => {
@@ -57,151 +108,268 @@ pub fn language() -> Language {
     }
 }
 
-pub fn banner_type(language: Language) -> BannerType {
-    let tip = vec![match language {
-        Language::ChineseSimplified => "输入数字选择卡池",
-        Language::English => "Input a number to select banner",
-    }
-    .to_string()]
-    .into_iter()
-    .chain(all::<BannerType>().enumerate().map(|(i, banner_type)| {
-        format!("{}. {}", i + 1, banner_type.display_name_for_user(language))
-    }))
-    .collect::<Vec<String>>()
-    .join("\n");
+/// 输出选项列表，让用户输入数字选择
+/// # 返回
+/// 选中的选项的下标
+pub fn select_index(tip: &str, options: &[String]) -> usize {
+    let tip = vec![tip.to_string()]
+        .into_iter()
+        .chain(numbered(options))
+        .collect::<Vec<String>>()
+        .join("\n");
     println!("{}", tip);
     loop {
-        let mut input = String::new();
-        print_input_tip(language);
+        print_input_tip();
         stdout().flush().unwrap();
-        stdin().read_line(&mut input).unwrap();
+        let input = read_line();
         let input = input.trim();
         match input.parse::<usize>() {
             Ok(index) => {
-                if index > 0 && index <= all::<BannerType>().count() {
-                    return all::<BannerType>().nth(index - 1).unwrap();
+                if index > 0 && index <= options.len() {
+                    return index - 1;
                 } else {
-                    print_invalid_input(input.to_string(), language);
+                    print_invalid_input(input.to_string());
                 }
             }
             Err(_) => {
-                print_invalid_input(input.to_string(), language);
+                print_invalid_input(input.to_string());
             }
         }
     }
 }
 
-pub fn input_account_id(language: Language) -> String {
+/// 让用户输入一行文本
+/// # 返回
+/// 去掉首尾空白的输入
+pub fn input_line(tip: &str) -> String {
+    print!("{}", tip);
+    stdout().flush().unwrap();
+    read_line().trim().to_string()
+}
+
+/// 让用户输入 y 确认
+pub fn confirm(tip: &str) -> bool {
+    print!("{} (y/n): ", tip);
+    stdout().flush().unwrap();
+    read_line().trim().eq_ignore_ascii_case("y")
+}
+
+pub fn banner_type() -> BannerType {
+    let options = all::<BannerType>()
+        .map(|banner_type| {
+            banner_type
+                .display_name_for_user(i18n::language())
+                .to_string()
+        })
+        .collect::<Vec<_>>();
+    let index = select_index(tr!("select_banner"), &options);
+    all::<BannerType>().nth(index).unwrap()
+}
+
+pub fn input_account_id() -> String {
     loop {
-        let tip = match language {
-            Language::ChineseSimplified => "输入账号ID：",
-            Language::English => "Input account ID: ",
-        };
-        print!("{}", tip);
+        print!("{}", tr!("input_account_id"));
         stdout().flush().unwrap();
-        let mut account_id = String::new();
-        stdin().read_line(&mut account_id).unwrap();
+        let account_id = read_line();
         let account_id = account_id.trim();
         if account_id.is_empty() {
-            let tip = match language {
-                Language::ChineseSimplified => "账号ID不能为空",
-                Language::English => "Account ID cannot be empty",
-            };
-            println!("{}", tip);
+            println!("{}", tr!("account_id_empty"));
         } else {
             return account_id.to_string();
         }
     }
 }
 
-pub fn wait_enter(language: Language) {
-    let tip = match language {
-        Language::ChineseSimplified => "按下回车键退出",
-        Language::English => "Press enter to exit",
-    };
-    println!("{}", tip);
-    let mut input = String::new();
-    stdin().read_line(&mut input).unwrap();
+pub fn wait_enter() {
+    println!("{}", tr!("press_enter_to_exit"));
+    read_line();
 }
 
-fn select_account_id(language: Language, account_ids: Vec<String>) -> String {
-    let tip = vec![match language {
-        Language::ChineseSimplified => "输入数字选择账号",
-        Language::English => "Input a number to select account",
-    }
-    .to_string()]
-    .into_iter()
-    .chain(
-        account_ids
-            .iter()
-            .enumerate()
-            .map(|(i, account_id)| format!("{}. {}", i + 1, account_id)),
-    )
-    .collect::<Vec<String>>()
-    .join("\n");
-    println!("{}", tip);
-    loop {
-        let mut input = String::new();
-        print_input_tip(language);
-        stdout().flush().unwrap();
-        stdin().read_line(&mut input).unwrap();
-        let input = input.trim();
-        match input.parse::<usize>() {
-            Ok(index) => {
-                if index > 0 && index <= account_ids.len() {
-                    return account_ids[index - 1].clone();
-                } else {
-                    print_invalid_input(input.to_string(), language);
-                }
-            }
-            Err(_) => {
-                print_invalid_input(input.to_string(), language);
-            }
-        }
-    }
+fn select_account_id(account_ids: Vec<String>) -> String {
+    let index = select_index(tr!("select_account"), &account_ids);
+    account_ids[index].clone()
 }
 
-pub fn account_id(language: Language, account_ids: Vec<String>) -> String {
+pub fn account_id(account_ids: Vec<String>) -> String {
     if account_ids.is_empty() {
-        let tip = match language {
-            Language::ChineseSimplified => "没有账号ID",
-            Language::English => "No account ID",
-        };
-        println!("{}", tip);
-        input_account_id(language)
+        if let Some(account_id) = config().default_account_id {
+            println!("{}", tr!("use_default_account", account = account_id));
+            return account_id;
+        }
+        println!("{}", tr!("no_account_id"));
+        input_account_id()
     } else {
-        let tip = match language {
-            Language::ChineseSimplified => "已有账号ID：",
-            Language::English => "Existing account IDs:",
-        };
-        println!("{}", tip);
-        for (i, account_id) in account_ids.iter().enumerate() {
-            println!("{}. {}", i + 1, account_id);
+        println!("{}", tr!("existing_account_ids"));
+        for line in numbered(&account_ids) {
+            println!("{line}");
         }
-        let tip = match language {
-            Language::ChineseSimplified => "输入1以选择已有账号，输入2以输入新账号",
-            Language::English => {
-                "Input 1 to select an existing account, input 2 to input a new account"
-            }
-        };
-        println!("{}", tip);
+        println!("{}", tr!("select_or_new_account"));
         loop {
-            let mut input = String::new();
-            print_input_tip(language);
+            print_input_tip();
             stdout().flush().unwrap();
-            stdin().read_line(&mut input).unwrap();
+            let input = read_line();
             let input = input.trim();
             match input {
                 "1" => {
-                    return select_account_id(language, account_ids);
+                    return select_account_id(account_ids);
                 }
                 "2" => {
-                    return input_account_id(language);
+                    return input_account_id();
                 }
                 _ => {
-                    print_invalid_input(input.to_string(), language);
+                    print_invalid_input(input.to_string());
                 }
             }
         }
     }
 }
+
+/// 打印按周期汇总的抽卡次数
+pub fn print_period_pulls(period: Period, period_pulls: &[PeriodPulls]) {
+    let title = match period {
+        Period::Week => tr!("period_pulls.week"),
+        Period::Month => tr!("period_pulls.month"),
+    };
+    println!("{}", title);
+    let mut table = Table::new([
+        tr!("excel.period"),
+        tr!("export.banner"),
+        tr!("excel.pulls"),
+        tr!("excel.5_star_count"),
+    ])
+    .align_right(&[2, 3]);
+    for period_pulls in period_pulls {
+        table.add_row(vec![
+            period_pulls.period.to_string(),
+            period_pulls
+                .banner_type
+                .display_name_for_user(i18n::language())
+                .to_string(),
+            period_pulls.pulls.to_string(),
+            period_pulls.five_star.to_string(),
+        ]);
+    }
+    table.print();
+}
+
+/// 打印每个卡池出5星的平均抽数和期望抽数
+pub fn print_luck(luck: &[Luck]) {
+    println!("{}", tr!("luck.title"));
+    let mut table = Table::new([
+        tr!("export.banner"),
+        tr!("excel.5_star_count"),
+        tr!("luck.average"),
+        tr!("luck.expected"),
+    ])
+    .align_right(&[1, 2, 3]);
+    for luck in luck {
+        table.add_row(vec![
+            luck.banner_type
+                .display_name_for_user(i18n::language())
+                .to_string(),
+            luck.five_star.to_string(),
+            format!("{:.1}", luck.average_pulls),
+            format!("{:.1}", luck.expected_pulls),
+        ]);
+    }
+    table.print();
+    for luck in luck {
+        if let Some(win_rate) = &luck.win_rate {
+            println!(
+                "  {}",
+                luck.banner_type.display_name_for_user(i18n::language())
+            );
+            print_win_rate(win_rate);
+        }
+    }
+}
+
+/// 打印限定卡池中5星是当期限定物品的比例，与概率模型比较
+fn print_win_rate(win_rate: &WinRate) {
+    let percent = |rate: f64| format!("{:.0}%", rate * 100.0);
+    let (low, high) = win_rate.confidence_interval();
+    println!(
+        "    {}",
+        tr!(
+            "luck.win_rate",
+            won = win_rate.won,
+            contested = win_rate.contested,
+            rate = percent(win_rate.rate()),
+            low = percent(low),
+            high = percent(high),
+            expected = percent(win_rate.expected_rate),
+            streak = win_rate.max_lost_streak
+        )
+    );
+    let verdict = if win_rate.is_consistent() {
+        tr!("luck.win_rate.consistent")
+    } else if win_rate.rate() > win_rate.expected_rate {
+        tr!("luck.win_rate.above")
+    } else {
+        tr!("luck.win_rate.below")
+    };
+    println!("    {verdict}");
+}
+
+/// 打印50%限定卡池的大保底状态
+pub fn print_guarantee_states(account_records: &OneAccountRecords) {
+    if account_records.guarantee_states.is_empty() {
+        return;
+    }
+    println!("{}", tr!("guarantee.title"));
+    let language = i18n::language();
+    let lines = all::<BannerType>()
+        .filter_map(|banner_type| {
+            let state = account_records.guarantee_state(banner_type)?;
+            Some(format!(
+                "{}: {}",
+                banner_type.display_name_for_user(language),
+                state.describe(language)
+            ))
+        })
+        .collect::<Vec<_>>();
+    for line in align_labels(&lines) {
+        println!("  {line}");
+    }
+}
+
+/// 在控制台输出5星/4星物品数量
+pub fn print_item_counts(item_counts: &[ItemCount]) {
+    println!("{}", tr!("item_counts"));
+    let mut table = Table::new([
+        tr!("excel.star"),
+        tr!("excel.name"),
+        tr!("excel.type"),
+        tr!("excel.copies"),
+    ])
+    .align_right(&[3]);
+    for item_count in item_counts {
+        table.add_row(vec![
+            format!("{}★", item_count.star),
+            item_count.item_name.clone(),
+            item_count
+                .item_type
+                .display_name_for_user(i18n::language())
+                .to_string(),
+            item_count.count.to_string(),
+        ]);
+    }
+    table.print();
+}
+
+/// 在控制台输出按属性、武器类型汇总的物品数量，物品字典中没有分类数据时不输出
+pub fn print_category_counts(item_counts: &[ItemCount]) {
+    let language = i18n::language();
+    for category in all::<ItemCategory>() {
+        let category_counts = category_counts(item_counts, category, language);
+        if category_counts.is_empty() {
+            continue;
+        }
+        let mut table =
+            Table::new([category.display_name(language), tr!("excel.copies")]).align_right(&[1]);
+        for category_count in category_counts {
+            table.add_row(vec![category_count.name, category_count.count.to_string()]);
+        }
+        table.print();
+    }
+}
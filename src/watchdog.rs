@@ -0,0 +1,60 @@
+//! 截图会话看门狗
+//!
+//! 游戏卡住或页码一直识别失败时，翻页循环会一直点击下去。
+//! 超过最长时间或最多页数时中止翻页。
+
+use std::time::{Duration, Instant};
+
+use anyhow::{anyhow, Result};
+
+pub struct SessionWatchdog {
+    start: Instant,
+    max_duration: Duration,
+    max_pages: u32,
+}
+
+impl SessionWatchdog {
+    pub fn new(max_duration: Duration, max_pages: u32) -> Self {
+        Self {
+            start: Instant::now(),
+            max_duration,
+            max_pages,
+        }
+    }
+
+    /// 检查会话是否应该中止
+    /// # 参数
+    /// - `page`: 当前所在的页码
+    pub fn check(&self, page: u32) -> Result<()> {
+        let elapsed = self.start.elapsed();
+        if elapsed > self.max_duration {
+            return Err(anyhow!(
+                "capture session timed out after {:?} on page {page}",
+                elapsed
+            ));
+        }
+        if page >= self.max_pages {
+            return Err(anyhow!(
+                "capture session reached max pages ({}) on page {page}",
+                self.max_pages
+            ));
+        }
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    #[test]
+    fn test_check() {
+        let watchdog = SessionWatchdog::new(Duration::from_secs(60), 3);
+        assert!(watchdog.check(2).is_ok());
+        assert!(watchdog.check(3).is_err());
+
+        let watchdog = SessionWatchdog::new(Duration::ZERO, 3);
+        std::thread::sleep(Duration::from_millis(1));
+        assert!(watchdog.check(1).is_err());
+    }
+}
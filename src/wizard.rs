@@ -0,0 +1,83 @@
+//! 首次运行向导
+//!
+//! 没有配置文件时，创建配置并选择语言后，依次设置账号ID、导出目录，
+//! 测试截图并说明支持的窗口模式，所有设置都保存到配置中。
+
+use anyhow::Result;
+use image::GenericImageView;
+
+use crate::capture::{capture_image, CaptureSession};
+use crate::config::{config, update_config};
+use crate::game_info::get_game_window_info;
+use crate::i18n::tr;
+use crate::record_image::RecordImage;
+use crate::screen::classify_screen;
+use crate::user_interaction::{confirm, input_line, print_remediation_hint};
+
+/// 运行首次运行向导，语言已经在创建配置时选择
+pub fn run_first_run_wizard() {
+    println!();
+    println!("{}", tr!("wizard.welcome"));
+
+    // 账号ID
+    let account_id = input_line(tr!("wizard.input_account_id"));
+    if !account_id.is_empty() {
+        log::info!("default account id: {account_id}");
+        save(update_config(|config| {
+            config.default_account_id = Some(account_id)
+        }));
+    }
+
+    // 导出目录
+    let output_dir = input_line(&tr!("wizard.input_output_dir", dir = config().output_dir));
+    if !output_dir.is_empty() {
+        log::info!("output dir: {output_dir}");
+        save(update_config(|config| config.output_dir = output_dir));
+    }
+
+    // 窗口模式和截图测试
+    println!("{}", tr!("wizard.window_modes"));
+    while confirm(tr!("wizard.capture_test")) {
+        match capture_test() {
+            Ok(()) => break,
+            Err(e) => {
+                log::error!("capture test failed: {:?}", e);
+                println!("{}", tr!("wizard.capture_failed", error = e));
+                print_remediation_hint(&e);
+            }
+        }
+    }
+
+    println!("{}", tr!("wizard.finished"));
+    println!();
+}
+
+fn save(result: Result<()>) {
+    if let Err(e) = result {
+        log::error!("failed to save config: {:?}", e);
+        println!("{}", tr!("save_failed", error = e));
+        print_remediation_hint(&e);
+    }
+}
+
+/// 截取一次游戏窗口，输出分辨率和当前所在的界面
+fn capture_test() -> Result<()> {
+    let (_, window_title) = get_game_window_info()?;
+    let image = {
+        let _capture_session = CaptureSession::start(window_title);
+        capture_image()
+    }?;
+    let (width, height) = image.dimensions();
+    log::info!("capture test: {width}x{height}");
+    println!(
+        "{}",
+        tr!("wizard.capture_ok", width = width, height = height)
+    );
+    let record_image = RecordImage::new(image);
+    if record_image.is_record_image() {
+        println!("{}", tr!("wizard.on_record_screen"));
+    } else {
+        println!("{}", classify_screen(&record_image).guidance());
+    }
+    Ok(())
+}